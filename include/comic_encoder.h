@@ -0,0 +1,54 @@
+/*
+ * C API of the comic encoder
+ *
+ * Build the shared library with:
+ *   cargo rustc --lib --release --features ffi --crate-type cdylib
+ *
+ * Strings returned by the library must be freed with `comic_enc_string_free`.
+ */
+
+#ifndef COMIC_ENCODER_H
+#define COMIC_ENCODER_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Compilation job, created from a JSON options string */
+typedef struct ComicEncJob ComicEncJob;
+
+/*
+ * Function receiving each progress event as a line of JSON (the same as with '--log-format json')
+ * The string is only valid during the call. The function may be called from different threads, but never concurrently.
+ */
+typedef void (*ComicEncProgressCallback)(const char *event_json, void *user_data);
+
+/*
+ * Create a compilation job from a JSON string like '{"encoding": {...}, "compilation": {...}}'
+ * Returns NULL if the options are invalid, in which case `error` (if not NULL) is set to a message describing the problem.
+ */
+ComicEncJob *comic_enc_job_new(const char *options_json, char **error);
+
+/*
+ * Build the volumes of a job, blocking until they are built
+ * Returns the exit code the command line would exit with (0 on success). `result` (if not NULL) is set to the JSON
+ * report of the produced volumes on success, or to the error's message on failure.
+ */
+int32_t comic_enc_job_run(const ComicEncJob *job, ComicEncProgressCallback callback, void *user_data, char **result);
+
+/* Stop a running job as soon as possible (from any thread), making `comic_enc_job_run` return 130 */
+void comic_enc_job_cancel(const ComicEncJob *job);
+
+/* Free a job that is not running */
+void comic_enc_job_free(ComicEncJob *job);
+
+/* Free a string returned by the library */
+void comic_enc_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* COMIC_ENCODER_H */
@@ -0,0 +1,91 @@
+use crate::actions::{compile, encode_one};
+use crate::cli::error::{BatchError, EncodingError};
+use crate::cli::opts::{Batch, EncodeSingle};
+use crate::lib::batch::BatchConfig;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Encode multiple series as described in a jobs file
+pub fn batch(opts: &Batch) -> Result<Vec<PathBuf>, BatchError> {
+    let started = Instant::now();
+
+    let cwd = env::current_dir().map_err(BatchError::FailedToGetCWD)?;
+    let config_path = cwd.join(&opts.config);
+
+    let config = fs::read_to_string(&config_path)
+        .map_err(|err| BatchError::FailedToReadConfig(config_path.clone(), err))?;
+
+    let config: BatchConfig = toml::from_str(&config)
+        .map_err(|err| BatchError::InvalidConfig(config_path.clone(), err))?;
+
+    let jobs = config
+        .jobs
+        .iter()
+        .filter(|job| opts.only.is_empty() || opts.only.contains(&job.name()))
+        .collect::<Vec<_>>();
+
+    if jobs.is_empty() {
+        return Err(BatchError::NoJobToRun);
+    }
+
+    // Paths in the jobs file are relative to it
+    let base_dir = config_path.parent().unwrap();
+
+    // Check all jobs' options before running any of them
+    let jobs = jobs
+        .into_iter()
+        .map(|job| {
+            job.options(&config.common, base_dir)
+                .map(|opts| (job.name(), opts))
+                .map_err(|err| BatchError::InvalidJobOptions(job.name(), err))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut output_files = vec![];
+    let mut failed = 0;
+
+    for (i, (name, (enc_opts, compile_opts))) in jobs.iter().enumerate() {
+        info!("Running job {}/{}: '{}'...", i + 1, jobs.len(), name);
+
+        let result = match compile_opts {
+            Some(compile_opts) => compile(compile_opts, enc_opts),
+            None => encode_one(&EncodeSingle {}, enc_opts),
+        };
+
+        match result {
+            Ok(report) => output_files.extend(report.paths()),
+            // The remaining jobs would be cancelled as well
+            Err(EncodingError::Cancelled) => {
+                return Err(BatchError::JobFailed(
+                    name.clone(),
+                    Box::new(EncodingError::Cancelled),
+                ))
+            }
+            Err(err) if opts.fail_fast => {
+                return Err(BatchError::JobFailed(name.clone(), Box::new(err)))
+            }
+            Err(err) => {
+                error!("{}", BatchError::JobFailed(name.clone(), Box::new(err)));
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(BatchError::FailedJobs(failed, jobs.len()));
+    }
+
+    let elapsed = started.elapsed();
+
+    info!(
+        "Successfully ran {} job{} in {}.{:03} s.",
+        jobs.len(),
+        if jobs.len() > 1 { "s" } else { "" },
+        elapsed.as_secs(),
+        elapsed.subsec_millis()
+    );
+
+    Ok(output_files)
+}
@@ -0,0 +1,116 @@
+use crate::cli::error::BenchError;
+use crate::cli::opts::Bench;
+use crate::lib::bench::{self, BenchReport, SamplePage};
+use crate::lib::codec;
+use crate::lib::deter;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Measure the speed and resulting size of each conversion option on a sample of pictures
+pub fn bench(opts: &Bench) -> Result<BenchReport, BenchError> {
+    let cwd = env::current_dir().map_err(BenchError::FailedToGetCWD)?;
+    let input = cwd.join(&opts.input);
+
+    if !input.is_dir() {
+        return Err(BenchError::InputDirectoryNotFound(input));
+    }
+
+    if opts.pages == 0 {
+        return Err(BenchError::AtLeast1Page);
+    }
+
+    let mut pictures = deter::readdir_files_recursive(
+        &input,
+        Some(&|path: &Path| deter::has_image_ext(path, opts.accept_extended_image_formats)),
+    )
+    .map_err(|err| BenchError::FailedToReadInputDirectory(input.clone(), err))?;
+
+    if pictures.is_empty() {
+        return Err(BenchError::NoPictureFound(input));
+    }
+
+    deter::natural_sort_by_path(&mut pictures, |picture| picture);
+
+    let sample = bench::pick_sample(&pictures, opts.pages);
+
+    debug!(
+        "Loading {} sample page{} out of {}...",
+        sample.len(),
+        if sample.len() > 1 { "s" } else { "" },
+        pictures.len()
+    );
+
+    let mut pages = vec![];
+
+    for path in sample {
+        let content =
+            fs::read(&path).map_err(|err| BenchError::FailedToReadPicture(path.clone(), err))?;
+
+        pages.push(SamplePage { path, content });
+    }
+
+    let source_size = pages.iter().map(|page| page.content.len() as u64).sum();
+
+    let mut results = vec![];
+
+    for (flags, conversion) in bench::available_options() {
+        debug!("Benchmarking options: {}...", flags);
+
+        results.push(
+            bench::bench_options(flags, &conversion, &pages)
+                .map_err(BenchError::FailedToRunBenchmark)?,
+        );
+    }
+
+    let report = BenchReport {
+        pages: pages.len(),
+        source_size,
+        results,
+    };
+
+    if opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(BenchError::FailedToSerializeReport)?
+        );
+    } else {
+        print_report(&report);
+    }
+
+    Ok(report)
+}
+
+/// Print a benchmark's results in a human-readable form
+fn print_report(report: &BenchReport) {
+    println!(
+        "Sample: {} page{} ({})",
+        report.pages,
+        if report.pages > 1 { "s" } else { "" },
+        deter::format_size(report.source_size)
+    );
+    println!("WebP quality: {}", codec::WEBP_QUALITY);
+    println!();
+    println!(
+        "{:<40} {:>12} {:>8} {:>10} {:>10}",
+        "Options", "Size", "Ratio", "Pages/s", "Time"
+    );
+
+    for result in &report.results {
+        println!(
+            "{:<40} {:>12} {:>8} {:>10.1} {:>8.3} s",
+            result.flags,
+            deter::format_size(result.output_size),
+            if report.source_size > 0 {
+                format!(
+                    "{:.1}%",
+                    result.output_size as f64 * 100.0 / report.source_size as f64
+                )
+            } else {
+                "-".to_owned()
+            },
+            result.pages_per_second,
+            result.seconds
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use crate::cli::error::CleanError;
+use crate::cli::opts::Clean;
+use crate::lib::archive::STAGING_EXT;
+use crate::lib::deter;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Remove the staging files left by interrupted runs
+pub fn clean(opts: &Clean) -> Result<Vec<PathBuf>, CleanError> {
+    let cwd = env::current_dir().map_err(CleanError::FailedToGetCWD)?;
+
+    let mut staging_files = vec![];
+
+    for dir in &opts.dirs {
+        let dir = cwd.join(dir);
+
+        if !dir.is_dir() {
+            return Err(CleanError::DirectoryNotFound(dir));
+        }
+
+        trace!(
+            "Looking for staging files in '{}'...",
+            dir.to_string_lossy()
+        );
+
+        staging_files.extend(
+            deter::readdir_files_recursive(
+                &dir,
+                Some(&|path: &Path| {
+                    path.extension()
+                        .map(|ext| ext == STAGING_EXT)
+                        .unwrap_or(false)
+                }),
+            )
+            .map_err(|err| CleanError::FailedToListFiles(dir.clone(), err))?,
+        );
+    }
+
+    if staging_files.is_empty() {
+        info!("No staging file found.");
+        return Ok(vec![]);
+    }
+
+    if opts.dry_run {
+        for path in &staging_files {
+            println!("{}", path.to_string_lossy());
+        }
+
+        info!(
+            "Found {} staging file{} (nothing was removed).",
+            staging_files.len(),
+            if staging_files.len() > 1 { "s" } else { "" }
+        );
+
+        return Ok(staging_files);
+    }
+
+    let mut removed = vec![];
+
+    for path in &staging_files {
+        match fs::remove_file(path) {
+            Ok(()) => {
+                debug!("Removed staging file '{}'.", path.to_string_lossy());
+                removed.push(path.clone());
+            }
+
+            Err(err) => error!(
+                "Failed to remove staging file '{}': {}",
+                path.to_string_lossy(),
+                err
+            ),
+        }
+    }
+
+    if removed.len() < staging_files.len() {
+        return Err(CleanError::FailedToRemoveFiles(
+            staging_files.len() - removed.len(),
+            staging_files.len(),
+        ));
+    }
+
+    info!(
+        "Removed {} staging file{}.",
+        removed.len(),
+        if removed.len() > 1 { "s" } else { "" }
+    );
+
+    Ok(removed)
+}
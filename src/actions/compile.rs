@@ -1,34 +1,86 @@
-use crate::cli::error::EncodingError;
-use crate::cli::opts::{CompilationMethod, CompilationOptions, EncodingOptions};
+use crate::cli::error::{error_chain, EncodingError};
+use crate::cli::opts::{
+    CompilationMethod, CompilationOptions, DuplicateChapterPolicy, EncodingOptions,
+    InvalidNamePolicy,
+};
 use crate::lib::build_vol::*;
-use crate::lib::deter;
+use crate::lib::cache::{BuildCache, CachedVolume, CACHE_FILENAME};
+use crate::lib::cancel::{self, CancellationToken};
+use crate::lib::chapter::{ChapterDetector, ChapterNumber};
+use crate::lib::deter::{self, RecursiveFilesSearchErr};
+use crate::lib::library_index::Library;
+use crate::lib::plan::{
+    CompilationPlan, CompilationProgress, CompletedVolume, PlannedChapter, PlannedVolume,
+    PROGRESS_FILENAME,
+};
+use crate::lib::progress::{CliProgress, ProgressSink, ReportingProgress};
+use crate::lib::progress_bars;
+use crate::lib::source::PageSource;
+use crate::lib::summary::JobReport;
+use crate::lib::volume_map::VolumeMap;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use std::cmp::Ordering;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
 
-/// Compile directories to volumes
-pub fn compile(
+/// Determine the volumes to build from the chapter directories, without writing anything
+/// Chapters are read from the provided source, which is opened from the input path
+pub fn plan_compilation(
     opts: &CompilationOptions,
     enc_opts: &EncodingOptions,
-) -> Result<Vec<PathBuf>, EncodingError> {
-    // Get the number of chapters to put in each volume
-    let chap_per_vol = match &opts.method {
-        CompilationMethod::Ranges(opts) => opts.chapters_per_volume,
-        CompilationMethod::Each(_) => 1,
+    source: &dyn PageSource,
+) -> Result<CompilationPlan, EncodingError> {
+    // Get the maximum number of chapters, pages and bytes to put in each volume
+    let (chap_per_vol, max_pages, max_size, volume_map) = match &opts.method {
+        CompilationMethod::Ranges(opts) => (
+            opts.chapters_per_volume.map(usize::from),
+            opts.max_pages_per_volume,
+            opts.max_size_per_volume,
+            opts.volume_map.as_ref(),
+        ),
+        CompilationMethod::Each(_) => (Some(1), None, None, None),
     };
 
-    if chap_per_vol == 0 {
+    if chap_per_vol == Some(0) {
         return Err(EncodingError::AtLeast1ChapterPerVolume);
     }
 
+    if max_pages == Some(0) {
+        return Err(EncodingError::InvalidMaxPagesPerVolume);
+    }
+
+    if max_size == Some(0) {
+        return Err(EncodingError::InvalidMaxSizePerVolume);
+    }
+
+    let max_size = max_size.map(|max_size| max_size * 1024 * 1024);
+
+    let volume_map = volume_map
+        .map(|path| {
+            VolumeMap::load(path).map_err(|err| EncodingError::FailedToReadVolumeMap(path.clone(), err))
+        })
+        .transpose()?;
+
+    // Number of the first volume to build
+    let first_volume = opts.start_volume.unwrap_or(1);
+
+    if first_volume == 0 {
+        return Err(EncodingError::InvalidStartVolume);
+    }
+
     if let Some(start_chapter) = opts.start_chapter {
-        if start_chapter == 0 {
+        if start_chapter == ChapterNumber::new(0) {
             return Err(EncodingError::InvalidStartChapter);
         }
     }
 
     if let Some(end_chapter) = opts.end_chapter {
-        if end_chapter == 0 {
+        if end_chapter == ChapterNumber::new(0) {
             return Err(EncodingError::InvalidEndChapter);
         }
     }
@@ -39,34 +91,40 @@ pub fn compile(
         }
     }
 
+    if let Some(regex) = &opts.chapter_regex {
+        if !regex.capture_names().any(|name| name == Some("num")) {
+            return Err(EncodingError::ChapterRegexHasNoNumGroup);
+        }
+    }
+
     // Get current directory
     let cwd = env::current_dir().map_err(EncodingError::FailedToGetCWD)?;
 
-    let input_dir = cwd.join(&enc_opts.input);
-
-    if !input_dir.is_dir() {
-        return Err(EncodingError::ChaptersDirectoryNotFound);
-    }
-
-    // Create the output directory if needed, and get the output path
+    // Get the output path (the directory itself is created when building)
     let output = match &enc_opts.output {
         Some(output) => {
             let output = cwd.join(output);
 
-            if !output.is_dir() {
-                if opts.create_output_dir {
-                    fs::create_dir_all(&output)
-                        .map_err(EncodingError::FailedToCreateOutputDirectory)?
-                } else {
-                    return Err(EncodingError::OutputDirectoryNotFound);
-                }
+            if !output.is_dir() && !opts.create_output_dir {
+                return Err(EncodingError::OutputDirectoryNotFound);
             }
 
             output
         }
 
-        // Output directory = input directory
-        None => input_dir.clone(),
+        // Output directory = input directory (or the directory containing the input archive)
+        None if source.root().is_dir() => source.root().to_path_buf(),
+        None => source
+            .root()
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| cwd.clone()),
+    };
+
+    // Volumes arranged for a server's library are put in their series' directory
+    let output = match enc_opts.layout {
+        Some(_) => output.join(deter::sanitize_filename(&enc_opts.series().unwrap_or_default())),
+        None => output,
     };
 
     // List of chapter directories
@@ -74,28 +132,71 @@ pub fn compile(
 
     trace!("Reading chapter directories...");
 
-    // Iterate over all items in the input directory
-    for entry in fs::read_dir(input_dir).map_err(EncodingError::FailedToReadChaptersDirectory)? {
-        let entry = entry.map_err(EncodingError::FailedToReadChaptersDirectory)?;
-        let path = entry.path();
+    // Iterate over all directories of the input
+    let input_dirs = source.chapters().map_err(|err| match err {
+        RecursiveFilesSearchErr::IOError(err) => EncodingError::FailedToReadChaptersDirectory(err),
+        RecursiveFilesSearchErr::InvalidFileName(path) => EncodingError::ItemHasInvalidUTF8Name(
+            path.file_name().unwrap_or(path.as_os_str()).to_os_string(),
+        ),
+    })?;
 
-        // Ignore files
-        if path.is_dir() {
-            let entry_name = entry
-                .file_name()
-                .into_string()
-                .map_err(|_| EncodingError::ItemHasInvalidUTF8Name(entry.file_name()))?;
+    for (path, entry_name) in input_dirs {
+        if !deter::has_utf8_name(&path) {
+            match enc_opts.on_invalid_name {
+                InvalidNamePolicy::Rename => {
+                    warn!("Warning: chapter directory '{}' has a name that is not valid UTF-8", entry_name)
+                }
 
-            // Ignore directories not starting by the provided prefix
-            if opts
-                .dirs_prefix
-                .as_ref()
-                .map(|prefix| entry_name.starts_with(prefix))
-                .unwrap_or(true)
-            {
-                chapter_dirs.push((path, entry_name));
+                InvalidNamePolicy::Error => {
+                    return Err(EncodingError::ItemHasInvalidUTF8Name(
+                        path.file_name().unwrap_or(path.as_os_str()).to_os_string(),
+                    ))
+                }
+
+                InvalidNamePolicy::Skip => {
+                    warn!("Warning: ignoring chapter directory '{}' as its name is not valid UTF-8", entry_name);
+                    continue;
+                }
             }
         }
+
+        // Ignore directories not starting by the provided prefix
+        if !opts
+            .dirs_prefix
+            .as_ref()
+            .map(|prefix| entry_name.starts_with(prefix))
+            .unwrap_or(true)
+        {
+            continue;
+        }
+
+        // Ignore directories filtered out by the inclusion and exclusion patterns
+        if !deter::matches_patterns(&entry_name, &opts.include, &opts.exclude) {
+            debug!("Ignoring directory '{}' as it is filtered out by the patterns", entry_name);
+            continue;
+        }
+
+        // Volumes built inside the input directory must not be read as a chapter by the next runs,
+        // but a chapter with pictures of its own can't be ignored
+        if deter::is_inside(&output, &path) {
+            let has_pictures = source
+                .files(&path)
+                .map(|files| {
+                    files.iter().any(|file| {
+                        !deter::is_inside(file, &output) && is_picture(source, enc_opts, file)
+                    })
+                })
+                .unwrap_or(false);
+
+            if has_pictures {
+                return Err(EncodingError::OutputDirectoryInsideChapter(path));
+            }
+
+            info!("Ignoring directory '{}' as it contains the output directory", entry_name);
+            continue;
+        }
+
+        chapter_dirs.push((path, entry_name));
     }
 
     trace!("Sorting chapter directories by name...");
@@ -103,112 +204,986 @@ pub fn compile(
     if enc_opts.simple_sorting {
         chapter_dirs.sort_by(|a, b| a.0.cmp(&b.0));
     } else {
-        chapter_dirs.sort_by(|a, b| deter::natural_paths_cmp(&a.0, &b.0));
+        deter::natural_sort_by_path(&mut chapter_dirs, |dir| &dir.0);
     }
 
     // Disable mutability for this variable
     let chapter_dirs = chapter_dirs;
 
-    // Current volume
-    let mut volume = 1;
+    // Number of volumes to make, before considering start and end chapter
+    // It is used to determine the number of digits volumes should be displayed with
+    // When volumes are cut depending on their content or a volume map, it is determined once the volumes are known instead
+    let untrimmed_volumes = deter::ceil_div(chapter_dirs.len(), chap_per_vol.unwrap_or(1));
+    let vol_num_len = (first_volume - 1 + untrimmed_volumes).to_string().len();
 
-    // List of chapter directories of the current volume
-    let mut volume_chapters = vec![];
+    let total_chapters = chapter_dirs.len();
 
-    // First chapter of current volume
-    let mut volume_start_chapter = 1;
+    // Chapters numbered in order continue the numbering of a previous compilation
+    let chapter_offset = opts.chapter_offset.unwrap_or(0);
 
-    // Number of volumes to make, before considering start and end chapter
-    // It is used to determine the number of digits volumes should be displayed with
-    let untrimmed_volumes = deter::ceil_div(chapter_dirs.len(), chap_per_vol.into());
-    let vol_num_len = untrimmed_volumes.to_string().len();
+    // Are the chapters numbered from their directory's name?
+    let numbered_from_names = opts.chapter_regex.is_some() || opts.detect_chapter_numbers;
+
+    // Number the chapters, either from their directory's name or in order
+    let chapters: Vec<PlannedChapter> = if numbered_from_names {
+        let detector = ChapterDetector::new(opts.detect_word_numbers);
+
+        let number_of = |name: &str| match &opts.chapter_regex {
+            Some(regex) => regex
+                .captures(name)
+                .and_then(|captures| captures.name("num"))
+                .and_then(|num| num.as_str().parse::<ChapterNumber>().ok()),
+            None => detector.detect(name),
+        };
+
+        let mut chapters =
+            number_chapters_from_names(chapter_dirs, number_of, opts.on_duplicate_chapter, source)?;
+
+        chapters.retain(|chapter| {
+            opts.start_chapter.map(|start| chapter.number >= start).unwrap_or(true)
+                && opts.end_chapter.map(|end| chapter.number <= end).unwrap_or(true)
+        });
+
+        chapters
+    } else {
+        // Chapters are numbered in order, so only the main part of the start and end chapters is meaningful
+        let start_chapter = opts.start_chapter.map(|chapter| chapter.main).unwrap_or(1) - 1;
+
+        let end_chapter = opts.end_chapter.map(|chapter| chapter.main).unwrap_or(total_chapters);
+
+        // End chapter cannot exceed the number of existing chapter directories minus the start chapter
+        let end_chapter = std::cmp::min(end_chapter, total_chapters.saturating_sub(start_chapter));
+
+        chapter_dirs
+            .into_iter()
+            .skip(start_chapter)
+            .take(end_chapter.saturating_sub(start_chapter))
+            .enumerate()
+            .map(|(chapter, (path, name))| PlannedChapter {
+                number: ChapterNumber::new(chapter_offset + chapter + 1),
+                path,
+                name,
+            })
+            .collect()
+    };
 
     // Determine the number of digits for chapters
-    let chapter_num_len = chapter_dirs.len().to_string().len();
+    let chapter_num_len = if numbered_from_names {
+        chapters
+            .iter()
+            .map(|chapter| chapter.number.main.to_string().len())
+            .max()
+            .unwrap_or(1)
+    } else {
+        (chapter_offset + total_chapters).to_string().len()
+    };
 
-    let start_chapter = opts.start_chapter.unwrap_or(1) - 1;
+    let mut plan = CompilationPlan {
+        output,
+        vol_num_len,
+        chapter_num_len,
+        volumes: vec![],
+    };
 
-    let end_chapter = opts.end_chapter.unwrap_or(chapter_dirs.len());
+    let (first_chapter, last_chapter) = match (chapters.first(), chapters.last()) {
+        (Some(first), Some(last)) => (first.number, last.number),
+        _ => return Ok(plan),
+    };
 
-    // End chapter cannot exceed the number of existing chapter directories minus the start chapter
-    let end_chapter = std::cmp::min(end_chapter, chapter_dirs.len() - start_chapter);
+    // Report the chapters that would give incomplete volumes
+    let mut empty_chapters = 0;
 
-    if end_chapter == 0 {
-        warn!("No chapter found. Nothing to do.");
-        return Ok(vec![]);
+    for chapter in &chapters {
+        if chapter_pictures(enc_opts, source, &chapter.path)?.is_empty() {
+            warn!(
+                "Warning: chapter {} ('{}') doesn't contain any image",
+                chapter.number, chapter.name
+            );
+            empty_chapters += 1;
+        }
     }
 
-    // Determine the real number of chapters to encode
-    let chapter_len = end_chapter - start_chapter;
+    // Chapters numbered in order can't have gaps
+    let missing_chapters = if numbered_from_names {
+        find_missing_chapters(&chapters)
+    } else {
+        vec![]
+    };
 
-    // Determine the real number of volumes to create
-    let volumes = deter::ceil_div(chapter_len, chap_per_vol.into());
+    for &(first, last) in &missing_chapters {
+        if first == last {
+            warn!("Warning: chapter {} is missing", first);
+        } else {
+            warn!("Warning: chapters {} to {} are missing", first, last);
+        }
+    }
+
+    if opts.fail_on_gaps && (empty_chapters > 0 || !missing_chapters.is_empty()) {
+        return Err(EncodingError::FoundChapterGaps {
+            missing: missing_chapters
+                .iter()
+                .map(|(first, last)| last - first + 1)
+                .sum(),
+            empty: empty_chapters,
+        });
+    }
+
+    // Determine the real number of chapters to encode
+    let chapter_len = chapters.len();
 
     info!(
-        "Going to treat chapter{} {} to {} ({} out of {}, {} to ignore) into {} volume{}.",
+        "Going to treat chapter{} {} to {} ({} out of {}, {} to ignore).",
         if chapter_len > 0 { "s" } else { "" },
-        start_chapter + 1,
-        end_chapter,
+        first_chapter,
+        last_chapter,
         chapter_len,
-        chapter_dirs.len(),
-        chapter_dirs.len() - chapter_len,
-        volumes,
-        if volumes > 1 { "s" } else { "" }
+        total_chapters,
+        total_chapters - chapter_len
     );
 
     trace!("Building chapters list for all volumes...");
 
+    match &volume_map {
+        // Volumes are explicitly delimited by the volume map
+        Some(map) => {
+            plan.volumes = assign_volumes_with_map(map, chapters);
+            plan.vol_num_len = map.last_volume().to_string().len();
+        }
+
+        None => {
+            plan.volumes = assign_volumes_with_limits(
+                enc_opts,
+                source,
+                chapters,
+                chap_per_vol,
+                max_pages,
+                max_size,
+            )?;
+
+            // Continue the numbering of a previous compilation
+            for planned in plan.volumes.iter_mut() {
+                planned.volume += first_volume - 1;
+            }
+
+            // Volumes cut depending on their content can't be counted beforehand
+            if max_pages.is_some() || max_size.is_some() {
+                plan.vol_num_len = (first_volume - 1 + plan.volumes.len()).to_string().len();
+            }
+        }
+    }
+
+    // Only build the volumes containing the selected chapters, without changing their content
+    if let Some(selection) = &opts.only_chapters {
+        plan.volumes.retain(|planned| {
+            planned
+                .chapters
+                .iter()
+                .any(|chapter| selection.contains(chapter.number))
+        });
+    }
+
+    info!(
+        "Going to build {} volume{}.",
+        plan.volumes.len(),
+        if plan.volumes.len() > 1 { "s" } else { "" }
+    );
+
+    Ok(plan)
+}
+
+/// Put chapters in volumes, starting a new volume when the current one contains enough chapters, or when the next
+/// chapter would make it exceed the maximum number of pages or size (in bytes)
+/// A chapter exceeding these limits by itself gets its own volume
+fn assign_volumes_with_limits(
+    enc_opts: &EncodingOptions,
+    source: &dyn PageSource,
+    chapters: Vec<PlannedChapter>,
+    chap_per_vol: Option<usize>,
+    max_pages: Option<usize>,
+    max_size: Option<u64>,
+) -> Result<Vec<PlannedVolume>, EncodingError> {
+    let measure_chapters = max_pages.is_some() || max_size.is_some();
+
+    let mut volumes: Vec<PlannedVolume> = vec![];
+
+    // Number of chapters, pages and bytes in the current volume
+    let mut volume_chapters = 0;
+    let mut volume_pages = 0;
+    let mut volume_size = 0;
+
+    for chapter in chapters {
+        let (pages, size) = if measure_chapters {
+            measure_chapter(enc_opts, source, &chapter.path)?
+        } else {
+            (0, 0)
+        };
+
+        match volumes.last_mut() {
+            // Directories merged into a single chapter are always put in the same volume
+            Some(planned)
+                if planned.chapters.last().map(|last| last.number) == Some(chapter.number) =>
+            {
+                planned.chapters.push(chapter);
+                volume_pages += pages;
+                volume_size += size;
+            }
+
+            Some(planned)
+                if chap_per_vol.map_or(true, |max| volume_chapters < max)
+                    && max_pages.map_or(true, |max| volume_pages + pages <= max)
+                    && max_size.map_or(true, |max| volume_size + size <= max) =>
+            {
+                planned.chapters.push(chapter);
+                volume_chapters += 1;
+                volume_pages += pages;
+                volume_size += size;
+            }
+
+            _ => {
+                if max_pages.map_or(false, |max| pages > max) || max_size.map_or(false, |max| size > max) {
+                    warn!(
+                        "Warning: chapter {} ('{}') exceeds the maximum size of a volume by itself",
+                        chapter.number, chapter.name
+                    );
+                }
+
+                volumes.push(PlannedVolume {
+                    volume: volumes.len() + 1,
+                    chapters: vec![chapter],
+                });
+
+                volume_chapters = 1;
+                volume_pages = pages;
+                volume_size = size;
+            }
+        }
+    }
+
+    Ok(volumes)
+}
+
+/// Put each chapter in the volume it belongs to according to a volume map
+/// Chapters preceding the first volume are ignored
+fn assign_volumes_with_map(map: &VolumeMap, chapters: Vec<PlannedChapter>) -> Vec<PlannedVolume> {
+    let mut volumes: Vec<PlannedVolume> = vec![];
+
+    for chapter in chapters {
+        let volume = match map.volume_of(chapter.number) {
+            Some(volume) => volume,
+            None => {
+                warn!(
+                    "Warning: ignoring chapter {} ('{}') as it precedes the first volume of the volume map",
+                    chapter.number, chapter.name
+                );
+                continue;
+            }
+        };
+
+        // Chapters are sorted, so the chapters of a volume follow each other
+        match volumes.last_mut() {
+            Some(planned) if planned.volume == volume => planned.chapters.push(chapter),
+            _ => volumes.push(PlannedVolume {
+                volume,
+                chapters: vec![chapter],
+            }),
+        }
+    }
+
+    volumes
+}
+
+/// Find the regular chapters missing from a list of chapters sorted by number, as ranges of main numbers
+/// Sub-chapters and extra chapters are not expected to follow each other, so they can't be missing
+fn find_missing_chapters(chapters: &[PlannedChapter]) -> Vec<(usize, usize)> {
+    let mut missing = vec![];
+    let mut previous: Option<usize> = None;
+
+    for chapter in chapters.iter().filter(|chapter| !chapter.number.extra) {
+        if let Some(previous) = previous {
+            if chapter.number.main > previous + 1 {
+                missing.push((previous + 1, chapter.number.main - 1));
+            }
+        }
+
+        previous = Some(chapter.number.main);
+    }
+
+    missing
+}
+
+/// List the pictures of a chapter that will be put in its volume
+fn chapter_pictures(
+    enc_opts: &EncodingOptions,
+    source: &dyn PageSource,
+    path: &Path,
+) -> Result<Vec<PathBuf>, EncodingError> {
+    let mut pictures = source
+        .files(path)
+        .map_err(|err| EncodingError::FailedToMeasureChapter(path.to_path_buf(), err))?;
+
+    pictures.retain(|picture| is_picture(source, enc_opts, picture));
+
+    if enc_opts.on_invalid_name == InvalidNamePolicy::Skip {
+        pictures.retain(|picture| deter::has_utf8_name(picture));
+    }
+
+    Ok(pictures)
+}
+
+/// Get the number of pages of a chapter, and their total size in bytes
+fn measure_chapter(
+    enc_opts: &EncodingOptions,
+    source: &dyn PageSource,
+    path: &Path,
+) -> Result<(usize, u64), EncodingError> {
+    let pictures = chapter_pictures(enc_opts, source, path)?;
+
+    let size = pictures
+        .iter()
+        .map(|picture| source.size(picture))
+        .sum::<Result<u64, _>>()
+        .map_err(|err| {
+            EncodingError::FailedToMeasureChapter(path.to_path_buf(), RecursiveFilesSearchErr::IOError(err))
+        })?;
+
+    Ok((pictures.len(), size))
+}
+
+/// Number chapters from their directory's name (using the 'num' group of '--chapter-regex' or the detection heuristics)
+/// Directories whose name doesn't contain a valid number are ignored, and chapters are sorted by number (sub-chapters
+/// like '10.5' right after their main chapter, and extra chapters like 'Extra 3' after all regular ones)
+/// Directories with the same number are handled depending on the provided policy
+fn number_chapters_from_names(
+    chapter_dirs: Vec<(PathBuf, String)>,
+    number_of: impl Fn(&str) -> Option<ChapterNumber>,
+    on_duplicate: DuplicateChapterPolicy,
+    source: &dyn PageSource,
+) -> Result<Vec<PlannedChapter>, EncodingError> {
+    let modified = |path: &Path| {
+        source
+            .modified(path)
+            .map_err(|err| EncodingError::FailedToGetChapterModificationDate(path.to_path_buf(), err))
+    };
+
+    let mut chapters: Vec<PlannedChapter> = vec![];
+
+    for (path, name) in chapter_dirs {
+        let number = match number_of(&name) {
+            Some(number) => number,
+            None => {
+                warn!(
+                    "Warning: ignoring directory '{}' as no chapter number was found in its name",
+                    name
+                );
+                continue;
+            }
+        };
+
+        let existing = match chapters.iter().position(|chapter| chapter.number == number) {
+            Some(existing) => existing,
+            None => {
+                chapters.push(PlannedChapter { number, path, name });
+                continue;
+            }
+        };
+
+        match on_duplicate {
+            DuplicateChapterPolicy::Newest => match modified(&path)?.cmp(&modified(&chapters[existing].path)?) {
+                Ordering::Greater => {
+                    warn!(
+                        "Warning: ignoring directory '{}' as chapter {} is also in more recent directory '{}'",
+                        chapters[existing].name, number, name
+                    );
+
+                    chapters[existing] = PlannedChapter { number, path, name };
+                }
+
+                Ordering::Less => warn!(
+                    "Warning: ignoring directory '{}' as chapter {} is also in more recent directory '{}'",
+                    name, number, chapters[existing].name
+                ),
+
+                // Directories are sorted by name, so ties always keep the first one
+                Ordering::Equal => warn!(
+                    "Warning: ignoring directory '{}' as chapter {} is also in directory '{}', which has the same modification date",
+                    name, number, chapters[existing].name
+                ),
+            },
+
+            DuplicateChapterPolicy::First => warn!(
+                "Warning: ignoring directory '{}' as chapter {} is already in directory '{}'",
+                name, number, chapters[existing].name
+            ),
+
+            DuplicateChapterPolicy::Error => {
+                return Err(EncodingError::DuplicateChapter(
+                    number,
+                    chapters[existing].path.clone(),
+                    path,
+                ))
+            }
+
+            DuplicateChapterPolicy::Merge => {
+                info!(
+                    "Merging directory '{}' into chapter {} (from directory '{}')",
+                    name, number, chapters[existing].name
+                );
+
+                chapters.push(PlannedChapter { number, path, name });
+            }
+        }
+    }
+
+    // The sort is stable, so chapters with the same number keep their natural order
+    chapters.sort_by_key(|chapter| chapter.number);
+
+    Ok(chapters)
+}
+
+/// Print the plan and ask for confirmation before building it, allowing to exclude some chapters first
+/// Returns `false` if the compilation was cancelled
+fn confirm_plan(plan: &mut CompilationPlan) -> Result<bool, EncodingError> {
+    let stdin = io::stdin();
+
+    loop {
+        for planned in &plan.volumes {
+            println!(
+                "Volume {:0vol_num_len$}: {} chapter{}",
+                planned.volume,
+                planned.chapters.len(),
+                if planned.chapters.len() > 1 { "s" } else { "" },
+                vol_num_len = plan.vol_num_len
+            );
+
+            for chapter in &planned.chapters {
+                println!(
+                    "    Chapter {:0chapter_num_len$}: '{}'",
+                    chapter.number,
+                    chapter.name,
+                    chapter_num_len = plan.chapter_num_len
+                );
+            }
+        }
+
+        print!(
+            "Build {} volume{}? [y]es, [n]o or [e]xclude chapters (e.g. 'e 3, 10.5, Extra 1'): ",
+            plan.volumes.len(),
+            if plan.volumes.len() > 1 { "s" } else { "" }
+        );
+
+        io::stdout().flush().map_err(EncodingError::FailedToReadConfirmation)?;
+
+        let mut answer = String::new();
+
+        // Consider the end of the input as a refusal
+        if stdin.lock().read_line(&mut answer).map_err(EncodingError::FailedToReadConfirmation)? == 0 {
+            println!();
+            return Ok(false);
+        }
+
+        let answer = answer.trim();
+        let (command, chapters) = answer.split_once(char::is_whitespace).unwrap_or((answer, ""));
+
+        match command.to_lowercase().as_str() {
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            "e" | "exclude" => {
+                for entry in chapters.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+                    let number = match entry.parse::<ChapterNumber>() {
+                        Ok(number) => number,
+                        Err(err) => {
+                            warn!("{}", err);
+                            continue;
+                        }
+                    };
+
+                    let mut found = false;
+
+                    for planned in plan.volumes.iter_mut() {
+                        let count = planned.chapters.len();
+                        planned.chapters.retain(|chapter| chapter.number != number);
+                        found = found || planned.chapters.len() != count;
+                    }
+
+                    if !found {
+                        warn!("Warning: chapter {} is not part of the plan", number);
+                    }
+                }
+
+                // Volumes left without any chapter are not built
+                plan.volumes.retain(|planned| !planned.chapters.is_empty());
+
+                if plan.volumes.is_empty() {
+                    warn!("All chapters were excluded.");
+                    return Ok(false);
+                }
+            }
+            _ => warn!("Please answer 'y', 'n' or 'e' followed by the chapters to exclude."),
+        }
+    }
+}
+
+/// Compile each sub-directory of the input directory as an independent series, in its own output sub-directory
+fn compile_each_series(
+    opts: &CompilationOptions,
+    enc_opts: &EncodingOptions,
+    sink: &dyn ProgressSink,
+    cancel: &CancellationToken,
+) -> Result<JobReport, EncodingError> {
+    let cwd = env::current_dir().map_err(EncodingError::FailedToGetCWD)?;
+
+    let input_dir = cwd.join(&enc_opts.input);
+
+    if !input_dir.is_dir() {
+        return Err(EncodingError::ChaptersDirectoryNotFound);
+    }
+
+    let output_dir = enc_opts.output.as_ref().map(|output| cwd.join(output));
+
+    if let Some(output_dir) = &output_dir {
+        if !output_dir.is_dir() && !opts.create_output_dir {
+            return Err(EncodingError::OutputDirectoryNotFound);
+        }
+    }
+
+    let mut series_dirs: Vec<(PathBuf, String)> = vec![];
+
+    for entry in fs::read_dir(&input_dir).map_err(EncodingError::FailedToReadChaptersDirectory)? {
+        let entry = entry.map_err(EncodingError::FailedToReadChaptersDirectory)?;
+        let path = entry.path();
+
+        if path.is_dir() && (enc_opts.no_default_ignores || !deter::is_os_junk(&path)) {
+            let entry_name = entry
+                .file_name()
+                .into_string()
+                .map_err(|_| EncodingError::ItemHasInvalidUTF8Name(entry.file_name()))?;
+
+            // Volumes built inside the input directory must not be read as a series by the next runs
+            if let Some(output_dir) = &output_dir {
+                if deter::is_inside(output_dir, &path) {
+                    info!("Ignoring directory '{}' as it contains the output directory", entry_name);
+                    continue;
+                }
+            }
+
+            series_dirs.push((path, entry_name));
+        }
+    }
+
+    deter::natural_sort_by_path(&mut series_dirs, |dir| &dir.0);
+
+    if series_dirs.is_empty() {
+        warn!("No series directory found. Nothing to do.");
+        return Ok(JobReport::default());
+    }
+
+    let mut series_opts = opts.clone();
+    series_opts.group_by_subdir = false;
+
+    // The output directory was checked above, each series' sub-directory is created as needed
+    series_opts.create_output_dir = true;
+
+    let mut report = JobReport::default();
+
+    for (i, (path, name)) in series_dirs.iter().enumerate() {
+        info!("Compiling series {}/{}: '{}'...", i + 1, series_dirs.len(), name);
+
+        // The series is named after its directory, and its volumes are put in a sub-directory with the same name
+        // (which a library layout already does)
+        let mut series_enc_opts = enc_opts.clone();
+        series_enc_opts.input = path.clone();
+        series_enc_opts.output = match enc_opts.layout {
+            Some(_) => output_dir.clone(),
+            None => output_dir.as_ref().map(|output_dir| output_dir.join(name)),
+        };
+
+        report.merge(compile_volumes(&series_opts, &series_enc_opts, sink, cancel)?);
+    }
+
+    Ok(report)
+}
+
+/// Compile directories to volumes
+pub fn compile(
+    opts: &CompilationOptions,
+    enc_opts: &EncodingOptions,
+) -> Result<JobReport, EncodingError> {
+    compile_volumes(opts, enc_opts, &CliProgress, &cancel::interrupt_token())
+}
+
+/// Compile directories to volumes, returning the report of the produced volumes
+/// The progress of the volumes being built is reported to the provided sink, and the compilation stops between two pages
+/// when the provided token is cancelled
+pub fn compile_volumes(
+    opts: &CompilationOptions,
+    enc_opts: &EncodingOptions,
+    sink: &dyn ProgressSink,
+    cancel: &CancellationToken,
+) -> Result<JobReport, EncodingError> {
+    if opts.group_by_subdir {
+        return compile_each_series(opts, enc_opts, sink, cancel);
+    }
+
+    let started = Instant::now();
+
+    // Keep the warnings for the report
+    let reporting = ReportingProgress::new(sink);
+    let sink: &dyn ProgressSink = &reporting;
+
+    // Open the chapters' directory or archive (the latter being kept open while the volumes are built)
+    let cwd = env::current_dir().map_err(EncodingError::FailedToGetCWD)?;
+    let source = open_input(&cwd.join(&enc_opts.input), enc_opts)?;
+
+    let mut plan = match &opts.from_plan {
+        Some(path) => CompilationPlan::load(path)
+            .map_err(|err| EncodingError::FailedToReadPlan(path.clone(), err))?,
+        None => plan_compilation(opts, enc_opts, source.as_ref())?,
+    };
+
+    if opts.interactive && !plan.volumes.is_empty() && !confirm_plan(&mut plan)? {
+        info!("Compilation cancelled.");
+        return Ok(JobReport::default());
+    }
+
+    // Disable mutability for this variable
+    let plan = plan;
+
+    if let Some(path) = &opts.emit_plan {
+        plan.save(path)
+            .map_err(|err| EncodingError::FailedToWritePlan(path.clone(), err))?;
+
+        info!(
+            "Written plan of {} volume{} to '{}'.",
+            plan.volumes.len(),
+            if plan.volumes.len() > 1 { "s" } else { "" },
+            path.to_string_lossy()
+        );
+
+        return Ok(JobReport::default());
+    }
+
+    if plan.volumes.is_empty() {
+        warn!("No chapter found. Nothing to do.");
+        return Ok(JobReport::default());
+    }
+
+    // Load the volumes' metadata
+    let volumes_metadata = load_volumes_metadata(enc_opts)?;
+
+    let pool = build_pages_pool(enc_opts)?;
+
     // Generate the build method
     let build_method = match &opts.method {
         CompilationMethod::Ranges(sub_opts) => BuildMethod::Ranges(sub_opts, opts),
         CompilationMethod::Each(sub_opts) => BuildMethod::Each(sub_opts, opts),
     };
 
+    // Individual chapters' volumes must contain exactly one chapter (possibly made of merged directories),
+    // even in edited plans
+    if let BuildMethod::Each(_, _) = build_method {
+        if let Some(planned) = plan.volumes.iter().find(|planned| {
+            planned.chapters.is_empty()
+                || planned
+                    .chapters
+                    .iter()
+                    .any(|chapter| chapter.number != planned.start_chapter())
+        }) {
+            return Err(EncodingError::IndividualVolumeMustContainOneChapter(
+                planned.volume,
+            ));
+        }
+    }
+
+    // Chapters of each volume, as expected by the volumes builder
+    let volumes_chapters: Vec<Vec<_>> = plan
+        .volumes
+        .iter()
+        .map(|planned| {
+            planned
+                .chapters
+                .iter()
+                .map(PlannedChapter::to_tuple)
+                .collect()
+        })
+        .collect();
+
+    let volumes_args: Vec<_> = plan
+        .volumes
+        .iter()
+        .zip(volumes_chapters.iter())
+        .map(|(planned, chapters)| BuildVolumeArgs {
+            method: &build_method,
+            enc_opts,
+            source: source.as_ref(),
+            output: &plan.output,
+            volume: planned.volume,
+            volumes: plan.volumes.len(),
+            vol_num_len: plan.vol_num_len,
+            chapter_num_len: plan.chapter_num_len,
+            start_chapter: planned.start_chapter(),
+            chapters,
+            metadata: &volumes_metadata,
+            pool: &pool,
+            progress: sink,
+            cancel,
+        })
+        .collect();
+
+    if enc_opts.dry_run {
+        if !plan.output.is_dir() {
+            println!(
+                "Output directory '{}' would be created.",
+                plan.output.to_string_lossy()
+            );
+        }
+
+        print_volumes_plan(&volumes_args, true)?;
+
+        info!("Dry run: nothing was written.");
+        return Ok(JobReport::default());
+    }
+
+    if enc_opts.verbose_plan {
+        print_volumes_plan(&volumes_args, false)?;
+    }
+
+    // Create the output directory if needed (the series' directory of a library layout is always created)
+    if !plan.output.is_dir() {
+        if !opts.create_output_dir && enc_opts.layout.is_none() {
+            return Err(EncodingError::OutputDirectoryNotFound);
+        }
+
+        fs::create_dir_all(&plan.output).map_err(EncodingError::FailedToCreateOutputDirectory)?;
+    }
+
+    // Resume the compilation if a previous run of the same plan was interrupted
+    let progress = match CompilationProgress::load(&plan.output) {
+        Ok(Some(progress)) if progress.plan == plan => {
+            info!(
+                "Resuming interrupted compilation ({} out of {} volumes already built).",
+                progress.completed.len(),
+                plan.volumes.len()
+            );
+            progress
+        }
+
+        Ok(_) => CompilationProgress::new(plan.clone()),
+
+        Err(err) => {
+            warn!(
+                "Warning: ignoring progress file of a previous compilation: {}",
+                err
+            );
+            CompilationProgress::new(plan.clone())
+        }
+    };
+
+    // Fail before building anything rather than in the middle of a volume
+    if !enc_opts.no_space_check {
+        let remaining_volumes: Vec<_> = volumes_args
+            .iter()
+            .filter(|args| progress.completed_volume(args.volume).is_none())
+            .collect();
+
+        check_available_space(&remaining_volumes, &plan.output)?;
+    }
+
+    let progress_path = plan.output.join(PROGRESS_FILENAME);
+
+    progress
+        .save()
+        .map_err(|err| EncodingError::FailedToWriteProgress(progress_path.clone(), err))?;
+
+    // Volumes are independent, so several of them can be built at once
+    let jobs_pool = ThreadPoolBuilder::new()
+        .num_threads(opts.jobs.unwrap_or(1))
+        .build()
+        .map_err(EncodingError::FailedToCreateThreadPool)?;
+
+    let progress = Mutex::new(progress);
+
+    // Volumes built so far, to summarize the compilation if it gets cancelled
+    let built_volumes = Mutex::new(vec![]);
+
+    // Hashes of the sources of the volumes already built, to skip the ones that haven't changed
+    let cache_path = plan.output.join(CACHE_FILENAME);
+
+    let cache = if opts.incremental {
+        Some(Mutex::new(match BuildCache::load(&plan.output) {
+            Ok(cache) => cache,
+            Err(err) => {
+                warn!("Warning: ignoring invalid cache file: {}", err);
+                BuildCache::default()
+            }
+        }))
+    } else {
+        None
+    };
+
+    // Display the progress of the volumes being built
+    let _progress_bars = progress_bars::start(plan.volumes.len());
+
     // The list of all created volume files
+    let results = jobs_pool.install(|| {
+        let results = volumes_args
+            .into_par_iter()
+            .map(|args| {
+                if cancel.is_cancelled() {
+                    return Err(EncodingError::Cancelled);
+                }
+
+                let completed = progress.lock().unwrap().completed_volume(args.volume).cloned();
+
+                if let Some(path) = completed {
+                    debug!(
+                        "Skipping volume {} as it was built before the compilation got interrupted",
+                        args.volume
+                    );
+                    args.progress.volume_skipped(args.volume, &path);
+                    return existing_volume_summary(&args, &path);
+                }
+
+                // Remove the staging file left by an interrupted build of this volume
+                let staging_path = volume_staging_path(&args);
+
+                if staging_path.is_file() {
+                    debug!(
+                        "Removing staging file '{}' left by an interrupted build...",
+                        staging_path.to_string_lossy()
+                    );
+                    fs::remove_file(&staging_path).map_err(|err| {
+                        EncodingError::FailedToRemoveStagingFile(args.volume, staging_path, err)
+                    })?;
+                }
+
+                let sources = match &cache {
+                    Some(cache) => {
+                        let sources = volume_sources(&args)?;
+
+                        let up_to_date = cache.lock().unwrap().up_to_date(args.volume, &sources).cloned();
+
+                        if let Some(path) = up_to_date {
+                            info!(
+                                "Skipping volume {} as its chapters haven't changed since it was built",
+                                args.volume
+                            );
+                            args.progress.volume_skipped(args.volume, &path);
+                            return existing_volume_summary(&args, &path);
+                        }
+
+                        Some(sources)
+                    }
+
+                    None => None,
+                };
+
+                let built = build_volume(&args)?;
+                let path = &built.path;
+
+                if let (Some(cache), Some(sources)) = (&cache, sources) {
+                    let mut cache = cache.lock().unwrap();
+
+                    let previous = cache.record(CachedVolume {
+                        volume: args.volume,
+                        path: path.clone(),
+                        sources,
+                    });
+
+                    // Remove the previous build of the volume if it had another name (e.g. a different number of pages)
+                    if let Some(previous) = previous.filter(|previous| &previous.path != path && previous.path.is_file()) {
+                        debug!("Removing previous build '{}'...", previous.path.to_string_lossy());
+
+                        if let Err(err) = fs::remove_file(&previous.path) {
+                            warn!("Failed to remove previous build '{}': {}", previous.path.to_string_lossy(), err);
+                        }
+                    }
+
+                    cache
+                        .save(&plan.output)
+                        .map_err(|err| EncodingError::FailedToWriteCache(cache_path.clone(), err))?;
+                }
+
+                let mut progress = progress.lock().unwrap();
+
+                progress.completed.push(CompletedVolume {
+                    volume: args.volume,
+                    path: path.clone(),
+                });
+
+                progress.save().map_err(|err| {
+                    EncodingError::FailedToWriteProgress(progress_path.clone(), err)
+                })?;
+
+                Ok(built)
+            })
+            .inspect(|result| {
+                if let Ok(built) = result {
+                    built_volumes.lock().unwrap().push(built.clone());
+                }
+            });
+
+        // Stop at the first failure unless the remaining volumes must be built anyway
+        if opts.keep_going {
+            Ok(results.collect::<Vec<_>>())
+        } else {
+            results
+                .collect::<Result<Vec<_>, _>>()
+                .map(|output_files| output_files.into_iter().map(Ok).collect())
+        }
+    });
+
+    // The progress file is kept, so running the same command again resumes the compilation
+    if cancel.is_cancelled() {
+        let mut built_volumes = built_volumes.into_inner().unwrap();
+        built_volumes.sort_by_key(|built| built.volume);
+
+        let mut report = JobReport::from_volumes(built_volumes, reporting.take_warnings(), started);
+        report.cancelled = true;
+
+        warn!(
+            "Cancelled after building {} out of {} volumes ({} pages, {}), run the same command again to resume.",
+            report.volumes.len(),
+            plan.volumes.len(),
+            report.pages,
+            deter::format_size(report.size)
+        );
+
+        report_summary(enc_opts, &plan.output, &report)?;
+
+        return Err(EncodingError::Cancelled);
+    }
+
+    let results = results?;
+
     let mut output_files = vec![];
+    let mut failures = vec![];
 
-    // Iterate over chapters
-    for (chapter, (path, chapter_name)) in chapter_dirs
-        .into_iter()
-        .skip(start_chapter)
-        .take(chapter_len)
-        .enumerate()
-    {
-        // Add this chapter to the current volume
-        volume_chapters.push((chapter + 1, path, chapter_name));
-
-        // If this volume contains enough chapters, build it
-        if volume_chapters.len() == chap_per_vol.into() {
-            output_files.push(build_volume(&BuildVolumeArgs {
-                method: &build_method,
-                enc_opts,
-                output: &output,
-                volume,
-                volumes,
-                vol_num_len,
-                chapter_num_len,
-                start_chapter: volume_start_chapter,
-                chapters: &volume_chapters,
-            })?);
-            volume_start_chapter += volume_chapters.len();
-            volume_chapters = vec![];
-            volume += 1;
+    for result in results {
+        match result {
+            Ok(built) => output_files.push(built),
+            Err(err) => failures.push(err),
         }
     }
 
-    // If there are remaining chapters, build a last volume with them
-    if volume_chapters.is_empty() {
-        output_files.push(build_volume(&BuildVolumeArgs {
-            method: &build_method,
-            enc_opts,
-            output: &output,
-            volume,
-            volumes,
-            vol_num_len,
-            chapter_num_len,
-            start_chapter: volume_start_chapter,
-            chapters: &volume_chapters,
-        })?);
+    // The progress file is kept when some volumes failed, so running the same command again only builds these ones
+    if failures.is_empty() {
+        progress
+            .into_inner()
+            .unwrap()
+            .remove()
+            .map_err(|err| EncodingError::FailedToWriteProgress(progress_path, err))?;
+    }
+
+    if !failures.is_empty() {
+        error!(
+            "Failed to build {} volume{}:",
+            failures.len(),
+            if failures.len() > 1 { "s" } else { "" }
+        );
+
+        for err in &failures {
+            error!("    {}", error_chain(err));
+        }
     }
 
     info!(
@@ -217,5 +1192,81 @@ pub fn compile(
         if output_files.len() > 1 { "s" } else { "" }
     );
 
-    Ok(output_files)
+    let report = JobReport::from_volumes(output_files, reporting.take_warnings(), started);
+
+    report_summary(enc_opts, &plan.output, &report)?;
+
+    // The library contains the series' directories with a layout, and the volumes themselves otherwise
+    if let Some(format) = enc_opts.library_index {
+        let library = match enc_opts.layout {
+            Some(_) => plan.output.parent().unwrap_or(&plan.output),
+            None => &plan.output,
+        };
+
+        let index = Library::scan(library)
+            .and_then(|index| index.write(library, format))
+            .map_err(|err| EncodingError::FailedToIndexLibrary(library.to_path_buf(), err))?;
+
+        info!("Library index written to '{}'.", index.to_string_lossy());
+    }
+
+    if !failures.is_empty() {
+        return Err(EncodingError::FailedVolumes(failures.len(), plan.volumes.len()));
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::opts::{Action, EncodingMethod, Opts};
+    use clap::Parser;
+
+    #[test]
+    fn compile_each_merges_chapters_with_the_same_number() {
+        let root = env::temp_dir().join(format!("comic-enc-test-each-merge-{}", std::process::id()));
+        let input = root.join("input");
+        let output = root.join("output");
+
+        for (dir, pages) in &[("Chapter 1", 2), ("Chapter 1 (HQ)", 1), ("Chapter 2", 1)] {
+            let dir = input.join(dir);
+            fs::create_dir_all(&dir).unwrap();
+
+            for page in 0..*pages {
+                image::RgbImage::new(1, 1).save(dir.join(format!("{}.png", page))).unwrap();
+            }
+        }
+
+        fs::create_dir_all(&output).unwrap();
+
+        let opts = Opts::try_parse_from(&[
+            "comic-enc",
+            "encode",
+            input.to_str().unwrap(),
+            "-o",
+            output.to_str().unwrap(),
+            "compile",
+            "each",
+            "--detect-chapter-numbers",
+            "--on-duplicate-chapter",
+            "merge",
+        ])
+        .unwrap();
+
+        let report = match &opts.action {
+            Action::Encode(encode) => match &encode.method {
+                EncodingMethod::Compile(compile_opts) => compile(compile_opts, &encode.options),
+                EncodingMethod::Single(_) => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        fs::remove_dir_all(&root).unwrap();
+
+        let report = report.unwrap();
+        assert_eq!(report.volumes.len(), 2);
+        assert_eq!(report.volumes[0].pages, 3);
+        assert_eq!(report.volumes[1].pages, 1);
+    }
 }
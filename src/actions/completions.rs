@@ -0,0 +1,28 @@
+use crate::cli::opts::{Completions, Manpage, Opts, Shell};
+use crate::lib::{completions, manpage};
+use clap::CommandFactory;
+
+/// Name of the program's binary, as called from the shell
+const BIN_NAME: &str = env!("CARGO_BIN_NAME");
+
+/// Print a completion script for a shell
+pub fn completions(opts: &Completions) {
+    let mut cmd = Opts::command();
+
+    // Propagate the global and auto-generated arguments (e.g. '--help') to the subcommands
+    cmd.build();
+
+    print!(
+        "{}",
+        match opts.shell {
+            Shell::Bash => completions::bash(&cmd, BIN_NAME),
+            Shell::Zsh => completions::zsh(&cmd, BIN_NAME),
+            Shell::Fish => completions::fish(&cmd, BIN_NAME),
+        }
+    );
+}
+
+/// Print the manual page
+pub fn manpage(_: &Manpage) {
+    print!("{}", manpage::manpage(&Opts::command(), BIN_NAME));
+}
@@ -0,0 +1,323 @@
+use crate::cli::error::ConvertError;
+use crate::cli::opts::{ContainerFormat, Convert};
+use crate::lib::archive::{self, ArchiveFormat, ComicArchive};
+use crate::lib::convert;
+use crate::lib::deter;
+use crate::lib::writer::{self, ArchiveWriter};
+use pdf::file::File as PDFFile;
+use pdf::object::{Resolve, XObject};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Repackage comic books into another container format
+pub fn convert(opts: &Convert) -> Result<Vec<PathBuf>, ConvertError> {
+    let cwd = env::current_dir().map_err(ConvertError::FailedToGetCWD)?;
+
+    let inputs = opts
+        .inputs
+        .iter()
+        .map(|input| cwd.join(input))
+        .collect::<Vec<_>>();
+
+    let archives = archive::find_archives(&inputs).map_err(ConvertError::FailedToFindArchives)?;
+
+    if archives.is_empty() {
+        return Err(ConvertError::NoArchiveFound);
+    }
+
+    // Create the output directory if needed
+    let output_dir = match &opts.output {
+        Some(output) => {
+            let output = cwd.join(output);
+
+            if !output.is_dir() {
+                if opts.create_output_dir {
+                    fs::create_dir_all(&output)
+                        .map_err(ConvertError::FailedToCreateOutputDirectory)?
+                } else {
+                    return Err(ConvertError::OutputDirectoryNotFound);
+                }
+            }
+
+            Some(output)
+        }
+
+        None => None,
+    };
+
+    let mut converted = vec![];
+
+    for (i, input) in archives.iter().enumerate() {
+        let output = output_dir
+            .as_deref()
+            .unwrap_or_else(|| input.parent().unwrap())
+            .join(format!(
+                "{}.{}",
+                input.file_stem().unwrap().to_string_lossy(),
+                opts.to.ext()
+            ));
+
+        if output.exists() && !opts.overwrite {
+            return Err(ConvertError::OutputFileAlreadyExists(output));
+        }
+
+        debug!(
+            "Converting comic book {}/{}: '{}'...",
+            i + 1,
+            archives.len(),
+            input.to_string_lossy()
+        );
+
+        let started = Instant::now();
+
+        let is_pdf = input
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.eq_ignore_ascii_case("pdf"))
+            .unwrap_or(false);
+
+        let pages = if is_pdf {
+            convert_pdf(opts, input, &output)?
+        } else if ArchiveFormat::from_path(input).is_some() {
+            convert_archive(opts, input, &output)?
+        } else {
+            return Err(ConvertError::UnsupportedFormat(input.clone()));
+        };
+
+        let elapsed = started.elapsed();
+
+        info!(
+            "Converted '{}' to '{}' ({} pages) in {}.{:03} s.",
+            input.to_string_lossy(),
+            output.to_string_lossy(),
+            pages,
+            elapsed.as_secs(),
+            elapsed.subsec_millis()
+        );
+
+        converted.push(output);
+    }
+
+    info!(
+        "Successfully converted {} comic book{}.",
+        converted.len(),
+        if converted.len() > 1 { "s" } else { "" }
+    );
+
+    Ok(converted)
+}
+
+/// Repackage an archive's files, converting its pages if required
+/// Returns the number of pages in the converted comic book
+fn convert_archive(opts: &Convert, input: &Path, output: &Path) -> Result<usize, ConvertError> {
+    let mut archive = ComicArchive::open(input)
+        .map_err(|err| ConvertError::FailedToOpenArchive(input.to_path_buf(), err))?;
+
+    let staging_path = archive::staging_path(output);
+    let mut archive_writer = create_staging_archive(opts, &staging_path)?;
+
+    // Keep the archive's comment, which may contain metadata
+    archive_writer.set_comment(&String::from_utf8_lossy(archive.comment()));
+
+    // PDF documents only hold pages, which must be written in reading order
+    let entries = match opts.to {
+        ContainerFormat::Pdf => archive
+            .pages(opts.accept_extended_image_formats, false)
+            .into_iter()
+            .cloned()
+            .collect(),
+        _ => archive.entries().to_vec(),
+    };
+
+    let mut pages = 0;
+
+    // Buffer used to copy the files that need to be compressed again
+    let mut copy_buffer = vec![0; archive::COPY_BUFFER_SIZE];
+
+    for entry in entries {
+        let mut path_in_zip = entry.path.clone();
+
+        if entry.is_dir {
+            archive_writer
+                .add_dir(&path_in_zip.to_string_lossy())
+                .map_err(|err| ConvertError::FailedToCreateFileInZip {
+                    archive: input.to_path_buf(),
+                    file_path: path_in_zip.clone(),
+                    err,
+                })?;
+
+            continue;
+        }
+
+        let is_page = deter::has_image_ext(&entry.path, opts.accept_extended_image_formats);
+
+        if is_page {
+            pages += 1;
+        }
+
+        // Files that don't need to be converted are copied as they are if they are already compressed the right way
+        if !is_page || !convert::needs_conversion(&opts.conversion, &entry.path) {
+            archive_writer
+                .copy_entry(
+                    &mut archive,
+                    entry.index,
+                    &path_in_zip.to_string_lossy(),
+                    is_page,
+                    &mut copy_buffer,
+                )
+                .map_err(|err| ConvertError::FailedToCopyFileToZip {
+                    archive: input.to_path_buf(),
+                    file_path: path_in_zip,
+                    err,
+                })?;
+
+            continue;
+        }
+
+        trace!("Converting page '{}'...", entry.path.to_string_lossy());
+
+        let content = archive
+            .read_entry(entry.index)
+            .map_err(|err| ConvertError::FailedToReadArchiveFile(input.to_path_buf(), err))?;
+
+        let content = convert::convert_page(&opts.conversion, &content).map_err(|err| {
+            ConvertError::FailedToConvertImage {
+                archive: input.to_path_buf(),
+                image_path: entry.path.clone(),
+                err,
+            }
+        })?;
+
+        if let Some(ext) = convert::converted_ext(&opts.conversion) {
+            path_in_zip.set_extension(ext);
+        }
+
+        write_page(&mut *archive_writer, input, &path_in_zip, &content)?;
+    }
+
+    complete_staging_archive(archive_writer, input, &staging_path, output)?;
+
+    Ok(pages)
+}
+
+/// Extract the images of a PDF file to an archive, converting them if required
+/// Returns the number of pages in the converted comic book
+fn convert_pdf(opts: &Convert, input: &Path, output: &Path) -> Result<usize, ConvertError> {
+    let pdf = PDFFile::open(input)
+        .map_err(|err| ConvertError::FailedToOpenPdfFile(input.to_path_buf(), err))?;
+
+    let mut images = vec![];
+
+    debug!("Looking for images in the provided PDF...");
+
+    // List all images in the PDF
+    for (i, page) in pdf.pages().enumerate() {
+        let page =
+            page.map_err(|err| ConvertError::FailedToGetPdfPage(input.to_path_buf(), i + 1, err))?;
+
+        let resources = page.resources().map_err(|err| {
+            ConvertError::FailedToGetPdfPageResources(input.to_path_buf(), i + 1, err)
+        })?;
+
+        images.extend(resources.xobjects.iter().filter_map(|(_, &o)| {
+            let xobj = pdf.get(o).ok()?;
+            match *xobj {
+                XObject::Image(_) => Some(xobj),
+                _ => None,
+            }
+        }));
+    }
+
+    let staging_path = archive::staging_path(output);
+    let mut archive_writer = create_staging_archive(opts, &staging_path)?;
+
+    let page_num_len = images.len().to_string().len();
+
+    for (i, image) in images.iter().enumerate() {
+        let image = match **image {
+            XObject::Image(ref im) => im,
+            _ => continue,
+        };
+
+        let mut path_in_zip = PathBuf::from(format!(
+            "{:0page_num_len$}.jpg",
+            i + 1,
+            page_num_len = page_num_len
+        ));
+
+        let mut content = image
+            .as_jpeg()
+            .ok_or_else(|| ConvertError::UnsupportedPdfImage(input.to_path_buf(), i + 1))?
+            .to_vec();
+
+        if convert::needs_conversion(&opts.conversion, &path_in_zip) {
+            trace!("Converting page {}...", i + 1);
+
+            content = convert::convert_page(&opts.conversion, &content).map_err(|err| {
+                ConvertError::FailedToConvertImage {
+                    archive: input.to_path_buf(),
+                    image_path: path_in_zip.clone(),
+                    err,
+                }
+            })?;
+
+            if let Some(ext) = convert::converted_ext(&opts.conversion) {
+                path_in_zip.set_extension(ext);
+            }
+        }
+
+        write_page(&mut *archive_writer, input, &path_in_zip, &content)?;
+    }
+
+    complete_staging_archive(archive_writer, input, &staging_path, output)?;
+
+    Ok(images.len())
+}
+
+/// Create the staging archive a comic book is converted to, in the target container format
+fn create_staging_archive(
+    opts: &Convert,
+    staging_path: &Path,
+) -> Result<Box<dyn ArchiveWriter>, ConvertError> {
+    writer::create_writer(
+        opts.to.volume_format(),
+        staging_path,
+        convert::compression_method(&opts.conversion),
+    )
+    .map_err(|err| ConvertError::FailedToCreateStagingFile(staging_path.to_path_buf(), err))
+}
+
+/// Write a page to the staging archive
+fn write_page(
+    archive_writer: &mut dyn ArchiveWriter,
+    input: &Path,
+    path_in_zip: &Path,
+    content: &[u8],
+) -> Result<(), ConvertError> {
+    archive_writer
+        .add_page(&path_in_zip.to_string_lossy(), content)
+        .map_err(|err| ConvertError::FailedToWriteFileToZip {
+            archive: input.to_path_buf(),
+            file_path: path_in_zip.to_path_buf(),
+            err,
+        })
+}
+
+/// Close the staging archive and move it to the output path
+fn complete_staging_archive(
+    archive_writer: Box<dyn ArchiveWriter>,
+    input: &Path,
+    staging_path: &Path,
+    output: &Path,
+) -> Result<(), ConvertError> {
+    trace!("Closing archive...");
+
+    archive_writer
+        .finish()
+        .map_err(|err| ConvertError::FailedToCloseZipArchive(input.to_path_buf(), err))?;
+
+    archive::finalize_staging_file(staging_path, output)
+        .map_err(|err| ConvertError::FailedToRenameCompleteArchive(output.to_path_buf(), err))
+}
@@ -0,0 +1,118 @@
+use crate::cli::error::CoverError;
+use crate::cli::opts::CoverExtract;
+use crate::lib::archive;
+use crate::lib::cover::{self, Cover};
+use image::{DynamicImage, ImageFormat};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Extract the cover of comic books
+pub fn cover_extract(opts: &CoverExtract) -> Result<Vec<PathBuf>, CoverError> {
+    let cwd = env::current_dir().map_err(CoverError::FailedToGetCWD)?;
+
+    let inputs = opts
+        .inputs
+        .iter()
+        .map(|input| cwd.join(input))
+        .collect::<Vec<_>>();
+
+    let archives = archive::find_archives(&inputs).map_err(CoverError::FailedToFindArchives)?;
+
+    if archives.is_empty() {
+        return Err(CoverError::NoArchiveFound);
+    }
+
+    let output = opts.output.as_ref().map(|output| cwd.join(output));
+
+    // A single cover can be written to an image file directly
+    let output_file = output
+        .as_ref()
+        .filter(|output| archives.len() == 1 && ImageFormat::from_path(output).is_ok());
+
+    // Create the output directory if needed
+    let output_dir = match &output {
+        Some(output) if output_file.is_none() => {
+            if !output.is_dir() {
+                if opts.create_output_dir {
+                    fs::create_dir_all(output).map_err(CoverError::FailedToCreateOutputDirectory)?
+                } else {
+                    return Err(CoverError::OutputDirectoryNotFound);
+                }
+            }
+
+            Some(output)
+        }
+
+        _ => None,
+    };
+
+    let mut extracted = vec![];
+
+    for input in &archives {
+        let cover = cover::read_cover(
+            input,
+            opts.accept_extended_image_formats,
+            opts.simple_sorting,
+        )
+        .map_err(|err| CoverError::FailedToReadCover(input.clone(), err))?;
+
+        let target = match output_file {
+            Some(output_file) => output_file.clone(),
+            None => {
+                let mut file_name = input.file_stem().unwrap().to_os_string();
+
+                if let Some(ext) = cover.entry.path.extension() {
+                    file_name.push(".");
+                    file_name.push(ext);
+                }
+
+                output_dir
+                    .map(PathBuf::as_path)
+                    .unwrap_or_else(|| input.parent().unwrap())
+                    .join(file_name)
+            }
+        };
+
+        if target.exists() && !opts.overwrite {
+            return Err(CoverError::OutputFileAlreadyExists(target));
+        }
+
+        write_cover(&cover, &target)?;
+
+        info!(
+            "Extracted cover of '{}' to '{}'.",
+            input.to_string_lossy(),
+            target.to_string_lossy()
+        );
+
+        extracted.push(target);
+    }
+
+    Ok(extracted)
+}
+
+/// Write a cover to an image file, converting it if the file's extension doesn't match the cover's format
+fn write_cover(cover: &Cover, target: &Path) -> Result<(), CoverError> {
+    let source_format = ImageFormat::from_path(&cover.entry.path).ok();
+    let target_format = ImageFormat::from_path(target).ok();
+
+    if target_format.is_none() || source_format == target_format {
+        return fs::write(target, &cover.content)
+            .map_err(|err| CoverError::FailedToWriteCover(target.to_path_buf(), err));
+    }
+
+    trace!(
+        "Converting cover from {:?} to {:?}...",
+        source_format,
+        target_format
+    );
+
+    image::load_from_memory(&cover.content)
+        .and_then(|img| match target_format {
+            // JPEG doesn't support transparency
+            Some(ImageFormat::Jpeg) => DynamicImage::ImageRgb8(img.to_rgb8()).save(target),
+            _ => img.save(target),
+        })
+        .map_err(|err| CoverError::FailedToConvertCover(target.to_path_buf(), err))
+}
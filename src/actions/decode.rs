@@ -1,43 +1,154 @@
 use crate::cli::error::DecodingError;
 use crate::cli::opts::Decode;
+use crate::lib::archive;
 use crate::lib::deter;
+use crate::lib::external::{self, ExternalFormat};
+use crate::lib::metadata::{self, COMET_FILENAME, COMIC_INFO_FILENAME};
+use crate::lib::summary::{DecodedSummary, JobReport};
+use crate::lib::tar::TarArchive;
+use crate::lib::template::{self, TemplateErr};
 use pdf::file::File as PDFFile;
 use pdf::object::{Resolve, XObject};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs::{self, File};
-use std::io;
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 use zip::ZipArchive;
 
 /// Perform a decoding using the provided configuration object
-pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
-    // Get absolute path to the input for path manipulation
-    let input = env::current_dir()
-        .map_err(DecodingError::FailedToGetCWD)?
-        .join(&dec.input);
+pub fn decode(dec: &Decode) -> Result<JobReport, DecodingError> {
+    let started = Instant::now();
+
+    let cwd = env::current_dir().map_err(DecodingError::FailedToGetCWD)?;
+
+    // Check the naming template before extracting anything, as it must give a different name to each page
+    if let Some(template) = &dec.name_template {
+        let first = render_page_name(template, 1, 2, Some("name"), Some("ext")).map_err(DecodingError::InvalidNameTemplate)?;
+        let second = render_page_name(template, 2, 2, Some("name"), Some("ext")).map_err(DecodingError::InvalidNameTemplate)?;
+
+        if first == second {
+            return Err(DecodingError::NameTemplateWithoutPageNumber);
+        }
+    }
+
+    // A comic book read from the standard input can only be decoded on its own
+    if dec.inputs.iter().any(|input| input == Path::new("-")) {
+        if dec.inputs.len() > 1 {
+            return Err(DecodingError::StdinMustBeTheOnlyInput);
+        }
+
+        return decode_stdin(dec).map(|decoded| JobReport::from_decoded(vec![decoded], started));
+    }
+
+    // A single comic book is extracted directly to the output directory
+    if let [input] = dec.inputs.as_slice() {
+        // Get absolute path to the input for path manipulation
+        let input = cwd.join(input);
+
+        if !input.is_dir() {
+            return decode_one(dec, input).map(|decoded| JobReport::from_decoded(vec![decoded], started));
+        }
+    }
+
+    let inputs = dec.inputs.iter().map(|input| cwd.join(input)).collect::<Vec<_>>();
+
+    let archives = archive::find_decodable_archives(&inputs).map_err(DecodingError::FailedToFindArchives)?;
+
+    if archives.is_empty() {
+        return Err(DecodingError::NoArchiveFound);
+    }
+
+    // Check the output directory before extracting anything
+    if let Some(output) = &dec.output {
+        check_output_dir(dec, output)?;
+    }
+
+    // Each comic book is extracted to its own directory, named after it
+    let mut outputs = HashSet::new();
+    let mut targets = vec![];
+
+    for archive in archives {
+        let output = match &dec.output {
+            Some(output) => output.join(archive.file_stem().unwrap()),
+            None => archive.with_extension(""),
+        };
+
+        if !outputs.insert(output.clone()) {
+            return Err(DecodingError::DuplicateOutputDirectory(output));
+        }
+
+        targets.push((archive, output));
+    }
+
+    let total = targets.len();
+
+    info!("Decoding {} comic books...", total);
+
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(dec.jobs.unwrap_or(0))
+        .build()
+        .map_err(DecodingError::FailedToCreateThreadPool)?;
+
+    let results = pool.install(|| {
+        targets
+            .into_par_iter()
+            .map(|(input, output)| {
+                let archive_started = Instant::now();
+
+                let result = fs::create_dir_all(&output)
+                    .map_err(DecodingError::FailedToCreateOutputDirectory)
+                    .and_then(|()| decode_archive(dec, Input::File(input.clone()), output.clone()))
+                    .map(|files| DecodedSummary::new(Some(input.clone()), output, files, archive_started));
+
+                if let Err(err) = &result {
+                    error!("Failed to decode '{}': {}", input.to_string_lossy(), err);
+                }
+
+                result
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut decoded = vec![];
+    let mut failed = 0;
+
+    for result in results {
+        match result {
+            Ok(summary) => decoded.push(summary),
+            Err(_) => failed += 1,
+        }
+    }
+
+    if failed > 0 {
+        return Err(DecodingError::FailedArchives(failed, total));
+    }
+
+    info!(
+        "Successfully decoded {} comic book{}.",
+        total,
+        if total > 1 { "s" } else { "" }
+    );
+
+    Ok(JobReport::from_decoded(decoded, started))
+}
+
+/// Decode a single comic book
+fn decode_one(dec: &Decode, input: PathBuf) -> Result<DecodedSummary, DecodingError> {
+    let started = Instant::now();
 
     // Check if the input file exists
     if !input.exists() {
         return Err(DecodingError::InputFileNotFound);
-    } else if !input.is_file() {
-        return Err(DecodingError::InputFileIsADirectory);
     }
 
     // Create the output directory if needed, and get the output path
     let output = match &dec.output {
         Some(output) => {
-            if !output.exists() {
-                if dec.create_output_dir {
-                    fs::create_dir_all(output)
-                        .map_err(DecodingError::FailedToCreateOutputDirectory)?
-                } else {
-                    return Err(DecodingError::OutputDirectoryNotFound);
-                }
-            } else if !output.is_dir() {
-                return Err(DecodingError::OutputDirectoryIsAFile);
-            }
-
+            check_output_dir(dec, output)?;
             output.to_owned()
         }
 
@@ -48,27 +159,129 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
         }
     };
 
-    // Get the input file's extension to determine its format
-    let ext = input
-        .extension()
-        .ok_or_else(|| DecodingError::UnsupportedFormat(String::new()))?;
+    let files = decode_archive(dec, Input::File(input.clone()), output.clone())?;
+
+    Ok(DecodedSummary::new(Some(input), output, files, started))
+}
+
+/// Decode a comic book read from the standard input
+/// As its format cannot be determined from its extension, it is guessed from its content
+fn decode_stdin(dec: &Decode) -> Result<DecodedSummary, DecodingError> {
+    let started = Instant::now();
+
+    // There is no file name to name the output directory after
+    let output = dec.output.as_ref().ok_or(DecodingError::StdinRequiresOutputDirectory)?;
+
+    check_output_dir(dec, output)?;
+
+    info!("Reading comic book from standard input...");
+
+    // Archives like ZIP files must be read from their end, so the whole input is kept in memory
+    let mut data = vec![];
+
+    io::stdin().lock().read_to_end(&mut data).map_err(DecodingError::FailedToReadStdin)?;
+
+    let files = decode_archive(dec, Input::Stdin(data), output.to_owned())?;
 
-    let ext = ext
-        .to_str()
-        .ok_or_else(|| DecodingError::InputFileHasInvalidUTF8FileExtension(
-            input.file_name().unwrap().to_os_string(),
-        ))?;
+    Ok(DecodedSummary::new(None, output.to_owned(), files, started))
+}
+
+/// Check the output directory, creating it if allowed to
+fn check_output_dir(dec: &Decode, output: &Path) -> Result<(), DecodingError> {
+    if !output.exists() {
+        if dec.create_output_dir {
+            fs::create_dir_all(output).map_err(DecodingError::FailedToCreateOutputDirectory)
+        } else {
+            Err(DecodingError::OutputDirectoryNotFound)
+        }
+    } else if !output.is_dir() {
+        Err(DecodingError::OutputDirectoryIsAFile)
+    } else {
+        Ok(())
+    }
+}
+
+/// Comic book to decode
+enum Input {
+    /// Archive file, whose format is determined from its extension
+    File(PathBuf),
+
+    /// Content of an archive read from the standard input
+    Stdin(Vec<u8>),
+}
+
+impl Input {
+    /// Open the archive for reading, from its start
+    fn open(&self) -> io::Result<InputReader<'_>> {
+        match self {
+            Self::File(path) => File::open(path).map(InputReader::File),
+            Self::Stdin(data) => Ok(InputReader::Memory(Cursor::new(data))),
+        }
+    }
+}
+
+/// Reader for a comic book to decode
+enum InputReader<'a> {
+    File(File),
+    Memory(Cursor<&'a Vec<u8>>),
+}
+
+impl Read for InputReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::File(file) => file.read(buf),
+            Self::Memory(cursor) => cursor.read(buf),
+        }
+    }
+}
+
+impl Seek for InputReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::File(file) => file.seek(pos),
+            Self::Memory(cursor) => cursor.seek(pos),
+        }
+    }
+}
+
+/// Decode a comic book to an existing output directory
+fn decode_archive(dec: &Decode, input: Input, output: PathBuf) -> Result<Vec<PathBuf>, DecodingError> {
+    let (ext, input_name) = match &input {
+        Input::File(path) => {
+            // Get the input file's extension to determine its format
+            let ext = path
+                .extension()
+                .ok_or_else(|| DecodingError::UnsupportedFormat(String::new()))?;
+
+            let ext = ext
+                .to_str()
+                .ok_or_else(|| DecodingError::InputFileHasInvalidUTF8FileExtension(
+                    path.file_name().unwrap().to_os_string(),
+                ))?;
+
+            // Get the input file's name to display it once the extraction is complete
+            (ext.to_owned(), path.file_name().unwrap().to_string_lossy().into_owned())
+        }
+
+        Input::Stdin(data) => {
+            let ext = deter::detect_decodable_format(data).ok_or(DecodingError::UnrecognizedStdinFormat)?;
+            (ext.to_owned(), "standard input".to_owned())
+        }
+    };
 
     // Get timestamp to measure decoding time
     let extraction_started = Instant::now();
 
+    // Metadata found in the comic book, to export along with its pages
+    let mut metadata_files = MetadataFiles::default();
+
     // Decode
     let result = match ext.to_lowercase().as_str() {
         "zip" | "cbz" => {
             debug!("Matched input format: ZIP / CBZ");
             trace!("Opening input file...");
 
-            let file = File::open(input).map_err(DecodingError::FailedToOpenZipFile)?;
+            let file = input.open().map_err(DecodingError::FailedToOpenZipFile)?;
 
             trace!("Opening ZIP archive...");
 
@@ -76,15 +289,11 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
 
             let zip_files = zip.len();
 
-            /// Represent a page that has been extracted from the comic archive
-            struct ExtractedFile {
-                path_in_zip: PathBuf,
-                extracted_path: PathBuf,
-                extension: Option<String>,
-            }
+            metadata_files.comment = String::from_utf8_lossy(zip.comment()).into_owned();
 
-            // List of extracted pages
-            let mut pages: Vec<ExtractedFile> = vec![];
+            // List of files that can be extracted, with their ID in the ZIP
+            let mut files = vec![];
+            let mut extras = vec![];
 
             for i in 0..zip.len() {
                 trace!("Retrieving ZIP file with ID {}...", i);
@@ -94,37 +303,80 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
 
                 // Ignore folders
                 if file.is_file() {
-                    let file_name = file.sanitized_name();
+                    let file_name = file.mangled_name();
+
+                    if !dec.no_metadata {
+                        if let Some(content) = metadata_files.slot(&file_name) {
+                            *content = Some(read_metadata_file(&mut file, &file_name)?);
+                        }
+                    }
 
                     // Ensure the file is an image if only images have to be extracted
-                    if dec.extract_images_only
-                        && !deter::has_image_ext(&file_name, dec.accept_extended_image_formats)
-                    {
-                        trace!("Ignoring file {}/{} based on extension", i + 1, zip_files);
+                    if !is_page(dec, &file_name) {
+                        trace!("File {}/{} is not a page based on its extension", i + 1, zip_files);
+                        extras.push((file_name, i));
                         continue;
                     }
 
-                    // Get the file's extension to determine output file's name
-                    let ext = file_name
-                        .extension()
-                        .map(|ext| {
-                            ext.to_str()
-                                .ok_or_else(|| DecodingError::ZipFileHasInvalidUTF8FileExtension(
-                                    file_name.clone(),
-                                ))
-                        })
-                        .transpose()?;
-
-                    let outpath = output.join(Path::new(&format!("___tmp_pic_{}", pages.len())));
-
-                    // Create output file
-                    trace!("File is a page. Creating an output file for it...");
+                    files.push((file_name, i));
+                }
+            }
+
+            let (pages, total_pages) = select_pages(dec, files)?;
+
+            let mut extracted = vec![];
+
+            for (page, file_name, i) in pages {
+                let mut file = zip.by_index(i).map_err(DecodingError::ZipError)?;
+
+                // Get the file's extension to determine output file's name
+                let ext = file_name
+                    .extension()
+                    .map(|ext| {
+                        ext.to_str()
+                            .ok_or_else(|| DecodingError::ZipFileHasInvalidUTF8FileExtension(
+                                file_name.clone(),
+                            ))
+                    })
+                    .transpose()?;
+
+                let outpath = if dec.keep_structure {
+                    structured_page_path(&output, &file_name)?
+                } else {
+                    page_path(dec, &output, page, total_pages, Some(&file_name), ext)?
+                };
+
+                // Create output file
+                trace!("File is a page. Creating an output file for it...");
+                let mut outfile = File::create(&outpath).map_err(|err| {
+                    DecodingError::FailedToCreateOutputFile(err, outpath.clone())
+                })?;
+
+                // Extract the page
+                debug!("Extracting page {} out of {}...", page, total_pages);
+                io::copy(&mut file, &mut outfile).map_err(|err| {
+                    DecodingError::FailedToExtractZipFile {
+                        path_in_zip: file_name.clone(),
+                        extract_to: outpath.clone(),
+                        err,
+                    }
+                })?;
+
+                extracted.push(outpath);
+            }
+
+            // Extract the files that are not pages to their own directory, if requested
+            if let Some(extras_dir) = report_extras(dec, &output, &extras) {
+                for (file_name, i) in extras {
+                    let mut file = zip.by_index(i).map_err(DecodingError::ZipError)?;
+
+                    let outpath = structured_page_path(&extras_dir, &file_name)?;
+
                     let mut outfile = File::create(&outpath).map_err(|err| {
                         DecodingError::FailedToCreateOutputFile(err, outpath.clone())
                     })?;
 
-                    // Extract the page
-                    debug!("Extracting file {} out of {}...", i + 1, zip_files);
+                    debug!("Extracting file '{}'...", file_name.to_string_lossy());
                     io::copy(&mut file, &mut outfile).map_err(|err| {
                         DecodingError::FailedToExtractZipFile {
                             path_in_zip: file_name.clone(),
@@ -132,64 +384,156 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
                             err,
                         }
                     })?;
-
-                    pages.push(ExtractedFile {
-                        extension: ext.map(|ext| ext.to_owned()),
-                        path_in_zip: file_name,
-                        extracted_path: outpath,
-                    });
                 }
             }
 
-            trace!("Sorting pages...");
+            Ok(extracted)
+        }
+
+        "tar" | "cbt" => {
+            debug!("Matched input format: TAR / CBT");
+            trace!("Opening input file...");
+
+            // TAR archives can only be read sequentially, so they are read once to list the pages, and once more to extract them
+            let open_tar = || {
+                input
+                    .open()
+                    .map(|file| TarArchive::new(BufReader::new(file)))
+                    .map_err(DecodingError::FailedToOpenTarFile)
+            };
+
+            let mut tar = open_tar()?;
+
+            // List of files that can be extracted, with their position in the TAR
+            let mut files = vec![];
+            let mut extras = vec![];
+            let mut position = 0;
+
+            while let Some(entry) = tar.next_file().map_err(DecodingError::InvalidTarArchive)? {
+                position += 1;
 
-            if dec.simple_sorting {
-                pages.sort_by(|a, b| a.path_in_zip.cmp(&b.path_in_zip));
-            } else {
-                pages.sort_by(|a, b| deter::natural_paths_cmp(&a.path_in_zip, &b.path_in_zip));
+                if !dec.no_metadata {
+                    if let Some(content) = metadata_files.slot(&entry.path) {
+                        *content = Some(read_metadata_file(&mut tar, &entry.path)?);
+                    }
+                }
+
+                // Ensure the file is an image if only images have to be extracted
+                if !is_page(dec, &entry.path) {
+                    trace!("File '{}' is not a page based on its extension", entry.path.to_string_lossy());
+                    extras.push((entry.path, position));
+                    continue;
+                }
+
+                files.push((entry.path, position));
             }
 
-            let total_pages = pages.len();
+            let (pages, total_pages) = select_pages(dec, files)?;
+
+            // Pages to extract, indexed by their position in the TAR
+            let mut pages: HashMap<usize, (usize, PathBuf)> = pages
+                .into_iter()
+                .map(|(page, path_in_tar, position)| (position, (page, path_in_tar)))
+                .collect();
+
+            // Files that are not pages, to extract to their own directory if requested
+            let extras_dir = report_extras(dec, &output, &extras);
+
+            let mut extras: HashMap<usize, PathBuf> = match extras_dir {
+                Some(_) => extras.into_iter().map(|(path_in_tar, position)| (position, path_in_tar)).collect(),
+                None => HashMap::new(),
+            };
 
             let mut extracted = vec![];
+            let mut tar = open_tar()?;
+            let mut position = 0;
+
+            while let Some(entry) = tar.next_file().map_err(DecodingError::InvalidTarArchive)? {
+                position += 1;
 
-            // Get the number of characters the last page takes to display
-            let page_num_len = pages.len().to_string().len();
+                let (outpath, path_in_tar, is_page) = if let Some((page, path_in_tar)) = pages.remove(&position) {
+                    let outpath = if dec.keep_structure {
+                        structured_page_path(&output, &path_in_tar)?
+                    } else {
+                        page_path(dec, &output, page, total_pages, Some(&path_in_tar), archive_file_ext(&path_in_tar)?)?
+                    };
 
-            debug!("Renaming pictures...");
+                    debug!("Extracting page {} out of {} ({} bytes)...", page, total_pages, entry.size);
 
-            for (i, page) in pages.into_iter().enumerate() {
-                let target = output.join(&match page.extension {
-                    None => format!("{:0page_num_len$}", i + 1, page_num_len = page_num_len),
-                    Some(ref ext) => format!(
-                        "{:0page_num_len$}.{}",
-                        i + 1,
-                        ext,
-                        page_num_len = page_num_len
-                    ),
-                });
+                    (outpath, path_in_tar, true)
+                } else if let (Some(path_in_tar), Some(extras_dir)) = (extras.remove(&position), &extras_dir) {
+                    debug!("Extracting file '{}' ({} bytes)...", path_in_tar.to_string_lossy(), entry.size);
+
+                    (structured_page_path(extras_dir, &path_in_tar)?, path_in_tar, false)
+                } else {
+                    continue;
+                };
 
-                trace!("Renaming picture {}/{}...", i + 1, total_pages);
+                // Create output file
+                let mut outfile = File::create(&outpath).map_err(|err| {
+                    DecodingError::FailedToCreateOutputFile(err, outpath.clone())
+                })?;
 
-                fs::rename(&page.extracted_path, &target).map_err(|err| {
-                    DecodingError::FailedToRenameTemporaryFile {
-                        from: page.extracted_path,
-                        to: target.to_owned(),
+                // Extract the file
+                io::copy(&mut tar, &mut outfile).map_err(|err| {
+                    DecodingError::FailedToExtractTarFile {
+                        path_in_tar,
+                        extract_to: outpath.clone(),
                         err,
                     }
                 })?;
 
-                extracted.push(target);
+                if is_page {
+                    extracted.push(outpath);
+                }
             }
 
             Ok(extracted)
         }
 
+        "rar" | "cbr" | "7z" | "cb7" => {
+            let format = match ext.to_lowercase().as_str() {
+                "rar" | "cbr" => ExternalFormat::Rar,
+                _ => ExternalFormat::SevenZip,
+            };
+
+            debug!("Matched input format: {}", format.name());
+
+            match &input {
+                Input::File(path) => decode_external(dec, format, path, &output, &mut metadata_files),
+
+                // External programs can only read files, so the archive is written to a temporary one
+                Input::Stdin(data) => {
+                    let tmp_input = output.join(format!("___tmp_input.{}", ext));
+
+                    fs::write(&tmp_input, data).map_err(|err| {
+                        DecodingError::FailedToWriteTemporaryFile(tmp_input.clone(), err)
+                    })?;
+
+                    let result = decode_external(dec, format, &tmp_input, &output, &mut metadata_files);
+
+                    if let Err(err) = fs::remove_file(&tmp_input) {
+                        warn!("Failed to remove temporary file '{}': {}", tmp_input.to_string_lossy(), err);
+                    }
+
+                    result
+                }
+            }
+        }
+
         "pdf" => {
             debug!("Matched input format: PDF");
             trace!("Opening input file...");
 
-            let pdf = PDFFile::open(input).map_err(DecodingError::FailedToOpenPdfFile)?;
+            let pdf = match input {
+                Input::File(path) => PDFFile::open(path),
+                Input::Stdin(data) => PDFFile::from_data(data),
+            }
+            .map_err(DecodingError::FailedToOpenPdfFile)?;
+
+            if dec.keep_structure {
+                warn!("PDF files have no internal structure, pages will be numbered in a single sequence.");
+            }
 
             let mut images = vec![];
 
@@ -212,7 +556,7 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
                             images.extend(resources.xobjects.iter().filter_map(|(_, &o)| {
                                 let xobj = pdf.get(o).ok()?;
                                 match *xobj {
-                                    XObject::Image(ref im) => Some(xobj),
+                                    XObject::Image(_) => Some(xobj),
                                     _ => None,
                                 }
                             }));
@@ -221,25 +565,23 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
                 }
             }
 
+            // Keep only the selected images
+            let (images, total_pages) = select_pdf_images(dec, images)?;
+
             info!("Extracting {} images from PDF...", images.len());
 
             let mut extracted = vec![];
-            let page_num_len = images.len().to_string().len();
 
             // Extract all images from the PDF
-            for (i, image) in images.iter().enumerate() {
+            for (i, image) in images.iter() {
                 let image = match **image {
                     XObject::Image(ref im) => im,
                     _ => continue,
                 };
 
-                let outpath = output.join(Path::new(&format!(
-                    "{:0page_num_len$}.jpg",
-                    i + 1,
-                    page_num_len = page_num_len
-                )));
+                let outpath = page_path(dec, &output, i + 1, total_pages, None, Some("jpg"))?;
 
-                debug!("Extracting page {}/{}...", i + 1, images.len());
+                debug!("Extracting page {}/{}...", i + 1, total_pages);
 
                 fs::write(&outpath, image.as_jpeg().unwrap()).map_err(|err| {
                     DecodingError::FailedToExtractPdfImage(i + 1, outpath.clone(), err)
@@ -252,19 +594,26 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
         }
 
         _ => {
-            if deter::is_supported_for_decoding(ext) {
+            if deter::is_supported_for_decoding(&ext) {
                 warn!("Internal error: format '{}' cannot be handled but is marked as supported nonetheless", ext);
             }
 
-            Err(DecodingError::UnsupportedFormat(ext.to_owned()))
+            Err(DecodingError::UnsupportedFormat(ext))
         }
     };
 
+    // Keep the metadata next to the pages, so it is used again when encoding them
+    let result = match result {
+        Ok(pages) if !dec.no_metadata => export_metadata(&input_name, &output, &metadata_files).map(|()| pages),
+        result => result,
+    };
+
     if let Ok(pages) = &result {
         let elapsed = extraction_started.elapsed();
         info!(
-            "Successfully extracted {} pages in {}.{:03} s!",
+            "Successfully extracted {} pages from '{}' in {}.{:03} s!",
             pages.len(),
+            input_name,
             elapsed.as_secs(),
             elapsed.subsec_millis()
         );
@@ -272,3 +621,333 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
 
     result
 }
+
+/// Content of the metadata files found in a comic book
+#[derive(Default)]
+struct MetadataFiles {
+    comic_info: Option<String>,
+    comet: Option<String>,
+    comment: String,
+}
+
+impl MetadataFiles {
+    /// Get where to store the content of a file from the archive, if it is a metadata file
+    fn slot(&mut self, path_in_archive: &Path) -> Option<&mut Option<String>> {
+        let name = path_in_archive.to_str()?;
+
+        if name.eq_ignore_ascii_case(COMIC_INFO_FILENAME) {
+            Some(&mut self.comic_info)
+        } else if name.eq_ignore_ascii_case(COMET_FILENAME) {
+            Some(&mut self.comet)
+        } else {
+            None
+        }
+    }
+}
+
+/// Read a metadata file from an archive
+fn read_metadata_file(mut reader: impl Read, path_in_archive: &Path) -> Result<String, DecodingError> {
+    let mut content = vec![];
+
+    reader
+        .read_to_end(&mut content)
+        .map_err(|err| DecodingError::FailedToReadMetadataFile(path_in_archive.to_owned(), err))?;
+
+    Ok(String::from_utf8_lossy(&content).into_owned())
+}
+
+/// Write the metadata of a comic book to a 'ComicInfo.xml' file in the output directory
+/// Metadata that cannot be parsed is ignored, as it doesn't prevent using the extracted pages
+fn export_metadata(input_name: &str, output: &Path, metadata_files: &MetadataFiles) -> Result<(), DecodingError> {
+    let metadata = match metadata::parse_archive_metadata(
+        metadata_files.comic_info.as_deref(),
+        metadata_files.comet.as_deref(),
+        &metadata_files.comment,
+    ) {
+        Ok(metadata) => metadata.merged(),
+        Err(err) => {
+            warn!("Ignoring invalid metadata of '{}': {}", input_name, err);
+            return Ok(());
+        }
+    };
+
+    if metadata.is_empty() {
+        return Ok(());
+    }
+
+    let path = output.join(COMIC_INFO_FILENAME);
+
+    debug!("Writing metadata to '{}'...", path.to_string_lossy());
+
+    fs::write(&path, metadata.to_comic_info_xml()).map_err(|err| DecodingError::FailedToWriteMetadataFile(path, err))
+}
+
+/// Get the extension of a file from an archive, to determine the output file's name
+fn archive_file_ext(path: &Path) -> Result<Option<String>, DecodingError> {
+    path.extension()
+        .map(|ext| {
+            ext.to_str()
+                .map(|ext| ext.to_owned())
+                .ok_or_else(|| DecodingError::ArchiveFileHasInvalidUTF8FileExtension(path.to_owned()))
+        })
+        .transpose()
+}
+
+/// Decode an archive using an external program, through a temporary directory
+fn decode_external(dec: &Decode, format: ExternalFormat, input: &Path, output: &Path, metadata_files: &mut MetadataFiles) -> Result<Vec<PathBuf>, DecodingError> {
+    // Let the external program extract everything in a temporary directory, then pick the pages from it
+    let tmp_dir = output.join("___tmp_extract");
+
+    fs::create_dir_all(&tmp_dir).map_err(|err| {
+        DecodingError::FailedToCreateTemporaryDirectory(tmp_dir.clone(), err)
+    })?;
+
+    info!("Extracting archive using '{}'...", format.program());
+
+    let result = extract_external(dec, format, input, output, &tmp_dir, metadata_files);
+
+    trace!("Removing temporary directory...");
+
+    match fs::remove_dir_all(&tmp_dir) {
+        Ok(()) => result,
+        Err(err) if result.is_ok() => Err(DecodingError::FailedToRemoveTemporaryDirectory(tmp_dir, err)),
+        Err(err) => {
+            warn!("Failed to remove temporary directory '{}': {}", tmp_dir.to_string_lossy(), err);
+            result
+        }
+    }
+}
+
+/// Extract an archive using an external program, and move its pages to the output directory
+fn extract_external(dec: &Decode, format: ExternalFormat, input: &Path, output: &Path, tmp_dir: &Path, metadata_files: &mut MetadataFiles) -> Result<Vec<PathBuf>, DecodingError> {
+    external::extract(format, input, tmp_dir).map_err(DecodingError::FailedToExtractWithExternalProgram)?;
+
+    trace!("Listing extracted files...");
+
+    let files_in_tmp = deter::readdir_files_recursive(tmp_dir, None::<&fn(&Path) -> bool>)
+        .map_err(|err| DecodingError::FailedToListExtractedFiles(tmp_dir.to_owned(), err))?;
+
+    // List of files that can be extracted
+    let mut files = vec![];
+    let mut extras = vec![];
+
+    for file in files_in_tmp {
+        let path_in_archive = file.strip_prefix(tmp_dir).unwrap_or(&file).to_owned();
+
+        if !dec.no_metadata {
+            if let Some(content) = metadata_files.slot(&path_in_archive) {
+                let reader = File::open(&file).map_err(|err| DecodingError::FailedToReadMetadataFile(path_in_archive.clone(), err))?;
+                *content = Some(read_metadata_file(reader, &path_in_archive)?);
+            }
+        }
+
+        // Ensure the file is an image if only images have to be extracted
+        if !is_page(dec, &path_in_archive) {
+            trace!("File '{}' is not a page based on its extension", path_in_archive.to_string_lossy());
+            extras.push((path_in_archive, file));
+            continue;
+        }
+
+        files.push((path_in_archive, file));
+    }
+
+    let (pages, total_pages) = select_pages(dec, files)?;
+
+    let mut extracted = vec![];
+
+    debug!("Moving pictures...");
+
+    for (page, path_in_archive, file) in pages {
+        let outpath = if dec.keep_structure {
+            structured_page_path(output, &path_in_archive)?
+        } else {
+            page_path(dec, output, page, total_pages, Some(&path_in_archive), archive_file_ext(&path_in_archive)?)?
+        };
+
+        trace!("Moving page {} out of {}...", page, total_pages);
+
+        fs::rename(&file, &outpath).map_err(|err| {
+            DecodingError::FailedToRenameTemporaryFile {
+                from: file.clone(),
+                to: outpath.clone(),
+                err,
+            }
+        })?;
+
+        extracted.push(outpath);
+    }
+
+    // Move the files that are not pages to their own directory, if requested
+    if let Some(extras_dir) = report_extras(dec, output, &extras) {
+        for (path_in_archive, file) in extras {
+            let outpath = structured_page_path(&extras_dir, &path_in_archive)?;
+
+            fs::rename(&file, &outpath).map_err(|err| {
+                DecodingError::FailedToRenameTemporaryFile {
+                    from: file.clone(),
+                    to: outpath.clone(),
+                    err,
+                }
+            })?;
+        }
+    }
+
+    Ok(extracted)
+}
+
+/// Selected pages of an archive, with their number, path in the archive and a format-specific item to extract them
+type SelectedPages<T> = Vec<(usize, PathBuf, T)>;
+
+/// Check if a file from an archive is a page, based on its extension
+/// Every file is considered as a page unless only images have to be extracted, or files that are not pages are put aside
+fn is_page(dec: &Decode, path: &Path) -> bool {
+    (!dec.extract_images_only && dec.extras_dir.is_none())
+        || deter::has_image_ext(path, dec.accept_extended_image_formats)
+}
+
+/// Report the files of an archive that are not pages
+/// Returns the directory they should be extracted to, if any
+fn report_extras<T>(dec: &Decode, output: &Path, extras: &[(PathBuf, T)]) -> Option<PathBuf> {
+    if extras.is_empty() {
+        return None;
+    }
+
+    let names = extras
+        .iter()
+        .map(|(path, _)| format!("'{}'", path.to_string_lossy()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match &dec.extras_dir {
+        Some(extras_dir) => {
+            let extras_dir = output.join(extras_dir);
+
+            info!(
+                "Extracting {} {} to '{}': {}",
+                extras.len(),
+                if extras.len() > 1 { "files that are not pages" } else { "file that is not a page" },
+                extras_dir.to_string_lossy(),
+                names
+            );
+
+            Some(extras_dir)
+        }
+
+        None => {
+            info!(
+                "Skipped {} {}: {}",
+                extras.len(),
+                if extras.len() > 1 { "files that are not pages" } else { "file that is not a page" },
+                names
+            );
+
+            None
+        }
+    }
+}
+
+/// Sort the files found in an archive, and keep only the selected pages
+/// Each page is returned with its number, alongside the total number of pages in the archive
+fn select_pages<T>(dec: &Decode, mut files: Vec<(PathBuf, T)>) -> Result<(SelectedPages<T>, usize), DecodingError> {
+    trace!("Sorting pages...");
+
+    if dec.simple_sorting {
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+    } else {
+        deter::natural_sort_by_path(&mut files, |file| &file.0);
+    }
+
+    let total_pages = files.len();
+
+    // Files that are not images are numbered as pages when they are not put aside
+    let not_images = files
+        .iter()
+        .filter(|(path, _)| !deter::has_image_ext(path, dec.accept_extended_image_formats))
+        .map(|(path, _)| format!("'{}'", path.to_string_lossy()))
+        .collect::<Vec<_>>();
+
+    if !not_images.is_empty() {
+        warn!(
+            "{} {} will be numbered as pages (use '--extract-images-only' or '--extras-dir' to put them aside): {}",
+            not_images.len(),
+            if not_images.len() > 1 { "files that are not images" } else { "file that is not an image" },
+            not_images.join(", ")
+        );
+    }
+
+    let pages = files
+        .into_iter()
+        .enumerate()
+        .map(|(i, (path, item))| (i + 1, path, item))
+        .filter(|(page, _, _)| dec.pages.as_ref().map_or(true, |selection| selection.contains(*page)))
+        .collect::<Vec<_>>();
+
+    if pages.is_empty() && dec.pages.is_some() {
+        return Err(DecodingError::NoPageSelected(total_pages));
+    }
+
+    Ok((pages, total_pages))
+}
+
+/// Keep only the selected images of a PDF, with their index
+/// The total number of images in the PDF is returned as well
+fn select_pdf_images<T>(dec: &Decode, images: Vec<T>) -> Result<(Vec<(usize, T)>, usize), DecodingError> {
+    let total_pages = images.len();
+
+    let images = images
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| dec.pages.as_ref().map_or(true, |selection| selection.contains(i + 1)))
+        .collect::<Vec<_>>();
+
+    if images.is_empty() && dec.pages.is_some() {
+        return Err(DecodingError::NoPageSelected(total_pages));
+    }
+
+    Ok((images, total_pages))
+}
+
+/// Render the name of a page using a naming template
+fn render_page_name(template: &str, page: usize, total_pages: usize, name: Option<&str>, ext: Option<&str>) -> Result<String, TemplateErr> {
+    let name = template::render_template(template, |placeholder| match placeholder {
+        "n" => Some(page.to_string()),
+        "total" => Some(total_pages.to_string()),
+        "name" => name.map(str::to_owned),
+        "ext" => Some(ext.unwrap_or_default().to_owned()),
+        _ => None,
+    })?;
+
+    // Path separators would put the page in another directory, pages without an extension would end with a dot,
+    // and some names can't be used on Windows
+    Ok(deter::sanitize_filename(name.trim()))
+}
+
+/// Get the path a page should be extracted to, based on its number
+fn page_path(dec: &Decode, output: &Path, page: usize, total_pages: usize, path_in_archive: Option<&Path>, ext: Option<impl AsRef<str>>) -> Result<PathBuf, DecodingError> {
+    if let Some(template) = &dec.name_template {
+        let name = path_in_archive.and_then(Path::file_stem).map(|stem| stem.to_string_lossy());
+
+        return render_page_name(template, page, total_pages, name.as_deref(), ext.as_ref().map(AsRef::as_ref))
+            .map(|name| output.join(name))
+            .map_err(DecodingError::InvalidNameTemplate);
+    }
+
+    // Get the number of characters the last page takes to display
+    let page_num_len = total_pages.to_string().len();
+
+    Ok(output.join(match ext {
+        None => format!("{:0page_num_len$}", page, page_num_len = page_num_len),
+        Some(ext) => format!("{:0page_num_len$}.{}", page, ext.as_ref(), page_num_len = page_num_len),
+    }))
+}
+
+/// Get the path a page should be extracted to when preserving the archive's structure, creating its parent directories
+fn structured_page_path(output: &Path, path_in_archive: &Path) -> Result<PathBuf, DecodingError> {
+    let outpath = output.join(path_in_archive);
+
+    if let Some(parent) = outpath.parent() {
+        fs::create_dir_all(parent).map_err(|err| DecodingError::FailedToCreatePageDirectory(parent.to_owned(), err))?;
+    }
+
+    Ok(outpath)
+}
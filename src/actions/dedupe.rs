@@ -0,0 +1,173 @@
+use crate::cli::error::DedupeError;
+use crate::cli::opts::Dedupe;
+use crate::lib::archive;
+use crate::lib::checksum;
+use crate::lib::diff;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A comic book of the library, with its hashes
+struct HashedArchive {
+    path: PathBuf,
+    file_hash: String,
+}
+
+/// Find duplicate comic books in a library
+pub fn dedupe(opts: &Dedupe) -> Result<Vec<PathBuf>, DedupeError> {
+    let cwd = env::current_dir().map_err(DedupeError::FailedToGetCWD)?;
+
+    let inputs = opts
+        .inputs
+        .iter()
+        .map(|input| cwd.join(input))
+        .collect::<Vec<_>>();
+
+    let archives = archive::find_archives(&inputs).map_err(DedupeError::FailedToFindArchives)?;
+
+    if archives.is_empty() {
+        return Err(DedupeError::NoArchiveFound);
+    }
+
+    info!("Hashing {} comic books...", archives.len());
+
+    let mut failed = 0;
+
+    // Comic books grouped by hash, in the order they were found
+    let mut groups: Vec<Vec<HashedArchive>> = vec![];
+    let mut groups_by_hash: HashMap<String, usize> = HashMap::new();
+
+    for (i, path) in archives.iter().enumerate() {
+        debug!(
+            "Hashing comic book {}/{}: '{}'...",
+            i + 1,
+            archives.len(),
+            path.to_string_lossy()
+        );
+
+        let file_hash = match checksum::file_sha256(path) {
+            Ok(hash) => hash,
+            Err(err) => {
+                error!("{}: {}", path.to_string_lossy(), err);
+                failed += 1;
+                continue;
+            }
+        };
+
+        let key = if opts.contents {
+            match contents_sha256(path, opts) {
+                Ok(hash) => hash,
+                Err(err) => {
+                    error!("{}: {}", path.to_string_lossy(), err);
+                    failed += 1;
+                    continue;
+                }
+            }
+        } else {
+            file_hash.clone()
+        };
+
+        let hashed = HashedArchive {
+            path: path.clone(),
+            file_hash,
+        };
+
+        match groups_by_hash.get(&key) {
+            Some(group) => groups[*group].push(hashed),
+            None => {
+                groups_by_hash.insert(key, groups.len());
+                groups.push(vec![hashed]);
+            }
+        }
+    }
+
+    let mut duplicates = vec![];
+
+    for group in groups.iter().filter(|group| group.len() > 1) {
+        let (original, copies) = group.split_first().unwrap();
+
+        println!("{}", original.path.to_string_lossy());
+
+        for copy in copies {
+            println!("  = {}", copy.path.to_string_lossy());
+            duplicates.push(copy.path.clone());
+
+            if !opts.hard_link {
+                continue;
+            }
+
+            // Only identical files can be linked without losing anything
+            if copy.file_hash != original.file_hash {
+                warn!(
+                    "Warning: not linking '{}' as its file differs from '{}' (only its pages are identical)",
+                    copy.path.to_string_lossy(),
+                    original.path.to_string_lossy()
+                );
+                continue;
+            }
+
+            match hard_link(&original.path, &copy.path) {
+                Ok(()) => debug!(
+                    "Replaced '{}' with a hard link to '{}'.",
+                    copy.path.to_string_lossy(),
+                    original.path.to_string_lossy()
+                ),
+
+                Err(err) => {
+                    error!(
+                        "Failed to replace '{}' with a hard link: {}",
+                        copy.path.to_string_lossy(),
+                        err
+                    );
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(DedupeError::FailedArchives(failed, archives.len()));
+    }
+
+    info!(
+        "Found {} duplicate comic book{}.",
+        duplicates.len(),
+        if duplicates.len() > 1 { "s" } else { "" }
+    );
+
+    Ok(duplicates)
+}
+
+/// Compute a hash of the pages of a comic book, in reading order
+fn contents_sha256(path: &Path, opts: &Dedupe) -> Result<String, diff::DiffErr> {
+    let fingerprints = diff::fingerprint_archive(
+        path,
+        opts.accept_extended_image_formats,
+        opts.simple_sorting,
+        false,
+    )?;
+
+    let mut hasher = Sha256::new();
+
+    for fingerprint in fingerprints {
+        hasher.update(fingerprint.sha256.as_bytes());
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Replace a file with a hard link to another one
+/// The link is created next to the file first, so the file is never lost if it fails
+fn hard_link(original: &Path, copy: &Path) -> io::Result<()> {
+    let staging_path = archive::staging_path(copy);
+
+    fs::hard_link(original, &staging_path)?;
+
+    fs::rename(&staging_path, copy).map_err(|err| {
+        let _ = fs::remove_file(&staging_path);
+        err
+    })
+}
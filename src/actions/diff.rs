@@ -0,0 +1,133 @@
+use crate::cli::error::DiffError;
+use crate::cli::opts::Diff;
+use crate::lib::diff::{self, DiffReport, PageDiff};
+use std::env;
+
+/// Compare the pages of two comic books
+pub fn diff(opts: &Diff) -> Result<DiffReport, DiffError> {
+    let cwd = env::current_dir().map_err(DiffError::FailedToGetCWD)?;
+
+    let mut fingerprints = vec![];
+
+    for input in &[&opts.before, &opts.after] {
+        let input = cwd.join(input);
+
+        if !input.exists() {
+            return Err(DiffError::InputFileNotFound(input));
+        } else if input.is_dir() {
+            return Err(DiffError::InputFileIsADirectory(input));
+        }
+
+        debug!("Reading pages of '{}'...", input.to_string_lossy());
+
+        fingerprints.push(
+            diff::fingerprint_archive(
+                &input,
+                opts.accept_extended_image_formats,
+                opts.simple_sorting,
+                opts.perceptual,
+            )
+            .map_err(|err| DiffError::FailedToReadArchive(input.clone(), err))?,
+        );
+    }
+
+    let (before, after) = (&fingerprints[0], &fingerprints[1]);
+
+    let differences = diff::diff_pages(before, after);
+    let significant = differences
+        .iter()
+        .filter(|diff| diff.is_significant())
+        .count();
+
+    let report = DiffReport {
+        before: cwd.join(&opts.before),
+        after: cwd.join(&opts.after),
+        pages_before: before.len(),
+        pages_after: after.len(),
+        identical: significant == 0,
+        differences,
+    };
+
+    if opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(DiffError::FailedToSerializeDiff)?
+        );
+
+        // Differences are part of the JSON output
+        return Ok(report);
+    }
+
+    print_report(&report);
+
+    if significant > 0 {
+        return Err(DiffError::ArchivesDiffer(significant));
+    }
+
+    Ok(report)
+}
+
+/// Display a comparison in a human-readable way
+fn print_report(report: &DiffReport) {
+    println!(
+        "--- {} ({} pages)",
+        report.before.to_string_lossy(),
+        report.pages_before
+    );
+    println!(
+        "+++ {} ({} pages)",
+        report.after.to_string_lossy(),
+        report.pages_after
+    );
+
+    for diff in &report.differences {
+        match diff {
+            PageDiff::Renamed { index, from, to } => println!(
+                "  = page {}: renamed '{}' => '{}'",
+                index + 1,
+                from.to_string_lossy(),
+                to.to_string_lossy()
+            ),
+
+            PageDiff::Similar {
+                index,
+                from,
+                to,
+                similarity,
+            } => println!(
+                "  ~ page {}: '{}' looks like '{}' ({:.0}% similar)",
+                index + 1,
+                to.to_string_lossy(),
+                from.to_string_lossy(),
+                similarity * 100.0
+            ),
+
+            PageDiff::Changed {
+                index,
+                from,
+                to,
+                size_before,
+                size_after,
+            } => println!(
+                "  ! page {}: '{}' ({} bytes) changed to '{}' ({} bytes)",
+                index + 1,
+                from.to_string_lossy(),
+                size_before,
+                to.to_string_lossy(),
+                size_after
+            ),
+
+            PageDiff::Added { index, path } => {
+                println!("  + page {}: '{}'", index + 1, path.to_string_lossy())
+            }
+
+            PageDiff::Removed { index, path } => {
+                println!("  - page {}: '{}'", index + 1, path.to_string_lossy())
+            }
+        }
+    }
+
+    if report.identical {
+        println!("Comic books hold the same pages.");
+    }
+}
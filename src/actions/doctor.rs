@@ -0,0 +1,104 @@
+use crate::cli::error::DoctorError;
+use crate::cli::opts::Doctor;
+use crate::lib::codec;
+use crate::lib::deter;
+use crate::lib::doctor;
+use std::env;
+
+/// Optional external programs, with what they are used for
+const OPTIONAL_PROGRAMS: &[(&str, &str)] = &[
+    ("unrar", "RAR ('.rar' / '.cbr') archives"),
+    ("7z", "7-Zip ('.7z' / '.cb7') archives"),
+];
+
+/// Check the environment before starting a long job
+pub fn doctor(opts: &Doctor) -> Result<(), DoctorError> {
+    let cwd = env::current_dir().map_err(DoctorError::FailedToGetCWD)?;
+
+    let mut problems = 0;
+
+    for (program, usage) in OPTIONAL_PROGRAMS {
+        match doctor::find_executable(program) {
+            Some(path) => info!("Found '{}' at '{}'.", program, path.to_string_lossy()),
+            None => warn!(
+                "Warning: '{}' was not found in PATH (optional, for {}).",
+                program, usage
+            ),
+        }
+    }
+
+    let codecs = codec::available()
+        .iter()
+        .map(|codec| codec.extensions()[0])
+        .collect::<Vec<_>>();
+
+    info!(
+        "Pages can be converted to: {}.",
+        if codecs.is_empty() {
+            "(none)".to_owned()
+        } else {
+            codecs.join(", ")
+        }
+    );
+
+    let output = match &opts.output {
+        Some(output) => cwd.join(output),
+        None => cwd,
+    };
+
+    if !output.is_dir() {
+        error!(
+            "Output directory '{}' was not found (use '--create-output-dir' when encoding to create it).",
+            output.to_string_lossy()
+        );
+
+        return Err(DoctorError::ProblemsFound(problems + 1));
+    }
+
+    match doctor::check_writable(&output) {
+        Ok(()) => info!(
+            "Output directory '{}' is writable.",
+            output.to_string_lossy()
+        ),
+
+        Err(err) => {
+            error!(
+                "Cannot write to output directory '{}': {} (check its permissions).",
+                output.to_string_lossy(),
+                err
+            );
+            problems += 1;
+        }
+    }
+
+    let min_free_space = opts.min_free_space * 1024 * 1024;
+
+    match doctor::available_space(&output) {
+        Ok(space) if space >= min_free_space => info!(
+            "{} available in the output directory.",
+            deter::format_size(space)
+        ),
+
+        Ok(space) => {
+            error!(
+                "Only {} available in the output directory, while at least {} are required (free some space or use '--min-free-space').",
+                deter::format_size(space),
+                deter::format_size(min_free_space)
+            );
+            problems += 1;
+        }
+
+        Err(err) => warn!(
+            "Warning: failed to check the space available in the output directory: {}",
+            err
+        ),
+    }
+
+    if problems > 0 {
+        return Err(DoctorError::ProblemsFound(problems));
+    }
+
+    info!("Everything looks fine.");
+
+    Ok(())
+}
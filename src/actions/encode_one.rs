@@ -1,13 +1,26 @@
 use crate::cli::opts::{EncodeSingle, EncodingOptions};
-use crate::lib::build_vol::{build_volume, BuildMethod};
+use crate::lib::build_vol::{
+    build_pages_pool, build_volume, load_volumes_metadata, print_volumes_plan, report_summary,
+    BuildMethod,
+};
+use crate::lib::cancel;
+use crate::lib::chapter::ChapterNumber;
+use crate::lib::deter;
+use crate::lib::progress::{CliProgress, ReportingProgress};
+use crate::lib::progress_bars;
+use crate::lib::source::DirSource;
+use crate::lib::summary::JobReport;
 use crate::{cli::error::EncodingError, lib::build_vol::BuildVolumeArgs};
-use std::path::PathBuf;
+use std::path::Path;
+use std::time::Instant;
 
 /// Compile a single directory to a single volume file
 pub fn encode_one(
     opts: &EncodeSingle,
     enc_opts: &EncodingOptions,
-) -> Result<PathBuf, EncodingError> {
+) -> Result<JobReport, EncodingError> {
+    let started = Instant::now();
+
     let input = enc_opts.input.clone();
 
     let output = match &enc_opts.output {
@@ -16,7 +29,7 @@ pub fn encode_one(
             let filename = input
                 .file_name()
                 .ok_or(EncodingError::SingleInputDirectorHasNoName)?;
-            input.join(filename).with_extension("cbz")
+            deter::append_ext(&input.join(filename), enc_opts.container.ext())
         }
     };
 
@@ -30,19 +43,64 @@ pub fn encode_one(
         return Err(EncodingError::OutputVolumeFileAlreadyExists(1, input));
     }
 
+    // Load the volume's metadata
+    let volumes_metadata = load_volumes_metadata(enc_opts)?;
+
+    let pool = build_pages_pool(enc_opts)?;
+
     let out_filename = output
         .file_name()
         .ok_or(EncodingError::SingleOutputFileHasNoName)?;
 
-    build_volume(&BuildVolumeArgs {
+    // The single chapter is the input directory itself
+    let source = DirSource::new(&input, !enc_opts.no_default_ignores);
+
+    let chapters = vec![(
+        ChapterNumber::new(1),
+        input,
+        out_filename.to_string_lossy().to_string(),
+    )];
+
+    // Keep the warnings for the report
+    let progress = ReportingProgress::new(&CliProgress);
+
+    let args = BuildVolumeArgs {
         method: &BuildMethod::Single(opts),
         enc_opts,
+        source: &source,
         output: &output,
         volume: 1,
         volumes: 1,
         vol_num_len: 1,
         chapter_num_len: 1,
-        start_chapter: 1,
-        chapters: &vec![(1, input, out_filename.to_string_lossy().to_string())],
-    })
+        start_chapter: ChapterNumber::new(1),
+        chapters: &chapters,
+        metadata: &volumes_metadata,
+        pool: &pool,
+        progress: &progress,
+        cancel: &cancel::interrupt_token(),
+    };
+
+    if enc_opts.dry_run || enc_opts.verbose_plan {
+        print_volumes_plan(std::slice::from_ref(&args), false)?;
+    }
+
+    if enc_opts.dry_run {
+        info!("Dry run: nothing was written.");
+        return Ok(JobReport::default());
+    }
+
+    let _progress_bars = progress_bars::start(1);
+
+    let built = build_volume(&args)?;
+
+    let report = JobReport::from_volumes(vec![built], progress.take_warnings(), started);
+
+    report_summary(
+        enc_opts,
+        output.parent().unwrap_or_else(|| Path::new(".")),
+        &report,
+    )?;
+
+    Ok(report)
 }
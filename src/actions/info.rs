@@ -0,0 +1,136 @@
+use crate::cli::error::InfoError;
+use crate::cli::opts::Info;
+use crate::lib::archive;
+use crate::lib::deter;
+use crate::lib::inspect::{self, ArchiveInfo};
+use std::collections::BTreeMap;
+use std::env;
+
+/// Display informations about existing comic books
+pub fn info(opts: &Info) -> Result<Vec<ArchiveInfo>, InfoError> {
+    let cwd = env::current_dir().map_err(InfoError::FailedToGetCWD)?;
+
+    let inputs = opts
+        .inputs
+        .iter()
+        .map(|input| cwd.join(input))
+        .collect::<Vec<_>>();
+
+    let archives = archive::find_archives(&inputs).map_err(InfoError::FailedToFindArchives)?;
+
+    if archives.is_empty() {
+        return Err(InfoError::NoArchiveFound);
+    }
+
+    let mut infos = vec![];
+
+    for (i, path) in archives.iter().enumerate() {
+        debug!(
+            "Inspecting comic book {}/{}: '{}'...",
+            i + 1,
+            archives.len(),
+            path.to_string_lossy()
+        );
+
+        infos.push(
+            inspect::inspect_archive(path, opts.accept_extended_image_formats)
+                .map_err(|err| InfoError::FailedToInspectArchive(path.clone(), err))?,
+        );
+    }
+
+    if opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&infos).map_err(InfoError::FailedToSerializeInfo)?
+        );
+    } else {
+        for info in &infos {
+            print_info(info);
+        }
+    }
+
+    Ok(infos)
+}
+
+/// Format a list of counters, most frequent first (e.g. 'JPEG (170), PNG (10)')
+fn format_counters(counters: &BTreeMap<String, usize>, max: usize) -> String {
+    let mut counters = counters.iter().collect::<Vec<_>>();
+    counters.sort_by(|a, b| b.1.cmp(a.1));
+
+    let mut formatted = counters
+        .iter()
+        .take(max)
+        .map(|(name, count)| format!("{} ({})", name, count))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if counters.len() > max {
+        formatted.push_str(&format!(", ... ({} more)", counters.len() - max));
+    }
+
+    formatted
+}
+
+/// Print informations in a human-readable form
+fn print_info(info: &ArchiveInfo) {
+    println!("{}:", info.path.to_string_lossy());
+    println!("  File size    : {}", deter::format_size(info.file_size));
+    println!("  Pages        : {}", info.pages);
+    println!("  Formats      : {}", format_counters(&info.formats, 5));
+    println!("  Resolutions  : {}", format_counters(&info.resolutions, 5));
+    println!(
+        "  Uncompressed : {}",
+        deter::format_size(info.uncompressed_size)
+    );
+    println!(
+        "  Compressed   : {}{}",
+        deter::format_size(info.compressed_size),
+        if info.uncompressed_size > 0 {
+            format!(
+                " ({:.1}%)",
+                info.compressed_size as f64 * 100.0 / info.uncompressed_size as f64
+            )
+        } else {
+            String::new()
+        }
+    );
+    println!(
+        "  Compression  : {}",
+        format_counters(&info.compression_methods, 5)
+    );
+
+    let metadata = [
+        ("ComicInfo", &info.metadata.comic_info),
+        ("CoMet", &info.metadata.comet),
+        ("ComicBookInfo", &info.metadata.comic_book_info),
+    ];
+
+    let found = metadata
+        .iter()
+        .filter(|(_, data)| data.is_some())
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>();
+
+    println!(
+        "  Metadata     : {}",
+        if found.is_empty() {
+            "None".to_owned()
+        } else {
+            found.join(", ")
+        }
+    );
+
+    for (format, data) in metadata.iter() {
+        if let Some(data) = data {
+            println!("    {}:", format);
+
+            for (name, value) in data.fields() {
+                if let Some(value) = value {
+                    println!("      {:12} : {}", name, value);
+                }
+            }
+        }
+    }
+
+    println!();
+}
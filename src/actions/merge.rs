@@ -0,0 +1,272 @@
+use crate::cli::error::MergeError;
+use crate::cli::opts::Merge;
+use crate::lib::archive::{self, ComicArchive};
+use crate::lib::convert;
+use crate::lib::metadata::{self, ComicMetadata, PageMetadata, COMIC_INFO_FILENAME};
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Instant;
+use zip::write::{FileOptions, ZipWriter};
+
+/// Join multiple comic books into a single one
+pub fn merge(opts: &Merge) -> Result<PathBuf, MergeError> {
+    let started = Instant::now();
+
+    let cwd = env::current_dir().map_err(MergeError::FailedToGetCWD)?;
+
+    let inputs = opts
+        .inputs
+        .iter()
+        .map(|input| cwd.join(input))
+        .collect::<Vec<_>>();
+
+    let archives = archive::find_archives(&inputs).map_err(MergeError::FailedToFindArchives)?;
+
+    if archives.len() < 2 {
+        return Err(MergeError::NotEnoughArchives);
+    }
+
+    let output = cwd.join(&opts.output);
+
+    if output.is_dir() {
+        return Err(MergeError::OutputFileIsADirectory(output));
+    } else if output.exists() && !opts.overwrite {
+        return Err(MergeError::OutputFileAlreadyExists(output));
+    }
+
+    let staging_path = archive::staging_path(&output);
+
+    let staging_file = File::create(&staging_path)
+        .map_err(|err| MergeError::FailedToCreateStagingFile(staging_path.clone(), err))?;
+
+    let mut zip_writer = ZipWriter::new(staging_file);
+
+    let zip_options =
+        FileOptions::default().compression_method(convert::compression_method(&opts.conversion));
+
+    // Length of the displayable volume number
+    let vol_num_len = archives.len().to_string().len();
+
+    // Metadata of each merged comic book
+    let mut merged_metadata = vec![];
+
+    // Bookmarks of the merged comic book
+    let mut bookmarks = vec![];
+
+    // Opened comic books with their pages grouped by chapter directory
+    let mut sources = vec![];
+
+    // Number of pages in the merged comic book
+    let mut total_pages = 0;
+
+    trace!("Reading comic books to merge...");
+
+    for input in &archives {
+        let mut archive = ComicArchive::open(input)
+            .map_err(|err| MergeError::FailedToOpenArchive(input.clone(), err))?;
+
+        let comic_info = metadata::read_archive_metadata(&mut archive)
+            .map_err(|err| MergeError::FailedToReadMetadata(input.clone(), err))?
+            .comic_info;
+
+        let chapters = archive.chapters(opts.accept_extended_image_formats, opts.simple_sorting);
+        let pages_count = chapters.iter().map(Vec::len).sum::<usize>();
+
+        // Keep the comic book's bookmarks, or bookmark its first page with its title
+        match comic_info.as_ref().filter(|info| !info.pages.is_empty()) {
+            Some(info) => bookmarks.extend(
+                info.pages
+                    .iter()
+                    .filter(|page| page.bookmark.is_some())
+                    .map(|page| PageMetadata {
+                        image: page.image + total_pages,
+                        bookmark: page.bookmark.clone(),
+                        ..PageMetadata::default()
+                    }),
+            ),
+
+            None if pages_count > 0 => bookmarks.push(PageMetadata {
+                image: total_pages,
+                bookmark: comic_info
+                    .as_ref()
+                    .and_then(|info| info.title.clone())
+                    .or_else(|| {
+                        input
+                            .file_stem()
+                            .map(|stem| stem.to_string_lossy().to_string())
+                    }),
+                ..PageMetadata::default()
+            }),
+
+            None => {}
+        }
+
+        if let Some(comic_info) = comic_info {
+            merged_metadata.push(comic_info);
+        }
+
+        total_pages += pages_count;
+
+        sources.push((input, archive, chapters));
+    }
+
+    // Length of the displayable chapter number
+    let chapter_num_len = sources
+        .iter()
+        .map(|(_, _, chapters)| chapters.len())
+        .sum::<usize>()
+        .to_string()
+        .len();
+
+    // Number of pages and chapters written so far
+    let mut pics_counter = 0;
+    let mut chapters_counter = 0;
+
+    for (vol_index, (input, mut archive, chapters)) in sources.into_iter().enumerate() {
+        let volume = vol_index + 1;
+
+        info!(
+            "Merging comic book {:0vol_num_len$} / {}: '{}'...",
+            volume,
+            archives.len(),
+            input.to_string_lossy(),
+            vol_num_len = vol_num_len
+        );
+
+        for chapter_pages in chapters {
+            chapters_counter += 1;
+
+            let zip_dir_name = format!(
+                "Vol_{:0vol_num_len$}_Chapter_{:0chapter_num_len$}",
+                volume,
+                chapters_counter,
+                vol_num_len = vol_num_len,
+                chapter_num_len = chapter_num_len
+            );
+
+            trace!("Adding directory '{}' to ZIP archive...", zip_dir_name);
+
+            zip_writer
+                .add_directory(&zip_dir_name, zip_options)
+                .map_err(|err| MergeError::FailedToCreateFileInZip {
+                    file_path: PathBuf::from(&zip_dir_name),
+                    err,
+                })?;
+
+            let pic_num_len = chapter_pages.len().to_string().len();
+
+            for (page_nb, page) in chapter_pages.iter().enumerate() {
+                let mut content = archive
+                    .read_entry(page.index)
+                    .map_err(|err| MergeError::FailedToReadArchiveFile(input.clone(), err))?;
+
+                let mut ext = page
+                    .path
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                if convert::needs_conversion(&opts.conversion, &page.path) {
+                    trace!("Converting page '{}'...", page.path.to_string_lossy());
+
+                    content = convert::convert_page(&opts.conversion, &content).map_err(|err| {
+                        MergeError::FailedToConvertImage {
+                            archive: input.clone(),
+                            image_path: page.path.clone(),
+                            err,
+                        }
+                    })?;
+
+                    if let Some(converted_ext) = convert::converted_ext(&opts.conversion) {
+                        ext = converted_ext.to_owned();
+                    }
+                }
+
+                let path_in_zip = format!(
+                    "{}/{}_Pic_{:0pic_num_len$}.{}",
+                    zip_dir_name,
+                    zip_dir_name,
+                    page_nb,
+                    ext,
+                    pic_num_len = pic_num_len
+                );
+
+                zip_writer
+                    .start_file(&path_in_zip, zip_options)
+                    .map_err(|err| MergeError::FailedToCreateFileInZip {
+                        file_path: PathBuf::from(&path_in_zip),
+                        err,
+                    })?;
+
+                zip_writer.write_all(&content).map_err(|err| {
+                    MergeError::FailedToWriteFileToZip {
+                        file_path: PathBuf::from(&path_in_zip),
+                        err,
+                    }
+                })?;
+
+                pics_counter += 1;
+            }
+        }
+    }
+
+    // Merge the metadata, the first comic books taking precedence
+    let mut comic_info = ComicMetadata::default();
+
+    for metadata in merged_metadata.iter().rev() {
+        comic_info.merge(metadata);
+    }
+
+    // Fields that are specific to a single volume do not make sense anymore
+    comic_info.title = opts.title.clone();
+    comic_info.number = None;
+    comic_info.volume = None;
+    comic_info.page_count = Some(pics_counter.to_string());
+    comic_info.pages = bookmarks;
+
+    trace!("Adding metadata file to ZIP archive...");
+
+    zip_writer
+        .start_file(COMIC_INFO_FILENAME, zip_options)
+        .map_err(|err| MergeError::FailedToCreateFileInZip {
+            file_path: PathBuf::from(COMIC_INFO_FILENAME),
+            err,
+        })?;
+
+    zip_writer
+        .write_all(comic_info.to_comic_info_xml().as_bytes())
+        .map_err(|err| MergeError::FailedToWriteFileToZip {
+            file_path: PathBuf::from(COMIC_INFO_FILENAME),
+            err,
+        })?;
+
+    trace!("Closing ZIP archive...");
+
+    zip_writer
+        .finish()
+        .map_err(MergeError::FailedToCloseZipArchive)?;
+
+    if output.exists() {
+        fs::remove_file(&output)
+            .map_err(|err| MergeError::FailedToRenameCompleteArchive(output.clone(), err))?;
+    }
+
+    archive::finalize_staging_file(&staging_path, &output)
+        .map_err(|err| MergeError::FailedToRenameCompleteArchive(output.clone(), err))?;
+
+    let elapsed = started.elapsed();
+
+    info!(
+        "Successfully merged {} comic books into '{}', containing {} chapters and {} pages in {}.{:03} s.",
+        archives.len(),
+        output.to_string_lossy(),
+        chapters_counter,
+        pics_counter,
+        elapsed.as_secs(),
+        elapsed.subsec_millis()
+    );
+
+    Ok(output)
+}
@@ -0,0 +1,95 @@
+use crate::cli::error::MetadataError;
+use crate::cli::opts::MetadataShow;
+use crate::lib::archive::ComicArchive;
+use crate::lib::metadata::{self, ArchiveMetadata, ComicMetadata};
+use std::env;
+
+/// Display the metadata embedded in a comic archive
+pub fn metadata_show(opts: &MetadataShow) -> Result<ArchiveMetadata, MetadataError> {
+    // Get absolute path to the input for path manipulation
+    let input = env::current_dir()
+        .map_err(MetadataError::FailedToGetCWD)?
+        .join(&opts.input);
+
+    // Check if the input file exists
+    if !input.exists() {
+        return Err(MetadataError::InputFileNotFound);
+    } else if !input.is_file() {
+        return Err(MetadataError::InputFileIsADirectory);
+    }
+
+    trace!("Opening archive...");
+
+    let mut archive = ComicArchive::open(&input).map_err(MetadataError::FailedToOpenArchive)?;
+
+    debug!("Reading metadata...");
+
+    let metadata = metadata::read_archive_metadata(&mut archive)
+        .map_err(MetadataError::FailedToReadMetadata)?;
+
+    if opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&metadata)
+                .map_err(MetadataError::FailedToSerializeMetadata)?
+        );
+    } else if metadata.is_empty() {
+        warn!("No metadata found in '{}'.", input.to_string_lossy());
+    } else {
+        for (format, data) in &[
+            ("ComicInfo", &metadata.comic_info),
+            ("CoMet", &metadata.comet),
+            ("ComicBookInfo (ZIP comment)", &metadata.comic_book_info),
+        ] {
+            if let Some(data) = data {
+                print_metadata(format, data);
+            }
+        }
+    }
+
+    Ok(metadata)
+}
+
+/// Print metadata in a human-readable form
+fn print_metadata(format: &str, metadata: &ComicMetadata) {
+    println!("{}:", format);
+
+    let fields = metadata.fields();
+
+    // Align values on the longest field name
+    let name_len = fields.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+
+    for (name, value) in fields {
+        if let Some(value) = value {
+            println!("  {:name_len$} : {}", name, value, name_len = name_len);
+        }
+    }
+
+    if !metadata.pages.is_empty() {
+        println!("  Pages:");
+
+        for page in &metadata.pages {
+            let mut details = vec![];
+
+            if let Some(kind) = &page.kind {
+                details.push(format!("type: {}", kind));
+            }
+
+            if let Some(bookmark) = &page.bookmark {
+                details.push(format!("bookmark: '{}'", bookmark));
+            }
+
+            if page.double_page == Some(true) {
+                details.push("double page".to_owned());
+            }
+
+            if let (Some(width), Some(height)) = (page.image_width, page.image_height) {
+                details.push(format!("{}x{}", width, height));
+            }
+
+            println!("    #{} {}", page.image, details.join(", "));
+        }
+    }
+
+    println!();
+}
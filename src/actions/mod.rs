@@ -1,7 +1,53 @@
+mod batch;
+mod bench;
+mod clean;
 mod compile;
+mod completions;
+mod convert;
+mod cover;
 mod decode;
+mod dedupe;
+mod diff;
+mod doctor;
 mod encode_one;
+mod info;
+mod merge;
+mod metadata;
+mod optimize;
+mod preset;
+mod rename;
+mod repack;
+mod serve;
+mod split;
+mod stats;
+mod thumbnails;
+mod validate;
+mod verify;
+mod watch;
 
-pub use compile::compile;
+pub use batch::batch;
+pub use bench::bench;
+pub use clean::clean;
+pub use compile::{compile, compile_volumes};
+pub use completions::{completions, manpage};
+pub use convert::convert;
+pub use cover::cover_extract;
 pub use decode::decode;
+pub use dedupe::dedupe;
+pub use diff::diff;
+pub use doctor::doctor;
 pub use encode_one::encode_one;
+pub use info::info;
+pub use merge::merge;
+pub use metadata::metadata_show;
+pub use optimize::optimize;
+pub use preset::{preset_list, preset_remove, preset_save};
+pub use rename::rename;
+pub use repack::repack;
+pub use serve::serve;
+pub use split::split;
+pub use stats::stats;
+pub use thumbnails::thumbnails;
+pub use validate::validate;
+pub use verify::verify;
+pub use watch::watch;
@@ -0,0 +1,217 @@
+use crate::cli::error::OptimizeError;
+use crate::cli::opts::Optimize;
+use crate::lib::archive::{self, ComicArchive};
+use crate::lib::convert;
+use crate::lib::deter;
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use zip::write::{FileOptions, ZipWriter};
+
+/// Rebuild existing comic books with the provided conversion options
+pub fn optimize(opts: &Optimize) -> Result<Vec<PathBuf>, OptimizeError> {
+    let cwd = env::current_dir().map_err(OptimizeError::FailedToGetCWD)?;
+
+    let inputs = opts
+        .inputs
+        .iter()
+        .map(|input| cwd.join(input))
+        .collect::<Vec<_>>();
+
+    let archives = archive::find_archives(&inputs).map_err(OptimizeError::FailedToFindArchives)?;
+
+    if archives.is_empty() {
+        return Err(OptimizeError::NoArchiveFound);
+    }
+
+    // Create the output directory if needed
+    let output_dir = match &opts.output {
+        Some(output) => {
+            let output = cwd.join(output);
+
+            if !output.is_dir() {
+                if opts.create_output_dir {
+                    fs::create_dir_all(&output)
+                        .map_err(OptimizeError::FailedToCreateOutputDirectory)?
+                } else {
+                    return Err(OptimizeError::OutputDirectoryNotFound);
+                }
+            }
+
+            Some(output)
+        }
+
+        None => None,
+    };
+
+    let mut optimized = vec![];
+
+    for (i, input) in archives.iter().enumerate() {
+        let output = match &output_dir {
+            Some(output_dir) => {
+                let output = output_dir.join(input.file_name().unwrap());
+
+                if output.exists() && !opts.overwrite {
+                    return Err(OptimizeError::OutputFileAlreadyExists(output));
+                }
+
+                output
+            }
+
+            None => input.clone(),
+        };
+
+        debug!(
+            "Optimizing comic book {}/{}: '{}'...",
+            i + 1,
+            archives.len(),
+            input.to_string_lossy()
+        );
+
+        optimized.push(optimize_archive(opts, input, &output)?);
+    }
+
+    info!(
+        "Successfully optimized {} comic book{}.",
+        optimized.len(),
+        if optimized.len() > 1 { "s" } else { "" }
+    );
+
+    Ok(optimized)
+}
+
+/// Rebuild a single comic book to the provided output path
+/// The new archive is written to a staging file, which then replaces the output file at once
+fn optimize_archive(
+    opts: &Optimize,
+    input: &Path,
+    output: &Path,
+) -> Result<PathBuf, OptimizeError> {
+    let started = Instant::now();
+
+    let mut archive = ComicArchive::open(input)
+        .map_err(|err| OptimizeError::FailedToOpenArchive(input.to_path_buf(), err))?;
+
+    let staging_path = archive::staging_path(output);
+
+    let staging_file = File::create(&staging_path)
+        .map_err(|err| OptimizeError::FailedToCreateStagingFile(staging_path.clone(), err))?;
+
+    let mut zip_writer = ZipWriter::new(staging_file);
+
+    let zip_options =
+        FileOptions::default().compression_method(convert::compression_method(&opts.conversion));
+
+    // Keep the archive's comment, which may contain metadata
+    zip_writer.set_comment(String::from_utf8_lossy(archive.comment()).to_string());
+
+    let mut converted_pages = 0;
+
+    // Buffer used to copy the files that need to be compressed again
+    let mut copy_buffer = vec![0; archive::COPY_BUFFER_SIZE];
+
+    for entry in archive.entries().to_vec() {
+        let mut path_in_zip = entry.path.clone();
+
+        if entry.is_dir {
+            zip_writer
+                .add_directory(path_in_zip.to_string_lossy(), zip_options)
+                .map_err(|err| OptimizeError::FailedToCreateFileInZip {
+                    archive: input.to_path_buf(),
+                    file_path: path_in_zip.clone(),
+                    err,
+                })?;
+
+            continue;
+        }
+
+        // Convert pages, leaving other files (e.g. metadata) untouched
+        // Files that don't need to be converted are copied as they are if they are already compressed the right way
+        if !deter::has_image_ext(&entry.path, opts.accept_extended_image_formats)
+            || !convert::needs_conversion(&opts.conversion, &entry.path)
+        {
+            archive
+                .copy_entry(
+                    entry.index,
+                    &mut zip_writer,
+                    &path_in_zip.to_string_lossy(),
+                    convert::compression_method(&opts.conversion),
+                    &mut copy_buffer,
+                )
+                .map_err(|err| OptimizeError::FailedToCopyFileToZip {
+                    archive: input.to_path_buf(),
+                    file_path: path_in_zip,
+                    err,
+                })?;
+
+            continue;
+        }
+
+        trace!("Converting page '{}'...", entry.path.to_string_lossy());
+
+        let content = archive
+            .read_entry(entry.index)
+            .map_err(|err| OptimizeError::FailedToReadArchiveFile(input.to_path_buf(), err))?;
+
+        let content = convert::convert_page(&opts.conversion, &content).map_err(|err| {
+            OptimizeError::FailedToConvertImage {
+                archive: input.to_path_buf(),
+                image_path: entry.path.clone(),
+                err,
+            }
+        })?;
+
+        if let Some(ext) = convert::converted_ext(&opts.conversion) {
+            path_in_zip.set_extension(ext);
+        }
+
+        converted_pages += 1;
+
+        zip_writer
+            .start_file(path_in_zip.to_string_lossy(), zip_options)
+            .map_err(|err| OptimizeError::FailedToCreateFileInZip {
+                archive: input.to_path_buf(),
+                file_path: path_in_zip.clone(),
+                err,
+            })?;
+
+        zip_writer
+            .write_all(&content)
+            .map_err(|err| OptimizeError::FailedToWriteFileToZip {
+                archive: input.to_path_buf(),
+                file_path: path_in_zip,
+                err,
+            })?;
+    }
+
+    trace!("Closing ZIP archive...");
+
+    zip_writer
+        .finish()
+        .map_err(|err| OptimizeError::FailedToCloseZipArchive(input.to_path_buf(), err))?
+        .sync_all()
+        .map_err(|err| OptimizeError::FailedToCreateStagingFile(staging_path.clone(), err))?;
+
+    let size_before = fs::metadata(input).map(|m| m.len()).unwrap_or(0);
+    let size_after = fs::metadata(&staging_path).map(|m| m.len()).unwrap_or(0);
+
+    // Replace the output file at once
+    archive::finalize_staging_file(&staging_path, output)
+        .map_err(|err| OptimizeError::FailedToReplaceArchive(output.to_path_buf(), err))?;
+
+    let elapsed = started.elapsed();
+
+    info!(
+        "Optimized '{}' ({} pages converted): {} => {} in {}.{:03} s.",
+        output.to_string_lossy(),
+        converted_pages,
+        deter::format_size(size_before),
+        deter::format_size(size_after),
+        elapsed.as_secs(),
+        elapsed.subsec_millis()
+    );
+
+    Ok(output.to_path_buf())
+}
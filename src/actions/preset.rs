@@ -0,0 +1,54 @@
+use crate::cli::error::PresetError;
+use crate::cli::opts::{PresetList, PresetRemove, PresetSave};
+use crate::lib::config::Config;
+
+/// Save options as a preset in the configuration file
+pub fn preset_save(opts: &PresetSave) -> Result<(), PresetError> {
+    let mut config = Config::load().map_err(PresetError::FailedToLoadConfig)?;
+
+    let replaced = config
+        .presets
+        .insert(opts.name.clone(), opts.options.clone())
+        .is_some();
+
+    let path = config.save().map_err(PresetError::FailedToSaveConfig)?;
+
+    info!(
+        "{} preset '{}' in '{}'.",
+        if replaced { "Replaced" } else { "Saved" },
+        opts.name,
+        path.to_string_lossy()
+    );
+
+    Ok(())
+}
+
+/// List the presets saved in the configuration file
+pub fn preset_list(_opts: &PresetList) -> Result<(), PresetError> {
+    let config = Config::load().map_err(PresetError::FailedToLoadConfig)?;
+
+    if config.presets.is_empty() {
+        info!("No preset saved (use 'comic-enc preset save <name> -- <options>' to create one).");
+    }
+
+    for (name, options) in &config.presets {
+        println!("{}: {}", name, options.join(" "));
+    }
+
+    Ok(())
+}
+
+/// Remove a preset from the configuration file
+pub fn preset_remove(opts: &PresetRemove) -> Result<(), PresetError> {
+    let mut config = Config::load().map_err(PresetError::FailedToLoadConfig)?;
+
+    if config.presets.remove(&opts.name).is_none() {
+        return Err(PresetError::PresetNotFound(opts.name.clone()));
+    }
+
+    config.save().map_err(PresetError::FailedToSaveConfig)?;
+
+    info!("Removed preset '{}'.", opts.name);
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use crate::cli::error::RenameError;
+use crate::cli::opts::Rename;
+use crate::lib::archive::{self, ComicArchive};
+use crate::lib::deter;
+use crate::lib::metadata::{self, ComicMetadata};
+use crate::lib::template;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Rename comic books according to a template
+pub fn rename(opts: &Rename) -> Result<Vec<PathBuf>, RenameError> {
+    let cwd = env::current_dir().map_err(RenameError::FailedToGetCWD)?;
+
+    let inputs = opts
+        .inputs
+        .iter()
+        .map(|input| cwd.join(input))
+        .collect::<Vec<_>>();
+
+    let archives = archive::find_archives(&inputs).map_err(RenameError::FailedToFindArchives)?;
+
+    if archives.is_empty() {
+        return Err(RenameError::NoArchiveFound);
+    }
+
+    // Compute all new names before renaming anything
+    let mut renames = vec![];
+    let mut targets = HashSet::new();
+
+    for input in &archives {
+        let mut archive = ComicArchive::open(input)
+            .map_err(|err| RenameError::FailedToOpenArchive(input.clone(), err))?;
+
+        let comic_info = metadata::read_archive_metadata(&mut archive)
+            .map_err(|err| RenameError::FailedToReadMetadata(input.clone(), err))?
+            .comic_info
+            .unwrap_or_default();
+
+        let chapters = archive.chapters(opts.accept_extended_image_formats, opts.simple_sorting);
+
+        let stem = input.file_stem().unwrap().to_string_lossy().to_string();
+
+        // Number of the chapter a page belongs to, taken from its directory's name
+        let chapter_number = |pages: Option<&Vec<archive::ArchiveEntry>>| {
+            pages
+                .and_then(|pages| pages[0].path.parent())
+                .and_then(|dir| dir.file_name())
+                .and_then(|dir| numbers_in(&dir.to_string_lossy()).pop())
+        };
+
+        let start = chapter_number(chapters.first());
+        let end = chapter_number(chapters.last());
+        let pages = chapters.iter().map(Vec::len).sum::<usize>();
+
+        let value = |name: &str| -> Option<String> {
+            match name {
+                "name" => Some(stem.clone()),
+                "start" => start.clone(),
+                "end" => end.clone(),
+                "pages" => Some(pages.to_string()),
+                "series" => comic_info
+                    .series
+                    .clone()
+                    .or_else(|| series_from_name(&stem)),
+                "volume" => comic_info
+                    .volume
+                    .clone()
+                    .or_else(|| numbers_in(&stem).into_iter().next()),
+                _ => ComicMetadata::FIELD_NAMES
+                    .iter()
+                    .find(|field| field.to_lowercase() == name.to_lowercase().replace('_', ""))
+                    .and_then(|field| {
+                        comic_info
+                            .fields()
+                            .into_iter()
+                            .find(|(name, _)| name == field)
+                            .and_then(|(_, value)| value.clone())
+                    }),
+            }
+        };
+
+        let name = template::render_template(&opts.template, value)
+            .map_err(|err| RenameError::InvalidTemplate(input.clone(), err))?;
+
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(RenameError::EmptyName(input.clone()));
+        }
+
+        // Path separators would move the comic book to another directory, and some names can't be used on Windows
+        let mut file_name = deter::sanitize_filename(name);
+
+        if let Some(ext) = input.extension() {
+            file_name.push('.');
+            file_name.push_str(&ext.to_string_lossy());
+        }
+
+        let target = input.with_file_name(file_name);
+
+        if !targets.insert(target.clone()) {
+            return Err(RenameError::DuplicateName(target));
+        }
+
+        if &target != input && target.exists() && !opts.overwrite {
+            return Err(RenameError::OutputFileAlreadyExists(target));
+        }
+
+        renames.push((input, target));
+    }
+
+    let mut renamed = vec![];
+
+    for (input, target) in renames {
+        if input == &target {
+            debug!("Keeping '{}' as it is", input.to_string_lossy());
+            continue;
+        }
+
+        info!(
+            "{} '{}' => '{}'",
+            if opts.dry_run {
+                "Would rename"
+            } else {
+                "Renaming"
+            },
+            input.to_string_lossy(),
+            target.file_name().unwrap().to_string_lossy()
+        );
+
+        if !opts.dry_run {
+            fs::rename(input, &target)
+                .map_err(|err| RenameError::FailedToRenameArchive(input.clone(), err))?;
+        }
+
+        renamed.push(target);
+    }
+
+    if opts.dry_run {
+        info!(
+            "{} comic book{} would be renamed (dry run).",
+            renamed.len(),
+            if renamed.len() > 1 { "s" } else { "" }
+        );
+
+        return Ok(vec![]);
+    }
+
+    info!(
+        "Successfully renamed {} comic book{}.",
+        renamed.len(),
+        if renamed.len() > 1 { "s" } else { "" }
+    );
+
+    Ok(renamed)
+}
+
+/// Guess a series' name from a comic book's name, e.g. "Berserk" for "Berserk - Vol. 03"
+fn series_from_name(name: &str) -> Option<String> {
+    let before_number = name.split(|c: char| c.is_ascii_digit()).next()?;
+    let mut series = before_number.trim_end_matches(|c: char| !c.is_alphanumeric());
+
+    // Remove the volume marker preceding the number, if any
+    if let Some((head, marker)) = series.rsplit_once(|c: char| !c.is_alphanumeric()) {
+        if ["v", "vol", "volume", "t", "tome"].contains(&marker.to_lowercase().as_str()) {
+            series = head.trim_end_matches(|c: char| !c.is_alphanumeric());
+        }
+    }
+
+    Some(series.to_owned()).filter(|series| !series.is_empty())
+}
+
+/// List the numbers in a string, without their leading zeros
+fn numbers_in(string: &str) -> Vec<String> {
+    string
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|number| !number.is_empty())
+        .map(|number| match number.trim_start_matches('0') {
+            "" => "0".to_owned(),
+            trimmed => trimmed.to_owned(),
+        })
+        .collect()
+}
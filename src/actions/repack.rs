@@ -0,0 +1,348 @@
+use crate::actions::decode;
+use crate::cli::error::RepackError;
+use crate::cli::opts::{Decode, Repack};
+use crate::lib::archive::{self, ArchiveFormat, ComicArchive, CopyErr};
+use crate::lib::convert;
+use crate::lib::deter;
+use crate::lib::metadata::COMIC_INFO_FILENAME;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Instant;
+use zip::write::{FileOptions, ZipWriter};
+
+/// Metadata carried over from the original comic book
+struct Metadata {
+    comment: Vec<u8>,
+    comic_info: Option<Vec<u8>>,
+}
+
+/// Decode comic books and rebuild them as CBZ files
+pub fn repack(opts: &Repack) -> Result<Vec<PathBuf>, RepackError> {
+    let cwd = env::current_dir().map_err(RepackError::FailedToGetCWD)?;
+
+    let inputs = opts
+        .inputs
+        .iter()
+        .map(|input| cwd.join(input))
+        .collect::<Vec<_>>();
+
+    let archives =
+        archive::find_decodable_archives(&inputs).map_err(RepackError::FailedToFindArchives)?;
+
+    if archives.is_empty() {
+        return Err(RepackError::NoArchiveFound);
+    }
+
+    let mut repacked = vec![];
+    let mut failed = 0;
+
+    for (i, input) in archives.iter().enumerate() {
+        debug!(
+            "Repacking comic book {}/{}: '{}'...",
+            i + 1,
+            archives.len(),
+            input.to_string_lossy()
+        );
+
+        match repack_archive(opts, input, i) {
+            Ok(output) => repacked.push(output),
+            Err(err) => {
+                error!("{}", err);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(RepackError::FailedArchives(failed, archives.len()));
+    }
+
+    info!(
+        "Successfully repacked {} comic book{}.",
+        repacked.len(),
+        if repacked.len() > 1 { "s" } else { "" }
+    );
+
+    Ok(repacked)
+}
+
+/// Repack a single comic book, using a temporary directory to extract its pages
+fn repack_archive(opts: &Repack, input: &Path, i: usize) -> Result<PathBuf, RepackError> {
+    let started = Instant::now();
+
+    // ZIP archives are replaced, other formats get a new CBZ file next to them
+    let output = match ArchiveFormat::from_path(input) {
+        Some(_) => input.to_path_buf(),
+        None => input.with_extension("cbz"),
+    };
+
+    if output != input && output.exists() && !opts.overwrite {
+        return Err(RepackError::OutputFileAlreadyExists(output));
+    }
+
+    let metadata = read_metadata(input)?;
+
+    let tmp_dir = env::temp_dir().join(format!("comic-enc-repack-{}-{}", process::id(), i));
+
+    let result = decode_pages(opts, input, &tmp_dir)
+        .and_then(|pages| write_archive(opts, input, &output, &pages, metadata));
+
+    trace!("Removing temporary directory...");
+
+    if let Err(err) = fs::remove_dir_all(&tmp_dir) {
+        warn!(
+            "Failed to remove temporary directory '{}': {}",
+            tmp_dir.to_string_lossy(),
+            err
+        );
+    }
+
+    // Don't leave a partial archive behind
+    if result.is_err() {
+        let staging_path = archive::staging_path(&output);
+
+        if staging_path.exists() {
+            if let Err(err) = fs::remove_file(&staging_path) {
+                warn!(
+                    "Failed to remove staging file '{}': {}",
+                    staging_path.to_string_lossy(),
+                    err
+                );
+            }
+        }
+    }
+
+    let pages = result?;
+
+    if output != input && opts.remove_original {
+        fs::remove_file(input)
+            .map_err(|err| RepackError::FailedToRemoveOriginal(input.to_path_buf(), err))?;
+    }
+
+    let elapsed = started.elapsed();
+
+    info!(
+        "Repacked '{}' to '{}' ({} pages) in {}.{:03} s.",
+        input.to_string_lossy(),
+        output.to_string_lossy(),
+        pages,
+        elapsed.as_secs(),
+        elapsed.subsec_millis()
+    );
+
+    Ok(output)
+}
+
+/// Read the metadata to carry over from a ZIP archive
+/// Other formats have no metadata that can be kept
+fn read_metadata(input: &Path) -> Result<Option<Metadata>, RepackError> {
+    if ArchiveFormat::from_path(input).is_none() {
+        return Ok(None);
+    }
+
+    let mut archive = ComicArchive::open(input)
+        .map_err(|err| RepackError::FailedToReadMetadata(input.to_path_buf(), err))?;
+
+    let comic_info = archive
+        .find_root_entry(COMIC_INFO_FILENAME)
+        .map(|entry| entry.index)
+        .map(|index| archive.read_entry(index))
+        .transpose()
+        .map_err(|err| RepackError::FailedToReadMetadata(input.to_path_buf(), err))?;
+
+    Ok(Some(Metadata {
+        comment: archive.comment().to_vec(),
+        comic_info,
+    }))
+}
+
+/// Extract the pages of a comic book to a temporary directory
+/// The extracted pages are returned in reading order
+fn decode_pages(opts: &Repack, input: &Path, tmp_dir: &Path) -> Result<Vec<PathBuf>, RepackError> {
+    let dec = Decode {
+        inputs: vec![input.to_path_buf()],
+        output: Some(tmp_dir.to_path_buf()),
+        create_output_dir: true,
+        extract_images_only: true,
+        extras_dir: None,
+        accept_extended_image_formats: opts.accept_extended_image_formats,
+        simple_sorting: false,
+        skip_bad_pdf_pages: false,
+        pages: None,
+        flatten: true,
+        keep_structure: false,
+        name_template: None,
+        jobs: None,
+        no_metadata: true,
+    };
+
+    let mut pages =
+        decode(&dec).map_err(|err| RepackError::FailedToDecode(input.to_path_buf(), err))?;
+
+    // Pages are named after their number, but are not always extracted in order
+    deter::natural_sort_by_path(&mut pages, |page| page);
+
+    Ok(pages)
+}
+
+/// Write the repacked archive, through a staging file which then replaces the output file at once
+/// Returns the number of pages in the repacked comic book
+fn write_archive(
+    opts: &Repack,
+    input: &Path,
+    output: &Path,
+    pages: &[PathBuf],
+    metadata: Option<Metadata>,
+) -> Result<usize, RepackError> {
+    let staging_path = archive::staging_path(output);
+
+    let staging_file = File::create(&staging_path)
+        .map_err(|err| RepackError::FailedToCreateStagingFile(staging_path.clone(), err))?;
+
+    let mut zip_writer = archive::buffered_zip_writer(staging_file);
+
+    let zip_options =
+        FileOptions::default().compression_method(convert::compression_method(&opts.conversion));
+
+    // Get the number of characters the last page takes to display
+    let page_num_len = pages.len().to_string().len();
+
+    // Buffer used to copy the pages that don't need to be converted
+    let mut copy_buffer = vec![0; archive::COPY_BUFFER_SIZE];
+
+    for (i, page) in pages.iter().enumerate() {
+        let mut path_in_zip = PathBuf::from(format!(
+            "{:0page_num_len$}",
+            i + 1,
+            page_num_len = page_num_len
+        ));
+
+        if let Some(ext) = page.extension() {
+            path_in_zip.set_extension(ext);
+        }
+
+        if !convert::needs_conversion(&opts.conversion, page) {
+            copy_page_to_zip(
+                &mut zip_writer,
+                zip_options,
+                input,
+                page,
+                &path_in_zip,
+                &mut copy_buffer,
+            )?;
+
+            continue;
+        }
+
+        trace!("Converting page {}...", i + 1);
+
+        let content =
+            fs::read(page).map_err(|err| RepackError::FailedToReadPage(page.clone(), err))?;
+
+        let content = convert::convert_page(&opts.conversion, &content).map_err(|err| {
+            RepackError::FailedToConvertImage {
+                archive: input.to_path_buf(),
+                image_path: path_in_zip.clone(),
+                err,
+            }
+        })?;
+
+        if let Some(ext) = convert::converted_ext(&opts.conversion) {
+            path_in_zip.set_extension(ext);
+        }
+
+        write_file_to_zip(&mut zip_writer, zip_options, input, &path_in_zip, &content)?;
+    }
+
+    if let Some(metadata) = metadata {
+        // Keep the archive's comment, which may contain metadata
+        zip_writer.set_comment(String::from_utf8_lossy(&metadata.comment).to_string());
+
+        if let Some(comic_info) = metadata.comic_info {
+            write_file_to_zip(
+                &mut zip_writer,
+                zip_options,
+                input,
+                Path::new(COMIC_INFO_FILENAME),
+                &comic_info,
+            )?;
+        }
+    }
+
+    trace!("Closing ZIP archive...");
+
+    archive::finish_buffered_zip(zip_writer)
+        .map_err(|err| RepackError::FailedToCloseZipArchive(input.to_path_buf(), err))?
+        .sync_all()
+        .map_err(|err| RepackError::FailedToCreateStagingFile(staging_path.clone(), err))?;
+
+    // Replace the output file at once
+    archive::finalize_staging_file(&staging_path, output)
+        .map_err(|err| RepackError::FailedToReplaceArchive(output.to_path_buf(), err))?;
+
+    Ok(pages.len())
+}
+
+/// Create a file in the staging archive
+fn start_file_in_zip(
+    zip_writer: &mut ZipWriter<BufWriter<File>>,
+    zip_options: FileOptions,
+    input: &Path,
+    path_in_zip: &Path,
+) -> Result<(), RepackError> {
+    zip_writer
+        .start_file(path_in_zip.to_string_lossy(), zip_options)
+        .map_err(|err| RepackError::FailedToCreateFileInZip {
+            archive: input.to_path_buf(),
+            file_path: path_in_zip.to_path_buf(),
+            err,
+        })
+}
+
+/// Copy a page that doesn't need to be converted to the staging archive, without loading it in memory
+fn copy_page_to_zip(
+    zip_writer: &mut ZipWriter<BufWriter<File>>,
+    zip_options: FileOptions,
+    input: &Path,
+    page: &Path,
+    path_in_zip: &Path,
+    buffer: &mut [u8],
+) -> Result<(), RepackError> {
+    let mut file =
+        File::open(page).map_err(|err| RepackError::FailedToReadPage(page.to_path_buf(), err))?;
+
+    start_file_in_zip(zip_writer, zip_options, input, path_in_zip)?;
+
+    archive::copy_to_zip(&mut file, zip_writer, buffer).map_err(|err| match err {
+        CopyErr::ReadError(err) => RepackError::FailedToReadPage(page.to_path_buf(), err),
+        CopyErr::WriteError(err) => RepackError::FailedToWriteFileToZip {
+            archive: input.to_path_buf(),
+            file_path: path_in_zip.to_path_buf(),
+            err,
+        },
+    })?;
+
+    Ok(())
+}
+
+/// Write a file to the staging archive
+fn write_file_to_zip(
+    zip_writer: &mut ZipWriter<BufWriter<File>>,
+    zip_options: FileOptions,
+    input: &Path,
+    path_in_zip: &Path,
+    content: &[u8],
+) -> Result<(), RepackError> {
+    start_file_in_zip(zip_writer, zip_options, input, path_in_zip)?;
+
+    zip_writer
+        .write_all(content)
+        .map_err(|err| RepackError::FailedToWriteFileToZip {
+            archive: input.to_path_buf(),
+            file_path: path_in_zip.to_path_buf(),
+            err,
+        })
+}
@@ -0,0 +1,453 @@
+use crate::actions::compile_volumes;
+use crate::cli::error::ServeError;
+use crate::cli::exit_code::{ExitCode, ExitCodeError};
+use crate::cli::opts::Serve;
+use crate::lib::cancel::CancellationToken;
+use crate::lib::chapter::ChapterNumber;
+use crate::lib::progress::ProgressSink;
+use crate::lib::queue::{JobQueue, QueuedJob};
+use crate::lib::serve::{
+    self, HttpErr, JobOptions, JobParams, JobState, JobStatus, RpcError, RpcRequest, RpcResponse,
+    RPC_PATH,
+};
+use crate::lib::summary::VolumeSummary;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::io::BufReader;
+use std::net::{TcpListener, TcpStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+/// Time after which a connection that doesn't send its request is closed
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Compilation job submitted to the server
+struct Job {
+    options: JobOptions,
+    status: Mutex<JobStatus>,
+    cancel: CancellationToken,
+}
+
+impl Job {
+    /// Update the job's status
+    fn update<T>(&self, update: impl FnOnce(&mut JobStatus) -> T) -> T {
+        update(
+            &mut self
+                .status
+                .lock()
+                .expect("Internal error: job's lock is poisoned"),
+        )
+    }
+
+    /// Get the job's status
+    fn status(&self) -> JobStatus {
+        self.status
+            .lock()
+            .expect("Internal error: job's lock is poisoned")
+            .clone()
+    }
+}
+
+impl ProgressSink for Job {
+    fn volume_started(&self, _volume: usize, _chapters: usize) {
+        self.update(|status| status.volumes_started += 1);
+    }
+
+    fn page_processed(
+        &self,
+        _volume: usize,
+        _chapter: ChapterNumber,
+        _page: usize,
+        _source: &Path,
+        size: u64,
+    ) {
+        self.update(|status| {
+            status.pages += 1;
+            status.size += size;
+        });
+    }
+
+    fn volume_finished(&self, _summary: &VolumeSummary) {
+        self.update(|status| status.volumes_finished += 1);
+    }
+
+    fn volume_skipped(&self, _volume: usize, _path: &Path) {
+        self.update(|status| status.volumes_finished += 1);
+    }
+
+    fn warning(&self, message: &str) {
+        warn!("Warning: {}", message);
+        self.update(|status| status.warnings.push(message.to_owned()));
+    }
+}
+
+/// Jobs submitted to the server, by identifier, built one at a time in submission order
+struct Jobs {
+    jobs: Mutex<BTreeMap<u64, Arc<Job>>>,
+    /// Identifier of the next submitted job
+    next_job: AtomicU64,
+    /// Notified when a job is submitted, to wake the worker up
+    submitted: Condvar,
+    /// File the queue is kept in
+    queue_file: PathBuf,
+}
+
+impl Jobs {
+    /// Restore the jobs of a queue
+    fn new(queue: JobQueue, queue_file: PathBuf) -> Self {
+        let jobs = queue
+            .jobs
+            .into_iter()
+            .map(|queued| {
+                (
+                    queued.status.job,
+                    Arc::new(Job {
+                        options: queued.options,
+                        status: Mutex::new(queued.status),
+                        cancel: CancellationToken::new(),
+                    }),
+                )
+            })
+            .collect();
+
+        Self {
+            jobs: Mutex::new(jobs),
+            next_job: AtomicU64::new(queue.next_job),
+            submitted: Condvar::new(),
+            queue_file,
+        }
+    }
+
+    /// Get a job from its identifier
+    fn get(&self, job: u64) -> Option<Arc<Job>> {
+        self.lock().get(&job).cloned()
+    }
+
+    /// Get the status of all jobs
+    fn statuses(&self) -> Vec<JobStatus> {
+        self.lock().values().map(|job| job.status()).collect()
+    }
+
+    /// Add a new job at the end of the queue
+    fn submit(&self, options: JobOptions) -> u64 {
+        let id = self.next_job.fetch_add(1, Ordering::SeqCst);
+
+        self.lock().insert(
+            id,
+            Arc::new(Job {
+                status: Mutex::new(JobStatus::new(id, options.encoding.input.clone())),
+                options,
+                cancel: CancellationToken::new(),
+            }),
+        );
+
+        self.save();
+        self.submitted.notify_one();
+
+        id
+    }
+
+    /// Cancel a job: a pending one is never run, while a running one stops between two pages
+    fn cancel(&self, job: &Job) {
+        job.cancel.cancel();
+
+        job.update(|status| {
+            if status.state == JobState::Pending {
+                status.state = JobState::Cancelled;
+            }
+        });
+
+        self.save();
+    }
+
+    /// Remove the finished jobs from the queue, returning how many were removed
+    fn clear(&self) -> usize {
+        let removed = {
+            let mut jobs = self.lock();
+            let count = jobs.len();
+            jobs.retain(|_, job| !job.status().is_finished());
+            count - jobs.len()
+        };
+
+        self.save();
+
+        removed
+    }
+
+    /// Wait for the oldest pending job
+    fn next_pending(&self) -> Arc<Job> {
+        let mut jobs = self.lock();
+
+        loop {
+            if let Some(job) = jobs
+                .values()
+                .find(|job| job.status().state == JobState::Pending)
+            {
+                return Arc::clone(job);
+            }
+
+            jobs = self
+                .submitted
+                .wait(jobs)
+                .expect("Internal error: jobs' lock is poisoned");
+        }
+    }
+
+    /// Write the queue to its file
+    /// Failures are only reported, as the jobs can still be built
+    fn save(&self) {
+        let queue = JobQueue {
+            next_job: self.next_job.load(Ordering::SeqCst),
+            jobs: self
+                .lock()
+                .values()
+                .map(|job| QueuedJob {
+                    options: job.options.clone(),
+                    status: job.status(),
+                })
+                .collect(),
+        };
+
+        if let Err(err) = queue.save(&self.queue_file) {
+            warn!(
+                "Warning: failed to save job queue to '{}': {}",
+                self.queue_file.to_string_lossy(),
+                err
+            );
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<BTreeMap<u64, Arc<Job>>> {
+        self.jobs
+            .lock()
+            .expect("Internal error: jobs' lock is poisoned")
+    }
+}
+
+/// Run a local server exposing a JSON-RPC API to submit compilation jobs, follow their progress and cancel them
+pub fn serve(opts: &Serve) -> Result<Vec<PathBuf>, ServeError> {
+    let cwd = env::current_dir().map_err(ServeError::FailedToGetCWD)?;
+    let queue_file = cwd.join(&opts.queue_file);
+
+    let queue = JobQueue::load(&queue_file)
+        .map_err(|err| ServeError::FailedToLoadQueue(queue_file.clone(), err))?;
+
+    let listener = TcpListener::bind(&opts.address)
+        .map_err(|err| ServeError::FailedToListen(opts.address.clone(), err))?;
+
+    let address = listener
+        .local_addr()
+        .map_err(|err| ServeError::FailedToListen(opts.address.clone(), err))?;
+
+    if !address.ip().is_loopback() {
+        warn!(
+            "Warning: the API has no authentication, anyone able to reach '{}' can submit jobs",
+            address
+        );
+    }
+
+    let jobs = Arc::new(Jobs::new(queue, queue_file));
+
+    let pending = jobs
+        .statuses()
+        .iter()
+        .filter(|status| status.state == JobState::Pending)
+        .count();
+
+    if pending > 0 {
+        info!(
+            "Resuming {} pending job{}...",
+            pending,
+            if pending > 1 { "s" } else { "" }
+        );
+    }
+
+    // Jobs are built one at a time, as each one already builds its volumes in parallel
+    let worker = Arc::clone(&jobs);
+    thread::spawn(move || loop {
+        run_job(&worker, &worker.next_pending());
+    });
+
+    info!("Listening on 'http://{}{}'...", address, RPC_PATH);
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let jobs = Arc::clone(&jobs);
+                thread::spawn(move || handle_connection(stream, &jobs));
+            }
+
+            Err(err) => warn!("Warning: failed to accept connection: {}", err),
+        }
+    }
+
+    Ok(vec![])
+}
+
+/// Build the volumes of a job, recording its outcome in its status
+fn run_job(jobs: &Jobs, job: &Job) {
+    let id = job.status().job;
+
+    // The job may have been cancelled since it was picked
+    if !job.update(|status| {
+        let pending = status.state == JobState::Pending;
+
+        if pending {
+            status.state = JobState::Running;
+        }
+
+        pending
+    }) {
+        return;
+    }
+
+    jobs.save();
+
+    info!(
+        "Job {}: compiling '{}'...",
+        id,
+        job.options.encoding.input.to_string_lossy()
+    );
+
+    // A panicking job must not leave its status as running forever
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        compile_volumes(
+            &job.options.compilation,
+            &job.options.encoding,
+            job,
+            &job.cancel,
+        )
+    }));
+
+    job.update(|status| match outcome {
+        Ok(Ok(report)) => {
+            info!("Job {}: done.", id);
+            status.state = JobState::Done;
+            status.exit_code = Some(0);
+            status.report = Some(report);
+        }
+
+        Ok(Err(err)) => {
+            let exit_code = err.exit_code();
+            error!("Job {} failed: {}", id, err.message());
+
+            status.state = if exit_code == ExitCode::Cancelled {
+                JobState::Cancelled
+            } else {
+                JobState::Failed
+            };
+            status.exit_code = Some(exit_code.code(job.options.compilation.keep_going));
+            status.error = Some(err.message());
+        }
+
+        Err(_) => {
+            error!("Job {} failed: the encoder panicked", id);
+            status.state = JobState::Failed;
+            status.exit_code = Some(ExitCode::Failure.code(false));
+            status.error = Some("Internal error: the encoder panicked".to_owned());
+        }
+    });
+
+    jobs.save();
+}
+
+/// Answer the request of a connection
+fn handle_connection(stream: TcpStream, jobs: &Jobs) {
+    if let Err(err) = stream.set_read_timeout(Some(READ_TIMEOUT)) {
+        warn!("Warning: failed to set connection's timeout: {}", err);
+    }
+
+    let mut reader = BufReader::new(&stream);
+
+    let (status, response) = match serve::read_request(&mut reader) {
+        Ok(request) if request.path != RPC_PATH => (
+            404,
+            RpcResponse::failure(
+                Value::Null,
+                RpcError::invalid_request(format!(
+                    "unknown path '{}' (expected '{}')",
+                    request.path, RPC_PATH
+                )),
+            ),
+        ),
+
+        Ok(request) if request.method != "POST" => (
+            405,
+            RpcResponse::failure(
+                Value::Null,
+                RpcError::invalid_request("requests must be sent with POST"),
+            ),
+        ),
+
+        Ok(request) => (
+            200,
+            match serde_json::from_slice::<RpcRequest>(&request.body) {
+                Ok(request) => call(jobs, request),
+                Err(err) => RpcResponse::failure(Value::Null, RpcError::parse_error(err)),
+            },
+        ),
+
+        Err(HttpErr::IOError(err)) => {
+            debug!("Failed to read request: {}", err);
+            return;
+        }
+
+        Err(err) => (
+            err.status(),
+            RpcResponse::failure(Value::Null, RpcError::invalid_request(&err)),
+        ),
+    };
+
+    let body = serde_json::to_vec(&response).expect("Internal error: failed to serialize response");
+
+    if let Err(err) = serve::write_response(&mut &stream, status, &body) {
+        debug!("Failed to write response: {}", err);
+    }
+}
+
+/// Call a method of the API
+fn call(jobs: &Jobs, request: RpcRequest) -> RpcResponse {
+    trace!("Calling method '{}'...", request.method);
+
+    let result = match request.method.as_str() {
+        "submit" => serde_json::from_value::<JobOptions>(request.params)
+            .map_err(RpcError::invalid_params)
+            .map(|options| serde_json::json!({ "job": jobs.submit(options) })),
+
+        "status" => job_params(request.params, jobs).map(|job| to_value(&job.status())),
+
+        "list" => Ok(to_value(&jobs.statuses())),
+
+        "cancel" => job_params(request.params, jobs).map(|job| {
+            jobs.cancel(&job);
+            to_value(&job.status())
+        }),
+
+        "clear" => Ok(serde_json::json!({ "removed": jobs.clear() })),
+
+        method => Err(RpcError::method_not_found(method)),
+    };
+
+    match result {
+        Ok(result) => RpcResponse::success(request.id, result),
+        Err(err) => RpcResponse::failure(request.id, err),
+    }
+}
+
+/// Get the job targeted by a method's parameters
+fn job_params(params: Value, jobs: &Jobs) -> Result<Arc<Job>, RpcError> {
+    let params = serde_json::from_value::<JobParams>(params).map_err(RpcError::invalid_params)?;
+
+    jobs.get(params.job)
+        .ok_or_else(|| RpcError::job_not_found(params.job))
+}
+
+/// Serialize a method's result
+fn to_value(value: &impl serde::Serialize) -> Value {
+    serde_json::to_value(value).expect("Internal error: failed to serialize result")
+}
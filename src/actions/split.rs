@@ -0,0 +1,234 @@
+use crate::cli::error::SplitError;
+use crate::cli::opts::Split;
+use crate::lib::archive::{self, ArchiveEntry, ComicArchive};
+use crate::lib::metadata::{self, PageMetadata, COMIC_INFO_FILENAME};
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Instant;
+use zip::write::{FileOptions, ZipWriter};
+
+/// Split a comic book into multiple parts, at chapter boundaries when possible
+pub fn split(opts: &Split) -> Result<Vec<PathBuf>, SplitError> {
+    let started = Instant::now();
+
+    let cwd = env::current_dir().map_err(SplitError::FailedToGetCWD)?;
+    let input = cwd.join(&opts.input);
+
+    if !input.exists() {
+        return Err(SplitError::InputFileNotFound(input));
+    } else if input.is_dir() {
+        return Err(SplitError::InputFileIsADirectory(input));
+    }
+
+    // Weight of a single page, compared to the maximum weight of a part
+    let (limit, page_weight): (u64, fn(&ArchiveEntry) -> u64) =
+        match (opts.max_pages, opts.max_size) {
+            (Some(max_pages), _) => (max_pages as u64, |_| 1),
+            (None, Some(max_size)) => (max_size * 1024 * 1024, |page| page.compressed_size),
+            (None, None) => unreachable!(),
+        };
+
+    if limit == 0 {
+        return Err(SplitError::InvalidLimit);
+    }
+
+    // Create the output directory if needed
+    let output_dir = match &opts.output {
+        Some(output) => {
+            let output = cwd.join(output);
+
+            if !output.is_dir() {
+                if opts.create_output_dir {
+                    fs::create_dir_all(&output)
+                        .map_err(SplitError::FailedToCreateOutputDirectory)?
+                } else {
+                    return Err(SplitError::OutputDirectoryNotFound);
+                }
+            }
+
+            output
+        }
+
+        None => input.parent().unwrap().to_path_buf(),
+    };
+
+    let mut archive = ComicArchive::open(&input).map_err(SplitError::FailedToOpenArchive)?;
+
+    let comic_info = metadata::read_archive_metadata(&mut archive)
+        .map_err(SplitError::FailedToReadMetadata)?
+        .comic_info;
+
+    let chapters = archive.chapters(opts.accept_extended_image_formats, opts.simple_sorting);
+
+    if chapters.is_empty() {
+        return Err(SplitError::NoPageFound);
+    }
+
+    // Distribute the chapters between parts, only splitting chapters that don't fit in a single part
+    let mut parts: Vec<Vec<ArchiveEntry>> = vec![];
+    let mut part = vec![];
+    let mut part_weight = 0;
+
+    for chapter in chapters {
+        let chapter_weight = chapter.iter().map(page_weight).sum::<u64>();
+
+        if !part.is_empty() && part_weight + chapter_weight > limit {
+            parts.push(part);
+            part = vec![];
+            part_weight = 0;
+        }
+
+        if chapter_weight <= limit {
+            part.extend(chapter);
+            part_weight += chapter_weight;
+            continue;
+        }
+
+        debug!(
+            "Chapter '{}' does not fit in a single part, splitting it...",
+            chapter[0]
+                .path
+                .parent()
+                .unwrap_or(&chapter[0].path)
+                .to_string_lossy()
+        );
+
+        for page in chapter {
+            let weight = page_weight(&page);
+
+            if !part.is_empty() && part_weight + weight > limit {
+                parts.push(part);
+                part = vec![];
+                part_weight = 0;
+            }
+
+            part.push(page);
+            part_weight += weight;
+        }
+    }
+
+    if !part.is_empty() {
+        parts.push(part);
+    }
+
+    let stem = input.file_stem().unwrap().to_string_lossy().to_string();
+    let ext = input.extension().unwrap().to_string_lossy().to_string();
+
+    // Length of the displayable part number
+    let part_num_len = parts.len().to_string().len();
+
+    let outputs = (1..=parts.len())
+        .map(|part| {
+            output_dir.join(format!(
+                "{} - Part {:0part_num_len$}.{}",
+                stem,
+                part,
+                ext,
+                part_num_len = part_num_len
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    if !opts.overwrite {
+        if let Some(output) = outputs.iter().find(|output| output.exists()) {
+            return Err(SplitError::OutputFileAlreadyExists(output.clone()));
+        }
+    }
+
+    // Index of the first page of the current part in the original comic book
+    let mut first_page = 0;
+
+    for (i, (pages, output)) in parts.iter().zip(outputs.iter()).enumerate() {
+        let part = i + 1;
+
+        info!(
+            "Writing part {:0part_num_len$} / {} ({} pages) to '{}'...",
+            part,
+            parts.len(),
+            pages.len(),
+            output.to_string_lossy(),
+            part_num_len = part_num_len
+        );
+
+        let staging_path = archive::staging_path(output);
+
+        let staging_file = File::create(&staging_path)
+            .map_err(|err| SplitError::FailedToCreateStagingFile(staging_path.clone(), err))?;
+
+        let mut zip_writer = ZipWriter::new(staging_file);
+
+        for page in pages {
+            let path_in_zip = page.path.to_string_lossy().replace('\\', "/");
+
+            trace!("Copying page '{}'...", path_in_zip);
+
+            archive
+                .raw_copy_entry(page.index, &mut zip_writer, &path_in_zip)
+                .map_err(|err| SplitError::FailedToCopyFileToZip {
+                    part,
+                    file_path: page.path.clone(),
+                    err,
+                })?;
+        }
+
+        // Keep the original metadata, with the bookmarks that belong to this part
+        if let Some(comic_info) = &comic_info {
+            let mut part_info = comic_info.clone();
+
+            part_info.page_count = Some(pages.len().to_string());
+            part_info.pages = comic_info
+                .pages
+                .iter()
+                .filter(|page| {
+                    page.bookmark.is_some()
+                        && page.image >= first_page
+                        && page.image < first_page + pages.len()
+                })
+                .map(|page| PageMetadata {
+                    image: page.image - first_page,
+                    bookmark: page.bookmark.clone(),
+                    ..PageMetadata::default()
+                })
+                .collect();
+
+            trace!("Adding metadata file to ZIP archive...");
+
+            zip_writer
+                .start_file(COMIC_INFO_FILENAME, FileOptions::default())
+                .map_err(|err| SplitError::FailedToCreateMetadataFileInZip(part, err))?;
+
+            zip_writer
+                .write_all(part_info.to_comic_info_xml().as_bytes())
+                .map_err(|err| SplitError::FailedToWriteMetadataFileToZip(part, err))?;
+        }
+
+        trace!("Closing ZIP archive...");
+
+        zip_writer
+            .finish()
+            .map_err(|err| SplitError::FailedToCloseZipArchive(part, err))?;
+
+        if output.exists() {
+            fs::remove_file(output)
+                .map_err(|err| SplitError::FailedToRenameCompleteArchive(output.clone(), err))?;
+        }
+
+        archive::finalize_staging_file(&staging_path, output)
+            .map_err(|err| SplitError::FailedToRenameCompleteArchive(output.clone(), err))?;
+
+        first_page += pages.len();
+    }
+
+    let elapsed = started.elapsed();
+
+    info!(
+        "Successfully split comic book into {} parts in {}.{:03} s.",
+        parts.len(),
+        elapsed.as_secs(),
+        elapsed.subsec_millis()
+    );
+
+    Ok(outputs)
+}
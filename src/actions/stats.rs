@@ -0,0 +1,121 @@
+use crate::cli::error::StatsError;
+use crate::cli::opts::Stats;
+use crate::lib::archive;
+use crate::lib::deter;
+use crate::lib::stats::LibraryStats;
+use std::env;
+
+/// Display statistics about existing comic books
+pub fn stats(opts: &Stats) -> Result<LibraryStats, StatsError> {
+    let cwd = env::current_dir().map_err(StatsError::FailedToGetCWD)?;
+
+    let inputs = opts
+        .inputs
+        .iter()
+        .map(|input| cwd.join(input))
+        .collect::<Vec<_>>();
+
+    let archives = archive::find_archives(&inputs).map_err(StatsError::FailedToFindArchives)?;
+
+    if archives.is_empty() {
+        return Err(StatsError::NoArchiveFound);
+    }
+
+    let mut stats = LibraryStats::default();
+
+    for (i, path) in archives.iter().enumerate() {
+        debug!(
+            "Inspecting comic book {}/{}: '{}'...",
+            i + 1,
+            archives.len(),
+            path.to_string_lossy()
+        );
+
+        stats
+            .add_archive(path, opts.accept_extended_image_formats)
+            .map_err(|err| StatsError::FailedToInspectArchive(path.clone(), err))?;
+    }
+
+    for source in &opts.source {
+        let source = cwd.join(source);
+
+        if !source.is_dir() {
+            return Err(StatsError::SourceDirectoryNotFound(source));
+        }
+
+        debug!("Reading source directory '{}'...", source.to_string_lossy());
+
+        stats
+            .add_source(&source, opts.accept_extended_image_formats)
+            .map_err(|err| StatsError::FailedToReadSource(source.clone(), err))?;
+    }
+
+    if opts.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&stats).map_err(StatsError::FailedToSerializeStats)?
+        );
+    } else {
+        print_stats(&stats);
+    }
+
+    Ok(stats)
+}
+
+/// Format a size ratio as a percentage
+fn format_ratio(size: u64, reference: u64) -> String {
+    if reference > 0 {
+        format!("{:.1}%", size as f64 * 100.0 / reference as f64)
+    } else {
+        "-".to_owned()
+    }
+}
+
+/// Print statistics in a human-readable form
+fn print_stats(stats: &LibraryStats) {
+    println!("Comics       : {}", stats.comics);
+    println!("Pages        : {}", stats.pages);
+    println!("File size    : {}", deter::format_size(stats.file_size));
+    println!(
+        "Uncompressed : {}",
+        deter::format_size(stats.uncompressed_size)
+    );
+    println!(
+        "Compressed   : {} ({})",
+        deter::format_size(stats.compressed_size),
+        format_ratio(stats.compressed_size, stats.uncompressed_size)
+    );
+
+    if let Some(source_size) = stats.source_size {
+        println!(
+            "Source size  : {} (comics are {} of it)",
+            deter::format_size(source_size),
+            format_ratio(stats.file_size, source_size)
+        );
+    }
+
+    println!(
+        "Average page : {}",
+        match &stats.average_resolution {
+            Some(resolution) => format!("{}x{}", resolution.width, resolution.height),
+            None => "-".to_owned(),
+        }
+    );
+
+    println!();
+    println!(
+        "{:<10} {:>8} {:>14} {:>14} {:>8}",
+        "Format", "Pages", "Uncompressed", "Compressed", "Ratio"
+    );
+
+    for (format, format_stats) in &stats.formats {
+        println!(
+            "{:<10} {:>8} {:>14} {:>14} {:>8}",
+            format,
+            format_stats.pages,
+            deter::format_size(format_stats.uncompressed_size),
+            deter::format_size(format_stats.compressed_size),
+            format_ratio(format_stats.compressed_size, format_stats.uncompressed_size)
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::cli::error::ThumbnailsError;
+use crate::cli::opts::Thumbnails;
+use crate::lib::archive;
+use crate::lib::cover;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Generate thumbnails for comic books
+pub fn thumbnails(opts: &Thumbnails) -> Result<Vec<PathBuf>, ThumbnailsError> {
+    let started = Instant::now();
+
+    if opts.width == 0 || opts.height == 0 {
+        return Err(ThumbnailsError::InvalidDimensions);
+    }
+
+    let cwd = env::current_dir().map_err(ThumbnailsError::FailedToGetCWD)?;
+
+    // Create the cache directory if needed
+    let cache_dir = match &opts.output {
+        Some(output) => {
+            let output = cwd.join(output);
+
+            if !output.is_dir() {
+                if opts.create_output_dir {
+                    fs::create_dir_all(&output)
+                        .map_err(ThumbnailsError::FailedToCreateOutputDirectory)?
+                } else {
+                    return Err(ThumbnailsError::OutputDirectoryNotFound);
+                }
+            }
+
+            Some(output)
+        }
+
+        None => None,
+    };
+
+    // List the comic books along with their thumbnail's path
+    let mut targets = vec![];
+
+    for input in &opts.inputs {
+        let input = cwd.join(input);
+
+        let archives = archive::find_archives(std::slice::from_ref(&input))
+            .map_err(ThumbnailsError::FailedToFindArchives)?;
+
+        // Directory the cache directory's structure is relative to
+        let base_dir = if input.is_dir() {
+            input.as_path()
+        } else {
+            input.parent().unwrap()
+        };
+
+        for archive in archives {
+            let target = thumbnail_path(opts, &archive, base_dir, cache_dir.as_deref());
+            targets.push((archive, target));
+        }
+    }
+
+    if targets.is_empty() {
+        return Err(ThumbnailsError::NoArchiveFound);
+    }
+
+    let mut generated = vec![];
+    let mut failed = 0;
+
+    for (i, (archive, target)) in targets.iter().enumerate() {
+        if !opts.overwrite && is_up_to_date(archive, target) {
+            debug!(
+                "Thumbnail '{}' is up to date, skipping it.",
+                target.to_string_lossy()
+            );
+            continue;
+        }
+
+        debug!(
+            "Generating thumbnail {}/{} for '{}'...",
+            i + 1,
+            targets.len(),
+            archive.to_string_lossy()
+        );
+
+        let result = cover::read_cover(
+            archive,
+            opts.accept_extended_image_formats,
+            opts.simple_sorting,
+        )
+        .map_err(|err| format!("{}", err))
+        .and_then(|cover| {
+            cover::make_thumbnail(&cover, opts.width, opts.height, opts.format)
+                .map_err(|err| format!("Failed to generate thumbnail: {}", err))
+        })
+        .and_then(|thumbnail| {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|err| format!("Failed to create thumbnail's directory: {}", err))?;
+            }
+
+            fs::write(target, thumbnail)
+                .map_err(|err| format!("Failed to write thumbnail: {}", err))
+        });
+
+        match result {
+            Ok(()) => {
+                trace!("Written thumbnail to '{}'.", target.to_string_lossy());
+                generated.push(target.clone());
+            }
+
+            Err(err) => {
+                error!("{}: {}", archive.to_string_lossy(), err);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(ThumbnailsError::FailedThumbnails(failed, targets.len()));
+    }
+
+    let elapsed = started.elapsed();
+
+    info!(
+        "Successfully generated {} thumbnail{} ({} up to date) in {}.{:03} s.",
+        generated.len(),
+        if generated.len() > 1 { "s" } else { "" },
+        targets.len() - generated.len(),
+        elapsed.as_secs(),
+        elapsed.subsec_millis()
+    );
+
+    Ok(generated)
+}
+
+/// Get the path of a comic book's thumbnail
+fn thumbnail_path(
+    opts: &Thumbnails,
+    archive: &Path,
+    base_dir: &Path,
+    cache_dir: Option<&Path>,
+) -> PathBuf {
+    let file_name = format!(
+        "{}.{}",
+        archive.file_stem().unwrap().to_string_lossy(),
+        opts.format.ext()
+    );
+
+    let archive_dir = archive.parent().unwrap();
+
+    match cache_dir {
+        Some(cache_dir) => cache_dir
+            .join(
+                archive_dir
+                    .strip_prefix(base_dir)
+                    .unwrap_or_else(|_| Path::new("")),
+            )
+            .join(file_name),
+        None => archive_dir.join(file_name),
+    }
+}
+
+/// Check if a thumbnail is more recent than its comic book
+fn is_up_to_date(archive: &Path, thumbnail: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified());
+
+    match (modified(archive), modified(thumbnail)) {
+        (Ok(archive), Ok(thumbnail)) => thumbnail >= archive,
+        _ => false,
+    }
+}
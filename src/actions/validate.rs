@@ -0,0 +1,77 @@
+use crate::cli::error::ValidationError;
+use crate::cli::opts::Validate;
+use crate::lib::archive;
+use crate::lib::validate::{self, Severity};
+use std::env;
+use std::path::PathBuf;
+
+/// Validate existing comic books, returning the list of valid ones
+pub fn validate(opts: &Validate) -> Result<Vec<PathBuf>, ValidationError> {
+    let cwd = env::current_dir().map_err(ValidationError::FailedToGetCWD)?;
+
+    let inputs = opts
+        .inputs
+        .iter()
+        .map(|input| cwd.join(input))
+        .collect::<Vec<_>>();
+
+    let archives = archive::find_extractable_archives(&inputs)
+        .map_err(ValidationError::FailedToFindArchives)?;
+
+    if archives.is_empty() {
+        return Err(ValidationError::NoArchiveFound);
+    }
+
+    info!(
+        "Validating {} comic book{}...",
+        archives.len(),
+        if archives.len() > 1 { "s" } else { "" }
+    );
+
+    let mut valid = vec![];
+
+    for (i, path) in archives.iter().enumerate() {
+        debug!(
+            "Validating comic book {}/{}: '{}'...",
+            i + 1,
+            archives.len(),
+            path.to_string_lossy()
+        );
+
+        let report = validate::validate_archive(path, opts.accept_extended_image_formats);
+
+        for issue in &report.issues {
+            match issue.severity {
+                Severity::Warning => warn!("{}: {}", path.to_string_lossy(), issue.message),
+                Severity::Error => error!("{}: {}", path.to_string_lossy(), issue.message),
+            }
+        }
+
+        if report.has_problems(opts.strict) {
+            continue;
+        }
+
+        info!(
+            "Comic book '{}' is valid ({} pages).",
+            path.to_string_lossy(),
+            report.pages
+        );
+
+        valid.push(path.clone());
+    }
+
+    if valid.len() < archives.len() {
+        return Err(ValidationError::InvalidArchives(
+            archives.len() - valid.len(),
+            archives.len(),
+        ));
+    }
+
+    info!(
+        "All {} comic book{} are valid.",
+        archives.len(),
+        if archives.len() > 1 { "s" } else { "" }
+    );
+
+    Ok(valid)
+}
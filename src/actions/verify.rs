@@ -0,0 +1,87 @@
+use crate::cli::error::VerifyError;
+use crate::cli::opts::Verify;
+use crate::lib::archive;
+use crate::lib::checksum::{self, ChecksumStatus};
+use std::env;
+use std::path::PathBuf;
+
+/// Check comic books against their checksum file
+pub fn verify(opts: &Verify) -> Result<Vec<PathBuf>, VerifyError> {
+    let cwd = env::current_dir().map_err(VerifyError::FailedToGetCWD)?;
+
+    let inputs = opts
+        .inputs
+        .iter()
+        .map(|input| cwd.join(input))
+        .collect::<Vec<_>>();
+
+    let archives = archive::find_archives(&inputs).map_err(VerifyError::FailedToFindArchives)?;
+
+    if archives.is_empty() {
+        return Err(VerifyError::NoArchiveFound);
+    }
+
+    info!("Verifying {} comic books...", archives.len());
+
+    let mut verified = vec![];
+    let mut missing = 0;
+
+    for (i, path) in archives.iter().enumerate() {
+        debug!(
+            "Verifying comic book {}/{}: '{}'...",
+            i + 1,
+            archives.len(),
+            path.to_string_lossy()
+        );
+
+        match checksum::verify_checksum(path) {
+            Ok(ChecksumStatus::Valid) => {
+                info!("Comic book '{}' is intact.", path.to_string_lossy());
+                verified.push(path.clone());
+            }
+
+            Ok(ChecksumStatus::Missing) => {
+                missing += 1;
+
+                let message = format!(
+                    "{}: no checksum file found at '{}'",
+                    path.to_string_lossy(),
+                    checksum::checksum_path(path).to_string_lossy()
+                );
+
+                if opts.strict {
+                    error!("{}", message);
+                } else {
+                    warn!("{}", message);
+                }
+            }
+
+            Ok(ChecksumStatus::Mismatch { expected, actual }) => error!(
+                "{}: checksum mismatch (expected {}, found {})",
+                path.to_string_lossy(),
+                expected,
+                actual
+            ),
+
+            Err(err) => error!("{}: {}", path.to_string_lossy(), err),
+        }
+    }
+
+    let failed = archives.len() - verified.len() - if opts.strict { 0 } else { missing };
+
+    if failed > 0 {
+        return Err(VerifyError::FailedArchives(failed, archives.len()));
+    }
+
+    info!(
+        "All {} verified comic books are intact{}.",
+        verified.len(),
+        if missing > 0 {
+            format!(" ({} without checksum file)", missing)
+        } else {
+            String::new()
+        }
+    );
+
+    Ok(verified)
+}
@@ -0,0 +1,227 @@
+use crate::cli::error::{error_chain, WatchError};
+use crate::cli::opts::{Action, CompilationMethod, EncodingMethod, EncodingOptions, Opts, Watch};
+use crate::lib::build_vol::*;
+use crate::lib::cancel::CancellationToken;
+use crate::lib::chapter::ChapterNumber;
+use crate::lib::progress::CliProgress;
+use crate::lib::deter;
+use crate::lib::source::DirSource;
+use crate::lib::watch::{self, WatchConfig, WatchState, WatchedVolume};
+use clap::Parser;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// Watch a directory for new chapters and compile them into volumes once they are complete
+pub fn watch(opts: &Watch) -> Result<Vec<PathBuf>, WatchError> {
+    let cwd = env::current_dir().map_err(WatchError::FailedToGetCWD)?;
+
+    let input = cwd.join(&opts.input);
+
+    if !input.is_dir() {
+        return Err(WatchError::InputDirectoryNotFound(input));
+    }
+
+    let config_path = cwd.join(&opts.config);
+
+    let config = fs::read_to_string(&config_path)
+        .map_err(|err| WatchError::FailedToReadConfig(config_path.clone(), err))?;
+
+    let config: WatchConfig = serde_json::from_str(&config)
+        .map_err(|err| WatchError::InvalidConfig(config_path.clone(), err))?;
+
+    if config.chapters_per_volume == 0 {
+        return Err(WatchError::AtLeast1ChapterPerVolume);
+    }
+
+    let output = config_path.parent().unwrap().join(&config.output);
+
+    if !output.is_dir() {
+        fs::create_dir_all(&output).map_err(WatchError::FailedToCreateOutputDirectory)?;
+    }
+
+    // Parse the encoding options the same way as on the command line
+    let mut args: Vec<OsString> = vec!["comic-enc".into(), "encode".into(), input.clone().into()];
+    args.extend(vec!["--output".into(), output.clone().into()]);
+    args.extend(config.encode_options.iter().map(OsString::from));
+    args.extend(vec![
+        "compile".into(),
+        "ranges".into(),
+        config.chapters_per_volume.to_string().into(),
+    ]);
+
+    let encode = match Opts::try_parse_from(args)
+        .map_err(|err| WatchError::InvalidEncodingOptions(err.to_string()))?
+        .action
+    {
+        Action::Encode(encode) => encode,
+        _ => unreachable!(),
+    };
+
+    let (compile_opts, ranges) = match &encode.method {
+        EncodingMethod::Compile(compile_opts) => match &compile_opts.method {
+            CompilationMethod::Ranges(ranges) => (compile_opts, ranges),
+            CompilationMethod::Each(_) => unreachable!(),
+        },
+        EncodingMethod::Single(_) => unreachable!(),
+    };
+
+    let volumes_metadata = load_volumes_metadata(&encode.options)
+        .map_err(|err| WatchError::InvalidEncodingOptions(error_chain(&err)))?;
+
+    let pool = build_pages_pool(&encode.options)
+        .map_err(|err| WatchError::InvalidEncodingOptions(error_chain(&err)))?;
+
+    let mut state = WatchState::load(&output).map_err(WatchError::FailedToLoadState)?;
+
+    // Watched chapters are always read from a directory
+    let source = DirSource::new(&input, !encode.options.no_default_ignores);
+
+    // Ctrl-C is not handled while watching (it stops the program), so volumes are never cancelled
+    let cancel = CancellationToken::new();
+
+    info!(
+        "Watching '{}' for new chapters ({} already compiled in {} volumes)...",
+        input.to_string_lossy(),
+        state.chapters_count(),
+        state.volumes.len()
+    );
+
+    let build_method = BuildMethod::Ranges(ranges, compile_opts);
+    let debounce = Duration::from_secs(config.debounce_secs);
+
+    let mut built = vec![];
+
+    loop {
+        trace!("Looking for complete chapters...");
+
+        // Chapters that were not put in a volume yet, in reading order
+        let mut pending = list_chapters(&input, &output, &config, &encode.options)?
+            .into_iter()
+            .filter(|(_, name)| !state.contains_chapter(name))
+            .collect::<Vec<_>>();
+
+        // Only consider the complete chapters that directly follow the already compiled ones
+        if let Some(incomplete) = pending
+            .iter()
+            .map(|(path, _)| {
+                watch::is_chapter_complete(path, debounce)
+                    .map_err(|err| WatchError::FailedToCheckChapter(path.clone(), err))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .position(|complete| !complete)
+        {
+            debug!(
+                "Waiting for chapter '{}' to be complete...",
+                pending[incomplete].1
+            );
+
+            pending.truncate(incomplete);
+        }
+
+        while pending.len() >= usize::from(config.chapters_per_volume) {
+            let volume = state.next_volume();
+            let start_chapter = state.chapters_count() + 1;
+
+            let chapters = pending
+                .drain(..usize::from(config.chapters_per_volume))
+                .enumerate()
+                .map(|(i, (path, name))| (ChapterNumber::new(start_chapter + i), path, name))
+                .collect::<Vec<_>>();
+
+            info!(
+                "Compiling chapters {} to {} into volume {}...",
+                start_chapter,
+                start_chapter + chapters.len() - 1,
+                volume
+            );
+
+            built.push(
+                build_volume(&BuildVolumeArgs {
+                    method: &build_method,
+                    enc_opts: &encode.options,
+                    source: &source,
+                    output: &output,
+                    volume,
+                    volumes: volume,
+                    vol_num_len: config.volume_digits,
+                    chapter_num_len: config.chapter_digits,
+                    start_chapter: ChapterNumber::new(start_chapter),
+                    chapters: &chapters,
+                    metadata: &volumes_metadata,
+                    pool: &pool,
+                    progress: &CliProgress,
+                    cancel: &cancel,
+                })
+                .map_err(|err| WatchError::FailedToBuildVolume(volume, Box::new(err)))?
+                .path,
+            );
+
+            state.volumes.push(WatchedVolume {
+                volume,
+                chapters: chapters.into_iter().map(|(_, _, name)| name).collect(),
+            });
+
+            state.save(&output).map_err(WatchError::FailedToSaveState)?;
+        }
+
+        if opts.once {
+            break;
+        }
+
+        thread::sleep(Duration::from_secs(config.poll_interval_secs));
+    }
+
+    Ok(built)
+}
+
+/// List the chapter directories in the watched directory, in reading order
+fn list_chapters(
+    input: &Path,
+    output: &Path,
+    config: &WatchConfig,
+    enc_opts: &EncodingOptions,
+) -> Result<Vec<(PathBuf, String)>, WatchError> {
+    let mut chapters = vec![];
+
+    for entry in fs::read_dir(input).map_err(WatchError::FailedToReadChaptersDirectory)? {
+        let entry = entry.map_err(WatchError::FailedToReadChaptersDirectory)?;
+        let path = entry.path();
+
+        if !path.is_dir() || (!enc_opts.no_default_ignores && deter::is_os_junk(&path)) {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        // Volumes built inside the watched directory must not be compiled as a chapter
+        if deter::is_inside(output, &path) {
+            debug!(
+                "Ignoring directory '{}' as it contains the output directory",
+                name
+            );
+            continue;
+        }
+
+        if config
+            .dirs_prefix
+            .as_ref()
+            .map(|prefix| name.starts_with(prefix))
+            .unwrap_or(true)
+        {
+            chapters.push((path, name));
+        }
+    }
+
+    if enc_opts.simple_sorting {
+        chapters.sort_by(|a, b| a.0.cmp(&b.0));
+    } else {
+        deter::natural_sort_by_path(&mut chapters, |chapter| &chapter.0);
+    }
+
+    Ok(chapters)
+}
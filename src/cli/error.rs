@@ -1,188 +1,277 @@
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::io::Error as IOError;
 use std::path::PathBuf;
 use std::fmt;
 use image::ImageError;
 use zip::result::ZipError;
 use pdf::error::PdfError;
+use rayon::ThreadPoolBuildError;
+use crate::lib::archive::{ArchiveErr, ArchiveSearchErr};
+use crate::lib::bench::BenchErr;
+use crate::lib::cache::CacheErr;
+use crate::lib::calibre::CalibreErr;
+use crate::lib::chapter::ChapterNumber;
+use crate::lib::config::ConfigErr;
+use crate::lib::cover::CoverErr;
+use crate::lib::deter::{self, RecursiveFilesSearchErr};
+use crate::lib::diff::DiffErr;
+use crate::lib::external::ExternalErr;
+use crate::lib::hooks::HookErr;
+use crate::lib::inspect::InspectErr;
+use crate::lib::library_index::LibraryIndexErr;
+use crate::lib::metadata::{MetadataCsvErr, MetadataReadErr};
+use crate::lib::plan::PlanErr;
+use crate::lib::queue::QueueErr;
+use crate::lib::source::SourceErr;
+use crate::lib::summary::SummaryErr;
+use crate::lib::tar::TarErr;
+use crate::lib::template::TemplateErr;
+use crate::lib::volume_map::VolumeMapErr;
+use crate::lib::watch::WatchStateErr;
+use thiserror::Error;
 
 /// Error during in the "encode" action
+/// The causes of the errors (e.g. I/O errors) are not part of their message, and are available through `source()`
+#[derive(Debug, Error)]
 pub enum EncodingError {
+    #[error("Please provide an output path")]
     MissingOutputPath,
+
+    #[error("Please provide a valid number of chapters per volume (integer, strictly higher than 0)")]
     InvalidNumberOfChaptersPerVolume,
+
+    #[error("Please provide a valid start chapter (integer, strictly higher than 0)")]
     InvalidStartChapter,
+
+    #[error("Please provide a valid end chapter (integer, strictly higher than 0)")]
     InvalidEndChapter,
+
+    #[error("Please provide a valid start volume (integer, strictly higher than 0)")]
+    InvalidStartVolume,
+
+    #[error("There must be at least 1 chapter per volume")]
     AtLeast1ChapterPerVolume,
+
+    #[error("Please provide a valid maximum number of pages per volume (integer, strictly higher than 0)")]
+    InvalidMaxPagesPerVolume,
+
+    #[error("Please provide a valid maximum size per volume (in MB, strictly higher than 0)")]
+    InvalidMaxSizePerVolume,
+
+    #[error("Start chapter cannot be higher than the end chapter")]
     StartChapterCannotBeHigherThanEndChapter,
-    FailedToGetCWD(IOError),
+
+    #[error("Chapter regular expression must contain a group named 'num' (e.g. '(?P<num>\\d+)')")]
+    ChapterRegexHasNoNumGroup,
+
+    #[error("Failed to read volume map '{}'", .0.display())]
+    FailedToReadVolumeMap(PathBuf, #[source] VolumeMapErr),
+
+    #[error("Failed to get current working directory")]
+    FailedToGetCWD(#[source] IOError),
+
+    #[error("Chapters directory was not found")]
     ChaptersDirectoryNotFound,
+
+    #[error("Failed to open the chapters from '{}'", .0.display())]
+    FailedToOpenInput(PathBuf, #[source] SourceErr),
+
+    #[error("Output directory was not found")]
     OutputDirectoryNotFound,
+
+    #[error("Output file does not have a valid UTF-8 name ('{}')", .0.to_string_lossy())]
     OutputFileHasInvalidUTF8Name(OsString),
+
+    #[error("Input directory was not found")]
     SingleInputDirectoryNotFound,
+
+    #[error("Input directory is not a directory")]
     SingleInputDirectoryIsNotADirectory,
+
+    #[error("Input directory has no name, so an output name cannot be inferred")]
     SingleInputDirectorHasNoName,
+
+    #[error("Output file does not have a valid name (e.g. '.' or '/')")]
     SingleOutputFileHasNoName,
-    FailedToCreateOutputDirectory(IOError),
-    FailedToReadChaptersDirectory(IOError),
+
+    #[error("Failed to create output directory")]
+    FailedToCreateOutputDirectory(#[source] IOError),
+
+    #[error("Failed to read the chapters directory")]
+    FailedToReadChaptersDirectory(#[source] IOError),
+
+    #[error("Failed to measure chapter directory '{}'", .0.display())]
+    FailedToMeasureChapter(PathBuf, #[source] RecursiveFilesSearchErr),
+
+    #[error("A file or directory has not a valid UTF-8 name in the input directory: {}", .0.to_string_lossy())]
     ItemHasInvalidUTF8Name(OsString),
-    FailedToCreateVolumeFile(usize, PathBuf, IOError),
+
+    #[error("Failed to create the file of volume {0} at path '{}'", .1.display())]
+    FailedToCreateVolumeFile(usize, PathBuf, #[source] IOError),
+
+    #[error("Output directory is inside chapter directory '{}' (use an output directory outside of the chapters)", .0.display())]
+    OutputDirectoryInsideChapter(PathBuf),
+
+    #[error("Directories '{}' and '{}' are both numbered as chapter {0} (use '--on-duplicate-chapter' to choose which one to keep)", .1.display(), .2.display())]
+    DuplicateChapter(ChapterNumber, PathBuf, PathBuf),
+
+    #[error("Failed to get the modification date of chapter directory '{}'", .0.display())]
+    FailedToGetChapterModificationDate(PathBuf, #[source] IOError),
+
+    #[error("Found {missing} missing and {empty} empty chapter(s) (see the warnings above, or remove '--fail-on-gaps')")]
+    FoundChapterGaps { missing: usize, empty: usize },
+
+    #[error("Not enough space left in the output directory: about {} required but only {} available (free some space or use '--no-space-check')", deter::format_size(*.required), deter::format_size(*.available))]
+    NotEnoughSpace { required: u64, available: u64 },
+
+    #[error("Failed to create the Calibre book directory of volume {0} at path '{}'", .1.display())]
+    FailedToCreateBookDirectory(usize, PathBuf, #[source] IOError),
+
+    #[error("Failed to create the file of volume {0} because path '{}' already exists (use '--overwrite' to force writing)", .1.display())]
     OutputVolumeFileAlreadyExists(usize, PathBuf),
+
+    #[error("Failed to create the file of volume {0} because path '{}' is a directory", .1.display())]
     OutputVolumeFileIsADirectory(usize, PathBuf),
-    FailedToOverwriteOutputVolumeFile(usize, PathBuf, IOError),
-    FailedToListChapterDirectoryFiles { volume: usize, chapter: usize, chapter_path: PathBuf, err: IOError },
-    FoundItemWithInvalidName { volume: usize, chapter: usize, chapter_path: PathBuf, invalid_item_path: PathBuf },
-    FailedToOpenImage { volume: usize, chapter: usize, chapter_path: PathBuf, image_path: PathBuf, err: IOError },
-    FailedToCreateChapterDirectoryInZip { volume: usize, chapter: usize, dir_name: String, err: ZipError },
-    FailedToCreateImageFileInZip { volume: usize, chapter: usize, file_path: PathBuf, err: ZipError },
-    FailedToReadImage { volume: usize, chapter: usize, chapter_path: PathBuf, image_path: PathBuf, err: IOError },
-    FailedToWriteImageFileToZip { volume: usize, chapter: usize, chapter_path: PathBuf, image_path: PathBuf, err: IOError },
-    FailedToConvertImageFileToZip { volume: usize, chapter: usize, chapter_path: PathBuf, image_path: PathBuf, err: ImageError },
-    FailedToCloseZipArchive(usize, ZipError),
-    FailedToRenameCompleteArchive(usize, IOError)
-}
 
-impl fmt::Display for EncodingError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", match self {
-            Self::MissingOutputPath =>
-                "Please provide an output path".to_string(),
+    #[error("Failed to overwrite the file of volume {0} at path '{}'", .1.display())]
+    FailedToOverwriteOutputVolumeFile(usize, PathBuf, #[source] IOError),
 
-            Self::InvalidNumberOfChaptersPerVolume =>
-                "Please provide a valid number of chapters per volume (integer, strictly higher than 0)".to_string(),
+    #[error("Failed to list files for chapter {chapter} in volume {volume} at '{}'", .chapter_path.display())]
+    FailedToListChapterDirectoryFiles { volume: usize, chapter: ChapterNumber, chapter_path: PathBuf, #[source] err: IOError },
 
-            Self::InvalidStartChapter =>
-                "Please provide a valid start chapter (integer, strictly higher than 0)".to_string(),
+    #[error(
+        "Found item with invalid filename for chapter {chapter} in volume {volume} at '{}': {}",
+        .chapter_path.display(),
+        .invalid_item_path.file_name().unwrap_or_else(|| OsStr::new("<unknown filename>")).to_string_lossy()
+    )]
+    FoundItemWithInvalidName { volume: usize, chapter: ChapterNumber, chapter_path: PathBuf, invalid_item_path: PathBuf },
 
-            Self::InvalidEndChapter =>
-                "Please provide a valid end chapter (integer, strictly higher than 0)".to_string(),
+    #[error("Failed to open image file '{}' from chapter {chapter} in volume {volume}", .image_path.display())]
+    FailedToOpenImage { volume: usize, chapter: ChapterNumber, chapter_path: PathBuf, image_path: PathBuf, #[source] err: IOError },
 
-            Self::AtLeast1ChapterPerVolume =>
-                "There must be at least 1 chapter per volume".to_string(),
+    #[error("Failed to create directory for chapter {chapter} in volume {volume}")]
+    FailedToCreateChapterDirectoryInZip { volume: usize, chapter: ChapterNumber, dir_name: String, #[source] err: IOError },
 
-            Self::StartChapterCannotBeHigherThanEndChapter =>
-                "Start chapter cannot be higher than the end chapter".to_string(),
+    #[error("Invalid entry naming template")]
+    InvalidEntryTemplate(#[source] TemplateErr),
 
-            Self::FailedToGetCWD(err) =>
-                format!("Failed to get current working directory: {}", err),
+    #[error("Entry naming template gives the same name '{1}' to multiple pages of volume {0} (use e.g. the 'volume_page' placeholder)")]
+    DuplicateEntryName(usize, String),
 
-            Self::ChaptersDirectoryNotFound =>
-                "Chapters directory was not found".to_string(),
-            
-            Self::OutputDirectoryNotFound =>
-                "Output directory was not found".to_string(),
+    #[error("Failed to read image file '{}' from chapter {chapter} in volume {volume}", .image_path.display())]
+    FailedToReadImage { volume: usize, chapter: ChapterNumber, chapter_path: PathBuf, image_path: PathBuf, #[source] err: IOError },
 
-            Self::OutputFileHasInvalidUTF8Name(name) =>
-                format!("Output file does not have a valid UTF-8 name ('{}')", name.to_string_lossy()),
+    #[error("Failed to write image file '{}' from chapter {chapter} in volume {volume}", .image_path.display())]
+    FailedToWriteImageFileToZip { volume: usize, chapter: ChapterNumber, chapter_path: PathBuf, image_path: PathBuf, #[source] err: IOError },
 
-            Self::SingleInputDirectoryNotFound =>
-                "Input directory was not found".to_string(),
+    #[error("Failed to write image file '{}' from chapter {chapter} in volume {volume}", .image_path.display())]
+    FailedToConvertImageFileToZip { volume: usize, chapter: ChapterNumber, chapter_path: PathBuf, image_path: PathBuf, #[source] err: Box<ImageError> },
 
-            Self::SingleInputDirectoryIsNotADirectory =>
-                "Input directory is not a directory".to_string(),
+    #[error("Failed to close archive for volume {0}")]
+    FailedToCloseZipArchive(usize, #[source] IOError),
 
-            Self::SingleInputDirectorHasNoName =>
-                "Input directory has no name, so an output name cannot be inferred".to_string(),
+    #[error("Failed to get size of volume {0}")]
+    FailedToGetVolumeSize(usize, #[source] IOError),
 
-            Self::SingleOutputFileHasNoName =>
-                "Output file does not have a valid name (e.g. '.' or '/')".to_string(),
+    #[error("Failed to read existing volume {0} at '{}'", .1.display())]
+    FailedToReadExistingVolume(usize, PathBuf, #[source] ArchiveErr),
 
-            Self::FailedToCreateOutputDirectory(err) =>
-                format!("Failed to create output directory: {}", err),
-            
-            Self::FailedToReadChaptersDirectory(err) =>
-                format!("Failed to read the chapters directory: {}", err),
-            
-            Self::ItemHasInvalidUTF8Name(path) =>
-                format!("A file or directory has not a valid UTF-8 name in the input directory: {}", path.to_string_lossy()),
-            
-            Self::FailedToCreateVolumeFile(volume, path, err) =>
-                format!("Failed to create the file of volume {} at path '{}': {}", volume, path.to_string_lossy(), err),
-            
-            Self::OutputVolumeFileAlreadyExists(volume, path) =>
-                format!("Failed to create the file of volume {} because path '{}' already exists (use '--overwrite' to force writing)", volume, path.to_string_lossy()),
+    #[error("Failed to write summary file '{}'", .0.display())]
+    FailedToWriteSummary(PathBuf, #[source] SummaryErr),
 
-            Self::OutputVolumeFileIsADirectory(volume, path) =>
-                format!("Failed to create the file of volume {} because path '{}' is a directory", volume, path.to_string_lossy()),
+    #[error("Failed to serialize summary")]
+    FailedToSerializeSummary(#[source] serde_json::Error),
 
-            Self::FailedToOverwriteOutputVolumeFile(volume, path, err) =>
-                format!("Failed to overwrite the file of volume {} at path '{}': {}", volume, path.to_string_lossy(), err),
+    #[error("Failed to index library '{}'", .0.display())]
+    FailedToIndexLibrary(PathBuf, #[source] LibraryIndexErr),
 
-            Self::FailedToListChapterDirectoryFiles { volume, chapter, chapter_path, err } =>
-                format!(
-                    "Failed to list files for chapter {} in volume {} at '{}': {}",
-                    chapter,
-                    volume,
-                    chapter_path.to_string_lossy(),
-                    err
-                ),
+    #[error("Archive of volume {0} is invalid once complete")]
+    InvalidCompleteArchive(usize, #[source] ArchiveErr),
 
-            Self::FoundItemWithInvalidName { volume, chapter, chapter_path, invalid_item_path } =>
-                format!(
-                    "Found item with invalid filename for chapter {} in volume {} at '{}': {}",
-                    chapter,
-                    volume,
-                    chapter_path.to_string_lossy(),
-                    match invalid_item_path.file_name() {
-                        None => "<unknown filename>".to_string(),
-                        Some(file_name) => file_name.to_string_lossy().to_string()
-                    }
-                ),
-            
-            Self::FailedToOpenImage { volume, chapter, chapter_path: _, image_path, err } =>
-                format!(
-                    "Failed to open image file '{}' from chapter {} in volume {}: {}",
-                    image_path.to_string_lossy(),
-                    chapter,
-                    volume,
-                    err
-                ),
-            
-            Self::FailedToCreateChapterDirectoryInZip { volume, chapter, dir_name: _, err } =>
-                format!("Failed to create directory for chapter {} in volume {}: {}", chapter, volume, err),
+    #[error("Volume {0} at path '{}' is corrupted: {2}", .1.display())]
+    CorruptedVolume(usize, PathBuf, String),
 
-            Self::FailedToCreateImageFileInZip { volume, chapter, file_path: _, err } =>
-                format!("Failed to create image file for chapter {} in volume {}: {}", chapter, volume, err),
+    #[error("Failed to rename complete archive for volume {0}")]
+    FailedToRenameCompleteArchive(usize, #[source] IOError),
 
-            Self::FailedToReadImage { volume, chapter, chapter_path: _, image_path, err } =>
-                format!(
-                    "Failed to read image file '{}' from chapter {} in volume {}: {}",
-                    image_path.to_string_lossy(),
-                    chapter,
-                    volume,
-                    err
-                ),
+    #[error("Failed to read metadata CSV file '{}'", .0.display())]
+    InvalidMetadataCsv(PathBuf, #[source] MetadataCsvErr),
 
-            Self::FailedToWriteImageFileToZip { volume, chapter, chapter_path: _, image_path, err } =>
-                format!(
-                    "Failed to write image file '{}' from chapter {} in volume {}: {}",
-                    image_path.to_string_lossy(),
-                    chapter,
-                    volume,
-                    err
-                ),
-            
-            Self::FailedToConvertImageFileToZip { volume, chapter, chapter_path: _, image_path, err } =>
-                format!(
-                    "Failed to write image file '{}' from chapter {} in volume {}: {}",
-                    image_path.to_string_lossy(),
-                    chapter,
-                    volume,
-                    err
-                ),
+    #[error("Failed to read chapter titles CSV file '{}'", .0.display())]
+    InvalidChapterTitlesCsv(PathBuf, #[source] MetadataCsvErr),
 
-            Self::FailedToCloseZipArchive(volume, err) =>
-                format!("Failed to close archive for volume {}: {}", volume, err),
+    #[error("Failed to write metadata file in volume {0}")]
+    FailedToWriteMetadataFileToZip(usize, #[source] IOError),
 
-            Self::FailedToRenameCompleteArchive(volume, err) =>
-                format!("Failed to rename complete archive for volume {}: {}", volume, err)
-        })
+    #[error("Failed to write checksum file of volume {0} to '{}'", .1.display())]
+    FailedToWriteChecksumFile(usize, PathBuf, #[source] IOError),
+
+    #[error("Failed to write the Calibre metadata and cover of volume {0}")]
+    FailedToWriteCalibreFiles(usize, #[source] CalibreErr),
+
+    #[error("Failed to read plan file '{}'", .0.display())]
+    FailedToReadPlan(PathBuf, #[source] PlanErr),
+
+    #[error("Failed to write plan file '{}'", .0.display())]
+    FailedToWritePlan(PathBuf, #[source] PlanErr),
+
+    #[error("Volume {0} must contain exactly one chapter when compiling each chapter individually")]
+    IndividualVolumeMustContainOneChapter(usize),
+
+    #[error("Failed to write progress file '{}'", .0.display())]
+    FailedToWriteProgress(PathBuf, #[source] PlanErr),
+
+    #[error("Failed to remove staging file of volume {0} at '{}'", .1.display())]
+    FailedToRemoveStagingFile(usize, PathBuf, #[source] IOError),
+
+    #[error("Failed to read metadata file '{}'", .0.display())]
+    FailedToReadMetadataFile(PathBuf, #[source] IOError),
+
+    #[error("Invalid metadata file '{}'", .0.display())]
+    InvalidMetadataFile(PathBuf, #[source] MetadataReadErr),
+
+    #[error("Failed to create thread pool")]
+    FailedToCreateThreadPool(#[source] ThreadPoolBuildError),
+
+    #[error("Failed to hash chapter {chapter} of volume {volume} at '{}'", .chapter_path.display())]
+    FailedToHashChapter { volume: usize, chapter: ChapterNumber, chapter_path: PathBuf, #[source] err: IOError },
+
+    #[error("Failed to write cache file '{}'", .0.display())]
+    FailedToWriteCache(PathBuf, #[source] CacheErr),
+
+    #[error("Failed to read confirmation")]
+    FailedToReadConfirmation(#[source] IOError),
+
+    #[error("{0} out of {1} volumes failed")]
+    FailedVolumes(usize, usize),
+
+    #[error("Post-volume command of volume {0} failed")]
+    PostVolumeCommandFailed(usize, #[source] HookErr),
+
+    #[error("Pre-chapter command of chapter {chapter} in volume {volume} failed")]
+    PreChapterCommandFailed { volume: usize, chapter: ChapterNumber, #[source] err: HookErr },
+
+    #[error("Compilation was cancelled")]
+    Cancelled
+}
+
+/// Get an error's message followed by the messages of its causes (e.g. "Failed to create output directory: Permission denied")
+pub fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut cause = err.source();
+
+    while let Some(err) = cause {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        cause = err.source();
     }
+
+    message
 }
 
 /// Error during in the "decode" action
 pub enum DecodingError {
     InputFileNotFound,
-    InputFileIsADirectory,
     OutputDirectoryNotFound,
     FailedToGetCWD(IOError),
     FailedToCreateOutputDirectory(IOError),
@@ -199,7 +288,31 @@ pub enum DecodingError {
     FailedToOpenPdfFile(PdfError),
     FailedToGetPdfPage(usize, PdfError),
     FailedToGetPdfPageResources(usize, PdfError),
-    FailedToExtractPdfImage(usize, PathBuf, IOError)
+    FailedToExtractPdfImage(usize, PathBuf, IOError),
+    FailedToOpenTarFile(IOError),
+    InvalidTarArchive(TarErr),
+    FailedToExtractTarFile { path_in_tar: PathBuf, extract_to: PathBuf, err: IOError },
+    ArchiveFileHasInvalidUTF8FileExtension(PathBuf),
+    FailedToCreateTemporaryDirectory(PathBuf, IOError),
+    FailedToExtractWithExternalProgram(ExternalErr),
+    FailedToListExtractedFiles(PathBuf, RecursiveFilesSearchErr),
+    FailedToRemoveTemporaryDirectory(PathBuf, IOError),
+    NoPageSelected(usize),
+    FailedToCreatePageDirectory(PathBuf, IOError),
+    InvalidNameTemplate(TemplateErr),
+    NameTemplateWithoutPageNumber,
+    FailedToFindArchives(ArchiveSearchErr),
+    NoArchiveFound,
+    DuplicateOutputDirectory(PathBuf),
+    FailedToCreateThreadPool(ThreadPoolBuildError),
+    FailedArchives(usize, usize),
+    FailedToReadMetadataFile(PathBuf, IOError),
+    FailedToWriteMetadataFile(PathBuf, IOError),
+    StdinMustBeTheOnlyInput,
+    StdinRequiresOutputDirectory,
+    FailedToReadStdin(IOError),
+    UnrecognizedStdinFormat,
+    FailedToWriteTemporaryFile(PathBuf, IOError)
 }
 
 impl fmt::Display for DecodingError {
@@ -208,9 +321,6 @@ impl fmt::Display for DecodingError {
             Self::InputFileNotFound =>
                 "Input file was not found".to_string(),
 
-            Self::InputFileIsADirectory =>
-                "Input file is a directory".to_string(),
-
             Self::OutputDirectoryNotFound =>
                 "Output directory was not found".to_string(),
 
@@ -260,7 +370,1069 @@ impl fmt::Display for DecodingError {
                 format!("Failed to get resources from PDF page n°{}: {}", page, err),
 
             Self::FailedToExtractPdfImage(page, path, err) =>
-                format!("Failed extract PDF image from page n°{} to path '{}': {}", page, path.to_string_lossy(), err)
+                format!("Failed extract PDF image from page n°{} to path '{}': {}", page, path.to_string_lossy(), err),
+
+            Self::FailedToOpenTarFile(err) =>
+                format!("Failed to open input TAR file: {}", err),
+
+            Self::InvalidTarArchive(err) =>
+                format!("Invalid TAR archive: {}", err),
+
+            Self::FailedToExtractTarFile { path_in_tar, extract_to, err } =>
+                format!("Failed to extract TAR file '{}' to '{}': {}", path_in_tar.to_string_lossy(), extract_to.to_string_lossy(), err),
+
+            Self::ArchiveFileHasInvalidUTF8FileExtension(path) =>
+                format!("A file in the archive has an invalid UTF-8 file extension ('{}')", path.to_string_lossy()),
+
+            Self::FailedToCreateTemporaryDirectory(path, err) =>
+                format!("Failed to create temporary directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToExtractWithExternalProgram(err) =>
+                format!("Failed to extract archive: {}", err),
+
+            Self::FailedToListExtractedFiles(path, RecursiveFilesSearchErr::IOError(err)) =>
+                format!("Failed to list extracted files in directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToListExtractedFiles(path, RecursiveFilesSearchErr::InvalidFileName(item)) =>
+                format!("Found extracted item with invalid name in directory '{}': {}", path.to_string_lossy(), item.to_string_lossy()),
+
+            Self::FailedToRemoveTemporaryDirectory(path, err) =>
+                format!("Failed to remove temporary directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::NoPageSelected(total) =>
+                format!("None of the {} pages of the input file matches the page selection", total),
+
+            Self::FailedToCreatePageDirectory(path, err) =>
+                format!("Failed to create directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::InvalidNameTemplate(err) =>
+                format!("Invalid naming template: {}", err),
+
+            Self::NameTemplateWithoutPageNumber =>
+                "Naming template must contain the page number ('{n}') to give a different name to each page".to_string(),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NoArchiveFound =>
+                "No comic book was found".to_string(),
+
+            Self::DuplicateOutputDirectory(path) =>
+                format!("Multiple comic books would be extracted to directory '{}'", path.to_string_lossy()),
+
+            Self::FailedToCreateThreadPool(err) =>
+                format!("Failed to create thread pool: {}", err),
+
+            Self::FailedArchives(failed, total) =>
+                format!("Failed to decode {} out of {} comic books", failed, total),
+
+            Self::FailedToReadMetadataFile(path, err) =>
+                format!("Failed to read metadata file '{}' from the archive: {}", path.to_string_lossy(), err),
+
+            Self::FailedToWriteMetadataFile(path, err) =>
+                format!("Failed to write metadata file '{}': {}", path.to_string_lossy(), err),
+
+            Self::StdinMustBeTheOnlyInput =>
+                "Standard input ('-') cannot be decoded along with other comic books".to_string(),
+
+            Self::StdinRequiresOutputDirectory =>
+                "Please provide an output directory when decoding from standard input".to_string(),
+
+            Self::FailedToReadStdin(err) =>
+                format!("Failed to read standard input: {}", err),
+
+            Self::UnrecognizedStdinFormat =>
+                "Format of the comic book read from standard input was not recognized".to_string(),
+
+            Self::FailedToWriteTemporaryFile(path, err) =>
+                format!("Failed to write temporary file '{}': {}", path.to_string_lossy(), err)
+        })
+    }
+}
+
+/// Error during in the "metadata" action
+pub enum MetadataError {
+    InputFileNotFound,
+    InputFileIsADirectory,
+    FailedToGetCWD(IOError),
+    FailedToOpenArchive(ArchiveErr),
+    FailedToReadMetadata(MetadataReadErr),
+    FailedToSerializeMetadata(serde_json::Error)
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::InputFileNotFound =>
+                "Input file was not found".to_string(),
+
+            Self::InputFileIsADirectory =>
+                "Input file is a directory".to_string(),
+
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToOpenArchive(err) =>
+                format!("Failed to open input archive: {}", err),
+
+            Self::FailedToReadMetadata(err) =>
+                format!("Failed to read metadata: {}", err),
+
+            Self::FailedToSerializeMetadata(err) =>
+                format!("Failed to serialize metadata to JSON: {}", err)
+        })
+    }
+}
+
+/// Error during in the "validate" action
+pub enum ValidationError {
+    FailedToGetCWD(IOError),
+    FailedToFindArchives(ArchiveSearchErr),
+    NoArchiveFound,
+    InvalidArchives(usize, usize)
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NoArchiveFound =>
+                "No comic book was found".to_string(),
+
+            Self::InvalidArchives(invalid, total) =>
+                format!("Found problems in {} out of {} comic book{}", invalid, total, if *total > 1 { "s" } else { "" })
+        })
+    }
+}
+
+/// Error during in the "info" action
+pub enum InfoError {
+    FailedToGetCWD(IOError),
+    FailedToFindArchives(ArchiveSearchErr),
+    NoArchiveFound,
+    FailedToInspectArchive(PathBuf, InspectErr),
+    FailedToSerializeInfo(serde_json::Error)
+}
+
+impl fmt::Display for InfoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NoArchiveFound =>
+                "No comic book was found".to_string(),
+
+            Self::FailedToInspectArchive(path, err) =>
+                format!("Failed to inspect comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToSerializeInfo(err) =>
+                format!("Failed to serialize informations to JSON: {}", err)
+        })
+    }
+}
+
+/// Error during in the "optimize" action
+pub enum OptimizeError {
+    FailedToGetCWD(IOError),
+    FailedToFindArchives(ArchiveSearchErr),
+    NoArchiveFound,
+    OutputDirectoryNotFound,
+    FailedToCreateOutputDirectory(IOError),
+    OutputFileAlreadyExists(PathBuf),
+    FailedToOpenArchive(PathBuf, ArchiveErr),
+    FailedToCreateStagingFile(PathBuf, IOError),
+    FailedToReadArchiveFile(PathBuf, ArchiveErr),
+    FailedToConvertImage { archive: PathBuf, image_path: PathBuf, err: ImageError },
+    FailedToCopyFileToZip { archive: PathBuf, file_path: PathBuf, err: ArchiveErr },
+    FailedToCreateFileInZip { archive: PathBuf, file_path: PathBuf, err: ZipError },
+    FailedToWriteFileToZip { archive: PathBuf, file_path: PathBuf, err: IOError },
+    FailedToCloseZipArchive(PathBuf, ZipError),
+    FailedToReplaceArchive(PathBuf, IOError)
+}
+
+impl fmt::Display for OptimizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NoArchiveFound =>
+                "No comic book was found".to_string(),
+
+            Self::OutputDirectoryNotFound =>
+                "Output directory was not found".to_string(),
+
+            Self::FailedToCreateOutputDirectory(err) =>
+                format!("Failed to create output directory: {}", err),
+
+            Self::OutputFileAlreadyExists(path) =>
+                format!("Output file '{}' already exists (use '--overwrite' to force writing)", path.to_string_lossy()),
+
+            Self::FailedToOpenArchive(path, err) =>
+                format!("Failed to open comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToCreateStagingFile(path, err) =>
+                format!("Failed to create staging file '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadArchiveFile(path, err) =>
+                format!("Failed to read comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToConvertImage { archive, image_path, err } =>
+                format!(
+                    "Failed to convert image file '{}' from comic book '{}': {}",
+                    image_path.to_string_lossy(),
+                    archive.to_string_lossy(),
+                    err
+                ),
+
+            Self::FailedToCopyFileToZip { archive, file_path, err } =>
+                format!(
+                    "Failed to copy file '{}' from comic book '{}': {}",
+                    file_path.to_string_lossy(),
+                    archive.to_string_lossy(),
+                    err
+                ),
+
+            Self::FailedToCreateFileInZip { archive, file_path, err } =>
+                format!(
+                    "Failed to create file '{}' in optimized comic book '{}': {}",
+                    file_path.to_string_lossy(),
+                    archive.to_string_lossy(),
+                    err
+                ),
+
+            Self::FailedToWriteFileToZip { archive, file_path, err } =>
+                format!(
+                    "Failed to write file '{}' to optimized comic book '{}': {}",
+                    file_path.to_string_lossy(),
+                    archive.to_string_lossy(),
+                    err
+                ),
+
+            Self::FailedToCloseZipArchive(path, err) =>
+                format!("Failed to close optimized comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReplaceArchive(path, err) =>
+                format!("Failed to move optimized comic book to '{}': {}", path.to_string_lossy(), err)
+        })
+    }
+}
+
+/// Error during in the "merge" action
+pub enum MergeError {
+    FailedToGetCWD(IOError),
+    FailedToFindArchives(ArchiveSearchErr),
+    NotEnoughArchives,
+    OutputFileAlreadyExists(PathBuf),
+    OutputFileIsADirectory(PathBuf),
+    FailedToOpenArchive(PathBuf, ArchiveErr),
+    FailedToReadMetadata(PathBuf, MetadataReadErr),
+    FailedToCreateStagingFile(PathBuf, IOError),
+    FailedToReadArchiveFile(PathBuf, ArchiveErr),
+    FailedToConvertImage { archive: PathBuf, image_path: PathBuf, err: ImageError },
+    FailedToCreateFileInZip { file_path: PathBuf, err: ZipError },
+    FailedToWriteFileToZip { file_path: PathBuf, err: IOError },
+    FailedToCloseZipArchive(ZipError),
+    FailedToRenameCompleteArchive(PathBuf, IOError)
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NotEnoughArchives =>
+                "At least 2 comic books are required to perform a merge".to_string(),
+
+            Self::OutputFileAlreadyExists(path) =>
+                format!("Output file '{}' already exists (use '--overwrite' to force writing)", path.to_string_lossy()),
+
+            Self::OutputFileIsADirectory(path) =>
+                format!("Output file '{}' is a directory", path.to_string_lossy()),
+
+            Self::FailedToOpenArchive(path, err) =>
+                format!("Failed to open comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadMetadata(path, err) =>
+                format!("Failed to read metadata from comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToCreateStagingFile(path, err) =>
+                format!("Failed to create staging file '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadArchiveFile(path, err) =>
+                format!("Failed to read comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToConvertImage { archive, image_path, err } =>
+                format!(
+                    "Failed to convert image file '{}' from comic book '{}': {}",
+                    image_path.to_string_lossy(),
+                    archive.to_string_lossy(),
+                    err
+                ),
+
+            Self::FailedToCreateFileInZip { file_path, err } =>
+                format!("Failed to create file '{}' in merged comic book: {}", file_path.to_string_lossy(), err),
+
+            Self::FailedToWriteFileToZip { file_path, err } =>
+                format!("Failed to write file '{}' to merged comic book: {}", file_path.to_string_lossy(), err),
+
+            Self::FailedToCloseZipArchive(err) =>
+                format!("Failed to close merged comic book: {}", err),
+
+            Self::FailedToRenameCompleteArchive(path, err) =>
+                format!("Failed to move merged comic book to '{}': {}", path.to_string_lossy(), err)
+        })
+    }
+}
+
+pub enum SplitError {
+    FailedToGetCWD(IOError),
+    InputFileNotFound(PathBuf),
+    InputFileIsADirectory(PathBuf),
+    InvalidLimit,
+    OutputDirectoryNotFound,
+    FailedToCreateOutputDirectory(IOError),
+    OutputFileAlreadyExists(PathBuf),
+    FailedToOpenArchive(ArchiveErr),
+    FailedToReadMetadata(MetadataReadErr),
+    NoPageFound,
+    FailedToCreateStagingFile(PathBuf, IOError),
+    FailedToCopyFileToZip { part: usize, file_path: PathBuf, err: ArchiveErr },
+    FailedToCreateMetadataFileInZip(usize, ZipError),
+    FailedToWriteMetadataFileToZip(usize, IOError),
+    FailedToCloseZipArchive(usize, ZipError),
+    FailedToRenameCompleteArchive(PathBuf, IOError)
+}
+
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::InputFileNotFound(path) =>
+                format!("Input file '{}' was not found", path.to_string_lossy()),
+
+            Self::InputFileIsADirectory(path) =>
+                format!("Input file '{}' is a directory", path.to_string_lossy()),
+
+            Self::InvalidLimit =>
+                "Maximum number of pages or size per part cannot be 0".to_string(),
+
+            Self::OutputDirectoryNotFound =>
+                "Output directory was not found".to_string(),
+
+            Self::FailedToCreateOutputDirectory(err) =>
+                format!("Failed to create output directory: {}", err),
+
+            Self::OutputFileAlreadyExists(path) =>
+                format!("Output file '{}' already exists (use '--overwrite' to force writing)", path.to_string_lossy()),
+
+            Self::FailedToOpenArchive(err) =>
+                format!("Failed to open comic book: {}", err),
+
+            Self::FailedToReadMetadata(err) =>
+                format!("Failed to read metadata from comic book: {}", err),
+
+            Self::NoPageFound =>
+                "Comic book does not contain any page".to_string(),
+
+            Self::FailedToCreateStagingFile(path, err) =>
+                format!("Failed to create staging file '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToCopyFileToZip { part, file_path, err } =>
+                format!("Failed to copy file '{}' to part {}: {}", file_path.to_string_lossy(), part, err),
+
+            Self::FailedToCreateMetadataFileInZip(part, err) =>
+                format!("Failed to create metadata file in part {}: {}", part, err),
+
+            Self::FailedToWriteMetadataFileToZip(part, err) =>
+                format!("Failed to write metadata file to part {}: {}", part, err),
+
+            Self::FailedToCloseZipArchive(part, err) =>
+                format!("Failed to close part {}: {}", part, err),
+
+            Self::FailedToRenameCompleteArchive(path, err) =>
+                format!("Failed to move part to '{}': {}", path.to_string_lossy(), err)
+        })
+    }
+}
+
+pub enum ConvertError {
+    FailedToGetCWD(IOError),
+    FailedToFindArchives(ArchiveSearchErr),
+    NoArchiveFound,
+    OutputDirectoryNotFound,
+    FailedToCreateOutputDirectory(IOError),
+    OutputFileAlreadyExists(PathBuf),
+    UnsupportedFormat(PathBuf),
+    FailedToOpenArchive(PathBuf, ArchiveErr),
+    FailedToOpenPdfFile(PathBuf, PdfError),
+    FailedToGetPdfPage(PathBuf, usize, PdfError),
+    FailedToGetPdfPageResources(PathBuf, usize, PdfError),
+    UnsupportedPdfImage(PathBuf, usize),
+    FailedToCreateStagingFile(PathBuf, IOError),
+    FailedToReadArchiveFile(PathBuf, ArchiveErr),
+    FailedToConvertImage { archive: PathBuf, image_path: PathBuf, err: ImageError },
+    FailedToCopyFileToZip { archive: PathBuf, file_path: PathBuf, err: ArchiveErr },
+    FailedToCreateFileInZip { archive: PathBuf, file_path: PathBuf, err: IOError },
+    FailedToWriteFileToZip { archive: PathBuf, file_path: PathBuf, err: IOError },
+    FailedToCloseZipArchive(PathBuf, IOError),
+    FailedToRenameCompleteArchive(PathBuf, IOError)
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NoArchiveFound =>
+                "No comic book was found".to_string(),
+
+            Self::OutputDirectoryNotFound =>
+                "Output directory was not found".to_string(),
+
+            Self::FailedToCreateOutputDirectory(err) =>
+                format!("Failed to create output directory: {}", err),
+
+            Self::OutputFileAlreadyExists(path) =>
+                format!("Output file '{}' already exists (use '--overwrite' to force writing)", path.to_string_lossy()),
+
+            Self::UnsupportedFormat(path) =>
+                format!("Cannot convert '{}': unsupported format (based on file extension)", path.to_string_lossy()),
+
+            Self::FailedToOpenArchive(path, err) =>
+                format!("Failed to open comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToOpenPdfFile(path, err) =>
+                format!("Failed to open PDF file '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToGetPdfPage(path, page, err) =>
+                format!("Failed to get page {} from PDF file '{}': {}", page, path.to_string_lossy(), err),
+
+            Self::FailedToGetPdfPageResources(path, page, err) =>
+                format!("Failed to get resources from page {} of PDF file '{}': {}", page, path.to_string_lossy(), err),
+
+            Self::UnsupportedPdfImage(path, image) =>
+                format!("Image {} of PDF file '{}' is not a JPEG image", image, path.to_string_lossy()),
+
+            Self::FailedToCreateStagingFile(path, err) =>
+                format!("Failed to create staging file '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadArchiveFile(path, err) =>
+                format!("Failed to read comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToConvertImage { archive, image_path, err } =>
+                format!(
+                    "Failed to convert image file '{}' from comic book '{}': {}",
+                    image_path.to_string_lossy(),
+                    archive.to_string_lossy(),
+                    err
+                ),
+
+            Self::FailedToCopyFileToZip { archive, file_path, err } =>
+                format!(
+                    "Failed to copy file '{}' from comic book '{}': {}",
+                    file_path.to_string_lossy(),
+                    archive.to_string_lossy(),
+                    err
+                ),
+
+            Self::FailedToCreateFileInZip { archive, file_path, err } =>
+                format!(
+                    "Failed to create file '{}' in converted comic book '{}': {}",
+                    file_path.to_string_lossy(),
+                    archive.to_string_lossy(),
+                    err
+                ),
+
+            Self::FailedToWriteFileToZip { archive, file_path, err } =>
+                format!(
+                    "Failed to write file '{}' to converted comic book '{}': {}",
+                    file_path.to_string_lossy(),
+                    archive.to_string_lossy(),
+                    err
+                ),
+
+            Self::FailedToCloseZipArchive(path, err) =>
+                format!("Failed to close converted comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToRenameCompleteArchive(path, err) =>
+                format!("Failed to move converted comic book to '{}': {}", path.to_string_lossy(), err)
+        })
+    }
+}
+
+pub enum RenameError {
+    FailedToGetCWD(IOError),
+    FailedToFindArchives(ArchiveSearchErr),
+    NoArchiveFound,
+    FailedToOpenArchive(PathBuf, ArchiveErr),
+    FailedToReadMetadata(PathBuf, MetadataReadErr),
+    InvalidTemplate(PathBuf, TemplateErr),
+    EmptyName(PathBuf),
+    DuplicateName(PathBuf),
+    OutputFileAlreadyExists(PathBuf),
+    FailedToRenameArchive(PathBuf, IOError)
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NoArchiveFound =>
+                "No comic book was found".to_string(),
+
+            Self::FailedToOpenArchive(path, err) =>
+                format!("Failed to open comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadMetadata(path, err) =>
+                format!("Failed to read metadata from comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::InvalidTemplate(path, err) =>
+                format!("Failed to render name of comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::EmptyName(path) =>
+                format!("Template renders an empty name for comic book '{}'", path.to_string_lossy()),
+
+            Self::DuplicateName(path) =>
+                format!("Multiple comic books would be renamed to '{}'", path.to_string_lossy()),
+
+            Self::OutputFileAlreadyExists(path) =>
+                format!("Output file '{}' already exists (use '--overwrite' to force writing)", path.to_string_lossy()),
+
+            Self::FailedToRenameArchive(path, err) =>
+                format!("Failed to rename comic book '{}': {}", path.to_string_lossy(), err)
+        })
+    }
+}
+
+pub enum CoverError {
+    FailedToGetCWD(IOError),
+    FailedToFindArchives(ArchiveSearchErr),
+    NoArchiveFound,
+    OutputDirectoryNotFound,
+    FailedToCreateOutputDirectory(IOError),
+    OutputFileAlreadyExists(PathBuf),
+    FailedToReadCover(PathBuf, CoverErr),
+    FailedToConvertCover(PathBuf, ImageError),
+    FailedToWriteCover(PathBuf, IOError)
+}
+
+impl fmt::Display for CoverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NoArchiveFound =>
+                "No comic book was found".to_string(),
+
+            Self::OutputDirectoryNotFound =>
+                "Output directory was not found".to_string(),
+
+            Self::FailedToCreateOutputDirectory(err) =>
+                format!("Failed to create output directory: {}", err),
+
+            Self::OutputFileAlreadyExists(path) =>
+                format!("Output file '{}' already exists (use '--overwrite' to force writing)", path.to_string_lossy()),
+
+            Self::FailedToReadCover(path, err) =>
+                format!("Failed to read cover of comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToConvertCover(path, err) =>
+                format!("Failed to convert cover to '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToWriteCover(path, err) =>
+                format!("Failed to write cover to '{}': {}", path.to_string_lossy(), err)
+        })
+    }
+}
+
+pub enum ThumbnailsError {
+    FailedToGetCWD(IOError),
+    FailedToFindArchives(ArchiveSearchErr),
+    NoArchiveFound,
+    InvalidDimensions,
+    OutputDirectoryNotFound,
+    FailedToCreateOutputDirectory(IOError),
+    FailedThumbnails(usize, usize)
+}
+
+impl fmt::Display for ThumbnailsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NoArchiveFound =>
+                "No comic book was found".to_string(),
+
+            Self::InvalidDimensions =>
+                "Thumbnails' width and height cannot be 0".to_string(),
+
+            Self::OutputDirectoryNotFound =>
+                "Output directory was not found".to_string(),
+
+            Self::FailedToCreateOutputDirectory(err) =>
+                format!("Failed to create output directory: {}", err),
+
+            Self::FailedThumbnails(failed, total) =>
+                format!("Failed to generate thumbnails for {} out of {} comic books", failed, total)
+        })
+    }
+}
+
+pub enum DiffError {
+    FailedToGetCWD(IOError),
+    InputFileNotFound(PathBuf),
+    InputFileIsADirectory(PathBuf),
+    FailedToReadArchive(PathBuf, DiffErr),
+    FailedToSerializeDiff(serde_json::Error),
+    ArchivesDiffer(usize)
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::InputFileNotFound(path) =>
+                format!("Input file '{}' was not found", path.to_string_lossy()),
+
+            Self::InputFileIsADirectory(path) =>
+                format!("Input file '{}' is a directory", path.to_string_lossy()),
+
+            Self::FailedToReadArchive(path, err) =>
+                format!("Failed to read comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToSerializeDiff(err) =>
+                format!("Failed to serialize differences: {}", err),
+
+            Self::ArchivesDiffer(count) =>
+                format!("Comic books differ ({} difference{})", count, if *count > 1 { "s" } else { "" })
+        })
+    }
+}
+
+pub enum VerifyError {
+    FailedToGetCWD(IOError),
+    FailedToFindArchives(ArchiveSearchErr),
+    NoArchiveFound,
+    FailedArchives(usize, usize)
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NoArchiveFound =>
+                "No comic book was found".to_string(),
+
+            Self::FailedArchives(failed, total) =>
+                format!("Verification failed for {} out of {} comic books", failed, total)
+        })
+    }
+}
+
+pub enum WatchError {
+    FailedToGetCWD(IOError),
+    InputDirectoryNotFound(PathBuf),
+    FailedToReadConfig(PathBuf, IOError),
+    InvalidConfig(PathBuf, serde_json::Error),
+    InvalidEncodingOptions(String),
+    AtLeast1ChapterPerVolume,
+    FailedToCreateOutputDirectory(IOError),
+    FailedToLoadState(WatchStateErr),
+    FailedToSaveState(WatchStateErr),
+    FailedToReadChaptersDirectory(IOError),
+    FailedToCheckChapter(PathBuf, RecursiveFilesSearchErr),
+    FailedToBuildVolume(usize, Box<EncodingError>)
+}
+
+impl fmt::Display for WatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::InputDirectoryNotFound(path) =>
+                format!("Input directory '{}' was not found", path.to_string_lossy()),
+
+            Self::FailedToReadConfig(path, err) =>
+                format!("Failed to read configuration file '{}': {}", path.to_string_lossy(), err),
+
+            Self::InvalidConfig(path, err) =>
+                format!("Invalid configuration file '{}': {}", path.to_string_lossy(), err),
+
+            Self::InvalidEncodingOptions(err) =>
+                format!("Invalid encoding options in configuration file: {}", err),
+
+            Self::AtLeast1ChapterPerVolume =>
+                "Cannot compile with 0 chapters per volume".to_string(),
+
+            Self::FailedToCreateOutputDirectory(err) =>
+                format!("Failed to create output directory: {}", err),
+
+            Self::FailedToLoadState(err) =>
+                format!("Failed to load watch state: {}", err),
+
+            Self::FailedToSaveState(err) =>
+                format!("Failed to save watch state: {}", err),
+
+            Self::FailedToReadChaptersDirectory(err) =>
+                format!("Failed to read chapters directory: {}", err),
+
+            Self::FailedToCheckChapter(path, RecursiveFilesSearchErr::IOError(err)) =>
+                format!("Failed to check chapter directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToCheckChapter(path, RecursiveFilesSearchErr::InvalidFileName(item)) =>
+                format!("Found item with invalid name in chapter directory '{}': {}", path.to_string_lossy(), item.to_string_lossy()),
+
+            Self::FailedToBuildVolume(volume, err) =>
+                format!("Failed to build volume {}: {}", volume, error_chain(err))
+        })
+    }
+}
+
+pub enum BatchError {
+    FailedToGetCWD(IOError),
+    FailedToReadConfig(PathBuf, IOError),
+    InvalidConfig(PathBuf, toml::de::Error),
+    NoJobToRun,
+    InvalidJobOptions(String, toml::de::Error),
+    JobFailed(String, Box<EncodingError>),
+    FailedJobs(usize, usize)
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToReadConfig(path, err) =>
+                format!("Failed to read jobs file '{}': {}", path.to_string_lossy(), err),
+
+            Self::InvalidConfig(path, err) =>
+                format!("Invalid jobs file '{}': {}", path.to_string_lossy(), err),
+
+            Self::NoJobToRun =>
+                "No job to run".to_string(),
+
+            Self::InvalidJobOptions(job, err) =>
+                format!("Invalid options for job '{}': {}", job, err),
+
+            Self::JobFailed(job, err) =>
+                format!("Job '{}' failed: {}", job, error_chain(err)),
+
+            Self::FailedJobs(failed, total) =>
+                format!("{} out of {} jobs failed", failed, total)
+        })
+    }
+}
+
+pub enum ServeError {
+    FailedToGetCWD(IOError),
+    FailedToLoadQueue(PathBuf, QueueErr),
+    FailedToListen(String, IOError)
+}
+
+impl fmt::Display for ServeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToLoadQueue(path, err) =>
+                format!("Failed to load job queue '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToListen(address, err) =>
+                format!("Failed to listen on '{}': {}", address, err)
+        })
+    }
+}
+
+pub enum CleanError {
+    FailedToGetCWD(IOError),
+    DirectoryNotFound(PathBuf),
+    FailedToListFiles(PathBuf, RecursiveFilesSearchErr),
+    FailedToRemoveFiles(usize, usize)
+}
+
+impl fmt::Display for CleanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::DirectoryNotFound(path) =>
+                format!("Directory '{}' was not found", path.to_string_lossy()),
+
+            Self::FailedToListFiles(path, RecursiveFilesSearchErr::IOError(err)) =>
+                format!("Failed to list files in directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToListFiles(path, RecursiveFilesSearchErr::InvalidFileName(item)) =>
+                format!("Found item with invalid name in directory '{}': {}", path.to_string_lossy(), item.to_string_lossy()),
+
+            Self::FailedToRemoveFiles(failed, total) =>
+                format!("Failed to remove {} out of {} staging files", failed, total)
+        })
+    }
+}
+
+pub enum DedupeError {
+    FailedToGetCWD(IOError),
+    FailedToFindArchives(ArchiveSearchErr),
+    NoArchiveFound,
+    FailedArchives(usize, usize)
+}
+
+impl fmt::Display for DedupeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NoArchiveFound =>
+                "No comic book was found".to_string(),
+
+            Self::FailedArchives(failed, total) =>
+                format!("Failed to check or deduplicate {} out of {} comic books", failed, total)
+        })
+    }
+}
+
+pub enum StatsError {
+    FailedToGetCWD(IOError),
+    FailedToFindArchives(ArchiveSearchErr),
+    NoArchiveFound,
+    FailedToInspectArchive(PathBuf, InspectErr),
+    SourceDirectoryNotFound(PathBuf),
+    FailedToReadSource(PathBuf, RecursiveFilesSearchErr),
+    FailedToSerializeStats(serde_json::Error)
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NoArchiveFound =>
+                "No comic book was found".to_string(),
+
+            Self::FailedToInspectArchive(path, err) =>
+                format!("Failed to inspect comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::SourceDirectoryNotFound(path) =>
+                format!("Source directory '{}' was not found", path.to_string_lossy()),
+
+            Self::FailedToReadSource(path, RecursiveFilesSearchErr::IOError(err)) =>
+                format!("Failed to read source directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadSource(path, RecursiveFilesSearchErr::InvalidFileName(item)) =>
+                format!("Found item with invalid name in source directory '{}': {}", path.to_string_lossy(), item.to_string_lossy()),
+
+            Self::FailedToSerializeStats(err) =>
+                format!("Failed to serialize statistics to JSON: {}", err)
+        })
+    }
+}
+
+pub enum DoctorError {
+    FailedToGetCWD(IOError),
+    ProblemsFound(usize)
+}
+
+impl fmt::Display for DoctorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::ProblemsFound(count) =>
+                format!("Found {} problem{}", count, if *count > 1 { "s" } else { "" })
+        })
+    }
+}
+
+/// Error during in the "bench" action
+pub enum BenchError {
+    FailedToGetCWD(IOError),
+    InputDirectoryNotFound(PathBuf),
+    AtLeast1Page,
+    FailedToReadInputDirectory(PathBuf, RecursiveFilesSearchErr),
+    NoPictureFound(PathBuf),
+    FailedToReadPicture(PathBuf, IOError),
+    FailedToRunBenchmark(BenchErr),
+    FailedToSerializeReport(serde_json::Error)
+}
+
+impl fmt::Display for BenchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::InputDirectoryNotFound(path) =>
+                format!("Input directory '{}' was not found", path.to_string_lossy()),
+
+            Self::AtLeast1Page =>
+                "The sample must contain at least 1 page".to_string(),
+
+            Self::FailedToReadInputDirectory(path, RecursiveFilesSearchErr::IOError(err)) =>
+                format!("Failed to read input directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadInputDirectory(path, RecursiveFilesSearchErr::InvalidFileName(item)) =>
+                format!("Found item with invalid name in input directory '{}': {}", path.to_string_lossy(), item.to_string_lossy()),
+
+            Self::NoPictureFound(path) =>
+                format!("No picture was found in directory '{}'", path.to_string_lossy()),
+
+            Self::FailedToReadPicture(path, err) =>
+                format!("Failed to read picture '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToRunBenchmark(err) =>
+                format!("Benchmark failed: {}", err),
+
+            Self::FailedToSerializeReport(err) =>
+                format!("Failed to serialize benchmark results to JSON: {}", err)
+        })
+    }
+}
+
+/// Error during in the "repack" action
+pub enum RepackError {
+    FailedToGetCWD(IOError),
+    FailedToFindArchives(ArchiveSearchErr),
+    NoArchiveFound,
+    OutputFileAlreadyExists(PathBuf),
+    FailedToDecode(PathBuf, DecodingError),
+    FailedToReadMetadata(PathBuf, ArchiveErr),
+    FailedToReadPage(PathBuf, IOError),
+    FailedToConvertImage { archive: PathBuf, image_path: PathBuf, err: ImageError },
+    FailedToCreateStagingFile(PathBuf, IOError),
+    FailedToCreateFileInZip { archive: PathBuf, file_path: PathBuf, err: ZipError },
+    FailedToWriteFileToZip { archive: PathBuf, file_path: PathBuf, err: IOError },
+    FailedToCloseZipArchive(PathBuf, ZipError),
+    FailedToReplaceArchive(PathBuf, IOError),
+    FailedToRemoveOriginal(PathBuf, IOError),
+    FailedArchives(usize, usize)
+}
+
+impl fmt::Display for RepackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToFindArchives(err) =>
+                format!("{}", err),
+
+            Self::NoArchiveFound =>
+                "No comic book was found".to_string(),
+
+            Self::OutputFileAlreadyExists(path) =>
+                format!("Output file '{}' already exists (use '--overwrite' to replace it)", path.to_string_lossy()),
+
+            Self::FailedToDecode(path, err) =>
+                format!("Failed to decode comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadMetadata(path, err) =>
+                format!("Failed to read metadata from comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadPage(path, err) =>
+                format!("Failed to read extracted page '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToConvertImage { archive, image_path, err } =>
+                format!("Failed to convert page '{}' of comic book '{}': {}", image_path.to_string_lossy(), archive.to_string_lossy(), err),
+
+            Self::FailedToCreateStagingFile(path, err) =>
+                format!("Failed to create staging file '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToCreateFileInZip { archive, file_path, err } =>
+                format!("Failed to create file '{}' in the repacked archive of '{}': {}", file_path.to_string_lossy(), archive.to_string_lossy(), err),
+
+            Self::FailedToWriteFileToZip { archive, file_path, err } =>
+                format!("Failed to write file '{}' to the repacked archive of '{}': {}", file_path.to_string_lossy(), archive.to_string_lossy(), err),
+
+            Self::FailedToCloseZipArchive(path, err) =>
+                format!("Failed to close the repacked archive of '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReplaceArchive(path, err) =>
+                format!("Failed to move the repacked archive to '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToRemoveOriginal(path, err) =>
+                format!("Failed to remove original comic book '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedArchives(failed, total) =>
+                format!("Failed to repack {} out of {} comic books", failed, total)
+        })
+    }
+}
+
+/// Error during in the "preset" action
+pub enum PresetError {
+    FailedToLoadConfig(ConfigErr),
+    FailedToSaveConfig(ConfigErr),
+    PresetNotFound(String)
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToLoadConfig(err) =>
+                format!("Failed to load configuration: {}", err),
+
+            Self::FailedToSaveConfig(err) =>
+                format!("Failed to save configuration: {}", err),
+
+            Self::PresetNotFound(name) =>
+                format!("Preset '{}' was not found", name)
         })
     }
 }
@@ -0,0 +1,562 @@
+use crate::cli::error::*;
+use crate::lib::config::ConfigErr;
+use std::fmt;
+
+/// Exit code of the program, depending on the kind of failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    /// Failure that doesn't fit in any other category
+    Failure = 1,
+    /// Invalid arguments, options, configuration or input paths
+    BadArguments = 2,
+    /// Failed to read or write a file or a directory
+    IOError = 3,
+    /// An output file already exists
+    OutputExists = 4,
+    /// An input archive or picture is corrupt or unsupported
+    CorruptInput = 5,
+    /// Some items (volumes, archives, jobs, ...) failed while the other ones succeeded
+    /// Only used with '--partial-success-exit-code', as it is reported as a failure otherwise
+    PartialSuccess = 6,
+    /// The user cancelled the run (Ctrl-C)
+    Cancelled = 130,
+}
+
+impl ExitCode {
+    /// Get the code to exit with
+    pub fn code(self, partial_success_exit_code: bool) -> i32 {
+        match self {
+            Self::PartialSuccess if !partial_success_exit_code => Self::Failure as i32,
+            _ => self as i32,
+        }
+    }
+}
+
+/// Error whose kind determines the program's exit code
+pub trait ExitCodeError: fmt::Display {
+    fn exit_code(&self) -> ExitCode;
+
+    /// Get the message to display, including the error's causes
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Failure of an action, with the message to display and the code to exit with
+pub struct Failure {
+    pub message: String,
+    pub exit_code: ExitCode,
+}
+
+impl<E: ExitCodeError> From<E> for Failure {
+    fn from(err: E) -> Self {
+        Self {
+            message: err.message(),
+            exit_code: err.exit_code(),
+        }
+    }
+}
+
+/// Exit code when some items out of a total failed
+fn partial(failed: usize, total: usize) -> ExitCode {
+    if failed < total {
+        ExitCode::PartialSuccess
+    } else {
+        ExitCode::Failure
+    }
+}
+
+impl ExitCodeError for ConfigErr {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::IOError(_, _) => ExitCode::IOError,
+            Self::NoConfigDirectory | Self::InvalidConfig(_, _) | Self::PresetNotFound(_) => {
+                ExitCode::BadArguments
+            }
+        }
+    }
+}
+
+impl ExitCodeError for EncodingError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::MissingOutputPath
+            | Self::InvalidNumberOfChaptersPerVolume
+            | Self::InvalidStartChapter
+            | Self::InvalidEndChapter
+            | Self::InvalidStartVolume
+            | Self::AtLeast1ChapterPerVolume
+            | Self::InvalidMaxPagesPerVolume
+            | Self::InvalidMaxSizePerVolume
+            | Self::StartChapterCannotBeHigherThanEndChapter
+            | Self::ChapterRegexHasNoNumGroup
+            | Self::FailedToReadVolumeMap(_, _)
+            | Self::ChaptersDirectoryNotFound
+            | Self::OutputDirectoryNotFound
+            | Self::OutputDirectoryInsideChapter(_)
+            | Self::OutputFileHasInvalidUTF8Name(_)
+            | Self::SingleInputDirectoryNotFound
+            | Self::SingleInputDirectoryIsNotADirectory
+            | Self::SingleInputDirectorHasNoName
+            | Self::SingleOutputFileHasNoName
+            | Self::InvalidEntryTemplate(_)
+            | Self::DuplicateEntryName(_, _)
+            | Self::InvalidMetadataCsv(_, _)
+            | Self::InvalidChapterTitlesCsv(_, _)
+            | Self::FailedToReadPlan(_, _)
+            | Self::IndividualVolumeMustContainOneChapter(_) => ExitCode::BadArguments,
+
+            Self::OutputVolumeFileAlreadyExists(_, _) | Self::OutputVolumeFileIsADirectory(_, _) => {
+                ExitCode::OutputExists
+            }
+
+            Self::ItemHasInvalidUTF8Name(_)
+            | Self::FoundItemWithInvalidName { .. }
+            | Self::FoundChapterGaps { .. }
+            | Self::DuplicateChapter(_, _, _)
+            | Self::FailedToOpenInput(_, _)
+            | Self::FailedToConvertImageFileToZip { .. }
+            | Self::FailedToReadExistingVolume(_, _, _)
+            | Self::InvalidMetadataFile(_, _) => ExitCode::CorruptInput,
+
+            Self::FailedVolumes(failed, total) => partial(*failed, *total),
+
+            Self::Cancelled => ExitCode::Cancelled,
+
+            Self::FailedToSerializeSummary(_)
+            | Self::FailedToCreateThreadPool(_)
+            | Self::PostVolumeCommandFailed(_, _)
+            | Self::PreChapterCommandFailed { .. } => ExitCode::Failure,
+
+            Self::FailedToGetCWD(_)
+            | Self::FailedToCreateOutputDirectory(_)
+            | Self::FailedToReadChaptersDirectory(_)
+            | Self::FailedToMeasureChapter(_, _)
+            | Self::FailedToGetChapterModificationDate(_, _)
+            | Self::FailedToCreateVolumeFile(_, _, _)
+            | Self::NotEnoughSpace { .. }
+            | Self::FailedToCreateBookDirectory(_, _, _)
+            | Self::FailedToOverwriteOutputVolumeFile(_, _, _)
+            | Self::FailedToListChapterDirectoryFiles { .. }
+            | Self::FailedToOpenImage { .. }
+            | Self::FailedToCreateChapterDirectoryInZip { .. }
+            | Self::FailedToReadImage { .. }
+            | Self::FailedToWriteImageFileToZip { .. }
+            | Self::FailedToCloseZipArchive(_, _)
+            | Self::FailedToGetVolumeSize(_, _)
+            | Self::FailedToWriteSummary(_, _)
+            | Self::FailedToIndexLibrary(_, _)
+            | Self::InvalidCompleteArchive(_, _)
+            | Self::CorruptedVolume(_, _, _)
+            | Self::FailedToRenameCompleteArchive(_, _)
+            | Self::FailedToWriteMetadataFileToZip(_, _)
+            | Self::FailedToWriteChecksumFile(_, _, _)
+            | Self::FailedToWriteCalibreFiles(_, _)
+            | Self::FailedToWritePlan(_, _)
+            | Self::FailedToWriteProgress(_, _)
+            | Self::FailedToRemoveStagingFile(_, _, _)
+            | Self::FailedToReadMetadataFile(_, _)
+            | Self::FailedToHashChapter { .. }
+            | Self::FailedToWriteCache(_, _)
+            | Self::FailedToReadConfirmation(_) => ExitCode::IOError,
+        }
+    }
+
+    fn message(&self) -> String {
+        error_chain(self)
+    }
+}
+
+impl ExitCodeError for DecodingError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::InputFileNotFound
+            | Self::OutputDirectoryNotFound
+            | Self::OutputDirectoryIsAFile
+            | Self::NoPageSelected(_)
+            | Self::InvalidNameTemplate(_)
+            | Self::NameTemplateWithoutPageNumber
+            | Self::NoArchiveFound
+            | Self::DuplicateOutputDirectory(_)
+            | Self::StdinMustBeTheOnlyInput
+            | Self::StdinRequiresOutputDirectory => ExitCode::BadArguments,
+
+            Self::InputFileHasInvalidUTF8FileExtension(_)
+            | Self::UnsupportedFormat(_)
+            | Self::InvalidZipArchive(_)
+            | Self::ZipError(_)
+            | Self::ZipFileHasInvalidUTF8FileExtension(_)
+            | Self::FailedToOpenPdfFile(_)
+            | Self::FailedToGetPdfPage(_, _)
+            | Self::FailedToGetPdfPageResources(_, _)
+            | Self::InvalidTarArchive(_)
+            | Self::ArchiveFileHasInvalidUTF8FileExtension(_)
+            | Self::FailedToExtractWithExternalProgram(_)
+            | Self::UnrecognizedStdinFormat => ExitCode::CorruptInput,
+
+            Self::FailedArchives(failed, total) => partial(*failed, *total),
+
+            Self::FailedToCreateThreadPool(_) => ExitCode::Failure,
+
+            Self::FailedToGetCWD(_)
+            | Self::FailedToCreateOutputDirectory(_)
+            | Self::FailedToOpenZipFile(_)
+            | Self::FailedToCreateOutputFile(_, _)
+            | Self::FailedToExtractZipFile { .. }
+            | Self::FailedToRenameTemporaryFile { .. }
+            | Self::FailedToExtractPdfImage(_, _, _)
+            | Self::FailedToOpenTarFile(_)
+            | Self::FailedToExtractTarFile { .. }
+            | Self::FailedToCreateTemporaryDirectory(_, _)
+            | Self::FailedToListExtractedFiles(_, _)
+            | Self::FailedToRemoveTemporaryDirectory(_, _)
+            | Self::FailedToCreatePageDirectory(_, _)
+            | Self::FailedToFindArchives(_)
+            | Self::FailedToReadMetadataFile(_, _)
+            | Self::FailedToWriteMetadataFile(_, _)
+            | Self::FailedToReadStdin(_)
+            | Self::FailedToWriteTemporaryFile(_, _) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for MetadataError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::InputFileNotFound | Self::InputFileIsADirectory => ExitCode::BadArguments,
+            Self::FailedToOpenArchive(_) | Self::FailedToReadMetadata(_) => ExitCode::CorruptInput,
+            Self::FailedToSerializeMetadata(_) => ExitCode::Failure,
+            Self::FailedToGetCWD(_) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for ValidationError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::NoArchiveFound => ExitCode::BadArguments,
+            Self::InvalidArchives(_, _) => ExitCode::CorruptInput,
+            Self::FailedToGetCWD(_) | Self::FailedToFindArchives(_) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for InfoError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::NoArchiveFound => ExitCode::BadArguments,
+            Self::FailedToInspectArchive(_, _) => ExitCode::CorruptInput,
+            Self::FailedToSerializeInfo(_) => ExitCode::Failure,
+            Self::FailedToGetCWD(_) | Self::FailedToFindArchives(_) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for OptimizeError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::NoArchiveFound | Self::OutputDirectoryNotFound => ExitCode::BadArguments,
+
+            Self::OutputFileAlreadyExists(_) => ExitCode::OutputExists,
+
+            Self::FailedToOpenArchive(_, _)
+            | Self::FailedToReadArchiveFile(_, _)
+            | Self::FailedToConvertImage { .. }
+            | Self::FailedToCopyFileToZip { .. } => ExitCode::CorruptInput,
+
+            Self::FailedToGetCWD(_)
+            | Self::FailedToFindArchives(_)
+            | Self::FailedToCreateOutputDirectory(_)
+            | Self::FailedToCreateStagingFile(_, _)
+            | Self::FailedToCreateFileInZip { .. }
+            | Self::FailedToWriteFileToZip { .. }
+            | Self::FailedToCloseZipArchive(_, _)
+            | Self::FailedToReplaceArchive(_, _) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for MergeError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::NotEnoughArchives => ExitCode::BadArguments,
+
+            Self::OutputFileAlreadyExists(_) | Self::OutputFileIsADirectory(_) => ExitCode::OutputExists,
+
+            Self::FailedToOpenArchive(_, _)
+            | Self::FailedToReadMetadata(_, _)
+            | Self::FailedToReadArchiveFile(_, _)
+            | Self::FailedToConvertImage { .. } => ExitCode::CorruptInput,
+
+            Self::FailedToGetCWD(_)
+            | Self::FailedToFindArchives(_)
+            | Self::FailedToCreateStagingFile(_, _)
+            | Self::FailedToCreateFileInZip { .. }
+            | Self::FailedToWriteFileToZip { .. }
+            | Self::FailedToCloseZipArchive(_)
+            | Self::FailedToRenameCompleteArchive(_, _) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for SplitError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::InputFileNotFound(_)
+            | Self::InputFileIsADirectory(_)
+            | Self::InvalidLimit
+            | Self::OutputDirectoryNotFound => ExitCode::BadArguments,
+
+            Self::OutputFileAlreadyExists(_) => ExitCode::OutputExists,
+
+            Self::FailedToOpenArchive(_)
+            | Self::FailedToReadMetadata(_)
+            | Self::NoPageFound
+            | Self::FailedToCopyFileToZip { .. } => ExitCode::CorruptInput,
+
+            Self::FailedToGetCWD(_)
+            | Self::FailedToCreateOutputDirectory(_)
+            | Self::FailedToCreateStagingFile(_, _)
+            | Self::FailedToCreateMetadataFileInZip(_, _)
+            | Self::FailedToWriteMetadataFileToZip(_, _)
+            | Self::FailedToCloseZipArchive(_, _)
+            | Self::FailedToRenameCompleteArchive(_, _) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for ConvertError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::NoArchiveFound | Self::OutputDirectoryNotFound => ExitCode::BadArguments,
+
+            Self::OutputFileAlreadyExists(_) => ExitCode::OutputExists,
+
+            Self::UnsupportedFormat(_)
+            | Self::FailedToOpenArchive(_, _)
+            | Self::FailedToOpenPdfFile(_, _)
+            | Self::FailedToGetPdfPage(_, _, _)
+            | Self::FailedToGetPdfPageResources(_, _, _)
+            | Self::UnsupportedPdfImage(_, _)
+            | Self::FailedToReadArchiveFile(_, _)
+            | Self::FailedToConvertImage { .. }
+            | Self::FailedToCopyFileToZip { .. } => ExitCode::CorruptInput,
+
+            Self::FailedToGetCWD(_)
+            | Self::FailedToFindArchives(_)
+            | Self::FailedToCreateOutputDirectory(_)
+            | Self::FailedToCreateStagingFile(_, _)
+            | Self::FailedToCreateFileInZip { .. }
+            | Self::FailedToWriteFileToZip { .. }
+            | Self::FailedToCloseZipArchive(_, _)
+            | Self::FailedToRenameCompleteArchive(_, _) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for RenameError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::NoArchiveFound | Self::InvalidTemplate(_, _) | Self::EmptyName(_) | Self::DuplicateName(_) => {
+                ExitCode::BadArguments
+            }
+
+            Self::OutputFileAlreadyExists(_) => ExitCode::OutputExists,
+
+            Self::FailedToOpenArchive(_, _) | Self::FailedToReadMetadata(_, _) => ExitCode::CorruptInput,
+
+            Self::FailedToGetCWD(_) | Self::FailedToFindArchives(_) | Self::FailedToRenameArchive(_, _) => {
+                ExitCode::IOError
+            }
+        }
+    }
+}
+
+impl ExitCodeError for CoverError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::NoArchiveFound | Self::OutputDirectoryNotFound => ExitCode::BadArguments,
+
+            Self::OutputFileAlreadyExists(_) => ExitCode::OutputExists,
+
+            Self::FailedToReadCover(_, _) | Self::FailedToConvertCover(_, _) => ExitCode::CorruptInput,
+
+            Self::FailedToGetCWD(_)
+            | Self::FailedToFindArchives(_)
+            | Self::FailedToCreateOutputDirectory(_)
+            | Self::FailedToWriteCover(_, _) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for ThumbnailsError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::NoArchiveFound | Self::InvalidDimensions | Self::OutputDirectoryNotFound => {
+                ExitCode::BadArguments
+            }
+
+            Self::FailedThumbnails(failed, total) => partial(*failed, *total),
+
+            Self::FailedToGetCWD(_) | Self::FailedToFindArchives(_) | Self::FailedToCreateOutputDirectory(_) => {
+                ExitCode::IOError
+            }
+        }
+    }
+}
+
+impl ExitCodeError for DiffError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::InputFileNotFound(_) | Self::InputFileIsADirectory(_) => ExitCode::BadArguments,
+            Self::FailedToReadArchive(_, _) => ExitCode::CorruptInput,
+            Self::FailedToSerializeDiff(_) | Self::ArchivesDiffer(_) => ExitCode::Failure,
+            Self::FailedToGetCWD(_) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for VerifyError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::NoArchiveFound => ExitCode::BadArguments,
+            Self::FailedArchives(_, _) => ExitCode::CorruptInput,
+            Self::FailedToGetCWD(_) | Self::FailedToFindArchives(_) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for WatchError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::InputDirectoryNotFound(_)
+            | Self::InvalidConfig(_, _)
+            | Self::InvalidEncodingOptions(_)
+            | Self::AtLeast1ChapterPerVolume => ExitCode::BadArguments,
+
+            Self::FailedToBuildVolume(_, err) => err.exit_code(),
+
+            Self::FailedToGetCWD(_)
+            | Self::FailedToReadConfig(_, _)
+            | Self::FailedToCreateOutputDirectory(_)
+            | Self::FailedToLoadState(_)
+            | Self::FailedToSaveState(_)
+            | Self::FailedToReadChaptersDirectory(_)
+            | Self::FailedToCheckChapter(_, _) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for BatchError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::InvalidConfig(_, _) | Self::NoJobToRun | Self::InvalidJobOptions(_, _) => ExitCode::BadArguments,
+            Self::JobFailed(_, err) => err.exit_code(),
+            Self::FailedJobs(failed, total) => partial(*failed, *total),
+            Self::FailedToGetCWD(_) | Self::FailedToReadConfig(_, _) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for ServeError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::FailedToGetCWD(_) | Self::FailedToLoadQueue(_, _) | Self::FailedToListen(_, _) => {
+                ExitCode::IOError
+            }
+        }
+    }
+}
+
+impl ExitCodeError for CleanError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::DirectoryNotFound(_) => ExitCode::BadArguments,
+            Self::FailedToRemoveFiles(failed, total) => partial(*failed, *total),
+            Self::FailedToGetCWD(_) | Self::FailedToListFiles(_, _) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for DedupeError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::NoArchiveFound => ExitCode::BadArguments,
+            Self::FailedArchives(failed, total) => partial(*failed, *total),
+            Self::FailedToGetCWD(_) | Self::FailedToFindArchives(_) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for StatsError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::NoArchiveFound | Self::SourceDirectoryNotFound(_) => ExitCode::BadArguments,
+            Self::FailedToInspectArchive(_, _) => ExitCode::CorruptInput,
+            Self::FailedToSerializeStats(_) => ExitCode::Failure,
+            Self::FailedToGetCWD(_) | Self::FailedToFindArchives(_) | Self::FailedToReadSource(_, _) => {
+                ExitCode::IOError
+            }
+        }
+    }
+}
+
+impl ExitCodeError for DoctorError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::ProblemsFound(_) => ExitCode::Failure,
+            Self::FailedToGetCWD(_) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for BenchError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::InputDirectoryNotFound(_) | Self::AtLeast1Page | Self::NoPictureFound(_) => {
+                ExitCode::BadArguments
+            }
+
+            Self::FailedToRunBenchmark(_) | Self::FailedToSerializeReport(_) => ExitCode::Failure,
+
+            Self::FailedToGetCWD(_) | Self::FailedToReadInputDirectory(_, _) | Self::FailedToReadPicture(_, _) => {
+                ExitCode::IOError
+            }
+        }
+    }
+}
+
+impl ExitCodeError for RepackError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::NoArchiveFound => ExitCode::BadArguments,
+
+            Self::OutputFileAlreadyExists(_) => ExitCode::OutputExists,
+
+            Self::FailedToDecode(_, err) => err.exit_code(),
+
+            Self::FailedToReadMetadata(_, _) | Self::FailedToConvertImage { .. } => ExitCode::CorruptInput,
+
+            Self::FailedArchives(failed, total) => partial(*failed, *total),
+
+            Self::FailedToGetCWD(_)
+            | Self::FailedToFindArchives(_)
+            | Self::FailedToReadPage(_, _)
+            | Self::FailedToCreateStagingFile(_, _)
+            | Self::FailedToCreateFileInZip { .. }
+            | Self::FailedToWriteFileToZip { .. }
+            | Self::FailedToCloseZipArchive(_, _)
+            | Self::FailedToReplaceArchive(_, _)
+            | Self::FailedToRemoveOriginal(_, _) => ExitCode::IOError,
+        }
+    }
+}
+
+impl ExitCodeError for PresetError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::FailedToLoadConfig(err) | Self::FailedToSaveConfig(err) => err.exit_code(),
+            Self::PresetNotFound(_) => ExitCode::BadArguments,
+        }
+    }
+}
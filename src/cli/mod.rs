@@ -1,2 +1,3 @@
 pub mod error;
+pub mod exit_code;
 pub mod opts;
@@ -1,4 +1,12 @@
-use clap::{crate_authors, crate_description, crate_version, Parser as Clap};
+use clap::{crate_authors, crate_description, crate_version, ArgEnum, Parser as Clap};
+use crate::lib::chapter::{ChapterNumber, ChapterSelection};
+use crate::lib::notify::NotifyTarget;
+use crate::lib::selection::PageSelection;
+use crate::lib::serde_str;
+use glob::Pattern;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::env;
 use std::path::PathBuf;
 
 #[derive(Clap, Debug)]
@@ -10,6 +18,7 @@ pub struct Opts {
     #[clap(
         global = true,
         long = "silent",
+        env = "COMIC_ENC_SILENT",
         conflicts_with = "verbose",
         conflicts_with = "debug"
     )]
@@ -19,6 +28,7 @@ pub struct Opts {
     #[clap(
         global = true,
         long = "verbose",
+        env = "COMIC_ENC_VERBOSE",
         short,
         conflicts_with = "silent",
         conflicts_with = "debug"
@@ -29,19 +39,92 @@ pub struct Opts {
     #[clap(
         global = true,
         long = "debug",
+        env = "COMIC_ENC_DEBUG",
         conflicts_with = "silent",
         conflicts_with = "verbose"
     )]
     pub debug: bool,
 
+    /// Do not display progress bars while building volumes
+    #[clap(global = true, long = "no-progress", env = "COMIC_ENC_NO_PROGRESS")]
+    pub no_progress: bool,
+
+    /// Format of the messages ('json' emits one JSON event per line, e.g. when a volume is built)
+    #[clap(global = true, long, env = "COMIC_ENC_LOG_FORMAT", arg_enum, default_value = "text")]
+    pub log_format: LogFormat,
+
+    /// Also write every message, including the most detailed ones, to this file with a timestamp
+    #[clap(global = true, long, env = "COMIC_ENC_LOG_FILE", parse(from_os_str))]
+    pub log_file: Option<PathBuf>,
+
+    /// Maximum size of the log file in MB, above which it is rotated when starting (keeping the 5 previous ones)
+    #[clap(global = true, long, env = "COMIC_ENC_LOG_FILE_MAX_SIZE", default_value = "10")]
+    pub log_file_max_size: u64,
+
+    /// Add the options saved in a preset (options provided on the command line take precedence)
+    #[clap(global = true, long, env = "COMIC_ENC_PRESET")]
+    pub preset: Option<String>,
+
+    /// Exit with a distinct code (6) when some items (volumes, archives, jobs, ...) failed while the other ones succeeded
+    #[clap(global = true, long, env = "COMIC_ENC_PARTIAL_SUCCESS_EXIT_CODE")]
+    pub partial_success_exit_code: bool,
+
+    /// Send a notification when the run finishes or fails: 'desktop', or 'webhook:<url>' to POST the run's outcome as JSON (can be repeated)
+    #[clap(global = true, long, env = "COMIC_ENC_NOTIFY", multiple_occurrences = true)]
+    pub notify: Vec<NotifyTarget>,
+
     #[clap(subcommand)]
     pub action: Action,
 }
 
+// Options are only parsed once, so the size of the encoding options doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Clap, Debug)]
 pub enum Action {
     Encode(Encode),
     Decode(Decode),
+    Metadata(Metadata),
+    Validate(Validate),
+    Info(Info),
+    Optimize(Optimize),
+    Merge(Merge),
+    Split(Split),
+    Convert(Convert),
+    Rename(Rename),
+    Cover(Cover),
+    Thumbnails(Thumbnails),
+    Diff(Diff),
+    Verify(Verify),
+    Watch(Watch),
+    Batch(Batch),
+    Serve(Serve),
+    Clean(Clean),
+    Dedupe(Dedupe),
+    Stats(Stats),
+    Doctor(Doctor),
+    Bench(Bench),
+    Preset(Preset),
+    Completions(Completions),
+    Manpage(Manpage),
+    Repack(Repack),
+}
+
+impl Action {
+    /// Check if the action prints machine-readable data to STDOUT, in which case logs must be hidden
+    pub fn prints_json(&self) -> bool {
+        match self {
+            Self::Metadata(metadata) => match &metadata.action {
+                MetadataAction::Show(show) => show.json,
+            },
+            Self::Info(info) => info.json,
+            Self::Diff(diff) => diff.json,
+            Self::Stats(stats) => stats.json,
+            Self::Bench(bench) => bench.json,
+            Self::Encode(encode) => encode.options.json,
+            Self::Completions(_) | Self::Manpage(_) => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Clap, Debug)]
@@ -54,6 +137,7 @@ pub struct Encode {
     pub options: EncodingOptions,
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Clap, Debug)]
 /// Encode directories to volumes
 pub enum EncodingMethod {
@@ -61,130 +145,1230 @@ pub enum EncodingMethod {
     Single(EncodeSingle),
 }
 
-#[derive(Clap, Debug)]
+#[derive(Clap, Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct EncodingOptions {
     /// Path to the directory containing the chapters or the volumes to encode
     #[clap(parse(from_os_str))]
     pub input: PathBuf,
 
     /// Path to the directory where the volumes should be put or to the single volume
-    #[clap(short, long, parse(from_os_str))]
+    #[clap(short, long, env = "COMIC_ENC_ENCODE_OUTPUT", parse(from_os_str))]
     pub output: Option<PathBuf>,
 
+    /// Name of the series, used in the volumes' filename and metadata (default: name of the input directory)
+    #[clap(global = true, long, env = "COMIC_ENC_SERIES")]
+    pub series: Option<String>,
+
+    /// Arrange the volumes for a server's library: they are put in a directory named after the series, inside the output directory, and named like 'Series Name v01.cbz' with their number in their metadata
+    #[clap(global = true, long, env = "COMIC_ENC_LAYOUT", arg_enum)]
+    pub layout: Option<Layout>,
+
+    /// Once the volumes are built, write or refresh an index of the library listing all the comic books it contains, so reading applications can browse it (the library is the output directory, or the directory containing the series' directories with '--layout')
+    #[clap(global = true, long, env = "COMIC_ENC_LIBRARY_INDEX", arg_enum)]
+    pub library_index: Option<LibraryIndex>,
+
+    /// Put each volume in its own 'Author/Title' directory along with a 'metadata.opf' file and a 'cover.jpg' picture, as in a Calibre library, so large series can be imported at once (the author is the first writer from the volume's metadata, or 'Unknown')
+    #[clap(global = true, long, env = "COMIC_ENC_CALIBRE", conflicts_with = "layout")]
+    #[serde(default)]
+    pub calibre: bool,
+
+    /// Container the volumes are written to
+    #[clap(global = true, long, env = "COMIC_ENC_CONTAINER", arg_enum, default_value = "cbz")]
+    #[serde(default)]
+    pub container: VolumeFormat,
+
     /// Overwrite existing files instead of failing
-    #[clap(global = true, long)]
+    #[clap(global = true, long, env = "COMIC_ENC_OVERWRITE")]
+    #[serde(default)]
     pub overwrite: bool,
 
     /// Add the number of pages at the end of each volume's filename
-    #[clap(global = true, long)]
+    #[clap(global = true, long, env = "COMIC_ENC_APPEND_PAGES_COUNT")]
+    #[serde(default)]
     pub append_pages_count: bool,
 
+    /// Skip the volumes whose output file already exists
+    #[clap(global = true, long, env = "COMIC_ENC_SKIP_EXISTING")]
+    #[serde(default)]
+    pub skip_existing: bool,
+
     /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
-    #[clap(global = true, short, long)]
+    #[clap(global = true, short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    #[serde(default)]
     pub accept_extended_image_formats: bool,
 
+    /// Recognize the pictures from their content instead of their extension, so pages with a wrong or no extension are included (and named after their real format), and other files with a picture's extension are left out (slower, as the beginning of each file is read)
+    #[clap(global = true, long, env = "COMIC_ENC_SNIFF_IMAGES")]
+    #[serde(default)]
+    pub sniff_images: bool,
+
+    /// What to do with the chapter directories and pages whose name is not valid UTF-8
+    #[clap(global = true, long, env = "COMIC_ENC_ON_INVALID_NAME", arg_enum, default_value = "rename")]
+    #[serde(default)]
+    pub on_invalid_name: InvalidNamePolicy,
+
+    /// Don't ignore the files created by operating systems ('.DS_Store', 'Thumbs.db', 'desktop.ini', '__MACOSX') and hidden files when looking for chapters and pages
+    #[clap(global = true, long, env = "COMIC_ENC_NO_DEFAULT_IGNORES")]
+    #[serde(default)]
+    pub no_default_ignores: bool,
+
     /// Disable natural sorting for pictures (use default UTF-8 sorting, a bit faster but unintuitive)
-    #[clap(global = true, short, long)]
+    #[clap(global = true, short, long, env = "COMIC_ENC_SIMPLE_SORTING")]
+    #[serde(default)]
     pub simple_sorting: bool,
 
+    #[clap(flatten)]
+    #[serde(default)]
+    pub conversion: ConversionOptions,
+
+    /// CSV file mapping volume numbers to metadata written in each volume's 'ComicInfo.xml'
+    /// (columns: 'volume', then e.g. 'title', 'release_date', 'isbn' or any ComicInfo field)
+    #[clap(global = true, long, env = "COMIC_ENC_METADATA_CSV", parse(from_os_str))]
+    pub metadata_csv: Option<PathBuf>,
+
+    /// Mark the volumes as manga (implies a right-to-left reading direction unless '--reading-direction' is provided)
+    #[clap(global = true, long, env = "COMIC_ENC_MANGA")]
+    #[serde(default)]
+    pub manga: bool,
+
+    /// Reading direction written in the metadata of manga (with '--manga'), pages are neither reordered nor split differently
+    #[clap(global = true, long, env = "COMIC_ENC_READING_DIRECTION", arg_enum)]
+    pub reading_direction: Option<ReadingDirection>,
+
+    /// Bookmark the first page of each chapter in the volumes' metadata, using the chapter directories' name as title
+    #[clap(global = true, long, env = "COMIC_ENC_CHAPTER_BOOKMARKS")]
+    #[serde(default)]
+    pub chapter_bookmarks: bool,
+
+    /// CSV file mapping chapters (number or directory name) to their title, used to bookmark each chapter's first page
+    /// (columns: 'chapter', 'title' ; chapters not listed are named after their directory)
+    #[clap(global = true, long, env = "COMIC_ENC_CHAPTER_TITLES", parse(from_os_str))]
+    pub chapter_titles: Option<PathBuf>,
+
+    /// Write a '.sha256' checksum file next to each volume, which can be checked later with the 'verify' action
+    #[clap(global = true, long, env = "COMIC_ENC_EMIT_CHECKSUMS")]
+    #[serde(default)]
+    pub emit_checksums: bool,
+
+    /// Read each CBZ volume back once it's built, checking the checksum of its files, the header of its pages and their number, so a corrupted volume fails the build
+    #[clap(global = true, long, env = "COMIC_ENC_VERIFY")]
+    #[serde(default)]
+    pub verify: bool,
+
+    /// Only put the first pages of each chapter in the volumes, to quickly check their naming, ordering and conversion
+    #[clap(global = true, long, env = "COMIC_ENC_SAMPLE_PAGES")]
+    pub sample_pages: Option<usize>,
+
+    /// Naming template for the pages in the volumes, which can contain '/' to put them in directories, e.g. "{chapter:03}/{page:03}.{ext}"
+    /// (available: 'volume', 'chapter', 'page' for the page number in its chapter, 'volume_page' for the page number in the volume, 'dir' for the chapter's directory name, 'name' for the original file name without extension, and 'ext')
+    #[clap(global = true, long, env = "COMIC_ENC_ENTRY_TEMPLATE", conflicts_with_all = &["keep-original-names", "flat"])]
+    pub entry_template: Option<String>,
+
+    /// Keep the chapters' directory names and the pages' original file names in the volumes (instead of 'Vol_X_Chapter_Y_Pic_Z')
+    #[clap(global = true, long, env = "COMIC_ENC_KEEP_ORIGINAL_NAMES", conflicts_with = "flat")]
+    #[serde(default)]
+    pub keep_original_names: bool,
+
+    /// Put all pages at the root of the volumes, numbered in a single sequence
+    #[clap(global = true, long, env = "COMIC_ENC_FLAT")]
+    #[serde(default)]
+    pub flat: bool,
+
+    /// Command run through the shell after each volume is written, e.g. "rclone copy '{path}' remote:comics"
+    /// (available: 'volume', 'path', 'pages', 'size' in bytes, 'first_chapter' and 'last_chapter', also provided as environment variables like 'COMIC_ENC_HOOK_PATH')
+    #[clap(global = true, long, env = "COMIC_ENC_POST_VOLUME_CMD")]
+    pub post_volume_cmd: Option<String>,
+
+    /// Command run through the shell on each chapter before its pages are put in a volume, e.g. "denoise --in-place '{path}'"
+    /// (available: 'volume', 'chapter', 'path' of the chapter's directory and 'name' of the directory, also provided as environment variables like 'COMIC_ENC_HOOK_PATH')
+    #[clap(global = true, long, env = "COMIC_ENC_PRE_CHAPTER_CMD")]
+    pub pre_chapter_cmd: Option<String>,
+
+    /// What to do when the pre-chapter command fails
+    #[clap(global = true, long, env = "COMIC_ENC_PRE_CHAPTER_CMD_FAILURE", arg_enum, default_value = "abort")]
+    #[serde(default)]
+    pub pre_chapter_cmd_failure: HookFailurePolicy,
+
+    /// Only display the volumes, chapter ranges and output files that would be created, without writing anything
+    #[clap(global = true, long, env = "COMIC_ENC_DRY_RUN")]
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// Don't check that the output directory has enough space left for the volumes before building them
+    #[clap(global = true, long, env = "COMIC_ENC_NO_SPACE_CHECK")]
+    #[serde(default)]
+    pub no_space_check: bool,
+
+    /// Display the volumes, chapter ranges, estimated number of pages and output files before building them (even with '--silent')
+    #[clap(global = true, long, env = "COMIC_ENC_VERBOSE_PLAN")]
+    #[serde(default)]
+    pub verbose_plan: bool,
+
+    /// Write a 'summary.json' file in the output directory listing the produced volumes
+    #[clap(global = true, long, env = "COMIC_ENC_SUMMARY")]
+    #[serde(default)]
+    pub summary: bool,
+
+    /// Print the summary of the produced volumes as JSON instead of the messages
+    #[clap(global = true, long, env = "COMIC_ENC_JSON")]
+    #[serde(default)]
+    pub json: bool,
+
+    /// Number of threads used to read and convert pages (default: number of CPU cores)
+    #[clap(global = true, long, env = "COMIC_ENC_THREADS")]
+    pub threads: Option<usize>,
+
+    /// Approximate memory limit for the pages converted at once in each volume, in MB (fewer pages are then converted in parallel)
+    #[clap(global = true, long, env = "COMIC_ENC_MAX_MEMORY")]
+    pub max_memory: Option<u64>,
+
+    /// Read up to this number of pages ahead while the current ones are written, to hide the disk's latency
+    /// (pages that don't need to be converted are streamed otherwise)
+    #[clap(global = true, long, env = "COMIC_ENC_READAHEAD")]
+    pub readahead: Option<usize>,
+}
+
+#[derive(Clap, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConversionOptions {
     /// Compress losslessly (a lot slower, save up about 5% of the final volumes' size)
-    #[clap(global = true, long)]
+    #[clap(global = true, long, env = "COMIC_ENC_COMPRESS_LOSSLESSLY")]
+    #[serde(default)]
     pub compress_losslessly: bool,
 
-    /// Compress webp
-    #[clap(global = true, long)]
+    /// Compress webp (same as '--convert-to webp')
+    #[clap(global = true, long, env = "COMIC_ENC_COMPRESS_WEBP", conflicts_with = "convert-to")]
+    #[serde(default)]
     pub compress_webp: bool,
+
+    /// Convert the pages to this format (pages already in this format are left as they are)
+    #[clap(global = true, long, arg_enum, env = "COMIC_ENC_CONVERT_TO")]
+    pub convert_to: Option<PageFormat>,
+
+    /// Quality of the converted pages, from 0 to 100 (default: 60 for WebP and AVIF, 85 for JPEG; ignored for PNG)
+    #[clap(global = true, long, env = "COMIC_ENC_QUALITY")]
+    pub quality: Option<u8>,
+
+    /// Downscale the converted pages whose width or height exceeds this number of pixels
+    /// (giant PNG and JPEG pages are downscaled while being decoded, which uses a lot less memory).
+    /// WebP pictures can't be larger than 16383 pixels
+    #[clap(global = true, long, env = "COMIC_ENC_MAX_DIMENSION")]
+    pub max_dimension: Option<u32>,
 }
 
-#[derive(Clap, Debug, Clone)]
+impl ConversionOptions {
+    /// Get the format pages are converted to, if any
+    pub fn target_format(&self) -> Option<PageFormat> {
+        match self.convert_to {
+            Some(format) => Some(format),
+            None if self.compress_webp => Some(PageFormat::Webp),
+            None => None,
+        }
+    }
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PageFormat {
+    /// WebP (requires the 'webp' feature, enabled by default)
+    Webp,
+    /// JPEG (requires the 'jpeg' feature, enabled by default)
+    Jpeg,
+    /// PNG (requires the 'png' feature, enabled by default)
+    Png,
+    /// AVIF (requires the 'avif' feature)
+    Avif,
+}
+
+impl PageFormat {
+    /// Get the human-readable name of the format
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Webp => "WebP",
+            Self::Jpeg => "JPEG",
+            Self::Png => "PNG",
+            Self::Avif => "AVIF",
+        }
+    }
+
+    /// Get the cargo feature enabling the format's codec
+    pub fn feature(self) -> &'static str {
+        match self {
+            Self::Webp => "webp",
+            Self::Jpeg => "jpeg",
+            Self::Png => "png",
+            Self::Avif => "avif",
+        }
+    }
+}
+
+impl EncodingOptions {
+    /// Get the effective reading direction of the volumes
+    pub fn reading_direction(&self) -> ReadingDirection {
+        match self.reading_direction {
+            Some(direction) => direction,
+            None if self.manga => ReadingDirection::Rtl,
+            None => ReadingDirection::Ltr,
+        }
+    }
+
+    /// Get the name of the series, which defaults to the name of the input directory (or archive, without its extension)
+    pub fn series(&self) -> Option<String> {
+        self.series.clone().or_else(|| {
+            let input = env::current_dir().ok()?.join(&self.input);
+
+            let name = if input.is_file() {
+                input.file_stem()
+            } else {
+                input.file_name()
+            };
+
+            name.map(|name| name.to_string_lossy().into_owned())
+        })
+    }
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable messages
+    Text,
+    /// One JSON event per line
+    Json,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReadingDirection {
+    /// Left to right
+    Ltr,
+    /// Right to left
+    Rtl,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    /// 'Series Name/Series Name v01.cbz'
+    Komga,
+    /// 'Series Name/Series Name Vol. 01.cbz'
+    Kavita,
+}
+
+impl Layout {
+    /// Get the text preceding the volume's number in the volumes' name
+    pub fn volume_prefix(self) -> &'static str {
+        match self {
+            Self::Komga => "v",
+            Self::Kavita => "Vol. ",
+        }
+    }
+
+    /// Get the ComicInfo fields the server reads the volume's number from
+    /// Kavita reads chapters' numbers from the 'Number' field, so it must be left empty for volumes
+    pub fn volume_number_fields(self) -> &'static [&'static str] {
+        match self {
+            Self::Komga => &["Volume", "Number"],
+            Self::Kavita => &["Volume"],
+        }
+    }
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LibraryIndex {
+    /// 'opds.xml' OPDS feed, which most reading applications can browse
+    Opds,
+    /// 'index.json' file listing the series and their volumes
+    Json,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VolumeFormat {
+    /// ZIP archive
+    #[default]
+    Cbz,
+    /// TAR archive
+    Cbt,
+    /// 7-Zip archive (requires the '7z' program)
+    Cb7,
+    /// PDF document with one picture per page (metadata is left out)
+    Pdf,
+}
+
+impl VolumeFormat {
+    /// Get the file extension of the volumes
+    pub fn ext(self) -> &'static str {
+        match self {
+            Self::Cbz => "cbz",
+            Self::Cbt => "cbt",
+            Self::Cb7 => "cb7",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookFailurePolicy {
+    /// Fail the volume
+    #[default]
+    Abort,
+    /// Leave the chapter out of the volume
+    Skip,
+    /// Put the chapter in the volume anyway
+    Ignore,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InvalidNamePolicy {
+    /// Replace the invalid characters (pages are renamed in the volumes anyway)
+    #[default]
+    Rename,
+    /// Fail the compilation
+    Error,
+    /// Leave the chapter or page out of the volumes
+    Skip,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicateChapterPolicy {
+    /// Keep the most recently modified directory
+    #[default]
+    Newest,
+    /// Keep the first directory, in sorting order
+    First,
+    /// Fail the compilation
+    Error,
+    /// Put the pages of all directories in a single chapter, in sorting order
+    Merge,
+}
+
+#[derive(Clap, Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 /// Compile chapter directories into volumes
 pub struct CompilationOptions {
     #[clap(subcommand)]
     pub method: CompilationMethod,
 
     /// Creates output directory if it does not exist yet
-    #[clap(global = true, long)]
+    #[clap(global = true, long, env = "COMIC_ENC_ENCODE_CREATE_OUTPUT_DIR")]
+    #[serde(default)]
     pub create_output_dir: bool,
 
     /// Prefix in the name of the chapter directories
-    #[clap(global = true, short, long)]
+    #[clap(global = true, short, long, env = "COMIC_ENC_DIRS_PREFIX")]
     pub dirs_prefix: Option<String>,
 
-    /// Start at a specific chapter/volume (ignore every chapter before this one)
-    #[clap(global = true, long)]
-    pub start_chapter: Option<usize>,
+    /// Only consider the chapter directories whose name matches this glob pattern (e.g. 'Chapter *'), can be repeated
+    #[clap(global = true, long, env = "COMIC_ENC_INCLUDE", multiple_occurrences = true)]
+    #[serde(default, with = "serde_str::vec")]
+    pub include: Vec<Pattern>,
+
+    /// Ignore the chapter directories whose name matches this glob pattern (e.g. 'Extras', '__MACOSX' or '.*'), can be repeated
+    #[clap(global = true, long, env = "COMIC_ENC_EXCLUDE", multiple_occurrences = true)]
+    #[serde(default, with = "serde_str::vec")]
+    pub exclude: Vec<Pattern>,
+
+    /// Get the chapters' numbers from their directory's name with this regular expression, which must contain a group named 'num' (e.g. 'Ch\.(?P<num>\d+)'), instead of numbering them in order
+    #[clap(global = true, long, env = "COMIC_ENC_CHAPTER_REGEX")]
+    #[serde(default, with = "serde_str::option")]
+    pub chapter_regex: Option<Regex>,
+
+    /// Detect the chapters' numbers from their directory's name (e.g. 'Chapter 12', 'Capítulo 12', 'Tome 3', '第12話' or 'Глава 12', including full-width digits), instead of numbering them in order
+    #[clap(global = true, long, env = "COMIC_ENC_DETECT_CHAPTER_NUMBERS", conflicts_with = "chapter-regex")]
+    #[serde(default)]
+    pub detect_chapter_numbers: bool,
+
+    /// With '--detect-chapter-numbers', also detect numbers written as roman numerals or in english words (e.g. 'Chapter IV', 'Chapter Twelve', 'Chapter Twenty-One')
+    #[clap(global = true, long, env = "COMIC_ENC_DETECT_WORD_NUMBERS", requires = "detect-chapter-numbers")]
+    #[serde(default)]
+    pub detect_word_numbers: bool,
+
+    /// What to do when multiple directories have the same chapter number with '--chapter-regex' or '--detect-chapter-numbers' (e.g. re-uploads or 'v2' releases)
+    #[clap(global = true, long, env = "COMIC_ENC_ON_DUPLICATE_CHAPTER", arg_enum, default_value = "newest")]
+    #[serde(default)]
+    pub on_duplicate_chapter: DuplicateChapterPolicy,
+
+    /// Number of the first volume, to continue the numbering of a previous compilation (ignored with '--volume-map', which numbers volumes explicitly)
+    #[clap(global = true, long, env = "COMIC_ENC_START_VOLUME")]
+    pub start_volume: Option<usize>,
+
+    /// Number to add to the chapters' numbers, to continue the numbering of a previous compilation
+    #[clap(global = true, long, env = "COMIC_ENC_CHAPTER_OFFSET", conflicts_with_all = &["chapter-regex", "detect-chapter-numbers"])]
+    pub chapter_offset: Option<usize>,
+
+    /// Start at a specific chapter/volume (ignore every chapter before this one), e.g. '10' or '10.5' with '--chapter-regex' or '--detect-chapter-numbers'
+    #[clap(global = true, long, env = "COMIC_ENC_START_CHAPTER")]
+    pub start_chapter: Option<ChapterNumber>,
+
+    /// End at a specific chapter/volume (ignore every chapter after this one), e.g. '11' (which includes chapter '10.5') with '--chapter-regex' or '--detect-chapter-numbers'
+    #[clap(global = true, long, env = "COMIC_ENC_END_CHAPTER")]
+    pub end_chapter: Option<ChapterNumber>,
+
+    /// Only build the volumes containing these chapters (e.g. '10-25,30'), with all their chapters, leaving the other volumes untouched
+    /// (chapters are still assigned to volumes as if all of them were built)
+    #[clap(global = true, long, env = "COMIC_ENC_ONLY_CHAPTERS", conflicts_with = "from-plan")]
+    #[serde(default, with = "serde_str::option")]
+    pub only_chapters: Option<ChapterSelection>,
+
+    /// Write the volumes and chapters assignment to a JSON file instead of building the volumes
+    #[clap(global = true, long, env = "COMIC_ENC_EMIT_PLAN", parse(from_os_str), conflicts_with = "from-plan")]
+    pub emit_plan: Option<PathBuf>,
+
+    /// Build the volumes from a (possibly edited) JSON file written with '--emit-plan'
+    #[clap(global = true, long, env = "COMIC_ENC_FROM_PLAN", parse(from_os_str))]
+    pub from_plan: Option<PathBuf>,
+
+    /// Skip the volumes whose chapters haven't changed since they were built (tracked in a '.comic-enc-cache' file in the output directory)
+    #[clap(global = true, long, env = "COMIC_ENC_INCREMENTAL")]
+    #[serde(default)]
+    pub incremental: bool,
 
-    /// End at a specific chapter/volume (ignore every chapter after this one)
-    #[clap(global = true, long)]
-    pub end_chapter: Option<usize>,
+    /// Number of volumes to build in parallel (default: 1, pages of each volume are already converted in parallel)
+    #[clap(global = true, short, long, env = "COMIC_ENC_ENCODE_JOBS")]
+    pub jobs: Option<usize>,
+
+    /// Keep building the remaining volumes when one of them fails, and report all failures at the end
+    #[clap(global = true, long, env = "COMIC_ENC_KEEP_GOING")]
+    #[serde(default)]
+    pub keep_going: bool,
+
+    /// Print the chapters assigned to each volume and ask for confirmation before building them, allowing to exclude some chapters
+    #[clap(global = true, long, env = "COMIC_ENC_INTERACTIVE")]
+    #[serde(default)]
+    pub interactive: bool,
+
+    /// Compile each sub-directory of the input directory as an independent series (with its own chapter directories), in its own output sub-directory
+    #[clap(global = true, long, env = "COMIC_ENC_GROUP_BY_SUBDIR", conflicts_with_all = &["series", "emit-plan", "from-plan"])]
+    #[serde(default)]
+    pub group_by_subdir: bool,
+
+    /// Fail before building anything if a chapter doesn't contain any image, or if chapters are missing from the numbering
+    /// (e.g. chapter 12 between chapters 11 and 13, with '--chapter-regex' or '--detect-chapter-numbers')
+    #[clap(global = true, long, env = "COMIC_ENC_FAIL_ON_GAPS")]
+    #[serde(default)]
+    pub fail_on_gaps: bool,
 }
 
-#[derive(Clap, Debug, Clone, Copy)]
+#[derive(Clap, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum CompilationMethod {
     Ranges(CompileRanges),
     Each(CompileEach),
 }
 
-#[derive(Clap, Debug, Clone, Copy)]
+#[derive(Clap, Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 /// Compile multiple chapters in single volumes (e.g. compile 10 to compile 10 chapters per volume)
 pub struct CompileRanges {
-    /// Number of chapters per volume
-    #[clap(global = true)]
-    pub chapters_per_volume: u16,
+    /// Number of chapters per volume (maximum number when using '--max-pages-per-volume' or '--max-size-per-volume')
+    #[clap(required_unless_present_any = &["max-pages-per-volume", "max-size-per-volume", "volume-map"])]
+    pub chapters_per_volume: Option<u16>,
+
+    /// Start a new volume (at a chapter boundary) when adding the next chapter would exceed this number of pages
+    #[clap(global = true, long, env = "COMIC_ENC_MAX_PAGES_PER_VOLUME")]
+    pub max_pages_per_volume: Option<usize>,
+
+    /// Start a new volume (at a chapter boundary) when adding the next chapter would exceed this size in MB (size of the source pages)
+    #[clap(global = true, long, env = "COMIC_ENC_MAX_SIZE_PER_VOLUME")]
+    pub max_size_per_volume: Option<u64>,
+
+    /// File listing the chapter starting each volume (e.g. 'v1: 1, v2: 8, v3: 16'), separated by commas or new lines
+    #[clap(
+        global = true,
+        long,
+        env = "COMIC_ENC_VOLUME_MAP",
+        parse(from_os_str),
+        conflicts_with_all = &["chapters-per-volume", "max-pages-per-volume", "max-size-per-volume"]
+    )]
+    pub volume_map: Option<PathBuf>,
 
     /// Add the start and end chapter at the end of each volume's filename
-    #[clap(global = true, long)]
+    #[clap(global = true, long, env = "COMIC_ENC_APPEND_CHAPTERS_RANGE")]
+    #[serde(default)]
     pub append_chapters_range: bool,
 
     /// Show path for each chapter put in a volume
-    #[clap(global = true, long)]
+    #[clap(global = true, long, env = "COMIC_ENC_DEBUG_CHAPTERS_PATH")]
+    #[serde(default)]
     pub debug_chapters_path: bool,
 }
 
-#[derive(Clap, Debug, Clone, Copy)]
+#[derive(Clap, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 /// Compile directories to individual volumes
 pub struct CompileEach {
-    /// Skip output chapter files that already exist
-    #[clap(global = true, long, conflicts_with = "append_pages_count")]
-    pub skip_existing: bool,
-
     /// Display full file names (by default names are truncated above 50 characters)
-    #[clap(global = true, long)]
+    #[clap(global = true, long, env = "COMIC_ENC_DISPLAY_FULL_NAMES")]
+    #[serde(default)]
     pub display_full_names: bool,
 }
 
-#[derive(Clap, Debug, Clone, Copy)]
+#[derive(Clap, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 /// Encode a single directory as a single volume
 pub struct EncodeSingle {}
 
 #[derive(Clap, Debug, Clone)]
 /// Extract images from an existing comic book
 pub struct Decode {
-    /// The comic book to decode
-    #[clap(parse(from_os_str))]
-    pub input: PathBuf,
+    /// The comic books to decode (directories are searched recursively, and each comic book is extracted to its own directory), or '-' to read one from standard input
+    #[clap(required = true, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
 
     /// Directory where images will be written
-    #[clap(global = true, short, long, parse(from_os_str))]
+    #[clap(global = true, short, long, env = "COMIC_ENC_DECODE_OUTPUT", parse(from_os_str))]
     pub output: Option<PathBuf>,
 
     /// Creates output directory if it does not exist yet
-    #[clap(global = true, long)]
+    #[clap(global = true, long, env = "COMIC_ENC_DECODE_CREATE_OUTPUT_DIR")]
     pub create_output_dir: bool,
 
     /// Only extract supported image formats
-    #[clap(global = true, short, long)]
+    #[clap(global = true, short, long, env = "COMIC_ENC_EXTRACT_IMAGES_ONLY")]
     pub extract_images_only: bool,
 
+    /// Extract the files that are not pages (e.g. metadata, credits) to this directory, relative to the output directory, instead of numbering them as pages
+    #[clap(global = true, long, env = "COMIC_ENC_EXTRAS_DIR", parse(from_os_str))]
+    pub extras_dir: Option<PathBuf>,
+
     /// When using '--extract-images-only', extract additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
-    #[clap(global = true, short, long, requires = "extract-images-only")]
+    #[clap(global = true, short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS", requires = "extract-images-only")]
     pub accept_extended_image_formats: bool,
 
     /// Disable natural sorting (use default UTF-8 sorting, a bit faster but unintuitive)
-    #[clap(global = true, short, long)]
+    #[clap(global = true, short, long, env = "COMIC_ENC_SIMPLE_SORTING")]
     pub simple_sorting: bool,
 
     /// Continue extraction even if some pages cannot be extracted from the input PDF (only if input file is PDF)
-    #[clap(global = true, long)]
+    #[clap(global = true, long, env = "COMIC_ENC_SKIP_BAD_PDF_PAGES")]
     pub skip_bad_pdf_pages: bool,
+
+    /// Only extract the provided pages, numbered from 1 in the sorting order (e.g. '1-20,35' or '100-')
+    #[clap(global = true, long, env = "COMIC_ENC_DECODE_PAGES")]
+    pub pages: Option<PageSelection>,
+
+    /// Put all pages directly in the output directory, numbered in a single sequence (default)
+    #[clap(global = true, long, env = "COMIC_ENC_FLATTEN", overrides_with = "keep-structure")]
+    pub flatten: bool,
+
+    /// Preserve the directories and file names of the archive instead of numbering all pages in a single sequence (ignored for PDF files)
+    #[clap(global = true, long, env = "COMIC_ENC_KEEP_STRUCTURE", overrides_with = "flatten")]
+    pub keep_structure: bool,
+
+    /// Naming template for the extracted pages, e.g. "page-{n:04}.{ext}"
+    /// (available: 'n' for the page number, 'total', 'name' for the original file name without extension, and 'ext')
+    #[clap(global = true, long, env = "COMIC_ENC_NAME_TEMPLATE", conflicts_with = "keep-structure")]
+    pub name_template: Option<String>,
+
+    /// Number of comic books to decode in parallel when decoding multiple ones (default: number of CPU cores)
+    #[clap(global = true, short, long, env = "COMIC_ENC_DECODE_JOBS")]
+    pub jobs: Option<usize>,
+
+    /// Don't write the metadata found in the comic book (ComicInfo, CoMet, ComicBookInfo) to a 'ComicInfo.xml' file in the output directory
+    #[clap(global = true, long, env = "COMIC_ENC_NO_METADATA")]
+    pub no_metadata: bool,
+}
+
+#[derive(Clap, Debug)]
+/// Inspect the metadata of existing comic books
+pub struct Metadata {
+    #[clap(subcommand)]
+    pub action: MetadataAction,
+}
+
+#[derive(Clap, Debug)]
+pub enum MetadataAction {
+    Show(MetadataShow),
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Display the metadata (ComicInfo, CoMet, ComicBookInfo) embedded in a comic book
+pub struct MetadataShow {
+    /// The comic book to inspect
+    #[clap(parse(from_os_str))]
+    pub input: PathBuf,
+
+    /// Display the metadata as JSON
+    #[clap(long, env = "COMIC_ENC_JSON")]
+    pub json: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Check the integrity of existing comic books
+pub struct Validate {
+    /// The comic books to validate (directories are searched recursively), RAR and 7-Zip archives require the 'unrar' and '7z' programs
+    #[clap(required = true, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
+
+    /// Accept additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    /// Consider warnings (e.g. pages that are not zero-padded) as problems
+    #[clap(long, env = "COMIC_ENC_VALIDATE_STRICT")]
+    pub strict: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Display informations about existing comic books
+pub struct Info {
+    /// The comic books to inspect (directories are searched recursively)
+    #[clap(required = true, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
+
+    /// Accept additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    /// Display the informations as JSON
+    #[clap(long, env = "COMIC_ENC_JSON")]
+    pub json: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Rebuild existing comic books with the provided conversion options, keeping their metadata
+pub struct Optimize {
+    /// The comic books to optimize (directories are searched recursively)
+    #[clap(required = true, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
+
+    /// Directory where the optimized comic books should be put (by default, the original files are replaced)
+    #[clap(short, long, env = "COMIC_ENC_OPTIMIZE_OUTPUT", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Creates output directory if it does not exist yet
+    #[clap(long, env = "COMIC_ENC_OPTIMIZE_CREATE_OUTPUT_DIR", requires = "output")]
+    pub create_output_dir: bool,
+
+    /// Overwrite existing files in the output directory instead of failing
+    #[clap(long, env = "COMIC_ENC_OVERWRITE", requires = "output")]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    #[clap(flatten)]
+    pub conversion: ConversionOptions,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Join multiple comic books into a single one
+pub struct Merge {
+    /// The comic books to merge, in reading order
+    #[clap(required = true, min_values = 2, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
+
+    /// Path to the merged comic book
+    #[clap(short, long, env = "COMIC_ENC_MERGE_OUTPUT", parse(from_os_str))]
+    pub output: PathBuf,
+
+    /// Overwrite the output file if it already exists instead of failing
+    #[clap(long, env = "COMIC_ENC_OVERWRITE")]
+    pub overwrite: bool,
+
+    /// Title of the merged comic book, written in its metadata
+    #[clap(long, env = "COMIC_ENC_TITLE")]
+    pub title: Option<String>,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    /// Disable natural sorting for pictures (use default UTF-8 sorting, a bit faster but unintuitive)
+    #[clap(short, long, env = "COMIC_ENC_SIMPLE_SORTING")]
+    pub simple_sorting: bool,
+
+    #[clap(flatten)]
+    pub conversion: ConversionOptions,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Split a comic book into multiple parts, at chapter boundaries when possible
+pub struct Split {
+    /// The comic book to split
+    #[clap(parse(from_os_str))]
+    pub input: PathBuf,
+
+    /// Maximum number of pages in each part
+    #[clap(
+        long,
+        env = "COMIC_ENC_MAX_PAGES",
+        required_unless_present = "max-size",
+        conflicts_with = "max-size"
+    )]
+    pub max_pages: Option<usize>,
+
+    /// Maximum size of each part, in MiB
+    #[clap(long, env = "COMIC_ENC_MAX_SIZE")]
+    pub max_size: Option<u64>,
+
+    /// Directory where the parts should be put (by default, next to the original comic book)
+    #[clap(short, long, env = "COMIC_ENC_SPLIT_OUTPUT", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Creates output directory if it does not exist yet
+    #[clap(long, env = "COMIC_ENC_SPLIT_CREATE_OUTPUT_DIR", requires = "output")]
+    pub create_output_dir: bool,
+
+    /// Overwrite existing parts instead of failing
+    #[clap(long, env = "COMIC_ENC_OVERWRITE")]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    /// Disable natural sorting for pictures (use default UTF-8 sorting, a bit faster but unintuitive)
+    #[clap(short, long, env = "COMIC_ENC_SIMPLE_SORTING")]
+    pub simple_sorting: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Repackage comic books (or PDF files) into another container format
+pub struct Convert {
+    /// The comic books to convert (directories are searched recursively)
+    #[clap(required = true, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
+
+    /// Container format to convert the comic books to
+    #[clap(long, env = "COMIC_ENC_TO", arg_enum)]
+    pub to: ContainerFormat,
+
+    /// Directory where the converted comic books should be put (by default, next to the original files)
+    #[clap(short, long, env = "COMIC_ENC_CONVERT_OUTPUT", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Creates output directory if it does not exist yet
+    #[clap(long, env = "COMIC_ENC_CONVERT_CREATE_OUTPUT_DIR", requires = "output")]
+    pub create_output_dir: bool,
+
+    /// Overwrite existing files instead of failing
+    #[clap(long, env = "COMIC_ENC_OVERWRITE")]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    #[clap(flatten)]
+    pub conversion: ConversionOptions,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerFormat {
+    /// ZIP archive with the comic book extension
+    Cbz,
+    /// Plain ZIP archive
+    Zip,
+    /// TAR archive
+    Cbt,
+    /// 7-Zip archive (requires the '7z' program)
+    Cb7,
+    /// PDF document with one picture per page (files that aren't pages are left out)
+    Pdf,
+}
+
+impl ContainerFormat {
+    /// Get the file extension of the container format
+    pub fn ext(&self) -> &'static str {
+        match self {
+            Self::Cbz => "cbz",
+            Self::Zip => "zip",
+            Self::Cbt => "cbt",
+            Self::Cb7 => "cb7",
+            Self::Pdf => "pdf",
+        }
+    }
+
+    /// Get the volume format written with the same container
+    pub fn volume_format(&self) -> VolumeFormat {
+        match self {
+            Self::Cbz | Self::Zip => VolumeFormat::Cbz,
+            Self::Cbt => VolumeFormat::Cbt,
+            Self::Cb7 => VolumeFormat::Cb7,
+            Self::Pdf => VolumeFormat::Pdf,
+        }
+    }
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Rename comic books according to a template filled from their current name and metadata
+pub struct Rename {
+    /// The comic books to rename (directories are searched recursively)
+    #[clap(required = true, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
+
+    /// Naming template, e.g. "{series} - v{volume:02} (c{start}-c{end})"
+    /// (available: 'name', 'series', 'volume', 'start', 'end', 'pages' and any ComicInfo field)
+    #[clap(short, long, env = "COMIC_ENC_TEMPLATE")]
+    pub template: String,
+
+    /// Only display the new names without renaming anything
+    #[clap(long, env = "COMIC_ENC_DRY_RUN")]
+    pub dry_run: bool,
+
+    /// Overwrite existing files instead of failing
+    #[clap(long, env = "COMIC_ENC_OVERWRITE")]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    /// Disable natural sorting for pictures (use default UTF-8 sorting, a bit faster but unintuitive)
+    #[clap(short, long, env = "COMIC_ENC_SIMPLE_SORTING")]
+    pub simple_sorting: bool,
+}
+
+#[derive(Clap, Debug)]
+/// Work with the covers of existing comic books
+pub struct Cover {
+    #[clap(subcommand)]
+    pub action: CoverAction,
+}
+
+#[derive(Clap, Debug)]
+pub enum CoverAction {
+    Extract(CoverExtract),
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Extract the cover (page marked as front cover in the metadata, or first page) of comic books
+pub struct CoverExtract {
+    /// The comic books to extract the cover of (directories are searched recursively)
+    #[clap(required = true, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
+
+    /// Output image (for a single comic book) or directory where the covers should be put (by default, next to the comic books)
+    /// Covers are converted if the output image's extension doesn't match their format
+    #[clap(short, long, env = "COMIC_ENC_COVER_OUTPUT", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Creates output directory if it does not exist yet
+    #[clap(long, env = "COMIC_ENC_COVER_CREATE_OUTPUT_DIR", requires = "output")]
+    pub create_output_dir: bool,
+
+    /// Overwrite existing images instead of failing
+    #[clap(long, env = "COMIC_ENC_OVERWRITE")]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    /// Disable natural sorting for pictures (use default UTF-8 sorting, a bit faster but unintuitive)
+    #[clap(short, long, env = "COMIC_ENC_SIMPLE_SORTING")]
+    pub simple_sorting: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Generate preview images of comic books from their cover
+pub struct Thumbnails {
+    /// The comic books to generate thumbnails for (directories are searched recursively)
+    #[clap(required = true, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
+
+    /// Cache directory where the thumbnails should be put, mirroring the input directories' structure (by default, next to the comic books)
+    #[clap(short, long, env = "COMIC_ENC_THUMBNAILS_OUTPUT", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Creates output directory if it does not exist yet
+    #[clap(long, env = "COMIC_ENC_THUMBNAILS_CREATE_OUTPUT_DIR", requires = "output")]
+    pub create_output_dir: bool,
+
+    /// Maximum width of the thumbnails, in pixels
+    #[clap(long, env = "COMIC_ENC_WIDTH", default_value = "300")]
+    pub width: u32,
+
+    /// Maximum height of the thumbnails, in pixels
+    #[clap(long, env = "COMIC_ENC_HEIGHT", default_value = "450")]
+    pub height: u32,
+
+    /// Image format of the thumbnails
+    #[clap(long, env = "COMIC_ENC_FORMAT", arg_enum, default_value = "jpeg")]
+    pub format: ThumbnailFormat,
+
+    /// Regenerate thumbnails even if they are more recent than their comic book
+    #[clap(long, env = "COMIC_ENC_OVERWRITE")]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    /// Disable natural sorting for pictures (use default UTF-8 sorting, a bit faster but unintuitive)
+    #[clap(short, long, env = "COMIC_ENC_SIMPLE_SORTING")]
+    pub simple_sorting: bool,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThumbnailFormat {
+    Jpeg,
+    Webp,
+}
+
+impl ThumbnailFormat {
+    /// Get the file extension of the thumbnails
+    pub fn ext(&self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Compare the pages of two comic books
+pub struct Diff {
+    /// The original comic book
+    #[clap(parse(from_os_str))]
+    pub before: PathBuf,
+
+    /// The comic book to compare it to
+    #[clap(parse(from_os_str))]
+    pub after: PathBuf,
+
+    /// Consider pages that look alike as identical, even if their content differs (e.g. after a re-encoding)
+    #[clap(long, env = "COMIC_ENC_PERCEPTUAL")]
+    pub perceptual: bool,
+
+    /// Display the differences as JSON
+    #[clap(long, env = "COMIC_ENC_JSON")]
+    pub json: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    /// Disable natural sorting for pictures (use default UTF-8 sorting, a bit faster but unintuitive)
+    #[clap(short, long, env = "COMIC_ENC_SIMPLE_SORTING")]
+    pub simple_sorting: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Check comic books against their checksum file to detect corruption
+pub struct Verify {
+    /// The comic books to verify (directories are searched recursively)
+    #[clap(required = true, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
+
+    /// Consider comic books without a checksum file as problems
+    #[clap(long, env = "COMIC_ENC_VERIFY_STRICT")]
+    pub strict: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Watch a directory for new chapters and compile them into volumes once they are complete
+pub struct Watch {
+    /// Directory where the chapters are downloaded
+    #[clap(parse(from_os_str))]
+    pub input: PathBuf,
+
+    /// JSON configuration file (output directory, chapters per volume, encoding options, ...)
+    #[clap(short, long, env = "COMIC_ENC_CONFIG", parse(from_os_str))]
+    pub config: PathBuf,
+
+    /// Check the directory a single time instead of watching it continuously
+    #[clap(long, env = "COMIC_ENC_ONCE")]
+    pub once: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Encode multiple series at once, as described in a TOML jobs file
+pub struct Batch {
+    /// TOML jobs file (input and output directories plus encoding and compilation options for each series)
+    #[clap(parse(from_os_str))]
+    pub config: PathBuf,
+
+    /// Only run the jobs with the provided names
+    #[clap(long, env = "COMIC_ENC_ONLY")]
+    pub only: Vec<String>,
+
+    /// Stop at the first failing job instead of running the remaining ones
+    #[clap(long, env = "COMIC_ENC_FAIL_FAST")]
+    pub fail_fast: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Run a local server exposing a JSON-RPC API to submit compilation jobs, follow their progress and cancel them
+pub struct Serve {
+    /// Address to listen on (the API has no authentication, so only listen on trusted networks)
+    #[clap(long, env = "COMIC_ENC_ADDRESS", default_value = "127.0.0.1:8420")]
+    pub address: String,
+
+    /// JSON file the submitted jobs are kept in, so pending jobs are resumed when the server restarts
+    #[clap(long, env = "COMIC_ENC_QUEUE_FILE", default_value = ".comic-enc-queue.json", parse(from_os_str))]
+    pub queue_file: PathBuf,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Remove the staging files left by interrupted runs
+pub struct Clean {
+    /// The directories to clean (searched recursively)
+    #[clap(required = true, parse(from_os_str))]
+    pub dirs: Vec<PathBuf>,
+
+    /// Only display the staging files without removing anything
+    #[clap(long, env = "COMIC_ENC_DRY_RUN")]
+    pub dry_run: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Find duplicate comic books in a library, even with different file names
+pub struct Dedupe {
+    /// The comic books to check (directories are searched recursively)
+    #[clap(required = true, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
+
+    /// Compare the pages of the comic books instead of their files (ignores metadata and compression differences)
+    #[clap(long, env = "COMIC_ENC_CONTENTS")]
+    pub contents: bool,
+
+    /// Replace identical files with hard links to the first one found
+    #[clap(long, env = "COMIC_ENC_HARD_LINK")]
+    pub hard_link: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    /// Disable natural sorting for pictures (use default UTF-8 sorting, a bit faster but unintuitive)
+    #[clap(short, long, env = "COMIC_ENC_SIMPLE_SORTING")]
+    pub simple_sorting: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Display statistics about a library or the volumes built by a job
+pub struct Stats {
+    /// The comic books to summarize (directories are searched recursively)
+    #[clap(required = true, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
+
+    /// Directory containing the source pictures the comic books were built from, to compare their sizes
+    #[clap(long, env = "COMIC_ENC_SOURCE", parse(from_os_str))]
+    pub source: Vec<PathBuf>,
+
+    /// Accept additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    /// Display the statistics as JSON
+    #[clap(long, env = "COMIC_ENC_JSON")]
+    pub json: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Check the environment before starting a long job
+pub struct Doctor {
+    /// Directory where the volumes will be written (defaults to the current directory)
+    #[clap(short, long, env = "COMIC_ENC_DOCTOR_OUTPUT", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Minimum space that must be available in the output directory, in MiB
+    #[clap(long, env = "COMIC_ENC_MIN_FREE_SPACE", default_value = "1024")]
+    pub min_free_space: u64,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Measure the speed and resulting size of each conversion option on a sample of pictures
+pub struct Bench {
+    /// Directory containing the pictures to take the sample from (searched recursively)
+    #[clap(parse(from_os_str))]
+    pub input: PathBuf,
+
+    /// Number of pictures in the sample, spread evenly across the directory
+    #[clap(long, env = "COMIC_ENC_BENCH_PAGES", default_value = "20")]
+    pub pages: usize,
+
+    /// Accept additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    /// Display the results as JSON
+    #[clap(long, env = "COMIC_ENC_JSON")]
+    pub json: bool,
+}
+
+#[derive(Clap, Debug)]
+/// Manage the presets of options saved in the configuration file
+pub struct Preset {
+    #[clap(subcommand)]
+    pub action: PresetAction,
+}
+
+#[derive(Clap, Debug)]
+pub enum PresetAction {
+    Save(PresetSave),
+    List(PresetList),
+    Remove(PresetRemove),
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Save options as a preset, to use them later with '--preset <name>'
+pub struct PresetSave {
+    /// Name of the preset (e.g. 'phone-webp')
+    pub name: String,
+
+    /// Options of the preset, after '--' (e.g. '-- --compress-webp --max-dimension 2048')
+    #[clap(last = true, required = true, multiple_values = true, allow_hyphen_values = true)]
+    pub options: Vec<String>,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// List the saved presets along with their options
+pub struct PresetList {}
+
+#[derive(Clap, Debug, Clone)]
+/// Remove a saved preset
+pub struct PresetRemove {
+    /// Name of the preset
+    pub name: String,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Print a completion script for the provided shell
+pub struct Completions {
+    /// Shell to generate the completion script for
+    #[clap(arg_enum)]
+    pub shell: Shell,
+}
+
+#[derive(ArgEnum, Debug, Clone, Copy)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Print the manual page, in the roff format
+pub struct Manpage {}
+
+#[derive(Clap, Debug)]
+/// Decode comic books and rebuild them as CBZ files, to normalize archives with unusual paths or mixed formats
+pub struct Repack {
+    /// The comic books to repack (directories are searched recursively)
+    #[clap(required = true, parse(from_os_str))]
+    pub inputs: Vec<PathBuf>,
+
+    /// Remove the original comic book once repacked, if it is not a ZIP archive (CBR, CB7, CBT and PDF files are kept next to the new CBZ otherwise)
+    #[clap(long, env = "COMIC_ENC_REMOVE_ORIGINAL")]
+    pub remove_original: bool,
+
+    /// Overwrite existing CBZ files when repacking comic books that are not ZIP archives
+    #[clap(long, env = "COMIC_ENC_OVERWRITE")]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long, env = "COMIC_ENC_ACCEPT_EXTENDED_IMAGE_FORMATS")]
+    pub accept_extended_image_formats: bool,
+
+    #[clap(flatten)]
+    pub conversion: ConversionOptions,
 }
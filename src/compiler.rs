@@ -0,0 +1,426 @@
+use crate::actions;
+use crate::cli::error::EncodingError;
+use crate::cli::opts::{
+    CompilationMethod, CompilationOptions, CompileEach, CompileRanges, ConversionOptions,
+    DuplicateChapterPolicy, EncodingOptions, HookFailurePolicy, InvalidNamePolicy, Layout,
+    PageFormat, ReadingDirection, VolumeFormat,
+};
+use crate::lib::cancel::CancellationToken;
+use crate::lib::chapter::ChapterNumber;
+use crate::lib::progress::{CliProgress, ProgressSink};
+use crate::lib::summary::JobReport;
+use regex::Regex;
+use std::path::PathBuf;
+
+/// How chapters are grouped into volumes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeSplit {
+    /// Put this number of chapters in each volume
+    ChaptersPerVolume(u16),
+    /// Start a new volume (at a chapter boundary) when adding the next chapter would exceed this number of pages
+    MaxPagesPerVolume(usize),
+    /// Put each chapter in its own volume
+    EachChapter,
+}
+
+/// How chapters are numbered
+#[derive(Debug, Clone)]
+pub enum ChapterNumbering {
+    /// Number the chapters in order, starting from 1
+    InOrder,
+    /// Get the numbers from the directories' name with a regular expression containing a group named 'num'
+    Regex(Regex),
+    /// Detect the numbers from the directories' name (e.g. 'Chapter 12', '第12話'), optionally written in words
+    Detect { words: bool },
+}
+
+/// Compile chapter directories into volumes, without going through the command line
+///
+/// ```no_run
+/// use comic_encoder::{Compiler, VolumeSplit};
+///
+/// let result = Compiler::new("/home/me/book")
+///     .output("/home/me/volumes")
+///     .split(VolumeSplit::ChaptersPerVolume(10))
+///     .compress_webp(true)
+///     .compile();
+///
+/// match result {
+///     Ok(report) => {
+///         for volume in report.volumes {
+///             println!("Built volume {} with {} pages", volume.volume, volume.pages);
+///         }
+///     }
+///     Err(err) => eprintln!("Failed to compile the volumes: {}", err),
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Compiler {
+    input: PathBuf,
+    output: Option<PathBuf>,
+    create_output_dir: bool,
+    series: Option<String>,
+    layout: Option<Layout>,
+    container: VolumeFormat,
+    split: VolumeSplit,
+    numbering: ChapterNumbering,
+    start_volume: Option<usize>,
+    start_chapter: Option<ChapterNumber>,
+    end_chapter: Option<ChapterNumber>,
+    append_chapters_range: bool,
+    overwrite: bool,
+    skip_existing: bool,
+    compress_webp: bool,
+    compress_losslessly: bool,
+    convert_to: Option<PageFormat>,
+    quality: Option<u8>,
+    max_dimension: Option<u32>,
+    metadata_csv: Option<PathBuf>,
+    manga: bool,
+    reading_direction: Option<ReadingDirection>,
+    emit_checksums: bool,
+    summary: bool,
+    incremental: bool,
+    keep_going: bool,
+    threads: Option<usize>,
+    jobs: Option<usize>,
+    cancel: CancellationToken,
+}
+
+impl Compiler {
+    /// Create a compiler for the chapter directories in the provided directory
+    /// Volumes contain 10 chapters each unless another split is chosen
+    pub fn new(input: impl Into<PathBuf>) -> Self {
+        Self {
+            input: input.into(),
+            output: None,
+            create_output_dir: false,
+            series: None,
+            layout: None,
+            container: VolumeFormat::Cbz,
+            split: VolumeSplit::ChaptersPerVolume(10),
+            numbering: ChapterNumbering::InOrder,
+            start_volume: None,
+            start_chapter: None,
+            end_chapter: None,
+            append_chapters_range: false,
+            overwrite: false,
+            skip_existing: false,
+            compress_webp: false,
+            compress_losslessly: false,
+            convert_to: None,
+            quality: None,
+            max_dimension: None,
+            metadata_csv: None,
+            manga: false,
+            reading_direction: None,
+            emit_checksums: false,
+            summary: false,
+            incremental: false,
+            keep_going: false,
+            threads: None,
+            jobs: None,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Put the volumes in this directory (default: the input directory)
+    pub fn output(mut self, output: impl Into<PathBuf>) -> Self {
+        self.output = Some(output.into());
+        self
+    }
+
+    /// Create the output directory if it does not exist yet
+    pub fn create_output_dir(mut self, create: bool) -> Self {
+        self.create_output_dir = create;
+        self
+    }
+
+    /// Name of the series, used in the volumes' filename and metadata (default: name of the input directory)
+    pub fn series(mut self, series: impl Into<String>) -> Self {
+        self.series = Some(series.into());
+        self
+    }
+
+    /// Arrange the volumes for a server's library
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Choose the container the volumes are written to (default: CBZ)
+    pub fn container(mut self, container: VolumeFormat) -> Self {
+        self.container = container;
+        self
+    }
+
+    /// Choose how chapters are grouped into volumes
+    pub fn split(mut self, split: VolumeSplit) -> Self {
+        self.split = split;
+        self
+    }
+
+    /// Choose how chapters are numbered
+    pub fn numbering(mut self, numbering: ChapterNumbering) -> Self {
+        self.numbering = numbering;
+        self
+    }
+
+    /// Number of the first volume, to continue the numbering of a previous compilation
+    pub fn start_volume(mut self, volume: usize) -> Self {
+        self.start_volume = Some(volume);
+        self
+    }
+
+    /// Ignore every chapter before this one
+    pub fn start_chapter(mut self, chapter: ChapterNumber) -> Self {
+        self.start_chapter = Some(chapter);
+        self
+    }
+
+    /// Ignore every chapter after this one
+    pub fn end_chapter(mut self, chapter: ChapterNumber) -> Self {
+        self.end_chapter = Some(chapter);
+        self
+    }
+
+    /// Add the start and end chapter at the end of each volume's filename
+    pub fn append_chapters_range(mut self, append: bool) -> Self {
+        self.append_chapters_range = append;
+        self
+    }
+
+    /// Overwrite existing files instead of failing
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// Skip the volumes whose output file already exists
+    pub fn skip_existing(mut self, skip: bool) -> Self {
+        self.skip_existing = skip;
+        self
+    }
+
+    /// Convert the pages to WebP
+    pub fn compress_webp(mut self, compress: bool) -> Self {
+        self.compress_webp = compress;
+        self
+    }
+
+    /// Compress the pages losslessly
+    pub fn compress_losslessly(mut self, compress: bool) -> Self {
+        self.compress_losslessly = compress;
+        self
+    }
+
+    /// Convert the pages to this format (its codec must be enabled through the cargo features)
+    pub fn convert_to(mut self, format: PageFormat) -> Self {
+        self.convert_to = Some(format);
+        self
+    }
+
+    /// Quality of the converted pages, from 0 to 100 (default: depends on the format)
+    pub fn quality(mut self, quality: u8) -> Self {
+        self.quality = Some(quality);
+        self
+    }
+
+    /// Downscale the converted pages whose width or height exceeds this number of pixels
+    pub fn max_dimension(mut self, pixels: u32) -> Self {
+        self.max_dimension = Some(pixels);
+        self
+    }
+
+    /// CSV file mapping volume numbers to metadata written in each volume's 'ComicInfo.xml'
+    pub fn metadata_csv(mut self, path: impl Into<PathBuf>) -> Self {
+        self.metadata_csv = Some(path.into());
+        self
+    }
+
+    /// Mark the volumes as manga (implies a right-to-left reading direction unless another one is chosen)
+    pub fn manga(mut self, manga: bool) -> Self {
+        self.manga = manga;
+        self
+    }
+
+    /// Reading direction written in the metadata of manga, pages are neither reordered nor split differently
+    pub fn reading_direction(mut self, direction: ReadingDirection) -> Self {
+        self.reading_direction = Some(direction);
+        self
+    }
+
+    /// Write a '.sha256' checksum file next to each volume
+    pub fn emit_checksums(mut self, emit: bool) -> Self {
+        self.emit_checksums = emit;
+        self
+    }
+
+    /// Write a 'summary.json' file in the output directory listing the produced volumes
+    pub fn summary(mut self, summary: bool) -> Self {
+        self.summary = summary;
+        self
+    }
+
+    /// Skip the volumes whose chapters haven't changed since they were built
+    pub fn incremental(mut self, incremental: bool) -> Self {
+        self.incremental = incremental;
+        self
+    }
+
+    /// Keep building the remaining volumes when one of them fails
+    pub fn keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// Number of threads used to read and convert pages (default: number of CPU cores)
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Number of volumes to build in parallel (default: 1)
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Stop building the volumes (between two pages) when this token is cancelled, e.g. from another thread
+    /// The staging file of the volumes being built is removed, and compiling again resumes where it stopped
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Build the volumes, returning the report listing the produced ones
+    /// Progress is reported like on the command line (through the 'log' crate)
+    pub fn compile(&self) -> Result<JobReport, EncodingError> {
+        self.compile_with_progress(&CliProgress)
+    }
+
+    /// Build the volumes, reporting their progress to the provided sink
+    pub fn compile_with_progress(
+        &self,
+        progress: &dyn ProgressSink,
+    ) -> Result<JobReport, EncodingError> {
+        actions::compile_volumes(
+            &self.compilation_options(),
+            &self.encoding_options(),
+            progress,
+            &self.cancel,
+        )
+    }
+
+    /// Get the equivalent of the command line's compilation options
+    fn compilation_options(&self) -> CompilationOptions {
+        let method = match self.split {
+            VolumeSplit::ChaptersPerVolume(chapters) => {
+                CompilationMethod::Ranges(self.ranges(Some(chapters), None))
+            }
+            VolumeSplit::MaxPagesPerVolume(pages) => {
+                CompilationMethod::Ranges(self.ranges(None, Some(pages)))
+            }
+            VolumeSplit::EachChapter => CompilationMethod::Each(CompileEach {
+                display_full_names: false,
+            }),
+        };
+
+        let (chapter_regex, detect_chapter_numbers, detect_word_numbers) = match &self.numbering {
+            ChapterNumbering::InOrder => (None, false, false),
+            ChapterNumbering::Regex(regex) => (Some(regex.clone()), false, false),
+            ChapterNumbering::Detect { words } => (None, true, *words),
+        };
+
+        CompilationOptions {
+            method,
+            create_output_dir: self.create_output_dir,
+            dirs_prefix: None,
+            include: vec![],
+            exclude: vec![],
+            chapter_regex,
+            detect_chapter_numbers,
+            detect_word_numbers,
+            on_duplicate_chapter: DuplicateChapterPolicy::Newest,
+            start_volume: self.start_volume,
+            chapter_offset: None,
+            start_chapter: self.start_chapter,
+            end_chapter: self.end_chapter,
+            only_chapters: None,
+            emit_plan: None,
+            from_plan: None,
+            incremental: self.incremental,
+            jobs: self.jobs,
+            keep_going: self.keep_going,
+            interactive: false,
+            group_by_subdir: false,
+            fail_on_gaps: false,
+        }
+    }
+
+    /// Get the options of the ranges compilation method
+    fn ranges(
+        &self,
+        chapters_per_volume: Option<u16>,
+        max_pages_per_volume: Option<usize>,
+    ) -> CompileRanges {
+        CompileRanges {
+            chapters_per_volume,
+            max_pages_per_volume,
+            max_size_per_volume: None,
+            volume_map: None,
+            append_chapters_range: self.append_chapters_range,
+            debug_chapters_path: false,
+        }
+    }
+
+    /// Get the equivalent of the command line's encoding options
+    fn encoding_options(&self) -> EncodingOptions {
+        EncodingOptions {
+            input: self.input.clone(),
+            output: self.output.clone(),
+            series: self.series.clone(),
+            layout: self.layout,
+            library_index: None,
+            calibre: false,
+            container: self.container,
+            overwrite: self.overwrite,
+            append_pages_count: false,
+            skip_existing: self.skip_existing,
+            accept_extended_image_formats: false,
+            sniff_images: false,
+            verify: false,
+            on_invalid_name: InvalidNamePolicy::Rename,
+            no_default_ignores: false,
+            simple_sorting: false,
+            conversion: ConversionOptions {
+                compress_losslessly: self.compress_losslessly,
+                compress_webp: self.compress_webp,
+                convert_to: self.convert_to,
+                quality: self.quality,
+                max_dimension: self.max_dimension,
+            },
+            metadata_csv: self.metadata_csv.clone(),
+            manga: self.manga,
+            reading_direction: self.reading_direction,
+            chapter_bookmarks: false,
+            chapter_titles: None,
+            emit_checksums: self.emit_checksums,
+            sample_pages: None,
+            entry_template: None,
+            keep_original_names: false,
+            flat: false,
+            post_volume_cmd: None,
+            pre_chapter_cmd: None,
+            pre_chapter_cmd_failure: HookFailurePolicy::Abort,
+            dry_run: false,
+            no_space_check: false,
+            verbose_plan: false,
+            summary: self.summary,
+            json: false,
+            threads: self.threads,
+            max_memory: None,
+            readahead: None,
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! Building and extracting comic archives
+//!
+//! This is the library behind the 'comic-enc' program, which can be embedded by other tools instead of running it.
+//! Chapter directories are compiled into volumes with the [`Compiler`] builder, and the other actions are available
+//! in the [`actions`] module, taking the same options as the command line. Programs written in other languages can use
+//! the C API of the `ffi` module instead (with the 'ffi' feature), and Python scripts the module built with the 'pyo3'
+//! feature.
+
+#![cfg_attr(not(any(feature = "ffi", feature = "pyo3")), forbid(unsafe_code))]
+#![cfg_attr(any(feature = "ffi", feature = "pyo3"), deny(unsafe_code))]
+#![deny(unused_must_use)]
+
+#[macro_use]
+extern crate log;
+
+pub mod actions;
+pub mod cli;
+pub mod compiler;
+#[cfg(feature = "ffi")]
+#[allow(unsafe_code)]
+pub mod ffi;
+#[allow(special_module_name)]
+pub mod lib;
+#[cfg(feature = "pyo3")]
+#[allow(unsafe_code)]
+pub mod python;
+
+pub use cli::error::EncodingError;
+pub use cli::opts::{PageFormat, VolumeFormat};
+pub use compiler::{ChapterNumbering, Compiler, VolumeSplit};
+pub use lib::cancel::CancellationToken;
+pub use lib::codec::ImageCodec;
+pub use lib::progress::ProgressSink;
+pub use lib::source::{DirSource, PageSource};
+pub use lib::summary::{DecodedSummary, JobReport, VolumeSummary};
+pub use lib::writer::ArchiveWriter;
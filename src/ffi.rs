@@ -0,0 +1,254 @@
+//! C API, to drive the encoder from programs written in other languages (e.g. a graphical interface)
+//!
+//! Only available with the 'ffi' feature. The matching header is 'include/comic_encoder.h', and the shared library
+//! is built with `cargo rustc --lib --release --features ffi --crate-type cdylib`.
+
+use crate::actions;
+use crate::cli::exit_code::{ExitCode, ExitCodeError};
+use crate::lib::cancel::CancellationToken;
+use crate::lib::chapter::ChapterNumber;
+use crate::lib::events::Event;
+use crate::lib::progress::ProgressSink;
+use crate::lib::serve::JobOptions;
+use crate::lib::summary::VolumeSummary;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+use std::sync::Mutex;
+
+/// Function receiving each progress event as a line of JSON (the same as with '--log-format json')
+/// The string is only valid during the call
+pub type ProgressCallback = extern "C" fn(event_json: *const c_char, user_data: *mut c_void);
+
+/// Compilation job created from a JSON options string
+pub struct Job {
+    options: JobOptions,
+    cancel: CancellationToken,
+}
+
+/// Create a compilation job from a JSON string like `{"encoding": {...}, "compilation": {...}}`, using the options'
+/// serialized form (see `EncodingOptions` and `CompilationOptions`)
+/// Returns a null pointer if the options are invalid, in which case `error` (if not null) is set to a string
+/// describing the problem, to free with `comic_enc_string_free`
+///
+/// # Safety
+///
+/// `options_json` must be a valid NUL-terminated string, and `error` either null or a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn comic_enc_job_new(
+    options_json: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut Job {
+    if options_json.is_null() {
+        set_string(error, "No options provided");
+        return ptr::null_mut();
+    }
+
+    let options = CStr::from_ptr(options_json)
+        .to_str()
+        .map_err(|err| format!("Options are not valid UTF-8: {}", err))
+        .and_then(|json| {
+            serde_json::from_str::<JobOptions>(json)
+                .map_err(|err| format!("Invalid options: {}", err))
+        });
+
+    match options {
+        Ok(options) => Box::into_raw(Box::new(Job {
+            options,
+            cancel: CancellationToken::new(),
+        })),
+
+        Err(err) => {
+            set_string(error, &err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Build the volumes of a job, blocking until they are built
+/// Progress events are provided to the callback (if not null) along with `user_data`, from the threads building the
+/// volumes but never concurrently
+/// Returns the exit code the command line would exit with (0 on success). `result` (if not null) is set to the JSON
+/// report of the produced volumes on success, or to the error's message on failure, to free with
+/// `comic_enc_string_free`
+///
+/// # Safety
+///
+/// `job` must have been created with `comic_enc_job_new` and not freed yet, and `result` either null or a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn comic_enc_job_run(
+    job: *const Job,
+    callback: Option<ProgressCallback>,
+    user_data: *mut c_void,
+    result: *mut *mut c_char,
+) -> i32 {
+    let job = match job.as_ref() {
+        Some(job) => job,
+        None => {
+            set_string(result, "No job provided");
+            return ExitCode::BadArguments.code(false);
+        }
+    };
+
+    let sink = CallbackProgress {
+        callback,
+        user_data: Mutex::new(UserData(user_data)),
+    };
+
+    // Panics must not unwind into the caller's code
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        actions::compile_volumes(
+            &job.options.compilation,
+            &job.options.encoding,
+            &sink,
+            &job.cancel,
+        )
+    }));
+
+    match outcome {
+        Ok(Ok(report)) => {
+            set_string(
+                result,
+                &serde_json::to_string(&report)
+                    .expect("Internal error: failed to serialize report"),
+            );
+            0
+        }
+
+        Ok(Err(err)) => {
+            set_string(result, &err.message());
+            err.exit_code().code(job.options.compilation.keep_going)
+        }
+
+        Err(_) => {
+            set_string(result, "Internal error: the encoder panicked");
+            ExitCode::Failure.code(false)
+        }
+    }
+}
+
+/// Stop a job being built as soon as possible, making `comic_enc_job_run` fail with the 'cancelled' exit code (130)
+/// Can be called from any thread while the job is running
+///
+/// # Safety
+///
+/// `job` must have been created with `comic_enc_job_new` and not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn comic_enc_job_cancel(job: *const Job) {
+    if let Some(job) = job.as_ref() {
+        job.cancel.cancel();
+    }
+}
+
+/// Free a job
+///
+/// # Safety
+///
+/// `job` must have been created with `comic_enc_job_new`, must not be running and must not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn comic_enc_job_free(job: *mut Job) {
+    if !job.is_null() {
+        drop(Box::from_raw(job));
+    }
+}
+
+/// Free a string returned by the library
+///
+/// # Safety
+///
+/// `string` must have been returned by the library and must not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn comic_enc_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Give a string to the caller through an output pointer, if it isn't null
+unsafe fn set_string(out: *mut *mut c_char, string: &str) {
+    if !out.is_null() {
+        *out = to_c_string(string).into_raw();
+    }
+}
+
+/// Convert a string to a C string, replacing the NUL characters it may contain
+fn to_c_string(string: &str) -> CString {
+    CString::new(string.replace('\0', "\u{FFFD}"))
+        .expect("Internal error: string still contains NUL characters")
+}
+
+/// Caller's data provided back to the callback
+struct UserData(*mut c_void);
+
+// The caller is responsible for its data being usable from the threads building the volumes
+unsafe impl Send for UserData {}
+
+/// Progress sink calling the caller's callback with each event in JSON
+struct CallbackProgress {
+    callback: Option<ProgressCallback>,
+    /// Locked during the calls, so the callback is never called concurrently
+    user_data: Mutex<UserData>,
+}
+
+impl CallbackProgress {
+    fn emit(&self, event: &Event) {
+        if let Some(callback) = self.callback {
+            let json = to_c_string(
+                &serde_json::to_string(event).expect("Internal error: failed to serialize event"),
+            );
+
+            let user_data = self
+                .user_data
+                .lock()
+                .expect("Internal error: callback's lock is poisoned");
+
+            callback(json.as_ptr(), user_data.0);
+        }
+    }
+}
+
+impl ProgressSink for CallbackProgress {
+    fn volume_started(&self, volume: usize, chapters: usize) {
+        self.emit(&Event::VolumeStarted { volume, chapters });
+    }
+
+    fn page_processed(
+        &self,
+        volume: usize,
+        chapter: ChapterNumber,
+        page: usize,
+        source: &Path,
+        size: u64,
+    ) {
+        self.emit(&Event::PageConverted {
+            volume,
+            chapter,
+            page,
+            source,
+            size,
+        });
+    }
+
+    fn volume_finished(&self, summary: &VolumeSummary) {
+        self.emit(&Event::VolumeFinished {
+            volume: summary.volume,
+            path: &summary.path,
+            size: summary.size,
+            pages: summary.pages,
+            seconds: summary.seconds,
+        });
+    }
+
+    fn volume_skipped(&self, volume: usize, path: &Path) {
+        self.emit(&Event::VolumeSkipped { volume, path });
+    }
+
+    fn warning(&self, message: &str) {
+        self.emit(&Event::Log {
+            level: "warn",
+            message: message.to_owned(),
+        });
+    }
+}
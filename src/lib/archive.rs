@@ -0,0 +1,487 @@
+use crate::lib::deter::{self, RecursiveFilesSearchErr};
+use crate::lib::external::ExternalFormat;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use zip::result::ZipError;
+use zip::write::{FileOptions, ZipWriter};
+use zip::{CompressionMethod, ZipArchive};
+
+/// Extension of the staging files archives are written to before being moved to their final path
+pub const STAGING_EXT: &str = "comic-enc-partial";
+
+/// Get the path of the staging file to write an archive to before moving it to its final path
+/// The extension is appended, so paths that only differ after their last dot (e.g. 'Series Vol. 01' and
+/// 'Series Vol. 02') get their own staging file
+pub fn staging_path(output: &Path) -> PathBuf {
+    deter::append_ext(output, STAGING_EXT)
+}
+
+/// Move a complete staging file to its final path
+/// The file is flushed to the disk before being renamed and its directory is synced afterwards, so a power loss can't
+/// leave a truncated file under the final name
+/// If both paths are on different filesystems, the file is copied next to its final path then renamed instead
+pub fn finalize_staging_file(staging: &Path, output: &Path) -> io::Result<()> {
+    let (staging, output) = (&deter::long_path(staging), &deter::long_path(output));
+
+    // Windows can only flush files opened for writing
+    OpenOptions::new().write(true).open(staging)?.sync_all()?;
+
+    if let Err(err) = fs::rename(staging, output) {
+        if !is_cross_device(&err) {
+            return Err(err);
+        }
+
+        debug!(
+            "Copying '{}' to another filesystem...",
+            staging.to_string_lossy()
+        );
+
+        let copy = staging_path(output);
+
+        fs::copy(staging, &copy)?;
+        OpenOptions::new().write(true).open(&copy)?.sync_all()?;
+        fs::rename(&copy, output)?;
+        fs::remove_file(staging)?;
+    }
+
+    match output.parent() {
+        Some(dir) if dir != Path::new("") => sync_dir(dir),
+        _ => sync_dir(Path::new(".")),
+    }
+}
+
+/// Check if a rename failed because both paths are on different filesystems
+fn is_cross_device(err: &io::Error) -> bool {
+    // 'EXDEV' on Unix-like platforms, 'ERROR_NOT_SAME_DEVICE' on Windows
+    if cfg!(windows) {
+        err.raw_os_error() == Some(17)
+    } else {
+        err.raw_os_error() == Some(18)
+    }
+}
+
+/// Flush a directory's entries to the disk, so a file renamed in it keeps its new name after a power loss
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// Flush a directory's entries to the disk (directories can't be opened as files on this platform)
+#[cfg(not(unix))]
+fn sync_dir(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Check that a ZIP archive can be opened, which requires its central directory (written last) to be complete
+pub fn check_zip(path: &Path) -> Result<(), ArchiveErr> {
+    let file = File::open(deter::long_path(path)).map_err(ArchiveErr::FailedToOpenFile)?;
+    ZipArchive::new(file).map_err(ArchiveErr::InvalidZipArchive)?;
+    Ok(())
+}
+
+/// Size of the buffers used to write archives and copy files to them
+pub const COPY_BUFFER_SIZE: usize = 256 * 1024;
+
+/// Create a ZIP archive writing to a file through a buffer, so its headers don't each require a system call
+pub fn buffered_zip_writer(file: File) -> ZipWriter<BufWriter<File>> {
+    ZipWriter::new(BufWriter::with_capacity(COPY_BUFFER_SIZE, file))
+}
+
+/// Finish writing a ZIP archive created with `buffered_zip_writer`, returning its file
+pub fn finish_buffered_zip(mut writer: ZipWriter<BufWriter<File>>) -> Result<File, ZipError> {
+    writer
+        .finish()?
+        .into_inner()
+        .map_err(|err| ZipError::Io(err.into_error()))
+}
+
+/// Copy the content of a reader to the current file of a ZIP archive through a reused buffer,
+/// without loading it in memory (the file's checksum is computed by the ZIP writer as the content is streamed)
+/// Returns the number of bytes copied
+pub fn copy_to_zip<R: Read + ?Sized, W: Write + Seek>(
+    reader: &mut R,
+    writer: &mut ZipWriter<W>,
+    buffer: &mut [u8],
+) -> Result<u64, CopyErr> {
+    copy_buffered(reader, writer, buffer)
+}
+
+/// Copy the content of a reader to a writer through a reused buffer, without loading it in memory
+/// Returns the number of bytes copied
+pub fn copy_buffered<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+    buffer: &mut [u8],
+) -> Result<u64, CopyErr> {
+    let mut copied = 0;
+
+    loop {
+        let read = match reader.read(buffer) {
+            Ok(0) => return Ok(copied),
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(CopyErr::ReadError(err)),
+        };
+
+        writer
+            .write_all(&buffer[..read])
+            .map_err(CopyErr::WriteError)?;
+
+        copied += read as u64;
+    }
+}
+
+/// Error while copying a file to an archive
+pub enum CopyErr {
+    ReadError(io::Error),
+    WriteError(io::Error),
+}
+
+/// Format of a comic archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Determine the format of an archive from its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "zip" | "cbz" => Some(Self::Zip),
+            _ => None,
+        }
+    }
+}
+
+/// Entry of a comic archive
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    /// Index of the entry in the archive
+    pub index: usize,
+
+    /// Path of the entry in the archive
+    pub path: PathBuf,
+
+    /// Is the entry a directory
+    pub is_dir: bool,
+
+    /// Uncompressed size, in bytes
+    pub size: u64,
+
+    /// Compressed size, in bytes
+    pub compressed_size: u64,
+
+    /// Compression method
+    pub compression: CompressionMethod,
+
+    /// Date the entry was last modified (ZIP archives store local times, which is enough to compare entries)
+    pub modified: SystemTime,
+}
+
+/// Comic archive opened for reading
+pub struct ComicArchive {
+    format: ArchiveFormat,
+    zip: ZipArchive<File>,
+    entries: Vec<ArchiveEntry>,
+}
+
+impl ComicArchive {
+    /// Open a comic archive, determining its format from its extension
+    pub fn open(path: &Path) -> Result<Self, ArchiveErr> {
+        let format = ArchiveFormat::from_path(path).ok_or_else(|| {
+            ArchiveErr::UnsupportedFormat(
+                path.extension()
+                    .map(|ext| ext.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            )
+        })?;
+
+        let file = File::open(path).map_err(ArchiveErr::FailedToOpenFile)?;
+        let mut zip = ZipArchive::new(file).map_err(ArchiveErr::InvalidZipArchive)?;
+
+        let mut entries = vec![];
+
+        for index in 0..zip.len() {
+            let file = zip.by_index_raw(index).map_err(ArchiveErr::ZipError)?;
+
+            entries.push(ArchiveEntry {
+                index,
+                path: file.mangled_name(),
+                is_dir: file.is_dir(),
+                size: file.size(),
+                compressed_size: file.compressed_size(),
+                compression: file.compression(),
+                modified: {
+                    let modified = file.last_modified();
+
+                    deter::utc_time(
+                        modified.year(),
+                        modified.month(),
+                        modified.day(),
+                        modified.hour(),
+                        modified.minute(),
+                        modified.second(),
+                    )
+                },
+            });
+        }
+
+        Ok(Self {
+            format,
+            zip,
+            entries,
+        })
+    }
+
+    /// Get the archive's format
+    pub fn format(&self) -> ArchiveFormat {
+        self.format
+    }
+
+    /// List all entries of the archive, in the archive's order
+    pub fn entries(&self) -> &[ArchiveEntry] {
+        &self.entries
+    }
+
+    /// List the image entries of the archive, sorted in reading order
+    pub fn pages(&self, extended_formats: bool, simple_sorting: bool) -> Vec<&ArchiveEntry> {
+        let mut pages = self
+            .entries
+            .iter()
+            .filter(|entry| !entry.is_dir && deter::has_image_ext(&entry.path, extended_formats))
+            .collect::<Vec<_>>();
+
+        if simple_sorting {
+            pages.sort_by(|a, b| a.path.cmp(&b.path));
+        } else {
+            deter::natural_sort_by_path(&mut pages, |page| &page.path);
+        }
+
+        pages
+    }
+
+    /// List the image entries of the archive in reading order, grouped by chapter (parent directory)
+    pub fn chapters(&self, extended_formats: bool, simple_sorting: bool) -> Vec<Vec<ArchiveEntry>> {
+        let mut chapters: Vec<Vec<ArchiveEntry>> = vec![];
+
+        for page in self.pages(extended_formats, simple_sorting) {
+            match chapters.last_mut() {
+                Some(chapter) if chapter[0].path.parent() == page.path.parent() => {
+                    chapter.push(page.clone())
+                }
+                _ => chapters.push(vec![page.clone()]),
+            }
+        }
+
+        chapters
+    }
+
+    /// Find an entry at the root of the archive from its name, case-insensitively
+    pub fn find_root_entry(&self, name: &str) -> Option<&ArchiveEntry> {
+        self.entries.iter().find(|entry| {
+            !entry.is_dir
+                && entry
+                    .path
+                    .to_str()
+                    .map(|path| path.eq_ignore_ascii_case(name))
+                    .unwrap_or(false)
+        })
+    }
+
+    /// Get the archive's comment
+    pub fn comment(&self) -> &[u8] {
+        self.zip.comment()
+    }
+
+    /// Get a reader over the content of an entry
+    /// Checksums are verified once the entry has been read entirely
+    pub fn entry_reader(&mut self, index: usize) -> Result<impl Read + '_, ArchiveErr> {
+        self.zip.by_index(index).map_err(ArchiveErr::ZipError)
+    }
+
+    /// Copy an entry to a ZIP archive under a new name, without decompressing and recompressing it
+    pub fn raw_copy_entry<W: Write + Seek>(
+        &mut self,
+        index: usize,
+        writer: &mut ZipWriter<W>,
+        name: &str,
+    ) -> Result<(), ArchiveErr> {
+        let file = self.zip.by_index_raw(index).map_err(ArchiveErr::ZipError)?;
+
+        writer
+            .raw_copy_file_rename(file, name)
+            .map_err(ArchiveErr::ZipError)
+    }
+
+    /// Copy an entry to a ZIP archive under a new name, compressed with the provided method
+    /// Entries already compressed with this method are copied as they are, without being decompressed and compressed again
+    pub fn copy_entry<W: Write + Seek>(
+        &mut self,
+        index: usize,
+        writer: &mut ZipWriter<W>,
+        name: &str,
+        compression: CompressionMethod,
+        buffer: &mut [u8],
+    ) -> Result<(), ArchiveErr> {
+        if self.entries[index].compression == compression {
+            return self.raw_copy_entry(index, writer, name);
+        }
+
+        let path = self.entries[index].path.clone();
+
+        writer
+            .start_file(name, FileOptions::default().compression_method(compression))
+            .map_err(ArchiveErr::ZipError)?;
+
+        let mut reader = self.zip.by_index(index).map_err(ArchiveErr::ZipError)?;
+
+        copy_to_zip(&mut reader, writer, buffer).map_err(|err| match err {
+            CopyErr::ReadError(err) => ArchiveErr::FailedToReadEntry(path, err),
+            CopyErr::WriteError(err) => ArchiveErr::FailedToWriteEntry(path, err),
+        })?;
+
+        Ok(())
+    }
+
+    /// Read the whole content of an entry
+    pub fn read_entry(&mut self, index: usize) -> Result<Vec<u8>, ArchiveErr> {
+        let path = self.entries[index].path.clone();
+        let mut buffer = Vec::with_capacity(self.entries[index].size as usize);
+
+        self.entry_reader(index)?
+            .read_to_end(&mut buffer)
+            .map_err(|err| ArchiveErr::FailedToReadEntry(path, err))?;
+
+        Ok(buffer)
+    }
+}
+
+/// List the comic archives designated by a list of paths
+/// Directories are searched recursively for files with a supported archive extension
+pub fn find_archives(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, ArchiveSearchErr> {
+    find_matching_files(inputs, |path| ArchiveFormat::from_path(path).is_some())
+}
+
+/// List the comic archives designated by a list of paths, including the ones read through an external program
+/// Directories are searched recursively for files with a supported archive extension
+pub fn find_extractable_archives(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, ArchiveSearchErr> {
+    find_matching_files(inputs, |path| {
+        ArchiveFormat::from_path(path).is_some() || ExternalFormat::from_path(path).is_some()
+    })
+}
+
+/// List the comic books designated by a list of paths
+/// Directories are searched recursively for files with an extension supported for decoding
+pub fn find_decodable_archives(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, ArchiveSearchErr> {
+    find_matching_files(inputs, |path| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(deter::is_supported_for_decoding)
+            .unwrap_or(false)
+    })
+}
+
+/// List the files designated by a list of paths, searching directories recursively for the files matching a filter
+/// Files provided directly are listed even if they don't match the filter
+fn find_matching_files<F: Fn(&Path) -> bool>(
+    inputs: &[PathBuf],
+    filter: F,
+) -> Result<Vec<PathBuf>, ArchiveSearchErr> {
+    let mut archives = vec![];
+
+    for input in inputs {
+        if input.is_dir() {
+            let mut found = deter::readdir_files_recursive(input, Some(&filter))
+                .map_err(|err| ArchiveSearchErr::FailedToReadDirectory(input.clone(), err))?;
+
+            deter::natural_sort_by_path(&mut found, |path| path);
+            archives.extend(found);
+        } else if input.is_file() {
+            archives.push(input.clone());
+        } else {
+            return Err(ArchiveSearchErr::NotFound(input.clone()));
+        }
+    }
+
+    Ok(archives)
+}
+
+/// Comic archives search error
+pub enum ArchiveSearchErr {
+    NotFound(PathBuf),
+    FailedToReadDirectory(PathBuf, RecursiveFilesSearchErr),
+}
+
+impl fmt::Display for ArchiveSearchErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotFound(path) => {
+                write!(f, "Input path '{}' was not found", path.to_string_lossy())
+            }
+            Self::FailedToReadDirectory(path, RecursiveFilesSearchErr::IOError(err)) => {
+                write!(
+                    f,
+                    "Failed to read directory '{}': {}",
+                    path.to_string_lossy(),
+                    err
+                )
+            }
+            Self::FailedToReadDirectory(path, RecursiveFilesSearchErr::InvalidFileName(item)) => {
+                write!(
+                    f,
+                    "Found item with invalid name in directory '{}': {}",
+                    path.to_string_lossy(),
+                    item.to_string_lossy()
+                )
+            }
+        }
+    }
+}
+
+/// Comic archive reading error
+#[derive(Debug)]
+pub enum ArchiveErr {
+    UnsupportedFormat(String),
+    FailedToOpenFile(io::Error),
+    InvalidZipArchive(ZipError),
+    ZipError(ZipError),
+    FailedToReadEntry(PathBuf, io::Error),
+    FailedToWriteEntry(PathBuf, io::Error),
+}
+
+impl fmt::Display for ArchiveErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedFormat(ext) => write!(
+                f,
+                "Unsupported archive format (based on file extension) '{}'",
+                ext
+            ),
+            Self::FailedToOpenFile(err) => write!(f, "Failed to open archive file: {}", err),
+            Self::InvalidZipArchive(err) => write!(f, "Invalid ZIP archive: {}", err),
+            Self::ZipError(err) => write!(f, "Error while reading ZIP archive: {}", err),
+            Self::FailedToReadEntry(path, err) => {
+                write!(
+                    f,
+                    "Failed to read file '{}' from archive: {}",
+                    path.to_string_lossy(),
+                    err
+                )
+            }
+            Self::FailedToWriteEntry(path, err) => {
+                write!(
+                    f,
+                    "Failed to write file '{}' to archive: {}",
+                    path.to_string_lossy(),
+                    err
+                )
+            }
+        }
+    }
+}
+
+impl Error for ArchiveErr {}
@@ -0,0 +1,117 @@
+use crate::cli::opts::{CompilationOptions, EncodingOptions};
+use serde::Deserialize;
+use std::path::Path;
+use toml::value::{Table, Value};
+
+/// Jobs file of the batch mode, written in TOML
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchConfig {
+    /// Options shared by all jobs, which the options of each job take precedence over
+    #[serde(default)]
+    pub common: BatchOptions,
+
+    /// Series to encode
+    pub jobs: Vec<BatchJob>,
+}
+
+/// Encoding and compilation options, in the options' serialized form (see `EncodingOptions` and `CompilationOptions`)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchOptions {
+    #[serde(default)]
+    pub encoding: Table,
+
+    #[serde(default)]
+    pub compilation: Table,
+}
+
+/// Encoding of a single series
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchJob {
+    /// Name of the job (defaults to the input directory's name)
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Directory containing the chapters or the volumes to encode (relative to the jobs file)
+    pub input: String,
+
+    /// Directory where the volumes should be put or path to the single volume (relative to the jobs file)
+    #[serde(default)]
+    pub output: Option<String>,
+
+    /// Encoding options of the job
+    #[serde(default)]
+    pub encoding: Table,
+
+    /// Compilation options of the job (the input directory is encoded as a single volume without them)
+    #[serde(default)]
+    pub compilation: Option<Table>,
+}
+
+impl BatchJob {
+    /// Get the job's display name
+    pub fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            let input = Path::new(&self.input);
+
+            input
+                .file_name()
+                .unwrap_or(input.as_os_str())
+                .to_string_lossy()
+                .to_string()
+        })
+    }
+
+    /// Get the job's options, on top of the common ones
+    /// Input and output paths are made relative to the provided directory
+    pub fn options(
+        &self,
+        common: &BatchOptions,
+        base_dir: &Path,
+    ) -> Result<(EncodingOptions, Option<CompilationOptions>), toml::de::Error> {
+        let mut encoding = merge_tables(common.encoding.clone(), self.encoding.clone());
+
+        encoding.insert("input".to_owned(), Value::String(self.input.clone()));
+
+        if let Some(output) = &self.output {
+            encoding.insert("output".to_owned(), Value::String(output.clone()));
+        }
+
+        let mut encoding: EncodingOptions = Value::Table(encoding).try_into()?;
+
+        encoding.input = base_dir.join(&encoding.input);
+        encoding.output = encoding.output.map(|output| base_dir.join(output));
+
+        let compilation = self
+            .compilation
+            .as_ref()
+            .map(|compilation| {
+                Value::Table(merge_tables(
+                    common.compilation.clone(),
+                    compilation.clone(),
+                ))
+                .try_into()
+            })
+            .transpose()?;
+
+        Ok((encoding, compilation))
+    }
+}
+
+/// Merge a table of options on top of another one, nested tables (e.g. 'conversion') being merged the same way
+fn merge_tables(mut base: Table, table: Table) -> Table {
+    for (key, value) in table {
+        let value = match (base.remove(&key), value) {
+            (Some(Value::Table(base)), Value::Table(table)) => {
+                Value::Table(merge_tables(base, table))
+            }
+            (_, value) => value,
+        };
+
+        base.insert(key, value);
+    }
+
+    base
+}
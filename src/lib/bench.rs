@@ -0,0 +1,156 @@
+use crate::cli::opts::ConversionOptions;
+use crate::lib::convert;
+use image::ImageError;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, Cursor, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+use zip::result::ZipError;
+use zip::write::{FileOptions, ZipWriter};
+
+/// A page of the sample, loaded in memory
+pub struct SamplePage {
+    pub path: PathBuf,
+    pub content: Vec<u8>,
+}
+
+/// Result of packing the sample pages with some conversion options
+#[derive(Debug, Serialize)]
+pub struct BenchResult {
+    /// Command-line flags selecting the conversion options
+    pub flags: String,
+
+    /// Size of the resulting archive, in bytes
+    pub output_size: u64,
+
+    /// Time taken to convert and pack the pages, in seconds
+    pub seconds: f64,
+
+    /// Number of pages converted and packed per second
+    pub pages_per_second: f64,
+}
+
+/// Results of a benchmark
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+    /// Number of pages in the sample
+    pub pages: usize,
+
+    /// Total size of the sample pages, in bytes
+    pub source_size: u64,
+
+    /// Results for each of the available conversion options
+    pub results: Vec<BenchResult>,
+}
+
+/// Get the conversion options available on the command line, along with the flags selecting them
+pub fn available_options() -> Vec<(&'static str, ConversionOptions)> {
+    [
+        ("(default)", false, false),
+        ("--compress-losslessly", true, false),
+        ("--compress-webp", false, true),
+        ("--compress-webp --compress-losslessly", true, true),
+    ]
+    .iter()
+    .map(|&(flags, compress_losslessly, compress_webp)| {
+        (
+            flags,
+            ConversionOptions {
+                compress_losslessly,
+                compress_webp,
+                ..ConversionOptions::default()
+            },
+        )
+    })
+    .collect()
+}
+
+/// Pick up to `count` pictures spread evenly across the provided ones
+pub fn pick_sample(pictures: &[PathBuf], count: usize) -> Vec<PathBuf> {
+    if pictures.len() <= count {
+        return pictures.to_vec();
+    }
+
+    (0..count)
+        .map(|i| pictures[i * pictures.len() / count].clone())
+        .collect()
+}
+
+/// Convert the sample pages and pack them in an in-memory archive, the same way volumes are built
+pub fn bench_options(
+    flags: &str,
+    opts: &ConversionOptions,
+    pages: &[SamplePage],
+) -> Result<BenchResult, BenchErr> {
+    let started = Instant::now();
+
+    // Pages are converted in parallel, as when building volumes
+    let converted = pages
+        .par_iter()
+        .map(|page| {
+            if convert::needs_conversion(opts, &page.path) {
+                convert::convert_page(opts, &page.content)
+                    .map(Some)
+                    .map_err(|err| BenchErr::FailedToConvertPage(page.path.clone(), err))
+            } else {
+                Ok(None)
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let zip_options = FileOptions::default().compression_method(convert::compression_method(opts));
+    let mut zip_writer = ZipWriter::new(Cursor::new(vec![]));
+
+    for (i, (page, converted)) in pages.iter().zip(converted).enumerate() {
+        zip_writer
+            .start_file(format!("{}", i), zip_options)
+            .map_err(BenchErr::ZipError)?;
+
+        zip_writer
+            .write_all(converted.as_ref().unwrap_or(&page.content))
+            .map_err(BenchErr::IOError)?;
+    }
+
+    let output_size = zip_writer
+        .finish()
+        .map_err(BenchErr::ZipError)?
+        .into_inner()
+        .len() as u64;
+
+    let seconds = started.elapsed().as_secs_f64();
+
+    Ok(BenchResult {
+        flags: flags.to_owned(),
+        output_size,
+        seconds,
+        pages_per_second: if seconds > 0.0 {
+            pages.len() as f64 / seconds
+        } else {
+            0.0
+        },
+    })
+}
+
+/// Error while running a benchmark
+pub enum BenchErr {
+    FailedToConvertPage(PathBuf, ImageError),
+    ZipError(ZipError),
+    IOError(io::Error),
+}
+
+impl fmt::Display for BenchErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FailedToConvertPage(path, err) => write!(
+                f,
+                "Failed to convert picture '{}': {}",
+                path.to_string_lossy(),
+                err
+            ),
+            Self::ZipError(err) => write!(f, "Failed to build ZIP archive: {}", err),
+            Self::IOError(err) => write!(f, "Failed to write to ZIP archive: {}", err),
+        }
+    }
+}
@@ -1,14 +1,41 @@
 use crate::cli::error::EncodingError;
 use crate::cli::opts::*;
+use crate::lib::archive::{self, CopyErr};
+use crate::lib::cache::{self, VolumeSources};
+use crate::lib::calibre;
+use crate::lib::cancel::CancellationToken;
+use crate::lib::chapter::ChapterNumber;
+use crate::lib::checksum;
+use crate::lib::convert;
 use crate::lib::deter;
-use image::{DynamicImage, GenericImageView};
-use std::fs::{self, File};
-use std::io::{Read, Write};
+use crate::lib::doctor;
+use crate::lib::hooks;
+use crate::lib::metadata::{
+    self, ComicMetadata, PageMetadata, VolumesMetadata, COMIC_INFO_FILENAME,
+};
+use crate::lib::pipeline::{self, BufferPool, PipelineLimits};
+use crate::lib::progress::ProgressSink;
+use crate::lib::source::{self, PageSource, SourceErr};
+use crate::lib::template;
+use crate::lib::summary::{JobReport, VolumeSummary, SUMMARY_FILENAME};
+use crate::lib::validate::{self, Severity};
+use crate::lib::writer::{self, ArchiveWriter};
+use image::ImageFormat;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Cursor, Read};
 use std::path::{Path, PathBuf};
 use std::time::Instant;
-use webp::Encoder;
-use zip::write::{FileOptions, ZipWriter};
-use zip::CompressionMethod;
+
+/// Number of bytes read at the beginning of a page to get its dimensions
+const PAGE_HEADER_SIZE: u64 = 64 * 1024;
+
+/// Space required on top of the estimated size of the volumes before building them, in percents
+const SPACE_HEADROOM_PERCENT: u64 = 10;
+
+/// Number of bytes read at the beginning of a file to recognize its image format
+const SNIFF_HEADER_SIZE: u64 = 32;
 
 #[derive(Debug, Clone)]
 pub enum BuildMethod<'a> {
@@ -17,96 +44,855 @@ pub enum BuildMethod<'a> {
     Single(&'a EncodeSingle),
 }
 
-#[derive(Debug)]
 pub struct BuildVolumeArgs<'a> {
     pub method: &'a BuildMethod<'a>,
     pub enc_opts: &'a EncodingOptions,
+    pub source: &'a dyn PageSource,
     pub output: &'a PathBuf,
     pub volume: usize,
     pub volumes: usize,
     pub vol_num_len: usize,
     pub chapter_num_len: usize,
-    pub start_chapter: usize,
-    pub chapters: &'a Vec<(usize, PathBuf, String)>,
+    pub start_chapter: ChapterNumber,
+    pub chapters: &'a Vec<(ChapterNumber, PathBuf, String)>,
+    pub metadata: &'a VolumesMetadata,
+    pub pool: &'a ThreadPool,
+    pub progress: &'a dyn ProgressSink,
+    pub cancel: &'a CancellationToken,
 }
 
-/// Build a volume
-/// `output` is the actual output path
-/// `volume` is the current volume number, starting at 1
-/// `volumes` is the total number of volumes
-/// `vol_num_len` is the maximum string length of the volume number (e.g. 1520 volumes => `vol_num_len == 4`)
-/// `chapter_num_len` is like `vol_num_len` but for chapters
-/// `start_chapter` is the number of the first chapter in this volume
-/// `chapters` is a list of the chapters this volume contains. It's a vector of tuples containing: (chapter number, path to the chapter's directory, chapter's directory's file name)
-pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
+impl<'a> BuildVolumeArgs<'a> {
+    /// Get the number of the last chapter in this volume
+    pub fn end_chapter(&self) -> ChapterNumber {
+        self.chapters
+            .last()
+            .map(|(chapter, _, _)| *chapter)
+            .unwrap_or(self.start_chapter)
+    }
+}
+
+/// Load the metadata to write in the volumes from the encoding options
+pub fn load_volumes_metadata(enc_opts: &EncodingOptions) -> Result<VolumesMetadata, EncodingError> {
+    let mut common = load_metadata_file(&enc_opts.input)?.unwrap_or_default();
+
+    // The series' name provided explicitly takes precedence over the one of the metadata file
+    if enc_opts.series.is_some() || common.series.is_none() {
+        common.series = enc_opts.series();
+    }
+
+    // ComicInfo only has a reading direction for manga, through its 'Manga' field
+    if enc_opts.manga {
+        common.manga = Some(
+            match enc_opts.reading_direction() {
+                ReadingDirection::Rtl => "YesAndRightToLeft",
+                ReadingDirection::Ltr => "Yes",
+            }
+            .to_owned(),
+        );
+    } else if enc_opts.reading_direction.is_some() {
+        warn!("Warning: ignoring '--reading-direction' as it is only written in the metadata of manga (with '--manga')");
+    }
+
+    let volumes = match &enc_opts.metadata_csv {
+        Some(path) => metadata::read_metadata_csv(path)
+            .map_err(|err| EncodingError::InvalidMetadataCsv(path.clone(), err))?,
+        None => HashMap::new(),
+    };
+
+    let chapter_titles = match &enc_opts.chapter_titles {
+        Some(path) => Some(
+            metadata::read_chapter_titles_csv(path)
+                .map_err(|err| EncodingError::InvalidChapterTitlesCsv(path.clone(), err))?,
+        ),
+        None if enc_opts.chapter_bookmarks => Some(HashMap::new()),
+        None => None,
+    };
+
+    Ok(VolumesMetadata {
+        common,
+        volumes,
+        chapter_titles,
+        volume_number_fields: enc_opts.layout.map(Layout::volume_number_fields).unwrap_or_default(),
+    })
+}
+
+/// Load the 'ComicInfo.xml' file at the root of the input directory (e.g. exported when decoding a comic book), if any
+/// Its pages are not kept, as they may not match the ones of the generated volumes
+fn load_metadata_file(input: &Path) -> Result<Option<ComicMetadata>, EncodingError> {
+    let path = input.join(COMIC_INFO_FILENAME);
+
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    debug!("Loading metadata from '{}'...", path.to_string_lossy());
+
+    let xml = fs::read_to_string(&path)
+        .map_err(|err| EncodingError::FailedToReadMetadataFile(path.clone(), err))?;
+
+    let mut metadata = metadata::parse_comic_info(&xml)
+        .map_err(|err| EncodingError::InvalidMetadataFile(path, err))?;
+
+    metadata.pages.clear();
+    metadata.page_count = None;
+
+    Ok(Some(metadata))
+}
+
+/// Open the input the chapters are read from: a directory, or an archive with one directory per chapter
+pub fn open_input(
+    input: &Path,
+    enc_opts: &EncodingOptions,
+) -> Result<Box<dyn PageSource>, EncodingError> {
+    source::open_source(input, !enc_opts.no_default_ignores).map_err(|err| match err {
+        SourceErr::NotFound => EncodingError::ChaptersDirectoryNotFound,
+        err => EncodingError::FailedToOpenInput(input.to_path_buf(), err),
+    })
+}
+
+/// Build the thread pool used to read and convert the pages of the volumes
+pub fn build_pages_pool(enc_opts: &EncodingOptions) -> Result<ThreadPool, EncodingError> {
+    ThreadPoolBuilder::new()
+        .num_threads(enc_opts.threads.unwrap_or(0))
+        .build()
+        .map_err(EncodingError::FailedToCreateThreadPool)
+}
+
+/// Compute the hashes of everything a volume is built from, to know if it has to be built again
+pub fn volume_sources(args: &BuildVolumeArgs) -> Result<VolumeSources, EncodingError> {
     let BuildVolumeArgs {
         method,
         enc_opts,
+        source,
+        volume,
+        chapters,
+        metadata,
+        ..
+    } = args;
+
+    let method_name = match method {
+        BuildMethod::Ranges(_, _) => "ranges",
+        BuildMethod::Each(_, _) => "each",
+        BuildMethod::Single(_) => "single",
+    };
+
+    let chapter_titles = chapters
+        .iter()
+        .map(|(chapter, _, chapter_name)| metadata.chapter_title(*chapter, chapter_name))
+        .collect::<Vec<_>>();
+
+    let options = format!(
+        "{:?}",
+        (
+            method_name,
+            volume_path_without_ext(args),
+            enc_opts.container,
+            &enc_opts.conversion,
+            enc_opts.simple_sorting,
+            enc_opts.sample_pages,
+            enc_opts.append_pages_count,
+            (&enc_opts.entry_template, enc_opts.keep_original_names, enc_opts.flat),
+            metadata.for_volume(*volume),
+            chapter_titles,
+        )
+    );
+
+    let chapters = chapters
+        .iter()
+        .map(|(chapter, chapter_path, _)| {
+            let mut pictures =
+                list_chapter_pictures(*source, enc_opts, *volume, *chapter, chapter_path)?;
+            pictures.sort();
+
+            cache::chapter_sha256(*source, chapter_path, &pictures).map_err(|err| {
+                EncodingError::FailedToHashChapter {
+                    volume: *volume,
+                    chapter: *chapter,
+                    chapter_path: chapter_path.clone(),
+                    err,
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(VolumeSources {
+        options: cache::text_sha256(&options),
+        chapters,
+    })
+}
+
+/// Estimate the memory required to load a page, in bytes
+/// Pages that don't need to be converted are streamed unless they are read ahead, so they don't require any
+fn page_memory_cost(source: &dyn PageSource, enc_opts: &EncodingOptions, file: &Path) -> u64 {
+    let file_size = source.size(file).unwrap_or(0);
+
+    if !convert::needs_conversion(&enc_opts.conversion, file) {
+        return match enc_opts.readahead {
+            Some(_) => file_size,
+            None => 0,
+        };
+    }
+
+    // The file's content and the converted page, along with the decoded bitmap
+
+    let bitmap_size = page_dimensions(source, file)
+        .map(|(width, height)| {
+            let (width, height) = convert::decoded_dimensions(&enc_opts.conversion, width, height);
+
+            u64::from(width) * u64::from(height) * 4
+        })
+        .unwrap_or(0);
+
+    file_size * 2 + bitmap_size
+}
+
+/// Read a page's dimensions from the beginning of its file, without decoding it
+fn page_dimensions(source: &dyn PageSource, file: &Path) -> Option<(u32, u32)> {
+    let mut header = vec![];
+
+    source
+        .open(file)
+        .ok()?
+        .take(PAGE_HEADER_SIZE)
+        .read_to_end(&mut header)
+        .ok()?;
+
+    image::io::Reader::new(Cursor::new(header))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Open a page's file
+fn open_page<'s>(
+    source: &'s dyn PageSource,
+    volume: usize,
+    chapter: ChapterNumber,
+    chapter_path: &Path,
+    file: &Path,
+) -> Result<Box<dyn Read + 's>, EncodingError> {
+    source.open(file).map_err(|err| EncodingError::FailedToOpenImage {
+        volume,
+        chapter,
+        chapter_path: chapter_path.to_path_buf(),
+        image_path: file.to_path_buf(),
+        err,
+    })
+}
+
+/// Read and convert a page
+/// Returns `None` if the page doesn't need to be converted and should be streamed to the ZIP archive instead of being read ahead
+/// Called from the pages' thread pool
+fn load_page(
+    source: &dyn PageSource,
+    enc_opts: &EncodingOptions,
+    buffers: &BufferPool,
+    volume: usize,
+    chapter: ChapterNumber,
+    chapter_path: &Path,
+    file: &Path,
+) -> Result<Option<Vec<u8>>, EncodingError> {
+    let needs_conversion = convert::needs_conversion(&enc_opts.conversion, file);
+
+    if !needs_conversion && enc_opts.readahead.is_none() {
+        return Ok(None);
+    }
+
+    // Read the real file
+    let mut f = open_page(source, volume, chapter, chapter_path, file)?;
+
+    // Take a buffer to store the picture's files
+    let mut buffer = buffers.take();
+
+    f.read_to_end(&mut buffer)
+        .map_err(|err| EncodingError::FailedToReadImage {
+            volume,
+            chapter,
+            chapter_path: chapter_path.to_path_buf(),
+            image_path: file.to_path_buf(),
+            err,
+        })?;
+
+    if !needs_conversion {
+        return Ok(Some(buffer));
+    }
+
+    trace!("Should convert {}", file.to_string_lossy());
+
+    let converted = convert::convert_page(&enc_opts.conversion, &buffer);
+
+    buffers.give_back(buffer);
+
+    converted
+        .map(Some)
+        .map_err(|err| EncodingError::FailedToConvertImageFileToZip {
+            volume,
+            chapter,
+            chapter_path: chapter_path.to_path_buf(),
+            image_path: file.to_path_buf(),
+            err: Box::new(err),
+        })
+}
+
+/// Copy a page that doesn't need to be converted to the volume, without loading it in memory when possible
+/// Returns the size of the page
+fn copy_page(
+    source: &dyn PageSource,
+    archive_writer: &mut dyn ArchiveWriter,
+    name_in_archive: &str,
+    buffer: &mut [u8],
+    volume: usize,
+    chapter: ChapterNumber,
+    chapter_path: &Path,
+    file: &Path,
+) -> Result<u64, EncodingError> {
+    let mut f = open_page(source, volume, chapter, chapter_path, file)?;
+
+    archive_writer
+        .add_page_from(name_in_archive, &mut *f, buffer)
+        .map_err(|err| match err {
+            CopyErr::ReadError(err) => EncodingError::FailedToReadImage {
+                volume,
+                chapter,
+                chapter_path: chapter_path.to_path_buf(),
+                image_path: file.to_path_buf(),
+                err,
+            },
+            CopyErr::WriteError(err) => EncodingError::FailedToWriteImageFileToZip {
+                volume,
+                chapter,
+                chapter_path: chapter_path.to_path_buf(),
+                image_path: file.to_path_buf(),
+                err,
+            },
+        })
+}
+
+/// Get the path of a volume's file, without its extension nor its number of pages
+pub fn volume_path_without_ext(args: &BuildVolumeArgs) -> PathBuf {
+    let path = volume_default_path_without_ext(args);
+
+    // The path of a single volume is provided as is
+    if let BuildMethod::Single(_) = args.method {
+        return path;
+    }
+
+    // Generated names must also be valid on Windows, where the volumes may be written or copied later
+    let title = deter::sanitize_filename(
+        &path
+            .file_name()
+            .expect("Internal error: output path when building has no filename")
+            .to_string_lossy(),
+    );
+
+    // Calibre expects each book in its own 'Author/Title' directory
+    if args.enc_opts.calibre {
+        calibre::book_path(
+            args.output,
+            &title,
+            args.metadata.for_volume(args.volume).as_ref(),
+        )
+    } else {
+        path.with_file_name(title)
+    }
+}
+
+/// Get the path of a volume's file directly in the output directory, without its extension nor its number of pages
+fn volume_default_path_without_ext(args: &BuildVolumeArgs) -> PathBuf {
+    let BuildVolumeArgs {
+        method,
         output,
         volume,
-        volumes,
         vol_num_len,
         chapter_num_len,
         start_chapter,
         chapters,
+        ..
     } = args;
 
-    // Dereference volume number to a simple 'usize'
-    let volume = *volume;
+    match method {
+        BuildMethod::Ranges(opts, _) => {
+            // Servers' libraries expect the series' name and the volume's number only
+            if let Some(layout) = args.enc_opts.layout {
+                let series = deter::sanitize_filename(&args.enc_opts.series().unwrap_or_default());
+
+                return output.join(format!(
+                    "{} {}{:0vol_num_len$}",
+                    series,
+                    layout.volume_prefix(),
+                    volume,
+                    vol_num_len = (*vol_num_len).max(2)
+                ));
+            }
 
-    // Get timestamp to measure performance
-    let build_started = Instant::now();
+            // Path separators in the series' name would put the volume in another directory
+            let series = match args.enc_opts.series() {
+                Some(series) => format!("{} - ", deter::sanitize_filename(&series)),
+                None => String::new(),
+            };
 
-    // Get the file name for this volume
-    let output_path_without_ext = match method {
-        BuildMethod::Ranges(opts, _) => {
             if !opts.append_chapters_range || chapters.is_empty() {
                 output.join(format!(
-                    "Volume-{:0vol_num_len$}",
+                    "{}Volume-{:0vol_num_len$}",
+                    series,
                     volume,
                     vol_num_len = vol_num_len
                 ))
             } else {
                 output.join(format!(
-                    "Volume-{:0vol_num_len$} (c{:0chapter_num_len$}-c{:0chapter_num_len$})",
+                    "{}Volume-{:0vol_num_len$} (c{:0chapter_num_len$}-c{:0chapter_num_len$})",
+                    series,
                     volume,
                     start_chapter,
-                    start_chapter + chapters.len() - 1,
+                    args.end_chapter(),
                     vol_num_len = vol_num_len,
                     chapter_num_len = chapter_num_len
                 ))
             }
         }
 
-        BuildMethod::Each(_, _) => {
-            assert_eq!(
-                chapters.len(),
-                1,
-                "Internal error: individual chapter's volume does contain exactly 1 chapter!"
+        // Directories merged because they share a chapter number are named after the first one
+        BuildMethod::Each(_, _) => output.join(&chapters[0].2),
+
+        // Only the volume's extension is removed, as its name can contain dots (e.g. 'Dr. Stone')
+        BuildMethod::Single(_) => match output.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case(args.enc_opts.container.ext()) => {
+                output.with_extension("")
+            }
+            _ => output.to_path_buf(),
+        },
+    }
+}
+
+/// Get the path of a volume's staging file, which is renamed once the volume is complete
+pub fn volume_staging_path(args: &BuildVolumeArgs) -> PathBuf {
+    archive::staging_path(&volume_path_without_ext(args))
+}
+
+/// Get the complete path of a volume's file from its path without extension
+fn volume_complete_path(
+    output_path_without_ext: &Path,
+    enc_opts: &EncodingOptions,
+    pages: usize,
+) -> PathBuf {
+    let ext = enc_opts.container.ext();
+
+    // The extension is appended, as volumes' names can contain dots (e.g. '(c10-c10.5)')
+    if !enc_opts.append_pages_count {
+        return deter::append_ext(output_path_without_ext, ext);
+    }
+
+    let mut filename_with_pages = output_path_without_ext
+        .file_name()
+        .expect("Internal error: output path when building has no filename")
+        .to_os_string();
+
+    filename_with_pages.push(format!(" ({} pages).{}", pages, ext));
+
+    output_path_without_ext.with_file_name(filename_with_pages)
+}
+
+/// Get the extension of the image format a file's content is in (e.g. 'jpg' for a JPEG picture named 'page.png'),
+/// or `None` if it isn't a supported picture
+pub fn sniff_image_ext(
+    source: &dyn PageSource,
+    file: &Path,
+    extended: bool,
+) -> io::Result<Option<&'static str>> {
+    let mut header = vec![];
+
+    source
+        .open(file)?
+        .take(SNIFF_HEADER_SIZE)
+        .read_to_end(&mut header)?;
+
+    let format = match image::guess_format(&header) {
+        Ok(format) => format,
+        Err(_) => return Ok(None),
+    };
+
+    // Any RIFF file (e.g. a WAV or AVI file) is guessed as WebP
+    if format == ImageFormat::WebP && header.get(8..12) != Some(&b"WEBP"[..]) {
+        return Ok(None);
+    }
+
+    Ok(format
+        .extensions_str()
+        .first()
+        .copied()
+        .filter(|ext| deter::has_image_ext(Path::new("page").with_extension(ext), extended)))
+}
+
+/// Check if a chapter's file is a picture, from its extension or from its content with '--sniff-images'
+/// Files that can't be read are checked from their extension, so reading them fails when building the volume
+pub fn is_picture(source: &dyn PageSource, enc_opts: &EncodingOptions, file: &Path) -> bool {
+    let extended = enc_opts.accept_extended_image_formats;
+
+    if !enc_opts.sniff_images {
+        return deter::has_image_ext(file, extended);
+    }
+
+    match sniff_image_ext(source, file, extended) {
+        Ok(Some(_)) => true,
+
+        Ok(None) => {
+            if deter::has_image_ext(file, extended) {
+                debug!(
+                    "Ignoring page '{}' as its content is not a supported picture",
+                    file.to_string_lossy()
+                );
+            }
+
+            false
+        }
+
+        Err(err) => {
+            debug!(
+                "Failed to read '{}' to recognize its format: {}",
+                file.to_string_lossy(),
+                err
             );
-            output.join(chapters[0].2.to_string())
+
+            deter::has_image_ext(file, extended)
+        }
+    }
+}
+
+/// Check that a complete CBZ volume can be read: its files must match their checksum, its pages must have a decodable
+/// header and it must contain the expected number of pages
+/// Returns the problems found, if any
+fn verify_volume(path: &Path, enc_opts: &EncodingOptions, pages: usize) -> Result<(), String> {
+    let report = validate::validate_archive(path, enc_opts.accept_extended_image_formats);
+
+    // Warnings (e.g. pages whose extension doesn't match their format) don't make the volume unreadable
+    let mut problems = report
+        .issues
+        .into_iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .map(|issue| issue.message)
+        .collect::<Vec<_>>();
+
+    if problems.is_empty() && report.pages != pages {
+        problems.push(format!("it contains {} pages instead of {}", report.pages, pages));
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join(", "))
+    }
+}
+
+/// Get the list of all image files in a chapter's directory, recursively
+fn list_chapter_pictures(
+    source: &dyn PageSource,
+    enc_opts: &EncodingOptions,
+    volume: usize,
+    chapter: ChapterNumber,
+    chapter_path: &Path,
+) -> Result<Vec<PathBuf>, EncodingError> {
+    let mut files = source.files(chapter_path).map_err(|err| match err {
+        deter::RecursiveFilesSearchErr::IOError(err) => {
+            EncodingError::FailedToListChapterDirectoryFiles {
+                volume,
+                chapter,
+                chapter_path: chapter_path.to_path_buf(),
+                err,
+            }
         }
 
-        BuildMethod::Single(_) => output.with_extension(""),
+        deter::RecursiveFilesSearchErr::InvalidFileName(path) => {
+            EncodingError::FoundItemWithInvalidName {
+                volume,
+                chapter,
+                chapter_path: chapter_path.to_path_buf(),
+                invalid_item_path: path,
+            }
+        }
+    })?;
+
+    files.retain(|path| is_picture(source, enc_opts, path));
+
+    // Pages are renamed in the volumes, so their original name is only used lossily
+    if let Some(invalid) = files.iter().find(|path| !deter::has_utf8_name(path)) {
+        match enc_opts.on_invalid_name {
+            InvalidNamePolicy::Rename => {}
+
+            InvalidNamePolicy::Error => {
+                return Err(EncodingError::FoundItemWithInvalidName {
+                    volume,
+                    chapter,
+                    chapter_path: chapter_path.to_path_buf(),
+                    invalid_item_path: invalid.clone(),
+                })
+            }
+
+            InvalidNamePolicy::Skip => files.retain(|path| {
+                let valid = deter::has_utf8_name(path);
+
+                if !valid {
+                    debug!("Ignoring page '{}' as its name is not valid UTF-8", path.to_string_lossy());
+                }
+
+                valid
+            }),
+        }
+    }
+
+    Ok(files)
+}
+
+/// Count the pages a volume will contain, without building it
+fn count_volume_pages(args: &BuildVolumeArgs) -> Result<usize, EncodingError> {
+    let mut pages = 0;
+
+    for (chapter, chapter_path, _) in args.chapters.iter() {
+        let chapter_pages =
+            list_chapter_pictures(args.source, args.enc_opts, args.volume, *chapter, chapter_path)?
+                .len();
+
+        pages += match args.enc_opts.sample_pages {
+            Some(sample_pages) => chapter_pages.min(sample_pages),
+            None => chapter_pages,
+        };
+    }
+
+    Ok(pages)
+}
+
+/// Estimate the space a volume's file will take, without building it
+fn estimate_volume_size(args: &BuildVolumeArgs) -> Result<u64, EncodingError> {
+    let mut size = 0;
+
+    for (chapter, chapter_path, _) in args.chapters.iter() {
+        let pictures =
+            list_chapter_pictures(args.source, args.enc_opts, args.volume, *chapter, chapter_path)?;
+
+        let sampled = args.enc_opts.sample_pages.unwrap_or(pictures.len());
+
+        for picture in pictures.iter().take(sampled) {
+            let picture_size = args.source.size(picture).unwrap_or(0);
+            size += convert::estimated_page_size(&args.enc_opts.conversion, picture, picture_size);
+        }
+    }
+
+    Ok(size)
+}
+
+/// Check that the output directory has enough space left for the volumes to build, with some headroom
+/// Nothing is checked if the available space can't be determined (e.g. on platforms without 'df')
+pub fn check_available_space(
+    volumes_args: &[&BuildVolumeArgs],
+    output_dir: &Path,
+) -> Result<(), EncodingError> {
+    let available = match doctor::available_space(output_dir) {
+        Ok(available) => available,
+        Err(err) => {
+            debug!("Not checking the available space: {}", err);
+            return Ok(());
+        }
     };
 
-    // If the number of pages won't be happened to the final name, we can predict the final name of the file
-    // Else we cannot as we don't know the number of pages in this volume, yet.
-    if let BuildMethod::Each(opts, _) = method {
-        // And if 'skip_existing' is set, that means we don't have to append the number of pages as this argument
-        // conflicts with the 'append_pages_count'.
-        if opts.skip_existing {
-            let complete_path = output_path_without_ext.with_extension("cbz");
-
-            if complete_path.exists() {
-                warn!("Warning: skipping volume {} containing chapters {} to {} as its output file '{}' already exists (--skip-existing provided)", volume, start_chapter, start_chapter + chapters.len() - 1, output.to_string_lossy());
-                return Ok(complete_path);
+    let mut required = 0;
+
+    for args in volumes_args {
+        required += estimate_volume_size(args)?;
+    }
+
+    // Archives' headers and poorly compressed pages take more space than estimated
+    let required = required + required / 100 * SPACE_HEADROOM_PERCENT;
+
+    debug!(
+        "About {} required to build the volumes, {} available",
+        deter::format_size(required),
+        deter::format_size(available)
+    );
+
+    if required > available {
+        return Err(EncodingError::NotEnoughSpace { required, available });
+    }
+
+    Ok(())
+}
+
+/// Predict the path of a volume's file without building it
+/// Chapters' pictures are counted if the number of pages must be appended to the file's name
+pub fn predict_volume_path(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
+    let pages = if args.enc_opts.append_pages_count {
+        count_volume_pages(args)?
+    } else {
+        0
+    };
+
+    Ok(volume_complete_path(
+        &volume_path_without_ext(args),
+        args.enc_opts,
+        pages,
+    ))
+}
+
+/// Print the volumes that are going to be built as a table (volume, chapters, estimated number of pages and output
+/// file), optionally followed by the chapters of each volume
+/// The table is printed on the standard output instead of being logged, so it is still displayed with '--silent'
+pub fn print_volumes_plan(volumes_args: &[BuildVolumeArgs], list_chapters: bool) -> Result<(), EncodingError> {
+    let header = [
+        "Volume".to_owned(),
+        "Chapters".to_owned(),
+        "Pages".to_owned(),
+        "Output file".to_owned(),
+    ];
+
+    let mut rows = vec![];
+
+    for args in volumes_args {
+        let chapters = if args.chapters.len() > 1 {
+            format!(
+                "{:0chapter_num_len$} to {:0chapter_num_len$} ({})",
+                args.start_chapter,
+                args.end_chapter(),
+                args.chapters.len(),
+                chapter_num_len = args.chapter_num_len
+            )
+        } else {
+            format!("{:0chapter_num_len$}", args.start_chapter, chapter_num_len = args.chapter_num_len)
+        };
+
+        let pages = count_volume_pages(args)?;
+
+        let path = volume_complete_path(&volume_path_without_ext(args), args.enc_opts, pages);
+
+        rows.push((
+            [
+                format!("{:0vol_num_len$} / {}", args.volume, args.volumes, vol_num_len = args.vol_num_len),
+                chapters,
+                pages.to_string(),
+                path.to_string_lossy().into_owned(),
+            ],
+            args,
+        ));
+    }
+
+    // Width of each column
+    let mut widths = header.clone().map(|title| title.chars().count());
+
+    for (row, _) in &rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |row: &[String; 4]| {
+        println!(
+            "{:<w0$}  {:<w1$}  {:>w2$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2]
+        )
+    };
+
+    print_row(&header);
+
+    for (row, args) in &rows {
+        print_row(row);
+
+        if list_chapters {
+            for (chapter, _, chapter_name) in args.chapters.iter() {
+                println!(
+                    "    Chapter {:0chapter_num_len$}: '{}'",
+                    chapter,
+                    chapter_name,
+                    chapter_num_len = args.chapter_num_len
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Build a volume
+/// `output` is the actual output path
+/// `volume` is the current volume number, starting at 1
+/// `volumes` is the total number of volumes
+/// `vol_num_len` is the maximum string length of the volume number (e.g. 1520 volumes => `vol_num_len == 4`)
+/// `chapter_num_len` is like `vol_num_len` but for chapters
+/// `start_chapter` is the number of the first chapter in this volume
+/// `chapters` is a list of the chapters this volume contains. It's a vector of tuples containing: (chapter number, path to the chapter's directory, chapter's directory's file name)
+/// `metadata` is the metadata to write in the volumes' 'ComicInfo.xml' file
+/// `cancel` stops the build between two pages, removing the volume's staging file
+/// Returns the summary of the built volume
+pub fn build_volume(args: &BuildVolumeArgs) -> Result<VolumeSummary, EncodingError> {
+    let result = write_volume(args);
+
+    if let Err(EncodingError::Cancelled) = result {
+        let staging_path = volume_staging_path(args);
+
+        if staging_path.is_file() {
+            debug!(
+                "Removing staging file '{}' of cancelled volume {}...",
+                staging_path.to_string_lossy(),
+                args.volume
+            );
+
+            if let Err(err) = fs::remove_file(&staging_path) {
+                warn!(
+                    "Warning: failed to remove staging file '{}': {}",
+                    staging_path.to_string_lossy(),
+                    err
+                );
             }
         }
     }
 
+    result
+}
+
+/// Write a volume to its staging file, then rename it to its final name
+fn write_volume(args: &BuildVolumeArgs) -> Result<VolumeSummary, EncodingError> {
+    let BuildVolumeArgs {
+        method,
+        enc_opts,
+        source,
+        volume,
+        volumes,
+        vol_num_len,
+        chapter_num_len,
+        start_chapter,
+        chapters,
+        metadata,
+        pool,
+        progress,
+        cancel,
+        ..
+    } = args;
+
+    // Dereference volume number to a simple 'usize'
+    let volume = *volume;
+
+    // Get timestamp to measure performance
+    let build_started = Instant::now();
+
+    // Get the file name for this volume
+    let output_path_without_ext = volume_path_without_ext(args);
+
+    // The final name of the file is predicted before doing any image work, even when it contains the number of pages
+    if enc_opts.skip_existing {
+        let complete_path = predict_volume_path(args)?;
+
+        if complete_path.exists() {
+            progress.warning(&format!("skipping volume {} containing chapters {} to {} as its output file '{}' already exists (--skip-existing provided)", volume, start_chapter, args.end_chapter(), complete_path.to_string_lossy()));
+            progress.volume_skipped(volume, &complete_path);
+            return existing_volume_summary(args, &complete_path);
+        }
+    }
+
     // Get the path to this volume's (staging) ZIP archive
-    let staging_path = output_path_without_ext.with_extension(".comic-enc-partial");
+    let staging_path = volume_staging_path(args);
 
     // Fail if the target file already exists and '--overwrite' has not been specified
     if staging_path.exists() && !enc_opts.overwrite {
@@ -116,19 +902,22 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
         ));
     }
 
-    // Create a ZIP file to this path
-    let zip_file = File::create(staging_path.clone()).map_err(|err| {
-        EncodingError::FailedToCreateVolumeFile(volume, staging_path.clone(), err)
-    })?;
-
-    let mut zip_writer = ZipWriter::new(zip_file);
+    // Create the book's directory of the Calibre library
+    if enc_opts.calibre {
+        if let Some(book_dir) = staging_path.parent() {
+            fs::create_dir_all(deter::long_path(book_dir)).map_err(|err| {
+                EncodingError::FailedToCreateBookDirectory(volume, book_dir.to_path_buf(), err)
+            })?;
+        }
+    }
 
-    // Consider compression
-    let zip_options = FileOptions::default().compression_method(if enc_opts.compress_losslessly {
-        CompressionMethod::Deflated
-    } else {
-        CompressionMethod::Stored
-    });
+    // Create the volume's container at this path, considering compression
+    let mut archive_writer = writer::create_writer(
+        enc_opts.container,
+        &staging_path,
+        convert::compression_method(&enc_opts.conversion),
+    )
+    .map_err(|err| EncodingError::FailedToCreateVolumeFile(volume, staging_path.clone(), err))?;
 
     // Determine the common display name for individual chapters
     let display_name_individual = match method {
@@ -165,8 +954,51 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
     // Count the number of pictures in this volume
     let mut pics_counter = 0;
 
+    // Buffer reused to copy the pages that don't need to be converted
+    let mut copy_buffer = vec![0; archive::COPY_BUFFER_SIZE];
+
+    // Buffers of the pages read ahead, reused from one page to the next
+    let buffers = BufferPool::default();
+
+    // Bookmarks on the first page of each chapter
+    let mut bookmarks = vec![];
+
+    // Paths of the pages in the volume's ZIP
+    let mut entry_names = HashSet::new();
+
+    // Length of the displayable picture number in the whole volume, when pages are numbered in a single sequence
+    let volume_pic_num_len = if enc_opts.flat {
+        count_volume_pages(args)?.to_string().len()
+    } else {
+        0
+    };
+
+    // First page of the volume, used as the book's cover in Calibre libraries
+    let mut cover_page = None;
+
+    // Directories merged into a single chapter are counted once
+    let chapters_count = chapters
+        .iter()
+        .map(|(number, _, _)| number)
+        .collect::<HashSet<_>>()
+        .len();
+
+    progress.volume_started(volume, chapters_count);
+
     // Treat each chapter of the volume
-    for (chapter, chapter_path, chapter_name) in chapters.iter() {
+    for (index, (chapter, chapter_path, chapter_name)) in chapters.iter().enumerate() {
+        // Directories with the same number as the previous one were merged with it (see '--on-duplicate-chapter')
+        if index > 0 && chapters[index - 1].0 == *chapter {
+            continue;
+        }
+
+        // Directories of the chapter: its own, followed by the ones merged with it
+        let chapter_dirs = chapters[index..]
+            .iter()
+            .take_while(|(number, _, _)| number == chapter)
+            .map(|(_, path, name)| (path, name))
+            .collect::<Vec<_>>();
+
         // Determine how to display the chapter's title in STDOUT
         let chapter_display_name = match method {
             BuildMethod::Each(_, _) => format!("'{}'", display_name_individual.as_ref().unwrap()),
@@ -177,45 +1009,86 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
             ),
         };
 
-        trace!(
-            "Reading files recursively from chapter {}'s directory '{}'...",
-            chapter,
-            chapter_name
-        );
-
-        // Get the list of all image files in the chapter's directory, recursively
-        let mut chapter_pics = deter::readdir_files_recursive(
-            &chapter_path,
-            Some(&|path: &PathBuf| {
-                deter::has_image_ext(path, enc_opts.accept_extended_image_formats)
-            }),
-        )
-        .map_err(|err| match err {
-            deter::RecursiveFilesSearchErr::IOError(err) => {
-                EncodingError::FailedToListChapterDirectoryFiles {
-                    volume,
-                    chapter: *chapter,
-                    chapter_path: chapter_path.to_path_buf(),
-                    err,
+        // Let the pre-chapter command process each of the chapter's directories before their pages are read
+        let mut kept_dirs = vec![];
+
+        for (dir_path, dir_name) in chapter_dirs {
+            if let Some(pre_chapter_cmd) = &enc_opts.pre_chapter_cmd {
+                trace!("Running pre-chapter command on chapter {}...", chapter);
+
+                let result = hooks::run_hook(
+                    pre_chapter_cmd,
+                    &[
+                        ("volume", volume.to_string()),
+                        ("chapter", chapter.to_string()),
+                        ("path", dir_path.to_string_lossy().into_owned()),
+                        ("name", dir_name.clone()),
+                    ],
+                );
+
+                if let Err(err) = result {
+                    match enc_opts.pre_chapter_cmd_failure {
+                        HookFailurePolicy::Abort => {
+                            return Err(EncodingError::PreChapterCommandFailed {
+                                volume,
+                                chapter: *chapter,
+                                err,
+                            })
+                        }
+
+                        HookFailurePolicy::Skip => {
+                            progress.warning(&format!(
+                                "leaving chapter {} ('{}') out of volume {} as its pre-chapter command failed: {}",
+                                chapter, dir_name, volume, err
+                            ));
+                            continue;
+                        }
+
+                        HookFailurePolicy::Ignore => progress.warning(&format!(
+                            "pre-chapter command of chapter {} in volume {} failed: {}",
+                            chapter, volume, err
+                        )),
+                    }
                 }
             }
 
-            deter::RecursiveFilesSearchErr::InvalidFileName(path) => {
-                EncodingError::FoundItemWithInvalidName {
-                    volume,
-                    chapter: *chapter,
-                    chapter_path: chapter_path.to_path_buf(),
-                    invalid_item_path: path,
-                }
-            }
-        })?;
+            kept_dirs.push((dir_path, dir_name));
+        }
 
-        trace!(
-            "Found '{}' picture files from chapter {}'s directory '{}'. Sorting them...",
-            chapter_pics.len(),
-            chapter,
-            chapter_name
-        );
+        if kept_dirs.is_empty() {
+            progress.chapter_started(volume, *chapter, 0);
+            continue;
+        }
+
+        // Get the list of all image files in the chapter's directories, recursively
+        let mut chapter_pics = vec![];
+
+        for (dir_path, dir_name) in &kept_dirs {
+            trace!(
+                "Reading files recursively from chapter {}'s directory '{}'...",
+                chapter,
+                dir_name
+            );
+
+            let mut dir_pics =
+                list_chapter_pictures(*source, enc_opts, volume, *chapter, dir_path)?;
+
+            trace!(
+                "Found '{}' picture files from chapter {}'s directory '{}'. Sorting them...",
+                dir_pics.len(),
+                chapter,
+                dir_name
+            );
+
+            // Sort the image files by name, the pages of merged directories following each other
+            if enc_opts.simple_sorting {
+                dir_pics.sort();
+            } else {
+                deter::natural_sort_by_path(&mut dir_pics, |pic| pic);
+            };
+
+            chapter_pics.extend(dir_pics);
+        }
 
         match method {
             BuildMethod::Ranges(opts, _) => {
@@ -240,173 +1113,299 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
             BuildMethod::Single(_) => {}
         }
 
-        // Sort the image files by name
-        if enc_opts.simple_sorting {
-            chapter_pics.sort();
-        } else {
-            chapter_pics.sort_by(deter::natural_paths_cmp);
-        };
+        // Only keep the first pages of the chapter when building a sample
+        if let Some(sample_pages) = enc_opts.sample_pages {
+            chapter_pics.truncate(sample_pages);
+        }
 
-        // Disable mutability for this variable
-        let chapter_path = chapter_path;
+        progress.chapter_started(volume, *chapter, chapter_pics.len());
 
-        // Determine the name of this chapter's directory in the volume's ZIP
-        let zip_dir_name = match method {
-            BuildMethod::Each(_, _) => chapters[0].2.clone(),
+        // Determine the name of this chapter's directory in the volume's ZIP, if pages are put in one
+        let zip_dir_name = if enc_opts.entry_template.is_some() || enc_opts.flat {
+            None
+        } else if enc_opts.keep_original_names {
+            Some(deter::sanitize_filename(chapter_name))
+        } else {
+            Some(match method {
+                BuildMethod::Each(_, _) => deter::sanitize_filename(&chapters[0].2),
 
-            _ => format!(
-                "Vol_{:0vol_num_len$}_Chapter_{:0chapter_num_len$}",
-                volume,
-                chapter,
-                vol_num_len = vol_num_len,
-                chapter_num_len = chapter_num_len
-            ),
+                _ => format!(
+                    "Vol_{:0vol_num_len$}_Chapter_{:0chapter_num_len$}",
+                    volume,
+                    chapter,
+                    vol_num_len = vol_num_len,
+                    chapter_num_len = chapter_num_len
+                ),
+            })
         };
 
-        trace!("Adding directory '{}' to ZIP archive...", zip_dir_name);
+        if let Some(zip_dir_name) = &zip_dir_name {
+            trace!("Adding directory '{}' to archive...", zip_dir_name);
 
-        // Create an empty directory for this chapter in the volume's ZIP
-        zip_writer
-            .add_directory(&zip_dir_name, zip_options)
-            .map_err(|err| EncodingError::FailedToCreateChapterDirectoryInZip {
-                volume,
-                chapter: *chapter,
-                dir_name: zip_dir_name.to_owned(),
-                err,
-            })?;
+            // Create an empty directory for this chapter in the volume
+            archive_writer
+                .add_dir(zip_dir_name)
+                .map_err(|err| EncodingError::FailedToCreateChapterDirectoryInZip {
+                    volume,
+                    chapter: *chapter,
+                    dir_name: zip_dir_name.to_owned(),
+                    err,
+                })?;
+        }
+
+        // Bookmark the chapter's first page
+        if let Some(title) = metadata.chapter_title(*chapter, chapter_name) {
+            if !chapter_pics.is_empty() {
+                bookmarks.push(PageMetadata {
+                    image: pics_counter,
+                    bookmark: Some(title),
+                    ..PageMetadata::default()
+                });
+            }
+        }
 
         // Compute the length of displayable picture number (e.g. 1520 pictures will give 4)
         let pic_num_len = chapter_pics.len().to_string().len();
 
-        // Iterate over each page
+        // Determine the name of each page in the ZIP directory
+        let mut pages = vec![];
+
         for (page_nb, file) in chapter_pics.iter().enumerate() {
-            // Determine the name of the file in the ZIP directory
-            let ext = if enc_opts.compress_webp {
-                "webp"
-            } else {
-                file.extension().unwrap().to_str().ok_or_else(|| {
+            // Directory the page comes from, for chapters made of multiple directories
+            let (dir_path, dir_name) = kept_dirs
+                .iter()
+                .find(|(dir_path, _)| file.starts_with(dir_path))
+                .unwrap_or(&kept_dirs[0]);
+
+            // Pages that are not converted are named after their content's format when it is recognized
+            let sniffed_ext = match convert::converted_ext(&enc_opts.conversion) {
+                None if enc_opts.sniff_images => {
+                    sniff_image_ext(*source, file, enc_opts.accept_extended_image_formats)
+                        .ok()
+                        .flatten()
+                }
+                _ => None,
+            };
+
+            let ext = match convert::converted_ext(&enc_opts.conversion).or(sniffed_ext) {
+                Some(ext) => ext,
+                None => file.extension().unwrap().to_str().ok_or_else(|| {
                     EncodingError::ItemHasInvalidUTF8Name(file.file_name().unwrap().to_os_string())
-                })?
+                })?,
             };
-            let name_in_zip = match method {
-                BuildMethod::Each(_, _) => format!(
-                    "{}_Pic_{:0pic_num_len$}.{file_ext}",
-                    volume_display_name,
-                    page_nb,
-                    file_ext = ext,
-                    pic_num_len = pic_num_len
-                ),
 
-                _ => format!(
-                    "Vol_{:0vol_num_len$}_Chapter_{:0chapter_num_len$}_Pic_{:0pic_num_len$}.{file_ext}",
-                    volume,
-                    chapter,
-                    page_nb,
-                    file_ext = ext,
-                    vol_num_len = vol_num_len,
-                    chapter_num_len = chapter_num_len,
-                    pic_num_len = pic_num_len
-                ),
+            // Number of the page in the volume, starting at 1
+            let volume_page = pics_counter + page_nb + 1;
+
+            let name_in_zip = if let Some(template) = &enc_opts.entry_template {
+                template::render_template(template, |name| match name {
+                    "volume" => Some(volume.to_string()),
+                    "chapter" => Some(chapter.to_string()),
+                    "page" => Some((page_nb + 1).to_string()),
+                    "volume_page" => Some(volume_page.to_string()),
+                    "dir" => Some(dir_name.to_string()),
+                    "name" => file.file_stem().map(|stem| stem.to_string_lossy().into_owned()),
+                    "ext" => Some(ext.to_owned()),
+                    _ => None,
+                })
+                .map_err(EncodingError::InvalidEntryTemplate)?
+            } else if enc_opts.flat {
+                format!(
+                    "{:0volume_pic_num_len$}.{}",
+                    volume_page,
+                    ext,
+                    volume_pic_num_len = volume_pic_num_len
+                )
+            } else if enc_opts.keep_original_names {
+                // Keep the page's path relative to its chapter's directory, using '/' as the separator in ZIP archives
+                file.strip_prefix(dir_path)
+                    .unwrap_or(file)
+                    .with_extension(ext)
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            } else {
+                match method {
+                    BuildMethod::Each(_, _) => format!(
+                        "{}_Pic_{:0pic_num_len$}.{file_ext}",
+                        volume_display_name,
+                        page_nb,
+                        file_ext = ext,
+                        pic_num_len = pic_num_len
+                    ),
+
+                    _ => format!(
+                        "Vol_{:0vol_num_len$}_Chapter_{:0chapter_num_len$}_Pic_{:0pic_num_len$}.{file_ext}",
+                        volume,
+                        chapter,
+                        page_nb,
+                        file_ext = ext,
+                        vol_num_len = vol_num_len,
+                        chapter_num_len = chapter_num_len,
+                        pic_num_len = pic_num_len
+                    ),
+                }
             };
 
-            trace!(
-                "Adding picture {:0pic_num_len$} at '{}' from chapter {} to volume {} as '{}/{}'...",
-                page_nb, file.to_string_lossy(), chapter_display_name, volume_display_name, zip_dir_name, name_in_zip, pic_num_len = pic_num_len
-            );
+            // Names coming from the pages or chapters must also be valid once extracted on Windows
+            let name_in_zip = deter::sanitize_entry_name(&name_in_zip);
 
-            // Determine the path of the file in the ZIP directory
-            let path_in_zip = &Path::new(&zip_dir_name).join(Path::new(&name_in_zip));
+            // Path of the page in the volume's ZIP
+            let name_in_zip = match &zip_dir_name {
+                Some(zip_dir_name) => format!("{}/{}", zip_dir_name, name_in_zip),
+                None => name_in_zip,
+            };
 
-            // Create the empty file in the archive
-            zip_writer
-                .start_file_from_path(path_in_zip, zip_options)
-                .map_err(|err| EncodingError::FailedToCreateImageFileInZip {
-                    volume,
-                    chapter: *chapter,
-                    file_path: path_in_zip.to_path_buf(),
-                    err,
-                })?;
+            // Pages named from a template could overwrite each other
+            if !entry_names.insert(name_in_zip.clone()) {
+                return Err(EncodingError::DuplicateEntryName(volume, name_in_zip));
+            }
 
-            // Read the real file
-            let mut f = File::open(file).map_err(|err| EncodingError::FailedToOpenImage {
-                volume,
-                chapter: *chapter,
-                chapter_path: chapter_path.to_path_buf(),
-                image_path: file.to_path_buf(),
-                err,
-            })?;
-            // Prepare a buffer to store the picture's files
-            let mut buffer = Vec::new();
+            pages.push((page_nb, file, name_in_zip));
+        }
 
-            f.read_to_end(&mut buffer)
-                .map_err(|err| EncodingError::FailedToReadImage {
-                    volume,
-                    chapter: *chapter,
-                    chapter_path: chapter_path.to_path_buf(),
-                    image_path: file.to_path_buf(),
-                    err,
-                })?;
+        // Pages to convert are read and converted in parallel, each thread going on with the next page as soon as
+        // it's done with the previous one so a large page (e.g. a double spread) doesn't keep the others waiting,
+        // while the previous pages are written to the ZIP archive in order
+        let max_memory = enc_opts
+            .max_memory
+            .map(|max_memory| max_memory * 1024 * 1024);
+
+        let costs = match max_memory {
+            None => vec![],
+            Some(max_memory) => pages
+                .iter()
+                .map(|(page_nb, file, _)| {
+                    let cost = page_memory_cost(*source, enc_opts, file);
+
+                    if cost > max_memory {
+                        debug!(
+                            "Page {} requires about {} to be converted, more than the memory limit",
+                            page_nb,
+                            deter::format_size(cost)
+                        );
+                    }
 
-            if enc_opts.compress_webp && !file.ends_with(".webp") {
-                trace!("Should convert {}", file.to_string_lossy());
-                let im = image::load_from_memory(&buffer).map_err(|err| {
-                    EncodingError::FailedToConvertImageFileToZip {
-                        volume,
-                        chapter: *chapter,
-                        chapter_path: chapter_path.to_path_buf(),
-                        image_path: file.to_path_buf(),
-                        err,
+                    cost
+                })
+                .collect(),
+        };
+
+        let limits = PipelineLimits {
+            // Number of loaded pages waiting to be written
+            window: enc_opts
+                .readahead
+                .unwrap_or_else(|| pool.current_num_threads() * 2),
+            costs: &costs,
+            max_cost: max_memory.unwrap_or(u64::MAX),
+        };
+
+        pipeline::ordered_pipeline(
+            pool,
+            &pages,
+            &limits,
+            |(_, file, _)| {
+                load_page(*source, enc_opts, &buffers, volume, *chapter, chapter_path, file)
+            },
+            |(page_nb, file, name_in_zip), buffer| {
+                if cancel.is_cancelled() {
+                    return Err(EncodingError::Cancelled);
+                }
+
+                let buffer = buffer?;
+
+                trace!(
+                    "Adding picture {:0pic_num_len$} at '{}' from chapter {} to volume {} as '{}'...",
+                    page_nb, file.to_string_lossy(), chapter_display_name, volume_display_name, name_in_zip, pic_num_len = pic_num_len
+                );
+
+                // Write the page to the volume
+                let written = match buffer {
+                    Some(buffer) => {
+                        archive_writer.add_page(name_in_zip, &buffer).map_err(|err| {
+                            EncodingError::FailedToWriteImageFileToZip {
+                                volume,
+                                chapter: *chapter,
+                                chapter_path: chapter_path.to_path_buf(),
+                                image_path: file.to_path_buf(),
+                                err,
+                            }
+                        })?;
+
+                        let written = buffer.len() as u64;
+                        buffers.give_back(buffer);
+                        written
                     }
-                })?;
-                let im = match im {
-                    DynamicImage::ImageLuma8(_) => DynamicImage::from(im.into_rgb8()),
-                    DynamicImage::ImageLumaA8(_) => DynamicImage::from(im.into_rgb8()),
-                    _ => im,
-                };
-                let enc = Encoder::from_image(&im).unwrap();
-                let res = enc.encode(60.0);
 
-                buffer = res.to_vec();
-            }
+                    None => copy_page(
+                        *source,
+                        archive_writer.as_mut(),
+                        name_in_zip,
+                        &mut copy_buffer,
+                        volume,
+                        *chapter,
+                        chapter_path,
+                        file,
+                    )?,
+                };
 
-            // Write the file to the ZIP archive
-            zip_writer.write_all(&buffer).map_err(|err| {
-                EncodingError::FailedToWriteImageFileToZip {
-                    volume,
-                    chapter: *chapter,
-                    chapter_path: chapter_path.to_path_buf(),
-                    image_path: file.to_path_buf(),
-                    err,
+                if enc_opts.calibre && cover_page.is_none() {
+                    cover_page = Some((*chapter, chapter_path.to_path_buf(), file.to_path_buf()));
                 }
-            })?;
 
-            buffer.clear();
+                pics_counter += 1;
 
-            pics_counter += 1;
-        }
+                progress.page_processed(volume, *chapter, *page_nb, file, written);
+
+                Ok(())
+            },
+        )?;
     }
 
-    trace!("Closing ZIP archive...");
+    // Get the volume's metadata
+    let mut volume_metadata = metadata.for_volume(volume);
+
+    if !bookmarks.is_empty() {
+        volume_metadata
+            .get_or_insert_with(ComicMetadata::default)
+            .pages = bookmarks;
+    }
+
+    // Write the volume's metadata
+    if let Some(mut metadata) = volume_metadata {
+        trace!("Adding metadata file to archive...");
+
+        metadata.page_count = Some(pics_counter.to_string());
+
+        archive_writer
+            .add_file(COMIC_INFO_FILENAME, metadata.to_comic_info_xml().as_bytes())
+            .map_err(|err| EncodingError::FailedToWriteMetadataFileToZip(volume, err))?;
+    }
+
+    trace!("Closing archive...");
 
     // Close the archive
-    zip_writer
+    let archive_file = archive_writer
         .finish()
         .map_err(|err| EncodingError::FailedToCloseZipArchive(volume, err))?;
 
-    // Determine the file's final path with the right (non-partial) extension + number of pages if asked to
-    let mut complete_path = output_path_without_ext.with_extension("cbz");
+    // Get the volume's size
+    let size = archive_file
+        .metadata()
+        .map_err(|err| EncodingError::FailedToGetVolumeSize(volume, err))?
+        .len();
 
-    if enc_opts.append_pages_count {
-        let mut filename_with_pages = complete_path
-            .with_extension("")
-            .file_name()
-            .expect("Internal error: output path when building has no filename")
-            .to_os_string();
+    // Make sure the archive was completely written before giving it its final name
+    if enc_opts.container == VolumeFormat::Cbz {
+        trace!("Checking archive...");
 
-        filename_with_pages.push(format!(" ({} pages).cbz", pics_counter));
+        archive::check_zip(&staging_path)
+            .map_err(|err| EncodingError::InvalidCompleteArchive(volume, err))?;
+    }
 
-        complete_path = complete_path.with_file_name(filename_with_pages)
-    };
+    // Determine the file's final path with the right (non-partial) extension + number of pages if asked to
+    let complete_path = volume_complete_path(&output_path_without_ext, enc_opts, pics_counter);
 
     // Check if final path exists
     if complete_path.exists() {
@@ -417,7 +1416,7 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
             ));
         }
 
-        if !complete_path.is_dir() {
+        if complete_path.is_dir() {
             return Err(EncodingError::OutputVolumeFileIsADirectory(
                 volume,
                 complete_path,
@@ -433,11 +1432,84 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
         }
     }
 
-    // Rename the staging file to its complete name
-    if let Err(err) = fs::rename(&staging_path, &complete_path) {
+    // Rename the staging file to its complete name, once its content is on the disk
+    if let Err(err) = archive::finalize_staging_file(&staging_path, &complete_path) {
         return Err(EncodingError::FailedToRenameCompleteArchive(volume, err));
     }
 
+    // Read the volume back, so a corrupted volume is detected before its chapters are removed
+    if enc_opts.verify && enc_opts.container == VolumeFormat::Cbz {
+        trace!("Verifying volume...");
+
+        verify_volume(&complete_path, enc_opts, pics_counter).map_err(|problems| {
+            EncodingError::CorruptedVolume(volume, complete_path.clone(), problems)
+        })?;
+    }
+
+    if enc_opts.emit_checksums {
+        trace!("Writing checksum file...");
+
+        checksum::write_checksum_file(&complete_path).map_err(|err| {
+            EncodingError::FailedToWriteChecksumFile(
+                volume,
+                checksum::checksum_path(&complete_path),
+                err,
+            )
+        })?;
+    }
+
+    if let Some((chapter, chapter_path, file)) = &cover_page {
+        trace!("Writing Calibre metadata and cover...");
+
+        let mut cover = vec![];
+
+        open_page(*source, volume, *chapter, chapter_path, file)?
+            .read_to_end(&mut cover)
+            .map_err(|err| EncodingError::FailedToReadImage {
+                volume,
+                chapter: *chapter,
+                chapter_path: chapter_path.to_path_buf(),
+                image_path: file.to_path_buf(),
+                err,
+            })?;
+
+        let mut book_metadata = metadata.for_volume(volume).unwrap_or_default();
+
+        if book_metadata.series.is_none() {
+            book_metadata.series = enc_opts.series();
+        }
+
+        // Volumes are ordered by their number in the series
+        if let BuildMethod::Ranges(_, _) = method {
+            book_metadata.volume.get_or_insert_with(|| volume.to_string());
+        }
+
+        let title = output_path_without_ext
+            .file_name()
+            .expect("Internal error: output path without extension has no filename when building")
+            .to_string_lossy();
+
+        calibre::write_book_files(&complete_path, &title, &book_metadata, cover)
+            .map_err(|err| EncodingError::FailedToWriteCalibreFiles(volume, err))?;
+    }
+
+    if let Some(post_volume_cmd) = &enc_opts.post_volume_cmd {
+        trace!("Running post-volume command...");
+
+        hooks::run_hook(
+            post_volume_cmd,
+            &[
+                ("volume", volume.to_string()),
+                ("path", complete_path.to_string_lossy().into_owned()),
+                ("pages", pics_counter.to_string()),
+                ("size", size.to_string()),
+                ("first_chapter", start_chapter.to_string()),
+                ("last_chapter", args.end_chapter().to_string()),
+            ],
+        )
+        .map_err(|err| EncodingError::PostVolumeCommandFailed(volume, err))?;
+    }
+
     let complete_filename = complete_path
         .file_name()
         .expect("Internal error: output path when building has no filename")
@@ -454,6 +1526,7 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
 
     // Compute elapsed time
     let elapsed = build_started.elapsed();
+    let seconds = elapsed.as_secs_f64();
 
     // Format elapsed time
     let elapsed = format!("{}.{:03} s", elapsed.as_secs(), elapsed.subsec_millis());
@@ -465,6 +1538,19 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
         String::new()
     };
 
+    let summary = VolumeSummary {
+        volume,
+        path: complete_path,
+        pages: pics_counter,
+        size,
+        first_chapter: *start_chapter,
+        last_chapter: args.end_chapter(),
+        seconds,
+        skipped: false,
+    };
+
+    progress.volume_finished(&summary);
+
     match method {
         BuildMethod::Each(_, _) => info!(
             "Successfully written volume {:0vol_num_len$} / {} to file '{}{}', containing {} pages in {}.",
@@ -482,7 +1568,7 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
             volume_display_name,
             volumes,
             start_chapter,
-            start_chapter + chapters.len() - 1,
+            args.end_chapter(),
             success_display_file_name,
             filename_right_padding,
             pics_counter,
@@ -491,5 +1577,48 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
         )
     }
 
-    Ok(staging_path)
+    Ok(summary)
+}
+
+/// Write the summary of the produced volumes to the output directory and/or print it, if asked to
+pub fn report_summary(
+    enc_opts: &EncodingOptions,
+    output_dir: &Path,
+    report: &JobReport,
+) -> Result<(), EncodingError> {
+    if !enc_opts.summary && !enc_opts.json {
+        return Ok(());
+    }
+
+    if enc_opts.summary {
+        report.save(output_dir).map_err(|err| {
+            EncodingError::FailedToWriteSummary(output_dir.join(SUMMARY_FILENAME), err)
+        })?;
+    }
+
+    if enc_opts.json {
+        println!(
+            "{}",
+            report
+                .to_json()
+                .map_err(EncodingError::FailedToSerializeSummary)?
+        );
+    }
+
+    Ok(())
+}
+
+/// Summarize a volume that was built before, from its file
+pub fn existing_volume_summary(
+    args: &BuildVolumeArgs,
+    path: &Path,
+) -> Result<VolumeSummary, EncodingError> {
+    VolumeSummary::existing(
+        args.volume,
+        path,
+        args.start_chapter,
+        args.end_chapter(),
+        args.enc_opts.accept_extended_image_formats,
+    )
+    .map_err(|err| EncodingError::FailedToReadExistingVolume(args.volume, path.to_path_buf(), err))
 }
@@ -0,0 +1,123 @@
+use crate::lib::source::PageSource;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the file storing the hashes of the built volumes' sources, in the output directory
+pub const CACHE_FILENAME: &str = ".comic-enc-cache";
+
+/// Hashes of everything a volume is built from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VolumeSources {
+    /// Hash of the options affecting the volume's content and name
+    pub options: String,
+    /// Hash of each chapter's pictures, in the volume's order
+    pub chapters: Vec<String>,
+}
+
+/// A volume built from known sources
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CachedVolume {
+    pub volume: usize,
+    pub path: PathBuf,
+    pub sources: VolumeSources,
+}
+
+/// Sources of the volumes built in an output directory, used to only build again the volumes that changed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildCache {
+    pub volumes: Vec<CachedVolume>,
+}
+
+impl BuildCache {
+    /// Load the cache of an output directory, which is empty if the directory has none
+    pub fn load(output: &Path) -> Result<Self, CacheErr> {
+        let path = output.join(CACHE_FILENAME);
+
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).map_err(CacheErr::IOError)?;
+        serde_json::from_str(&content).map_err(CacheErr::InvalidCache)
+    }
+
+    /// Save the cache to an output directory
+    pub fn save(&self, output: &Path) -> Result<(), CacheErr> {
+        let content = serde_json::to_string_pretty(self).map_err(CacheErr::InvalidCache)?;
+        fs::write(output.join(CACHE_FILENAME), content).map_err(CacheErr::IOError)
+    }
+
+    /// Get the path of a volume built from the same sources, if it still exists
+    pub fn up_to_date(&self, volume: usize, sources: &VolumeSources) -> Option<&PathBuf> {
+        self.volumes
+            .iter()
+            .find(|cached| cached.volume == volume && &cached.sources == sources)
+            .map(|cached| &cached.path)
+            .filter(|path| path.is_file())
+    }
+
+    /// Record the sources of a volume, returning the ones it replaces if any
+    pub fn record(&mut self, built: CachedVolume) -> Option<CachedVolume> {
+        let previous = self
+            .volumes
+            .iter()
+            .position(|cached| cached.volume == built.volume)
+            .map(|index| self.volumes.remove(index));
+
+        self.volumes.push(built);
+        self.volumes.sort_by_key(|cached| cached.volume);
+
+        previous
+    }
+}
+
+/// Compute the hash of a text, as an hexadecimal string
+pub fn text_sha256(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+/// Compute the hash of a chapter from the path (relative to the chapter's directory) and content of its pictures
+pub fn chapter_sha256(
+    source: &dyn PageSource,
+    chapter_path: &Path,
+    pictures: &[PathBuf],
+) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+
+    for picture in pictures {
+        let relative = picture.strip_prefix(chapter_path).unwrap_or(picture);
+
+        hasher.update(relative.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(source.size(picture)?.to_le_bytes());
+        io::copy(&mut source.open(picture)?, &mut hasher)?;
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Error while loading or saving the cache
+#[derive(Debug)]
+pub enum CacheErr {
+    IOError(io::Error),
+    InvalidCache(serde_json::Error),
+}
+
+impl fmt::Display for CacheErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidCache(err) => write!(f, "Invalid cache file: {}", err),
+        }
+    }
+}
+
+impl Error for CacheErr {}
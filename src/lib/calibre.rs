@@ -0,0 +1,204 @@
+use crate::lib::deter;
+use crate::lib::metadata::ComicMetadata;
+use image::{DynamicImage, ImageError, ImageOutputFormat};
+use quick_xml::escape::escape;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+
+/// Name of the metadata file Calibre reads, in each book's directory
+pub const OPF_FILENAME: &str = "metadata.opf";
+
+/// Name of the cover picture Calibre reads, in each book's directory
+pub const COVER_FILENAME: &str = "cover.jpg";
+
+/// Name of the author's directory for books without a writer, as in Calibre libraries
+const UNKNOWN_AUTHOR: &str = "Unknown";
+
+/// Quality of the covers converted to JPEG
+const COVER_JPEG_QUALITY: u8 = 90;
+
+/// Get the path of a book in a Calibre library: 'Author/Title/Title', without extension
+pub fn book_path(output: &Path, title: &str, metadata: Option<&ComicMetadata>) -> PathBuf {
+    // Calibre names the directory after the first author only
+    let author = metadata
+        .and_then(|metadata| metadata.writer.as_deref())
+        .and_then(|writer| {
+            writer
+                .split(',')
+                .map(str::trim)
+                .find(|author| !author.is_empty())
+        })
+        .unwrap_or(UNKNOWN_AUTHOR);
+
+    let author = deter::sanitize_filename(author);
+
+    output.join(author).join(title).join(title)
+}
+
+/// Generate the content of a book's 'metadata.opf' file (OPF 2.0, with Calibre's series fields)
+pub fn to_opf(title: &str, metadata: &ComicMetadata) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+    xml.push_str("<package xmlns=\"http://www.idpf.org/2007/opf\" unique-identifier=\"id\" version=\"2.0\">\n");
+    xml.push_str("  <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:opf=\"http://www.idpf.org/2007/opf\">\n");
+
+    let series = metadata.series.as_deref().unwrap_or_default();
+    let title = metadata.title.as_deref().unwrap_or(title);
+
+    xml.push_str(&format!(
+        "    <dc:identifier id=\"id\">urn:comic-enc:{}:{}</dc:identifier>\n",
+        escape(series),
+        escape(title)
+    ));
+    xml.push_str(&format!("    <dc:title>{}</dc:title>\n", escape(title)));
+
+    for (role, people) in &[
+        ("aut", &metadata.writer),
+        ("art", &metadata.penciller),
+        ("edt", &metadata.editor),
+    ] {
+        for person in list(people) {
+            xml.push_str(&format!(
+                "    <dc:creator opf:role=\"{}\">{}</dc:creator>\n",
+                role,
+                escape(person)
+            ));
+        }
+    }
+
+    for (element, value) in &[
+        ("publisher", &metadata.publisher),
+        ("description", &metadata.summary),
+        ("language", &metadata.language_iso),
+    ] {
+        if let Some(value) = value {
+            xml.push_str(&format!(
+                "    <dc:{}>{}</dc:{}>\n",
+                element,
+                escape(value),
+                element
+            ));
+        }
+    }
+
+    if let Some(year) = &metadata.year {
+        let date = match (&metadata.month, &metadata.day) {
+            (Some(month), Some(day)) => format!("{}-{:0>2}-{:0>2}", year, month, day),
+            (Some(month), None) => format!("{}-{:0>2}", year, month),
+            _ => year.clone(),
+        };
+
+        xml.push_str(&format!("    <dc:date>{}</dc:date>\n", escape(&date)));
+    }
+
+    for subject in list(&metadata.genre).chain(list(&metadata.tags)) {
+        xml.push_str(&format!(
+            "    <dc:subject>{}</dc:subject>\n",
+            escape(subject)
+        ));
+    }
+
+    if let Some(gtin) = &metadata.gtin {
+        xml.push_str(&format!(
+            "    <dc:identifier opf:scheme=\"ISBN\">{}</dc:identifier>\n",
+            escape(gtin)
+        ));
+    }
+
+    if !series.is_empty() {
+        xml.push_str(&format!(
+            "    <meta name=\"calibre:series\" content=\"{}\"/>\n",
+            escape(series)
+        ));
+
+        if let Some(index) = metadata.volume.as_ref().or(metadata.number.as_ref()) {
+            xml.push_str(&format!(
+                "    <meta name=\"calibre:series_index\" content=\"{}\"/>\n",
+                escape(index)
+            ));
+        }
+    }
+
+    xml.push_str("  </metadata>\n");
+    xml.push_str("  <guide>\n");
+    xml.push_str(&format!(
+        "    <reference type=\"cover\" title=\"Cover\" href=\"{}\"/>\n",
+        COVER_FILENAME
+    ));
+    xml.push_str("  </guide>\n");
+    xml.push_str("</package>\n");
+    xml
+}
+
+/// Write the 'metadata.opf' file and the cover of a book next to its file
+/// The cover is the content of the book's first page, converted to JPEG if it isn't already
+pub fn write_book_files(
+    book: &Path,
+    title: &str,
+    metadata: &ComicMetadata,
+    cover: Vec<u8>,
+) -> Result<(), CalibreErr> {
+    let dir = deter::long_path(book.parent().unwrap_or_else(|| Path::new(".")));
+
+    let opf_path = dir.join(OPF_FILENAME);
+    fs::write(&opf_path, to_opf(title, metadata))
+        .map_err(|err| CalibreErr::FailedToWriteFile(opf_path, err))?;
+
+    // JPEG files start with the 'FF D8 FF' marker
+    let cover = if cover.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        cover
+    } else {
+        let mut buffer = vec![];
+
+        // JPEG doesn't support transparency
+        DynamicImage::ImageRgb8(
+            image::load_from_memory(&cover)
+                .map_err(CalibreErr::FailedToConvertCover)?
+                .into_rgb8(),
+        )
+        .write_to(
+            &mut Cursor::new(&mut buffer),
+            ImageOutputFormat::Jpeg(COVER_JPEG_QUALITY),
+        )
+        .map_err(CalibreErr::FailedToConvertCover)?;
+
+        buffer
+    };
+
+    let cover_path = dir.join(COVER_FILENAME);
+    fs::write(&cover_path, cover).map_err(|err| CalibreErr::FailedToWriteFile(cover_path, err))
+}
+
+/// Split a field listing multiple values separated by commas
+fn list(field: &Option<String>) -> impl Iterator<Item = &str> {
+    field
+        .iter()
+        .flat_map(|values| values.split(','))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+/// Error while writing the files of a book in a Calibre library
+#[derive(Debug)]
+pub enum CalibreErr {
+    FailedToConvertCover(ImageError),
+    FailedToWriteFile(PathBuf, io::Error),
+}
+
+impl fmt::Display for CalibreErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FailedToConvertCover(err) => {
+                write!(f, "Failed to convert cover to JPEG: {}", err)
+            }
+            Self::FailedToWriteFile(path, err) => {
+                write!(f, "Failed to write '{}': {}", path.to_string_lossy(), err)
+            }
+        }
+    }
+}
+
+impl Error for CalibreErr {}
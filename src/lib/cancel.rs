@@ -0,0 +1,48 @@
+use crate::cli::exit_code::ExitCode;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// Token cancelled when Ctrl-C is pressed, once interrupts are handled
+static INTERRUPT: OnceLock<CancellationToken> = OnceLock::new();
+
+/// Token checked between volumes and pages, to stop building volumes cleanly
+/// Clones share the same state, so the token can be cancelled from another thread
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a token that is not cancelled yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop building volumes as soon as possible
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Check if the token was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Get the token cancelled when Ctrl-C is pressed (it is never cancelled unless interrupts are handled)
+pub fn interrupt_token() -> CancellationToken {
+    INTERRUPT.get_or_init(CancellationToken::new).clone()
+}
+
+/// Cancel the interrupt token when Ctrl-C is pressed instead of exiting, and exit immediately if it is pressed again
+pub fn handle_interrupts() -> Result<(), ctrlc::Error> {
+    let token = interrupt_token();
+
+    ctrlc::set_handler(move || {
+        if token.is_cancelled() {
+            process::exit(ExitCode::Cancelled.code(false));
+        }
+
+        warn!("Cancelling... (press Ctrl-C again to exit immediately, leaving partial files)");
+        token.cancel();
+    })
+}
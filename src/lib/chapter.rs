@@ -0,0 +1,413 @@
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Prefix of the extra chapters' numbers (e.g. 'Extra 3')
+const EXTRA_PREFIX: &str = "Extra";
+
+/// Number of a chapter, which can be a sub-chapter (e.g. '10.5') or an extra chapter (e.g. 'Extra 3')
+/// Extra chapters are sorted after all regular ones, and sub-chapters right after their main chapter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChapterNumber {
+    /// Is this an extra chapter?
+    pub extra: bool,
+    /// Main number of the chapter
+    pub main: usize,
+    /// Number of the sub-chapter, if any (e.g. 5 for '10.5')
+    pub sub: Option<usize>,
+}
+
+impl ChapterNumber {
+    /// Get a regular chapter's number
+    pub fn new(main: usize) -> Self {
+        Self {
+            extra: false,
+            main,
+            sub: None,
+        }
+    }
+
+    /// Check if this is the number of a regular chapter (not a sub-chapter nor an extra one)
+    pub fn is_regular(&self) -> bool {
+        !self.extra && self.sub.is_none()
+    }
+}
+
+impl From<usize> for ChapterNumber {
+    fn from(main: usize) -> Self {
+        Self::new(main)
+    }
+}
+
+impl FromStr for ChapterNumber {
+    type Err = ChapterNumberErr;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let trimmed = input.trim();
+
+        let (extra, number) = match trimmed.get(..EXTRA_PREFIX.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(EXTRA_PREFIX) => (
+                true,
+                trimmed[EXTRA_PREFIX.len()..].trim_start_matches(|c: char| {
+                    c.is_whitespace() || c == '-' || c == '_'
+                }),
+            ),
+            _ => (false, trimmed),
+        };
+
+        let parse = |part: &str| {
+            if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+                return Err(ChapterNumberErr(input.to_owned()));
+            }
+
+            part.parse::<usize>()
+                .map_err(|_| ChapterNumberErr(input.to_owned()))
+        };
+
+        let (main, sub) = match number.split_once('.') {
+            Some((main, sub)) => (parse(main)?, Some(parse(sub)?)),
+            None => (parse(number)?, None),
+        };
+
+        Ok(Self { extra, main, sub })
+    }
+}
+
+impl fmt::Display for ChapterNumber {
+    /// Display the number, padding its main part with zeros if a width is provided (e.g. '{:03}' gives '010.5')
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.extra {
+            write!(f, "{} ", EXTRA_PREFIX)?;
+        }
+
+        match f.width() {
+            Some(width) if f.sign_aware_zero_pad() => write!(f, "{:0width$}", self.main, width = width)?,
+            _ => write!(f, "{}", self.main)?,
+        }
+
+        if let Some(sub) = self.sub {
+            write!(f, ".{}", sub)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for ChapterNumber {
+    /// Regular chapters' numbers are serialized as integers, other ones as strings
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.is_regular() {
+            serializer.serialize_u64(self.main as u64)
+        } else {
+            serializer.serialize_str(&self.to_string())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ChapterNumber {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Integer(usize),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Integer(main) => Ok(Self::new(main)),
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Markers preceding a chapter's number in directory names, in various languages (e.g. 'Chapter 12', 'Ch.12',
+/// 'Capítulo 12', 'Глава 12')
+const CHAPTER_MARKERS: &str = r"(?i)\b(?:chapters?|chapitre|chap|ch|cap[ií]tulo|cap|kapitel|kap|capitolo|hoofdstuk|rozdział|tome|episode|ep|глава|гл|розділ)\.?\s*[-_#:.]?\s*";
+
+/// Chapter numbers enclosed by CJK markers (e.g. '第12話', '第12章', '12화')
+const CJK_CHAPTER_MARKERS: &str = r"(?:第\s*(?P<num>[0-9]+(?:\.[0-9]+)?)\s*[話话章回]|(?P<korean>[0-9]+(?:\.[0-9]+)?)\s*화)";
+
+/// Any number
+const ANY_NUMBER: &str = r"[0-9]+(?:\.[0-9]+)?";
+
+/// Words of the numbers below twenty
+const UNITS: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve",
+    "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+];
+
+/// Words of the tens, starting at twenty
+const TENS: [&str; 8] = ["twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+
+/// Value of each roman numeral
+const ROMAN_NUMERALS: [(usize, &str); 13] = [
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+
+/// Heuristics detecting chapters' numbers from their directory's name
+pub struct ChapterDetector {
+    markers: Regex,
+    cjk_markers: Regex,
+    any_number: Regex,
+    /// Words following a chapter marker, to parse as a roman numeral or a number written in words
+    marked_words: Option<Regex>,
+}
+
+impl ChapterDetector {
+    /// Create the detector, which also parses numbers written as roman numerals or in words (e.g. 'Chapter IV',
+    /// 'Chapter Twelve') if `words` is set
+    pub fn new(words: bool) -> Self {
+        Self {
+            markers: Regex::new(&format!(r"{}(?P<num>[0-9]+(?:[.,][0-9]+)?)", CHAPTER_MARKERS)).unwrap(),
+            cjk_markers: Regex::new(CJK_CHAPTER_MARKERS).unwrap(),
+            any_number: Regex::new(ANY_NUMBER).unwrap(),
+            marked_words: if words {
+                Some(Regex::new(&format!(r"{}(?P<words>\p{{L}}+(?:[\s-]+\p{{L}}+)*)", CHAPTER_MARKERS)).unwrap())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Detect the number of a chapter from its directory's name
+    /// The number following a chapter marker is used if there is one, otherwise the name must contain a single number
+    pub fn detect(&self, name: &str) -> Option<ChapterNumber> {
+        let name = normalize_digits(name);
+
+        let marked = self.markers.captures(&name).and_then(|captures| captures.name("num"));
+
+        if marked.is_none() {
+            let from_words = self
+                .marked_words
+                .as_ref()
+                .and_then(|regex| regex.captures(&name))
+                .and_then(|captures| captures.name("words"))
+                .and_then(|words| parse_roman_numeral(words.as_str()).or_else(|| parse_words_number(words.as_str())));
+
+            if let Some(number) = from_words {
+                return Some(ChapterNumber::new(number));
+            }
+        }
+
+        let marked = marked.or_else(|| {
+            let captures = self.cjk_markers.captures(&name)?;
+            captures.name("num").or_else(|| captures.name("korean"))
+        });
+
+        let number = match marked {
+            Some(number) => number.as_str(),
+            None => {
+                let mut numbers = self.any_number.find_iter(&name);
+
+                match (numbers.next(), numbers.next()) {
+                    (Some(number), None) => number.as_str(),
+                    _ => return None,
+                }
+            }
+        };
+
+        number.replace(',', ".").parse().ok()
+    }
+}
+
+/// Parse the roman numeral starting the provided words (e.g. 'IV' in 'IV - The Return')
+/// Only numerals in their canonical form are accepted, so regular words made of the same letters (e.g. 'Mix') aren't
+fn parse_roman_numeral(words: &str) -> Option<usize> {
+    let numeral = words.split(|c: char| c.is_whitespace() || c == '-').next()?.to_ascii_uppercase();
+
+    let mut rest = numeral.as_str();
+    let mut value = 0;
+
+    for (numeral_value, symbol) in ROMAN_NUMERALS.iter() {
+        while let Some(stripped) = rest.strip_prefix(symbol) {
+            value += numeral_value;
+            rest = stripped;
+        }
+    }
+
+    if !rest.is_empty() || value == 0 || to_roman_numeral(value) != numeral {
+        return None;
+    }
+
+    Some(value)
+}
+
+/// Write a number as a roman numeral
+fn to_roman_numeral(mut value: usize) -> String {
+    let mut numeral = String::new();
+
+    for (numeral_value, symbol) in ROMAN_NUMERALS.iter() {
+        while value >= *numeral_value {
+            numeral.push_str(symbol);
+            value -= numeral_value;
+        }
+    }
+
+    numeral
+}
+
+/// Parse the number written in english words starting the provided words (e.g. 'Twelve', 'Twenty-One',
+/// 'One Hundred and Five'), stopping at the first word which isn't part of a number
+fn parse_words_number(words: &str) -> Option<usize> {
+    let mut total = 0;
+    let mut current = 0;
+    let mut found = false;
+
+    for word in words.split(|c: char| c.is_whitespace() || c == '-').filter(|word| !word.is_empty()) {
+        let word = word.to_lowercase();
+
+        if let Some(unit) = UNITS.iter().position(|unit| *unit == word) {
+            current += unit;
+        } else if let Some(tens) = TENS.iter().position(|tens| *tens == word) {
+            current += (tens + 2) * 10;
+        } else if word == "hundred" && found {
+            current *= 100;
+        } else if word == "thousand" && found {
+            total += current * 1000;
+            current = 0;
+        } else if word == "and" && found {
+            continue;
+        } else {
+            break;
+        }
+
+        found = true;
+    }
+
+    if found {
+        Some(total + current)
+    } else {
+        None
+    }
+}
+
+/// Convert full-width digits and dots (e.g. '１２．５') to their ASCII equivalent
+fn normalize_digits(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '０'..='９' => char::from(b'0' + (c as u32 - '０' as u32) as u8),
+            '．' => '.',
+            c => c,
+        })
+        .collect()
+}
+
+/// Selection of chapters, as a list of single chapters and inclusive ranges (e.g. '10-25,30')
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChapterSelection {
+    /// First and last chapter of each range (both the same for single chapters)
+    ranges: Vec<(ChapterNumber, ChapterNumber)>,
+}
+
+impl ChapterSelection {
+    /// Check if a chapter is selected
+    pub fn contains(&self, chapter: ChapterNumber) -> bool {
+        self.ranges
+            .iter()
+            .any(|(first, last)| *first <= chapter && chapter <= *last)
+    }
+}
+
+impl FromStr for ChapterSelection {
+    type Err = ChapterSelectionErr;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut ranges = vec![];
+
+        for entry in input.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (first, last) = match entry.split_once('-') {
+                Some((first, last)) => (first.parse()?, last.parse()?),
+                None => {
+                    let chapter = entry.parse()?;
+                    (chapter, chapter)
+                }
+            };
+
+            if last < first {
+                return Err(ChapterSelectionErr::InvalidRange(entry.to_owned()));
+            }
+
+            ranges.push((first, last));
+        }
+
+        if ranges.is_empty() {
+            return Err(ChapterSelectionErr::Empty);
+        }
+
+        Ok(Self { ranges })
+    }
+}
+
+impl fmt::Display for ChapterSelection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (first, last)) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+
+            if first == last {
+                write!(f, "{}", first)?;
+            } else {
+                write!(f, "{}-{}", first, last)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Error while parsing a chapter selection
+#[derive(Debug)]
+pub enum ChapterSelectionErr {
+    InvalidChapter(ChapterNumberErr),
+    InvalidRange(String),
+    Empty,
+}
+
+impl From<ChapterNumberErr> for ChapterSelectionErr {
+    fn from(err: ChapterNumberErr) -> Self {
+        Self::InvalidChapter(err)
+    }
+}
+
+impl fmt::Display for ChapterSelectionErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidChapter(err) => write!(f, "{}", err),
+            Self::InvalidRange(range) => write!(f, "Range '{}' ends before it starts", range),
+            Self::Empty => write!(f, "No chapter selected (expected e.g. '10-25,30')"),
+        }
+    }
+}
+
+impl Error for ChapterSelectionErr {}
+
+/// Error while parsing a chapter number
+#[derive(Debug)]
+pub struct ChapterNumberErr(String);
+
+impl fmt::Display for ChapterNumberErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid chapter number '{}' (expected e.g. '10', '10.5' or 'Extra 3')",
+            self.0
+        )
+    }
+}
+
+impl Error for ChapterNumberErr {}
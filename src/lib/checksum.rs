@@ -0,0 +1,97 @@
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Extension of the checksum files written next to the volumes
+pub const CHECKSUM_EXT: &str = "sha256";
+
+/// Get the path of the checksum file of an archive (e.g. 'Volume 1.cbz.sha256')
+pub fn checksum_path(archive: &Path) -> PathBuf {
+    let mut file_name = archive.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(".");
+    file_name.push(CHECKSUM_EXT);
+    archive.with_file_name(file_name)
+}
+
+/// Compute the SHA-256 hash of a file, as an hexadecimal string
+pub fn file_sha256(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Write the checksum file of an archive, in the format used by the 'sha256sum' tool
+pub fn write_checksum_file(archive: &Path) -> io::Result<PathBuf> {
+    let path = checksum_path(archive);
+    let hash = file_sha256(archive)?;
+
+    fs::write(
+        &path,
+        format!(
+            "{}  {}\n",
+            hash,
+            archive.file_name().unwrap_or_default().to_string_lossy()
+        ),
+    )?;
+
+    Ok(path)
+}
+
+/// Result of an archive's verification against its checksum file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    /// Archive matches its checksum
+    Valid,
+    /// Archive does not have a checksum file
+    Missing,
+    /// Archive does not match its checksum anymore
+    Mismatch { expected: String, actual: String },
+}
+
+/// Verify an archive against its checksum file
+pub fn verify_checksum(archive: &Path) -> Result<ChecksumStatus, ChecksumErr> {
+    let path = checksum_path(archive);
+
+    if !path.is_file() {
+        return Ok(ChecksumStatus::Missing);
+    }
+
+    let content = fs::read_to_string(&path).map_err(ChecksumErr::FailedToReadChecksumFile)?;
+
+    let expected = content
+        .split_whitespace()
+        .next()
+        .filter(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or(ChecksumErr::InvalidChecksumFile)?
+        .to_lowercase();
+
+    let actual = file_sha256(archive).map_err(ChecksumErr::FailedToReadArchive)?;
+
+    if actual == expected {
+        Ok(ChecksumStatus::Valid)
+    } else {
+        Ok(ChecksumStatus::Mismatch { expected, actual })
+    }
+}
+
+/// Checksum verification error
+pub enum ChecksumErr {
+    FailedToReadChecksumFile(io::Error),
+    InvalidChecksumFile,
+    FailedToReadArchive(io::Error),
+}
+
+impl fmt::Display for ChecksumErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FailedToReadChecksumFile(err) => {
+                write!(f, "Failed to read checksum file: {}", err)
+            }
+            Self::InvalidChecksumFile => write!(f, "Checksum file does not contain a SHA-256 hash"),
+            Self::FailedToReadArchive(err) => write!(f, "Failed to read archive: {}", err),
+        }
+    }
+}
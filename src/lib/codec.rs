@@ -0,0 +1,268 @@
+use crate::cli::opts::PageFormat;
+use image::error::{ImageFormatHint, UnsupportedError, UnsupportedErrorKind};
+use image::{DynamicImage, ImageError, ImageFormat, ImageResult};
+use std::path::Path;
+
+#[cfg(any(feature = "jpeg", feature = "png"))]
+use image::ImageOutputFormat;
+#[cfg(any(feature = "jpeg", feature = "png", feature = "avif"))]
+use std::io::Cursor;
+
+/// Default quality of the WebP images generated from other formats
+pub const WEBP_QUALITY: u8 = 60;
+
+/// Maximum width and height of a WebP image
+pub const WEBP_MAX_DIMENSION: u32 = 16383;
+
+/// Default quality of the JPEG images generated from other formats
+pub const JPEG_QUALITY: u8 = 85;
+
+/// Default quality of the AVIF images generated from other formats
+pub const AVIF_QUALITY: u8 = 60;
+
+/// Speed of the AVIF encoder, from 1 (slowest, smallest files) to 10 (fastest)
+pub const AVIF_SPEED: u8 = 6;
+
+/// Number of pixels from which a page is split in tiles encoded by multiple threads with AVIF (e.g. double spreads)
+pub const AVIF_LARGE_PAGE_PIXELS: u64 = 8_000_000;
+
+/// Image format pages can be converted to
+/// Codecs are enabled through cargo features, so formats that aren't needed don't grow the binary
+pub trait ImageCodec: Send + Sync {
+    /// Get the format encoded by the codec
+    fn format(&self) -> ImageFormat;
+
+    /// Get the extensions of the files encoded with the codec (the first one is given to converted pages)
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Get the quality pictures are encoded with when none is provided (from 0 to 100)
+    fn default_quality(&self) -> u8;
+
+    /// Get the maximum width and height of the pictures the codec can encode
+    fn max_dimension(&self) -> Option<u32> {
+        None
+    }
+
+    /// Decode a picture encoded in the codec's format
+    fn decode(&self, buffer: &[u8]) -> ImageResult<DynamicImage> {
+        image::load_from_memory_with_format(buffer, self.format())
+    }
+
+    /// Encode a picture with the provided quality (from 0 to 100, ignored by lossless codecs)
+    fn encode(&self, im: DynamicImage, quality: u8) -> ImageResult<Vec<u8>>;
+
+    /// Check if a file has one of the codec's extensions
+    fn handles(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| {
+                self.extensions()
+                    .iter()
+                    .any(|codec_ext| ext.eq_ignore_ascii_case(codec_ext))
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// Get the codec of a format
+/// Returns `None` if the codec wasn't enabled when building the program
+pub fn for_format(format: PageFormat) -> Option<&'static dyn ImageCodec> {
+    match format {
+        #[cfg(feature = "webp")]
+        PageFormat::Webp => Some(&WebpCodec),
+        #[cfg(feature = "jpeg")]
+        PageFormat::Jpeg => Some(&JpegCodec),
+        #[cfg(feature = "png")]
+        PageFormat::Png => Some(&PngCodec),
+        #[cfg(feature = "avif")]
+        PageFormat::Avif => Some(&AvifCodec),
+
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+/// Get the codecs enabled when building the program
+pub fn available() -> Vec<&'static dyn ImageCodec> {
+    [
+        PageFormat::Webp,
+        PageFormat::Jpeg,
+        PageFormat::Png,
+        PageFormat::Avif,
+    ]
+    .iter()
+    .filter_map(|&format| for_format(format))
+    .collect()
+}
+
+/// Get the codec of a format, failing if it wasn't enabled when building the program
+pub fn require(format: PageFormat) -> ImageResult<&'static dyn ImageCodec> {
+    for_format(format).ok_or_else(|| {
+        ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+            ImageFormatHint::Name(format.name().to_owned()),
+            UnsupportedErrorKind::Format(ImageFormatHint::Name(format!(
+                "{} (build with the '{}' feature to enable it)",
+                format.name(),
+                format.feature()
+            ))),
+        ))
+    })
+}
+
+/// Decode a picture, using the codec of its format if it is enabled
+pub fn decode(buffer: &[u8]) -> ImageResult<DynamicImage> {
+    let format = image::guess_format(buffer)?;
+
+    match available().into_iter().find(|codec| codec.format() == format) {
+        Some(codec) => codec.decode(buffer),
+        None => image::load_from_memory_with_format(buffer, format),
+    }
+}
+
+/// WebP codec, using 'libwebp'
+#[cfg(feature = "webp")]
+pub struct WebpCodec;
+
+#[cfg(feature = "webp")]
+impl ImageCodec for WebpCodec {
+    fn format(&self) -> ImageFormat {
+        ImageFormat::WebP
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["webp"]
+    }
+
+    fn default_quality(&self) -> u8 {
+        WEBP_QUALITY
+    }
+
+    fn max_dimension(&self) -> Option<u32> {
+        Some(WEBP_MAX_DIMENSION)
+    }
+
+    fn encode(&self, im: DynamicImage, quality: u8) -> ImageResult<Vec<u8>> {
+        // WebP encoder only supports RGB and RGBA pictures
+        let im = match im {
+            DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => im,
+            _ if im.color().has_alpha() => DynamicImage::from(im.into_rgba8()),
+            _ => DynamicImage::from(im.into_rgb8()),
+        };
+
+        let encoder = webp::Encoder::from_image(&im)
+            .expect("Internal error: WebP encoder does not support RGB or RGBA pictures");
+
+        Ok(encoder.encode(f32::from(quality.min(100))).to_vec())
+    }
+}
+
+/// JPEG codec
+#[cfg(feature = "jpeg")]
+pub struct JpegCodec;
+
+#[cfg(feature = "jpeg")]
+impl ImageCodec for JpegCodec {
+    fn format(&self) -> ImageFormat {
+        ImageFormat::Jpeg
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["jpg", "jpeg"]
+    }
+
+    fn default_quality(&self) -> u8 {
+        JPEG_QUALITY
+    }
+
+    fn max_dimension(&self) -> Option<u32> {
+        Some(u32::from(u16::MAX))
+    }
+
+    fn encode(&self, im: DynamicImage, quality: u8) -> ImageResult<Vec<u8>> {
+        let mut buffer = vec![];
+
+        // JPEG doesn't support transparency
+        DynamicImage::ImageRgb8(im.into_rgb8()).write_to(
+            &mut Cursor::new(&mut buffer),
+            ImageOutputFormat::Jpeg(quality.clamp(1, 100)),
+        )?;
+
+        Ok(buffer)
+    }
+}
+
+/// PNG codec (lossless, the quality is ignored)
+#[cfg(feature = "png")]
+pub struct PngCodec;
+
+#[cfg(feature = "png")]
+impl ImageCodec for PngCodec {
+    fn format(&self) -> ImageFormat {
+        ImageFormat::Png
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["png"]
+    }
+
+    fn default_quality(&self) -> u8 {
+        100
+    }
+
+    fn encode(&self, im: DynamicImage, _quality: u8) -> ImageResult<Vec<u8>> {
+        let mut buffer = vec![];
+        im.write_to(&mut Cursor::new(&mut buffer), ImageOutputFormat::Png)?;
+        Ok(buffer)
+    }
+}
+
+/// AVIF codec, using 'ravif' (a lot slower to encode, but produces smaller files than WebP)
+#[cfg(feature = "avif")]
+pub struct AvifCodec;
+
+#[cfg(feature = "avif")]
+impl ImageCodec for AvifCodec {
+    fn format(&self) -> ImageFormat {
+        ImageFormat::Avif
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["avif"]
+    }
+
+    fn default_quality(&self) -> u8 {
+        AVIF_QUALITY
+    }
+
+    fn encode(&self, im: DynamicImage, quality: u8) -> ImageResult<Vec<u8>> {
+        use image::codecs::avif::AvifEncoder;
+        use image::ImageEncoder;
+
+        // AVIF encoder only supports 8-bit RGB and RGBA pictures here
+        let im = match im {
+            DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => im,
+            _ if im.color().has_alpha() => DynamicImage::from(im.into_rgba8()),
+            _ => DynamicImage::from(im.into_rgb8()),
+        };
+
+        // Large pages are split in tiles encoded by all threads of the current pool, so they don't keep the
+        // other pages waiting, while smaller pages are encoded on a single thread as pages are already converted in
+        // parallel
+        let threads = match u64::from(im.width()) * u64::from(im.height()) {
+            pixels if pixels >= AVIF_LARGE_PAGE_PIXELS => None,
+            _ => Some(1),
+        };
+
+        let mut buffer = vec![];
+
+        AvifEncoder::new_with_speed_quality(
+            Cursor::new(&mut buffer),
+            AVIF_SPEED,
+            quality.clamp(1, 100),
+        )
+        .with_num_threads(threads)
+        .write_image(im.as_bytes(), im.width(), im.height(), im.color())?;
+
+        Ok(buffer)
+    }
+}
@@ -0,0 +1,389 @@
+use clap::{Arg, Command};
+
+/// A command (or subcommand) with the options it accepts, including the global ones of its parents
+struct CommandEntry<'a, 'help> {
+    /// Path of the command (e.g. 'comic-enc__encode__compile')
+    key: String,
+    /// Command itself
+    cmd: &'a Command<'help>,
+    /// Named arguments accepted by the command
+    options: Vec<&'a Arg<'help>>,
+}
+
+/// List all visible commands, recursively, with the named arguments they accept
+fn list_commands<'a, 'help>(
+    cmd: &'a Command<'help>,
+    key: String,
+    inherited: &[&'a Arg<'help>],
+    entries: &mut Vec<CommandEntry<'a, 'help>>,
+) {
+    let mut options = inherited.to_vec();
+
+    for arg in cmd.get_arguments() {
+        if !arg.is_positional()
+            && !arg.is_hide_set()
+            && !options.iter().any(|other| other.get_id() == arg.get_id())
+        {
+            options.push(arg);
+        }
+    }
+
+    let globals = options
+        .iter()
+        .filter(|arg| arg.is_global_set())
+        .copied()
+        .collect::<Vec<_>>();
+
+    for sub in visible_subcommands(cmd) {
+        list_commands(
+            sub,
+            format!("{}__{}", key, sub.get_name()),
+            &globals,
+            entries,
+        );
+    }
+
+    entries.push(CommandEntry { key, cmd, options });
+}
+
+/// Get the visible subcommands of a command
+fn visible_subcommands<'a, 'help>(cmd: &'a Command<'help>) -> Vec<&'a Command<'help>> {
+    cmd.get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .collect()
+}
+
+/// Get the flags of an argument (e.g. ['-o', '--output'])
+fn arg_flags(arg: &Arg) -> Vec<String> {
+    let mut flags = vec![];
+
+    if let Some(short) = arg.get_short() {
+        flags.push(format!("-{}", short));
+    }
+
+    if let Some(long) = arg.get_long() {
+        flags.push(format!("--{}", long));
+    }
+
+    flags
+}
+
+/// Get the possible values of an argument, if it only accepts a fixed set of values
+fn arg_values<'a>(arg: &'a Arg) -> Option<Vec<&'a str>> {
+    let values = arg
+        .get_possible_values()?
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name())
+        .collect::<Vec<_>>();
+
+    if values.is_empty() {
+        None
+    } else {
+        Some(values)
+    }
+}
+
+/// Get the first line of a help message
+fn short_help(help: Option<&str>) -> String {
+    help.and_then(|help| help.lines().next())
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// Generate the transitions between commands, used to find the command being completed from the previous words
+fn transitions<'a, 'help>(entries: &[CommandEntry<'a, 'help>]) -> Vec<(String, String)> {
+    let mut transitions = vec![];
+
+    for entry in entries {
+        for sub in visible_subcommands(entry.cmd) {
+            let mut names = vec![sub.get_name()];
+            names.extend(sub.get_all_aliases());
+
+            for name in names {
+                transitions.push((
+                    format!("{}__{}", entry.key, name),
+                    format!("{}__{}", entry.key, sub.get_name()),
+                ));
+            }
+        }
+    }
+
+    transitions
+}
+
+/// Generate a completion script for Bash
+pub fn bash(cmd: &Command, bin_name: &str) -> String {
+    let mut entries = vec![];
+    list_commands(cmd, bin_name.to_owned(), &[], &mut entries);
+
+    let function = format!("_{}", bin_name.replace('-', "_"));
+
+    let mut script = format!(
+        "{function}() {{
+    local cur prev cmd i opts
+    COMPREPLY=()
+    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"
+    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"
+    cmd=\"{bin_name}\"
+
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        case \"${{cmd}}__${{COMP_WORDS[i]}}\" in
+",
+        function = function,
+        bin_name = bin_name
+    );
+
+    for (from, to) in transitions(&entries) {
+        script.push_str(&format!("            \"{}\") cmd=\"{}\" ;;\n", from, to));
+    }
+
+    script.push_str(
+        "        esac
+    done
+
+    case \"${cmd}\" in
+",
+    );
+
+    for entry in &entries {
+        let mut words = visible_subcommands(entry.cmd)
+            .iter()
+            .map(|sub| sub.get_name().to_owned())
+            .collect::<Vec<_>>();
+
+        for arg in &entry.options {
+            words.extend(arg_flags(arg));
+        }
+
+        script.push_str(&format!("        \"{}\")\n", entry.key));
+
+        let valued = entry
+            .options
+            .iter()
+            .filter_map(|arg| arg_values(arg).map(|values| (arg_flags(arg), values)))
+            .collect::<Vec<_>>();
+
+        if !valued.is_empty() {
+            script.push_str("            case \"${prev}\" in\n");
+
+            for (flags, values) in valued {
+                script.push_str(&format!(
+                    "                {})\n                    COMPREPLY=($(compgen -W \"{}\" -- \"${{cur}}\"))\n                    return 0\n                    ;;\n",
+                    flags.join("|"),
+                    values.join(" ")
+                ));
+            }
+
+            script.push_str("            esac\n");
+        }
+
+        script.push_str(&format!(
+            "            opts=\"{}\"\n            ;;\n",
+            words.join(" ")
+        ));
+    }
+
+    script.push_str(&format!(
+        "    esac
+
+    COMPREPLY=($(compgen -W \"${{opts}}\" -- \"${{cur}}\"))
+
+    if [[ \"${{cur}}\" != -* ]]; then
+        COMPREPLY+=($(compgen -f -- \"${{cur}}\"))
+    fi
+
+    return 0
+}}
+
+complete -o filenames -F {function} {bin_name}
+",
+        function = function,
+        bin_name = bin_name
+    ));
+
+    script
+}
+
+/// Escape a string to put it in a single-quoted Fish string
+fn escape_fish(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+/// Escape a string to put it in a single-quoted Zsh string, as a '_describe' candidate
+fn escape_zsh(value: &str) -> String {
+    value.replace(':', "\\:").replace('\'', "'\\''")
+}
+
+/// Generate a completion script for Zsh
+pub fn zsh(cmd: &Command, bin_name: &str) -> String {
+    let mut entries = vec![];
+    list_commands(cmd, bin_name.to_owned(), &[], &mut entries);
+
+    let function = format!("_{}", bin_name.replace('-', "_"));
+
+    let mut script = format!(
+        "#compdef {bin_name}
+
+{function}() {{
+    local cmd=\"{bin_name}\" word
+    local -a candidates
+
+    for word in \"${{(@)words[2,CURRENT-1]}}\"; do
+        case \"${{cmd}}__${{word}}\" in
+",
+        function = function,
+        bin_name = bin_name
+    );
+
+    for (from, to) in transitions(&entries) {
+        script.push_str(&format!("            \"{}\") cmd=\"{}\" ;;\n", from, to));
+    }
+
+    script.push_str(
+        "        esac
+    done
+
+    case \"${cmd}\" in
+",
+    );
+
+    for entry in &entries {
+        script.push_str(&format!("        \"{}\")\n", entry.key));
+
+        let valued = entry
+            .options
+            .iter()
+            .filter_map(|arg| arg_values(arg).map(|values| (arg_flags(arg), values)))
+            .collect::<Vec<_>>();
+
+        if !valued.is_empty() {
+            script.push_str("            case \"${words[CURRENT-1]}\" in\n");
+
+            for (flags, values) in valued {
+                script.push_str(&format!(
+                    "                {})\n                    compadd -- {}\n                    return\n                    ;;\n",
+                    flags.join("|"),
+                    values.join(" ")
+                ));
+            }
+
+            script.push_str("            esac\n");
+        }
+
+        let mut candidates = vec![];
+
+        for sub in visible_subcommands(entry.cmd) {
+            candidates.push(format!(
+                "'{}:{}'",
+                sub.get_name(),
+                escape_zsh(&short_help(sub.get_about()))
+            ));
+        }
+
+        for arg in &entry.options {
+            let help = escape_zsh(&short_help(arg.get_help()));
+
+            for flag in arg_flags(arg) {
+                candidates.push(format!("'{}:{}'", flag, help));
+            }
+        }
+
+        script.push_str(&format!(
+            "            candidates=({})\n            ;;\n",
+            candidates.join(" ")
+        ));
+    }
+
+    script.push_str(&format!(
+        "    esac
+
+    _describe '{bin_name}' candidates
+    _files
+}}
+
+{function} \"$@\"
+",
+        function = function,
+        bin_name = bin_name
+    ));
+
+    script
+}
+
+/// Generate a completion script for Fish
+pub fn fish(cmd: &Command, bin_name: &str) -> String {
+    let mut entries = vec![];
+    list_commands(cmd, bin_name.to_owned(), &[], &mut entries);
+
+    let function = format!("__{}_command", bin_name.replace('-', "_"));
+
+    let mut script = format!(
+        "function {function}
+    set -l cmd \"{bin_name}\"
+
+    for word in (commandline -opc)[2..-1]
+        switch \"$cmd\"__\"$word\"
+",
+        function = function,
+        bin_name = bin_name
+    );
+
+    for (from, to) in transitions(&entries) {
+        script.push_str(&format!(
+            "            case \"{}\"\n                set cmd \"{}\"\n",
+            from, to
+        ));
+    }
+
+    script.push_str(
+        "        end
+    end
+
+    echo $cmd
+end
+
+",
+    );
+
+    for entry in &entries {
+        let condition = format!("test ({}) = \"{}\"", function, entry.key);
+
+        for sub in visible_subcommands(entry.cmd) {
+            script.push_str(&format!(
+                "complete -c {} -n '{}' -f -a '{}' -d '{}'\n",
+                bin_name,
+                condition,
+                sub.get_name(),
+                escape_fish(&short_help(sub.get_about()))
+            ));
+        }
+
+        for arg in &entry.options {
+            let mut line = format!("complete -c {} -n '{}'", bin_name, condition);
+
+            if let Some(short) = arg.get_short() {
+                line.push_str(&format!(" -s {}", short));
+            }
+
+            if let Some(long) = arg.get_long() {
+                line.push_str(&format!(" -l {}", long));
+            }
+
+            if let Some(values) = arg_values(arg) {
+                line.push_str(&format!(" -x -a '{}'", values.join(" ")));
+            } else if arg.is_takes_value_set() {
+                line.push_str(" -r");
+            }
+
+            line.push_str(&format!(
+                " -d '{}'\n",
+                escape_fish(&short_help(arg.get_help()))
+            ));
+
+            script.push_str(&line);
+        }
+    }
+
+    script
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Name of the configuration file, in the user's configuration directory
+pub const CONFIG_FILENAME: &str = "config.json";
+
+/// User configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Named bundles of options, as provided on the command line (e.g. '--compress-webp')
+    #[serde(default)]
+    pub presets: BTreeMap<String, Vec<String>>,
+}
+
+impl Config {
+    /// Load the configuration file, or an empty configuration if it doesn't exist
+    pub fn load() -> Result<Self, ConfigErr> {
+        let path = config_path()?;
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).map_err(|err| ConfigErr::IOError(path.clone(), err))?;
+
+        serde_json::from_str(&content).map_err(|err| ConfigErr::InvalidConfig(path, err))
+    }
+
+    /// Save the configuration file, creating its directory if needed
+    pub fn save(&self) -> Result<PathBuf, ConfigErr> {
+        let path = config_path()?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| ConfigErr::IOError(parent.to_path_buf(), err))?;
+        }
+
+        let content = serde_json::to_string_pretty(self).map_err(|err| ConfigErr::InvalidConfig(path.clone(), err))?;
+
+        fs::write(&path, content).map_err(|err| ConfigErr::IOError(path.clone(), err))?;
+
+        Ok(path)
+    }
+
+    /// Get the options of a preset
+    pub fn preset(&self, name: &str) -> Result<&[String], ConfigErr> {
+        self.presets
+            .get(name)
+            .map(Vec::as_slice)
+            .ok_or_else(|| ConfigErr::PresetNotFound(name.to_owned()))
+    }
+}
+
+/// Get the path to the configuration file
+/// (in '$XDG_CONFIG_HOME/comic-enc', '~/.config/comic-enc' or '%APPDATA%\comic-enc')
+pub fn config_path() -> Result<PathBuf, ConfigErr> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .ok_or(ConfigErr::NoConfigDirectory)?;
+
+    Ok(dir.join("comic-enc").join(CONFIG_FILENAME))
+}
+
+/// Add the options of a preset to command-line arguments
+/// Options already present in the arguments are not added, so they take precedence over the preset's
+pub fn apply_preset(args: &mut Vec<OsString>, preset: &[String]) {
+    // Split the preset into options followed by their values
+    let mut options: Vec<Vec<&String>> = vec![];
+
+    for arg in preset {
+        match options.last_mut() {
+            Some(option) if !arg.starts_with('-') => option.push(arg),
+            _ => options.push(vec![arg]),
+        }
+    }
+
+    for option in options {
+        let name = option[0].split('=').next().unwrap_or_default();
+
+        let provided = args.iter().any(|arg| {
+            let arg = arg.to_string_lossy();
+            arg == name || arg.starts_with(&format!("{}=", name))
+        });
+
+        if !provided {
+            args.extend(option.into_iter().map(OsString::from));
+        }
+    }
+}
+
+/// Error while loading or saving the configuration file
+#[derive(Debug)]
+pub enum ConfigErr {
+    NoConfigDirectory,
+    IOError(PathBuf, io::Error),
+    InvalidConfig(PathBuf, serde_json::Error),
+    PresetNotFound(String),
+}
+
+impl fmt::Display for ConfigErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoConfigDirectory => write!(
+                f,
+                "Failed to find the configuration directory (neither 'XDG_CONFIG_HOME', 'HOME' nor 'APPDATA' is set)"
+            ),
+            Self::IOError(path, err) => write!(
+                f,
+                "Failed to access configuration file '{}': {}",
+                path.to_string_lossy(),
+                err
+            ),
+            Self::InvalidConfig(path, err) => write!(
+                f,
+                "Invalid configuration file '{}': {}",
+                path.to_string_lossy(),
+                err
+            ),
+            Self::PresetNotFound(name) => write!(
+                f,
+                "Preset '{}' was not found (use 'comic-enc preset list' to list the saved presets)",
+                name
+            ),
+        }
+    }
+}
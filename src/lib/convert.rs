@@ -0,0 +1,234 @@
+use crate::cli::opts::{ConversionOptions, PageFormat};
+use crate::lib::codec;
+use crate::lib::deter;
+use image::codecs::jpeg::JpegDecoder;
+use image::codecs::png::PngDecoder;
+use image::error::{EncodingError, ImageFormatHint, ParameterError, ParameterErrorKind};
+use image::imageops::FilterType;
+use image::io::Reader as ImageReader;
+use image::{
+    ColorType, DynamicImage, ImageBuffer, ImageDecoder, ImageError, ImageFormat, ImageResult,
+};
+use std::convert::TryFrom;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use zip::CompressionMethod;
+
+/// Get the compression method to store the pages with
+pub fn compression_method(opts: &ConversionOptions) -> CompressionMethod {
+    if opts.compress_losslessly {
+        CompressionMethod::Deflated
+    } else {
+        CompressionMethod::Stored
+    }
+}
+
+/// Check if a page needs to be converted before being stored
+pub fn needs_conversion(opts: &ConversionOptions, path: &Path) -> bool {
+    match opts.target_format() {
+        // Pages can't be left as they are if the format's codec isn't available, so they fail to be converted
+        Some(format) => codec::for_format(format).map_or(true, |codec| !codec.handles(path)),
+        None => false,
+    }
+}
+
+/// Estimate the size of a page once stored in a volume, from the size of its file
+/// Converted pages are assumed to shrink less than they usually do, so the estimate errs on the safe side
+pub fn estimated_page_size(opts: &ConversionOptions, path: &Path, size: u64) -> u64 {
+    if !needs_conversion(opts, path) {
+        return size;
+    }
+
+    match opts.target_format() {
+        Some(PageFormat::Webp) | Some(PageFormat::Avif) => size / 4 * 3,
+        Some(PageFormat::Jpeg) | None => size,
+        Some(PageFormat::Png) => size * 2,
+    }
+}
+
+/// Get the extension a page will have once converted
+/// Returns `None` if the page keeps its original extension
+pub fn converted_ext(opts: &ConversionOptions) -> Option<&'static str> {
+    opts.target_format()
+        .and_then(codec::for_format)
+        .map(|codec| codec.extensions()[0])
+}
+
+/// Convert a page according to the provided options
+pub fn convert_page(opts: &ConversionOptions, buffer: &[u8]) -> Result<Vec<u8>, ImageError> {
+    let format = match opts.target_format() {
+        Some(format) => format,
+        None => return Ok(buffer.to_vec()),
+    };
+
+    let codec = codec::require(format)?;
+
+    let im = match opts.max_dimension {
+        Some(max_dimension) => load_downscaled(buffer, max_dimension)?,
+        None => codec::decode(buffer)?,
+    };
+
+    if let Some(codec_max_dimension) = codec.max_dimension() {
+        if im.width() > codec_max_dimension || im.height() > codec_max_dimension {
+            return Err(ImageError::Encoding(EncodingError::new(
+                ImageFormatHint::Exact(codec.format()),
+                format!(
+                    "pictures can't be larger than {} pixels, use '--max-dimension {}' to downscale larger pages",
+                    codec_max_dimension, codec_max_dimension
+                ),
+            )));
+        }
+    }
+
+    codec.encode(im, opts.quality.unwrap_or_else(|| codec.default_quality()))
+}
+
+/// Get the factor a picture is divided by to fit in the maximum dimension
+fn downscale_factor(max_dimension: u32, width: u32, height: u32) -> u32 {
+    deter::ceil_div(width.max(height), max_dimension.max(1))
+}
+
+/// Get the dimensions a page is decoded at when converted, from its original dimensions
+pub fn decoded_dimensions(opts: &ConversionOptions, width: u32, height: u32) -> (u32, u32) {
+    match opts.max_dimension {
+        Some(max_dimension) => {
+            let factor = downscale_factor(max_dimension, width, height).max(1);
+            (
+                deter::ceil_div(width, factor),
+                deter::ceil_div(height, factor),
+            )
+        }
+        None => (width, height),
+    }
+}
+
+/// Load a picture, downscaling it if it doesn't fit in the maximum dimension
+/// PNG pictures are downscaled while being decoded and JPEG ones are decoded at a reduced scale,
+/// so their full-size bitmap is never loaded in memory
+fn load_downscaled(buffer: &[u8], max_dimension: u32) -> ImageResult<DynamicImage> {
+    let (width, height) = ImageReader::new(Cursor::new(buffer))
+        .with_guessed_format()
+        .map_err(ImageError::IoError)?
+        .into_dimensions()?;
+
+    let factor = downscale_factor(max_dimension, width, height);
+
+    if factor <= 1 {
+        return codec::decode(buffer);
+    }
+
+    let (target_width, target_height) = (
+        deter::ceil_div(width, factor),
+        deter::ceil_div(height, factor),
+    );
+
+    trace!(
+        "Downscaling {}x{} picture to {}x{}...",
+        width,
+        height,
+        target_width,
+        target_height
+    );
+
+    let im = match image::guess_format(buffer)? {
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(Cursor::new(buffer))?;
+
+            match decoder.color_type() {
+                ColorType::L8 | ColorType::La8 | ColorType::Rgb8 | ColorType::Rgba8 => {
+                    downscale_while_decoding(decoder, factor)?
+                }
+
+                // 16-bit pictures are rare enough to be decoded at full size
+                _ => image::load_from_memory(buffer)?,
+            }
+        }
+
+        ImageFormat::Jpeg => {
+            let mut decoder = JpegDecoder::new(Cursor::new(buffer))?;
+
+            // JPEG dimensions always fit in 16 bits
+            decoder.scale(
+                u16::try_from(target_width).unwrap_or(u16::MAX),
+                u16::try_from(target_height).unwrap_or(u16::MAX),
+            )?;
+
+            DynamicImage::from_decoder(decoder)?
+        }
+
+        _ => codec::decode(buffer)?,
+    };
+
+    // Pictures decoded at a reduced scale may still be a bit too large
+    if im.width() > target_width || im.height() > target_height {
+        Ok(im.resize(target_width, target_height, FilterType::Triangle))
+    } else {
+        Ok(im)
+    }
+}
+
+/// Decode an 8-bit picture row by row, averaging each block of `factor`x`factor` pixels
+fn downscale_while_decoding<'a>(
+    decoder: impl ImageDecoder<'a>,
+    factor: u32,
+) -> ImageResult<DynamicImage> {
+    let (width, height) = decoder.dimensions();
+    let color = decoder.color_type();
+
+    let out_width = deter::ceil_div(width, factor);
+    let out_height = deter::ceil_div(height, factor);
+
+    let (width, height, factor) = (width as usize, height as usize, factor as usize);
+    let channels = usize::from(color.channel_count());
+
+    let mut reader = decoder.into_reader()?;
+    let mut row = vec![0; width * channels];
+    let mut sums = vec![0u64; out_width as usize * channels];
+    let mut pixels = Vec::with_capacity(out_width as usize * out_height as usize * channels);
+
+    for out_y in 0..out_height as usize {
+        let rows = factor.min(height - out_y * factor);
+
+        sums.iter_mut().for_each(|sum| *sum = 0);
+
+        for _ in 0..rows {
+            reader.read_exact(&mut row).map_err(ImageError::IoError)?;
+
+            for (x, pixel) in row.chunks_exact(channels).enumerate() {
+                let out = x / factor * channels;
+
+                for (channel, &value) in pixel.iter().enumerate() {
+                    sums[out + channel] += u64::from(value);
+                }
+            }
+        }
+
+        for (out_x, block) in sums.chunks_exact(channels).enumerate() {
+            let count = (factor.min(width - out_x * factor) * rows) as u64;
+
+            pixels.extend(block.iter().map(|sum| ((sum + count / 2) / count) as u8));
+        }
+    }
+
+    let im = match color {
+        ColorType::L8 => {
+            ImageBuffer::from_raw(out_width, out_height, pixels).map(DynamicImage::ImageLuma8)
+        }
+        ColorType::La8 => {
+            ImageBuffer::from_raw(out_width, out_height, pixels).map(DynamicImage::ImageLumaA8)
+        }
+        ColorType::Rgb8 => {
+            ImageBuffer::from_raw(out_width, out_height, pixels).map(DynamicImage::ImageRgb8)
+        }
+        ColorType::Rgba8 => {
+            ImageBuffer::from_raw(out_width, out_height, pixels).map(DynamicImage::ImageRgba8)
+        }
+        _ => None,
+    };
+
+    im.ok_or_else(|| {
+        ImageError::Parameter(ParameterError::from_kind(
+            ParameterErrorKind::DimensionMismatch,
+        ))
+    })
+}
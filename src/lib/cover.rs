@@ -0,0 +1,111 @@
+use crate::cli::opts::{PageFormat, ThumbnailFormat};
+use crate::lib::archive::{ArchiveEntry, ArchiveErr, ComicArchive};
+use crate::lib::codec;
+use crate::lib::metadata::{self, MetadataReadErr};
+use image::{DynamicImage, ImageError, ImageOutputFormat};
+use std::fmt;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Page type designating a comic's cover in the ComicInfo format
+pub const FRONT_COVER_PAGE_TYPE: &str = "FrontCover";
+
+/// Quality of the JPEG thumbnails
+pub const THUMBNAIL_JPEG_QUALITY: u8 = 80;
+
+/// Cover page of a comic archive
+pub struct Cover {
+    /// Entry of the cover page in the archive
+    pub entry: ArchiveEntry,
+
+    /// Content of the cover page
+    pub content: Vec<u8>,
+}
+
+/// Read the cover page of a comic archive
+/// The page designated as front cover in the archive's metadata is used, or the first page otherwise
+pub fn read_cover(
+    path: &Path,
+    extended_formats: bool,
+    simple_sorting: bool,
+) -> Result<Cover, CoverErr> {
+    let mut archive = ComicArchive::open(path).map_err(CoverErr::ArchiveError)?;
+
+    let pages = archive
+        .pages(extended_formats, simple_sorting)
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if pages.is_empty() {
+        return Err(CoverErr::NoPage);
+    }
+
+    let metadata =
+        metadata::read_archive_metadata(&mut archive).map_err(CoverErr::MetadataError)?;
+
+    let cover_index = metadata
+        .comic_info
+        .iter()
+        .flat_map(|info| info.pages.iter())
+        .find(|page| page.kind.as_deref() == Some(FRONT_COVER_PAGE_TYPE))
+        .map(|page| page.image)
+        .filter(|&index| index < pages.len())
+        .unwrap_or(0);
+
+    let entry = pages[cover_index].clone();
+
+    trace!("Using page '{}' as cover...", entry.path.to_string_lossy());
+
+    let content = archive
+        .read_entry(entry.index)
+        .map_err(CoverErr::ArchiveError)?;
+
+    Ok(Cover { entry, content })
+}
+
+/// Generate a thumbnail from a cover, fitting in the provided dimensions while keeping its aspect ratio
+pub fn make_thumbnail(
+    cover: &Cover,
+    max_width: u32,
+    max_height: u32,
+    format: ThumbnailFormat,
+) -> Result<Vec<u8>, ImageError> {
+    let thumbnail = image::load_from_memory(&cover.content)?.thumbnail(max_width, max_height);
+
+    match format {
+        ThumbnailFormat::Jpeg => {
+            let mut buffer = vec![];
+
+            // JPEG doesn't support transparency
+            DynamicImage::ImageRgb8(thumbnail.into_rgb8()).write_to(
+                &mut Cursor::new(&mut buffer),
+                ImageOutputFormat::Jpeg(THUMBNAIL_JPEG_QUALITY),
+            )?;
+
+            Ok(buffer)
+        }
+
+        ThumbnailFormat::Webp => {
+            let codec = codec::require(PageFormat::Webp)?;
+            codec.encode(thumbnail, codec.default_quality())
+        }
+    }
+}
+
+/// Cover reading error
+pub enum CoverErr {
+    ArchiveError(ArchiveErr),
+    MetadataError(MetadataReadErr),
+    NoPage,
+}
+
+impl fmt::Display for CoverErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ArchiveError(err) => write!(f, "{}", err),
+            Self::MetadataError(err) => write!(f, "Failed to read metadata: {}", err),
+            Self::NoPage => write!(f, "Archive does not contain any page"),
+        }
+    }
+}
@@ -1,10 +1,14 @@
+use glob::Pattern;
 use std::cmp::{Ordering, PartialEq};
+use std::error::Error;
+use std::fmt;
 use std::fs;
 use std::io;
 use std::iter::Peekable;
 use std::ops::{Add, Div, Rem};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::str::Chars;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Perform a ceiling division of the provided number by the divider
 ///
@@ -30,6 +34,109 @@ pub fn ceil_div<
         }
 }
 
+/// Format a size in bytes to a human-readable string
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(format_size(512), "512 B");
+/// assert_eq!(format_size(1536), "1.50 KiB");
+/// assert_eq!(format_size(3 * 1024 * 1024), "3.00 MiB");
+/// ```
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.2} {}", size, UNITS[unit])
+}
+
+/// Format a point in time as a date and time in UTC, with the provided separator between them
+/// and optionally the milliseconds
+///
+/// # Examples
+///
+/// ```
+/// let time = UNIX_EPOCH + Duration::from_millis(1615734566535);
+/// assert_eq!(format_utc(time, ' ', true), "2021-03-14 15:09:26.535");
+/// assert_eq!(format_utc(time, 'T', false), "2021-03-14T15:09:26");
+/// ```
+pub fn format_utc(time: SystemTime, separator: char, millis: bool) -> String {
+    let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    let secs = elapsed.as_secs();
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+
+    // Convert the number of days since 1970-01-01 to a civil date
+    let days = days as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    let formatted = format!(
+        "{:04}-{:02}-{:02}{}{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        separator,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    );
+
+    if millis {
+        format!("{}.{:03}", formatted, elapsed.subsec_millis())
+    } else {
+        formatted
+    }
+}
+
+/// Get the point in time of a date and time in UTC (the reverse of `format_utc`)
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(utc_time(2021, 3, 14, 15, 9, 26), UNIX_EPOCH + Duration::from_secs(1615734566));
+/// ```
+pub fn utc_time(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> SystemTime {
+    let (month, day) = (i64::from(month), i64::from(day));
+
+    // Convert the civil date to a number of days since 1970-01-01
+    let year = i64::from(year) - if month <= 2 { 1 } else { 0 };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let secs = days * 86400 + i64::from(hour) * 3600 + i64::from(minute) * 60 + i64::from(second);
+
+    UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
+}
+
+/// Check if a directory is selected by inclusion and exclusion patterns
+/// Directories are selected if they match any inclusion pattern (or if there isn't any), and no exclusion pattern
+pub fn matches_patterns(name: &str, include: &[Pattern], exclude: &[Pattern]) -> bool {
+    (include.is_empty() || include.iter().any(|pattern| pattern.matches(name)))
+        && !exclude.iter().any(|pattern| pattern.matches(name))
+}
+
 /// Check if a path has a common image format extension
 /// Additional formats that may not be widely supported can be accepted using the `extended` parameter
 ///
@@ -38,6 +145,7 @@ pub fn ceil_div<
 /// ```
 /// assert_eq!(has_image_ext(Path::new("file.png"), false), true);
 /// assert_eq!(has_image_ext(Path::new("file.Jpeg"), false), true);
+/// assert_eq!(has_image_ext(Path::new("file.webp"), false), true);
 /// assert_eq!(has_image_ext(Path::new("file.bgp"), false), false);
 ///
 /// // With extended image formats
@@ -49,11 +157,11 @@ pub fn has_image_ext(path: impl AsRef<Path>, extended: bool) -> bool {
         Some(ext) => match ext.to_str() {
             None => false,
             Some(ext) => match ext.to_lowercase().as_str() {
-                "jpg" | "jpeg" | "png" | "bmp" => true,
+                "jpg" | "jpeg" | "png" | "bmp" | "webp" => true,
 
                 "tif" | "tiff" | "gif" | "eps" | "raw" | "cr2" | "nef" | "orf" | "sr2" | "ppm"
-                | "webp" | "pgm" | "pbm" | "pnm" | "ico" | "flif" | "pam" | "pcx" | "pgf"
-                | "sgi" | "sid" | "bgp" => extended,
+                | "pgm" | "pbm" | "pnm" | "ico" | "flif" | "pam" | "pcx" | "pgf" | "sgi"
+                | "sid" | "bgp" => extended,
 
                 _ => false,
             },
@@ -61,6 +169,26 @@ pub fn has_image_ext(path: impl AsRef<Path>, extended: bool) -> bool {
     }
 }
 
+/// Check if a path's file name is valid UTF-8
+pub fn has_utf8_name(path: &Path) -> bool {
+    path.file_name().map_or(true, |name| name.to_str().is_some())
+}
+
+/// Names of the files and directories operating systems create on their own
+const OS_JUNK_NAMES: &[&str] = &[".DS_Store", "Thumbs.db", "desktop.ini", "__MACOSX"];
+
+/// Check if a file or directory was created by the operating system (e.g. '.DS_Store' or '__MACOSX') or is hidden
+pub fn is_os_junk(path: &Path) -> bool {
+    path.file_name().map_or(false, |name| {
+        let name = name.to_string_lossy();
+
+        name.starts_with('.')
+            || OS_JUNK_NAMES
+                .iter()
+                .any(|junk| junk.eq_ignore_ascii_case(&name))
+    })
+}
+
 /// Check if a comic format is supported for decoding
 ///
 /// # Examples
@@ -68,15 +196,16 @@ pub fn has_image_ext(path: impl AsRef<Path>, extended: bool) -> bool {
 /// ```
 /// assert_eq!(is_supported_for_decoding("zip"), true);
 /// assert_eq!(is_supported_for_decoding("PdF"), true);
+/// assert_eq!(is_supported_for_decoding("cbt"), true);
 /// assert_eq!(is_supported_for_decoding("mp3"), false);
 /// ```
 pub fn is_supported_for_decoding(ext: &str) -> bool {
     match ext.to_lowercase().as_str() {
         // Common archive formats
-        "zip" => true,
+        "zip" | "tar" | "rar" | "7z" => true,
 
         // Common archive formats with comic-related extension
-        "cbz" => true,
+        "cbz" | "cbt" | "cbr" | "cb7" => true,
 
         // Non-archive formats
         "pdf" => true,
@@ -86,6 +215,30 @@ pub fn is_supported_for_decoding(ext: &str) -> bool {
     }
 }
 
+/// Guess the format of an archive from its first bytes, as the extension it would usually have
+/// Used when the archive has no file name to get the extension from (e.g. when reading from the standard input)
+///
+/// ```
+/// assert_eq!(detect_decodable_format(b"PK\x03\x04..."), Some("zip"));
+/// assert_eq!(detect_decodable_format(b"%PDF-1.7..."), Some("pdf"));
+/// assert_eq!(detect_decodable_format(b"ID3..."), None);
+/// ```
+pub fn detect_decodable_format(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06") {
+        Some("zip")
+    } else if data.get(257..262) == Some(b"ustar") {
+        Some("tar")
+    } else if data.starts_with(b"Rar!\x1a\x07") {
+        Some("rar")
+    } else if data.starts_with(b"7z\xbc\xaf\x27\x1c") {
+        Some("7z")
+    } else if data.starts_with(b"%PDF") {
+        Some("pdf")
+    } else {
+        None
+    }
+}
+
 /// Get the largest possible number from the first characters of the provided characters iterator
 /// The iterator *will* advance up to the first non-digit character
 /// Only integers are supported, but there is no size limit
@@ -97,7 +250,7 @@ fn take_num(chars: &mut Peekable<Chars>) -> Vec<u8> {
         match chars.peek() {
             Some(c) if c.is_ascii_digit() => {
                 let code = u32::from(chars.next().unwrap());
-                assert!(code >= 0x30 && code <= 0x39);
+                assert!((0x30..=0x39).contains(&code));
 
                 let num = code as u8 - 0x30;
 
@@ -113,8 +266,46 @@ fn take_num(chars: &mut Peekable<Chars>) -> Vec<u8> {
     digits
 }
 
-/// Compare two strings using natural order, which is equivalent to traditional UTF-8 sorting \
+/// Part of a natural sort key
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NaturalToken {
+    /// A whole number, with its first character (which is compared against non-digit characters) and its digits
+    /// without leading zeros
+    Num(char, Vec<u8>),
+    /// Any other character
+    Char(char),
+}
+
+impl NaturalToken {
+    /// Get the first character the token was made from
+    fn first_char(&self) -> char {
+        match self {
+            Self::Num(c, _) | Self::Char(c) => *c,
+        }
+    }
+}
+
+impl Ord for NaturalToken {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Num(_, left), Self::Num(_, right)) => {
+                left.len().cmp(&right.len()).then_with(|| left.cmp(right))
+            }
+            _ => self.first_char().cmp(&other.first_char()),
+        }
+    }
+}
+
+impl PartialOrd for NaturalToken {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Key to sort strings using natural order, which is equivalent to traditional UTF-8 sorting \
 /// but compares whole numbers instead of single digits
+/// The string is only parsed once, so sorting many strings with `sort_by_cached_key` is much faster than comparing
+/// them directly
 ///
 /// # Examples
 ///
@@ -126,60 +317,37 @@ fn take_num(chars: &mut Peekable<Chars>) -> Vec<u8> {
 /// println!("{:?}", directories); // ["Folder 1", "Folder 100", "Folder 20"]
 ///
 /// // Natural sort
-/// directories.sort_by(natural_cmp);
+/// directories.sort_by_cached_key(|dir| NaturalKey::new(dir));
 /// println!("{:?}", directories); // ["Folder 1", "Folder 20", "Folder 100"]
 /// ```
 ///
-pub fn natural_cmp(left: &str, right: &str) -> Ordering {
-    let left = left.to_lowercase();
-    let right = right.to_lowercase();
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NaturalKey(Vec<NaturalToken>);
 
-    let mut left = left.chars().peekable();
-    let mut right = right.chars().peekable();
+impl NaturalKey {
+    /// Compute the key of a string
+    pub fn new(text: &str) -> Self {
+        let text = text.to_lowercase();
+        let mut chars = text.chars().peekable();
+        let mut tokens = vec![];
 
-    loop {
-        return match (left.peek().copied(), right.peek().copied()) {
-            (Some(lc), Some(rc)) => {
-                if lc.is_ascii_digit() && rc.is_ascii_digit() {
-                    let lnum = take_num(&mut left);
-                    let rnum = take_num(&mut right);
-
-                    let cmp = lnum.len().cmp(&rnum.len());
-
-                    if cmp != Ordering::Equal {
-                        return cmp;
-                    }
-
-                    for (ldig, rdig) in lnum.iter().zip(rnum.iter()) {
-                        let cmp = ldig.cmp(rdig);
-
-                        if cmp != Ordering::Equal {
-                            return cmp;
-                        }
-                    }
-
-                    Ordering::Equal
-                } else {
-                    left.next().unwrap();
-                    right.next().unwrap();
-
-                    match lc.cmp(&rc) {
-                        Ordering::Equal => continue,
-                        ordering => ordering,
-                    }
-                }
+        while let Some(c) = chars.peek().copied() {
+            if c.is_ascii_digit() {
+                tokens.push(NaturalToken::Num(c, take_num(&mut chars)));
+            } else {
+                chars.next().unwrap();
+                tokens.push(NaturalToken::Char(c));
             }
-            (Some(_), None) => Ordering::Greater,
-            (None, Some(_)) => Ordering::Less,
-            (None, None) => Ordering::Equal,
-        };
+        }
+
+        Self(tokens)
     }
 }
 
 /// Read a directory's files, recursively
 /// Files list comes in the provided fs::read_dir() order, which means there is no guarantee it is sorted in any way
 /// Absolute paths to the files is returned as a vector
-pub fn readdir_files_recursive<F: Fn(&PathBuf) -> bool>(
+pub fn readdir_files_recursive<F: Fn(&Path) -> bool>(
     dir: impl AsRef<Path>,
     filter: Option<&F>,
 ) -> Result<Vec<PathBuf>, RecursiveFilesSearchErr> {
@@ -202,30 +370,182 @@ pub fn readdir_files_recursive<F: Fn(&PathBuf) -> bool>(
     Ok(files)
 }
 
-/// Compare two paths using natural order
-/// See the "natural_cmp" function for more informations
-pub fn natural_paths_cmp(a: &PathBuf, b: &PathBuf) -> Ordering {
-    let mut a = a.components();
-    let mut b = b.components();
+/// Same as `readdir_files_recursive`, without the files and directories for which `skip` returns `true`
+/// Skipped items are ignored before their name is checked, so they are never considered as invalid
+pub fn readdir_files_recursive_skipping<S: Fn(&Path) -> bool>(
+    dir: impl AsRef<Path>,
+    skip: &S,
+) -> Result<Vec<PathBuf>, RecursiveFilesSearchErr> {
+    let mut files = vec![];
+
+    for entry in fs::read_dir(dir.as_ref()).map_err(RecursiveFilesSearchErr::IOError)? {
+        let path = entry.map_err(RecursiveFilesSearchErr::IOError)?.path();
+
+        if skip(&path) {
+            continue;
+        }
+
+        if !path.exists() {
+            return Err(RecursiveFilesSearchErr::InvalidFileName(path));
+        }
+
+        if path.is_dir() {
+            files.extend_from_slice(&readdir_files_recursive_skipping(&path, skip)?);
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Names Windows reserves for devices, whatever their extension
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2",
+    "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Length from which Windows paths must be written in their extended form
+const WINDOWS_MAX_PATH: usize = 260;
+
+/// Make a generated file name valid on all platforms, so it can be written or extracted on Windows too
+/// Forbidden characters are replaced by '_', trailing dots and spaces are removed and reserved names get a '_' suffix
+pub fn sanitize_filename(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    sanitized.truncate(sanitized.trim_end_matches(|c| c == '.' || c == ' ').len());
+
+    if sanitized.is_empty() {
+        return "_".to_owned();
+    }
+
+    let stem_len = sanitized.find('.').unwrap_or_else(|| sanitized.len());
+
+    if WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(sanitized[..stem_len].trim_end()))
+    {
+        sanitized.insert(stem_len, '_');
+    }
+
+    sanitized
+}
+
+/// Make each component of a path inside an archive (separated by '/') valid on all platforms
+pub fn sanitize_entry_name(name: &str) -> String {
+    name.split('/').map(sanitize_filename).collect::<Vec<_>>().join("/")
+}
+
+/// Add an extension to a path, keeping all of its file name
+/// Unlike `Path::with_extension`, nothing after the last dot is replaced (e.g. 'Series Vol. 01' gives 'Series Vol. 01.cbz'
+/// instead of 'Series Vol.cbz')
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(append_ext(Path::new("Dr. Stone"), "cbz"), PathBuf::from("Dr. Stone.cbz"));
+/// assert_eq!(append_ext(Path::new("Volume-1 (c10-c10.5)"), "cbz"), PathBuf::from("Volume-1 (c10-c10.5).cbz"));
+/// ```
+pub fn append_ext(path: &Path, ext: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_os_string();
+    path.push(".");
+    path.push(ext);
+    PathBuf::from(path)
+}
+
+/// Get the form of a path that can be opened even if it is longer than Windows' usual limit
+/// Long absolute paths get the '\\?\' prefix on Windows, other paths are returned as they are
+pub fn long_path(path: &Path) -> PathBuf {
+    if !cfg!(windows) || path.as_os_str().len() < WINDOWS_MAX_PATH || !path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    // Extended paths are not normalized by Windows, so they can't contain relative components
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Prefix(prefix) if prefix.kind().is_verbatim() => return path.to_path_buf(),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    match normalized.to_str() {
+        Some(normalized) => match normalized.strip_prefix(r"\\") {
+            Some(unc) => PathBuf::from(format!(r"\\?\UNC\{}", unc)),
+            None => PathBuf::from(format!(r"\\?\{}", normalized)),
+        },
+        None => path.to_path_buf(),
+    }
+}
+
+/// Resolve a path's symbolic links and relative components, even if it doesn't exist yet
+/// Its longest existing ancestor is resolved and the remaining components are appended to it
+pub fn resolve_path(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut missing = vec![];
 
     loop {
-        return match (a.next(), b.next()) {
-            (Some(a_cp), Some(b_cp)) => match natural_cmp(
-                &a_cp.as_os_str().to_string_lossy(),
-                &b_cp.as_os_str().to_string_lossy(),
-            ) {
-                Ordering::Equal => continue,
-                ordering => ordering,
-            },
-            (Some(_), None) => Ordering::Greater,
-            (None, Some(_)) => Ordering::Less,
-            (None, None) => Ordering::Equal,
-        };
+        if let Ok(resolved) = fs::canonicalize(existing) {
+            return missing.iter().rev().fold(resolved, |resolved, component| resolved.join(component));
+        }
+
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+
+            _ => return path.to_path_buf(),
+        }
     }
 }
 
+/// Check if a path is the same as a directory or is inside it, once both are resolved
+pub fn is_inside(path: &Path, dir: &Path) -> bool {
+    resolve_path(path).starts_with(resolve_path(dir))
+}
+
+/// Key to sort paths using natural order, component by component
+/// See `NaturalKey` for more informations
+pub fn natural_path_key(path: &Path) -> Vec<NaturalKey> {
+    path.components()
+        .map(|component| NaturalKey::new(&component.as_os_str().to_string_lossy()))
+        .collect()
+}
+
+/// Sort items by path using natural order, computing each path's key only once
+pub fn natural_sort_by_path<T>(items: &mut [T], path: impl Fn(&T) -> &Path) {
+    items.sort_by_cached_key(|item| natural_path_key(path(item)));
+}
+
 /// Recursive files search error
+#[derive(Debug)]
 pub enum RecursiveFilesSearchErr {
     IOError(io::Error),
     InvalidFileName(PathBuf),
 }
+
+impl fmt::Display for RecursiveFilesSearchErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidFileName(path) => {
+                write!(f, "Found item with invalid name: {}", path.to_string_lossy())
+            }
+        }
+    }
+}
+
+impl Error for RecursiveFilesSearchErr {}
@@ -0,0 +1,293 @@
+use crate::lib::archive::{ArchiveErr, ComicArchive};
+use image::imageops::FilterType;
+use image::ImageError;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of differing bits between the perceptual hashes of two pages for them to be considered similar
+pub const SIMILARITY_THRESHOLD: u32 = 10;
+
+/// Fingerprint of a comic archive's page
+#[derive(Debug, Clone, Serialize)]
+pub struct PageFingerprint {
+    /// Path of the page in the archive
+    pub path: PathBuf,
+
+    /// Size of the page, in bytes
+    pub size: u64,
+
+    /// SHA-256 hash of the page's content
+    pub sha256: String,
+
+    /// Perceptual hash of the page (difference hash), if computed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub perceptual_hash: Option<u64>,
+}
+
+impl PageFingerprint {
+    /// Check if two pages have the same content, or look alike if perceptual hashes are available
+    fn matches(&self, other: &Self) -> bool {
+        self.sha256 == other.sha256 || self.similarity(other).is_some()
+    }
+
+    /// Get the perceptual similarity between two pages (from 0 to 1), if they look alike
+    fn similarity(&self, other: &Self) -> Option<f64> {
+        let distance = (self.perceptual_hash? ^ other.perceptual_hash?).count_ones();
+
+        if distance <= SIMILARITY_THRESHOLD {
+            Some(1.0 - f64::from(distance) / 64.0)
+        } else {
+            None
+        }
+    }
+}
+
+/// Difference between two comic archives' pages
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PageDiff {
+    /// Page has the same content in both archives, but a different name
+    Renamed {
+        index: usize,
+        from: PathBuf,
+        to: PathBuf,
+    },
+
+    /// Page's content differs but looks alike in both archives (e.g. after a re-encoding)
+    Similar {
+        index: usize,
+        from: PathBuf,
+        to: PathBuf,
+        similarity: f64,
+    },
+
+    /// Page's content differs between the two archives
+    Changed {
+        index: usize,
+        from: PathBuf,
+        to: PathBuf,
+        size_before: u64,
+        size_after: u64,
+    },
+
+    /// Page only exists in the second archive
+    Added { index: usize, path: PathBuf },
+
+    /// Page only exists in the first archive
+    Removed { index: usize, path: PathBuf },
+}
+
+impl PageDiff {
+    /// Check if the difference means the two archives don't hold the same pages
+    pub fn is_significant(&self) -> bool {
+        !matches!(self, Self::Renamed { .. } | Self::Similar { .. })
+    }
+}
+
+/// Comparison of two comic archives
+#[derive(Debug, Serialize)]
+pub struct DiffReport {
+    /// Path to the first archive
+    pub before: PathBuf,
+
+    /// Path to the second archive
+    pub after: PathBuf,
+
+    /// Number of pages in the first archive
+    pub pages_before: usize,
+
+    /// Number of pages in the second archive
+    pub pages_after: usize,
+
+    /// Do both archives hold the same pages (renamed or similar pages aside)
+    pub identical: bool,
+
+    /// Differences between the archives' pages
+    pub differences: Vec<PageDiff>,
+}
+
+/// Compute the fingerprint of each page of a comic archive, in reading order
+pub fn fingerprint_archive(
+    path: &Path,
+    extended_formats: bool,
+    simple_sorting: bool,
+    perceptual: bool,
+) -> Result<Vec<PageFingerprint>, DiffErr> {
+    let mut archive = ComicArchive::open(path).map_err(DiffErr::ArchiveError)?;
+
+    let pages = archive
+        .pages(extended_formats, simple_sorting)
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut fingerprints = vec![];
+
+    for page in pages {
+        trace!("Fingerprinting page '{}'...", page.path.to_string_lossy());
+
+        let content = archive
+            .read_entry(page.index)
+            .map_err(DiffErr::ArchiveError)?;
+
+        let perceptual_hash = if perceptual {
+            Some(
+                perceptual_hash(&content)
+                    .map_err(|err| DiffErr::FailedToDecodePage(page.path.clone(), err))?,
+            )
+        } else {
+            None
+        };
+
+        fingerprints.push(PageFingerprint {
+            path: page.path,
+            size: content.len() as u64,
+            sha256: format!("{:x}", Sha256::digest(&content)),
+            perceptual_hash,
+        });
+    }
+
+    Ok(fingerprints)
+}
+
+/// Compute the difference hash of a picture, which is close for pictures that look alike
+fn perceptual_hash(content: &[u8]) -> Result<u64, ImageError> {
+    let thumbnail = image::load_from_memory(content)?
+        .resize_exact(9, 8, FilterType::Triangle)
+        .into_luma8();
+
+    let mut hash = 0;
+
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+
+            if thumbnail.get_pixel(x, y)[0] < thumbnail.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+
+    Ok(hash)
+}
+
+/// Compare the pages of two comic archives
+/// Pages are aligned on their content first, so a single added or removed page doesn't shift the whole comparison
+pub fn diff_pages(before: &[PageFingerprint], after: &[PageFingerprint]) -> Vec<PageDiff> {
+    // Longest common subsequence of matching pages
+    let mut lcs = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lcs[i][j] = if before[i].matches(&after[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diffs = vec![];
+
+    // Pages of the current unmatched run, which are paired as changed pages
+    let mut removed = vec![];
+    let mut added = vec![];
+
+    let (mut i, mut j) = (0, 0);
+
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && before[i].matches(&after[j]) {
+            flush_unmatched(before, after, &mut removed, &mut added, &mut diffs);
+
+            let (from, to) = (&before[i], &after[j]);
+
+            if from.sha256 != to.sha256 {
+                diffs.push(PageDiff::Similar {
+                    index: j,
+                    from: from.path.clone(),
+                    to: to.path.clone(),
+                    similarity: from.similarity(to).unwrap_or(0.0),
+                });
+            } else if from.path != to.path {
+                diffs.push(PageDiff::Renamed {
+                    index: j,
+                    from: from.path.clone(),
+                    to: to.path.clone(),
+                });
+            }
+
+            i += 1;
+            j += 1;
+        } else if j == after.len() || (i < before.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            removed.push(i);
+            i += 1;
+        } else {
+            added.push(j);
+            j += 1;
+        }
+    }
+
+    flush_unmatched(before, after, &mut removed, &mut added, &mut diffs);
+
+    diffs
+}
+
+/// Turn a run of unmatched pages into differences, pairing removed and added pages as changed pages
+fn flush_unmatched(
+    before: &[PageFingerprint],
+    after: &[PageFingerprint],
+    removed: &mut Vec<usize>,
+    added: &mut Vec<usize>,
+    diffs: &mut Vec<PageDiff>,
+) {
+    let paired = removed.len().min(added.len());
+
+    for (&i, &j) in removed.iter().zip(added.iter()) {
+        diffs.push(PageDiff::Changed {
+            index: j,
+            from: before[i].path.clone(),
+            to: after[j].path.clone(),
+            size_before: before[i].size,
+            size_after: after[j].size,
+        });
+    }
+
+    for &i in &removed[paired..] {
+        diffs.push(PageDiff::Removed {
+            index: i,
+            path: before[i].path.clone(),
+        });
+    }
+
+    for &j in &added[paired..] {
+        diffs.push(PageDiff::Added {
+            index: j,
+            path: after[j].path.clone(),
+        });
+    }
+
+    removed.clear();
+    added.clear();
+}
+
+/// Comic archives comparison error
+pub enum DiffErr {
+    ArchiveError(ArchiveErr),
+    FailedToDecodePage(PathBuf, ImageError),
+}
+
+impl fmt::Display for DiffErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ArchiveError(err) => write!(f, "{}", err),
+            Self::FailedToDecodePage(path, err) => write!(
+                f,
+                "Failed to decode page '{}': {}",
+                path.to_string_lossy(),
+                err
+            ),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Name of the file written to check if a directory is writable
+const WRITE_CHECK_FILENAME: &str = ".comic-enc-write-check";
+
+/// Find an executable in the directories of the 'PATH' environment variable
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    let names = if cfg!(windows) {
+        vec![format!("{}.exe", name), name.to_owned()]
+    } else {
+        vec![name.to_owned()]
+    };
+
+    env::split_paths(&env::var_os("PATH")?)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+/// Check if files can be written in a directory, by writing and removing a small one
+pub fn check_writable(dir: &Path) -> io::Result<()> {
+    let path = dir.join(WRITE_CHECK_FILENAME);
+    fs::write(&path, b"comic-enc")?;
+    fs::remove_file(&path)
+}
+
+/// Get the space available to the current user on the filesystem containing a path, in bytes
+/// Relies on the POSIX 'df' utility, so it is only supported on Unix-like platforms
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    let output = Command::new("df").arg("-Pk").arg(path).output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+
+    // Output is a header line followed by: filesystem, total, used, available (in KiB), capacity, mount point
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .nth(1)
+        .and_then(|line| line.split_whitespace().nth(3))
+        .and_then(|available| available.parse::<u64>().ok())
+        .map(|available| available * 1024)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "unexpected output from 'df'"))
+}
+
+/// Get the space available to the current user on the filesystem containing a path, in bytes
+#[cfg(not(unix))]
+pub fn available_space(_: &Path) -> io::Result<u64> {
+    Err(io::Error::other(
+        "checking the available space is not supported on this platform",
+    ))
+}
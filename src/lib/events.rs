@@ -0,0 +1,59 @@
+use crate::lib::chapter::ChapterNumber;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Are events emitted?
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Event emitted on STDOUT as a single line of JSON when using '--log-format json'
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// A message was logged
+    Log { level: &'a str, message: String },
+
+    /// A volume started being built
+    VolumeStarted { volume: usize, chapters: usize },
+
+    /// A page was written to a volume, after being converted if required
+    PageConverted {
+        volume: usize,
+        chapter: ChapterNumber,
+        page: usize,
+        source: &'a Path,
+        size: u64,
+    },
+
+    /// A volume was built
+    VolumeFinished {
+        volume: usize,
+        path: &'a Path,
+        size: u64,
+        pages: usize,
+        seconds: f64,
+    },
+
+    /// A volume was not built as it already exists
+    VolumeSkipped { volume: usize, path: &'a Path },
+}
+
+/// Emit events instead of displaying human-readable messages
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Check if events are emitted
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Emit an event, if events are enabled
+pub fn emit(event: &Event) {
+    if enabled() {
+        println!(
+            "{}",
+            serde_json::to_string(event).expect("Internal error: failed to serialize event")
+        );
+    }
+}
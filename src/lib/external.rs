@@ -0,0 +1,146 @@
+use crate::lib::doctor;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Archive format extracted through an external program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalFormat {
+    Rar,
+    SevenZip,
+}
+
+impl ExternalFormat {
+    /// Get the program used to extract this format
+    pub fn program(self) -> &'static str {
+        match self {
+            Self::Rar => "unrar",
+            Self::SevenZip => "7z",
+        }
+    }
+
+    /// Get the format's name
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rar => "RAR / CBR",
+            Self::SevenZip => "7-Zip / CB7",
+        }
+    }
+
+    /// Determine the format of an archive from its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "rar" | "cbr" => Some(Self::Rar),
+            "7z" | "cb7" => Some(Self::SevenZip),
+            _ => None,
+        }
+    }
+}
+
+/// Extract all files of an archive to a directory using the format's external program
+pub fn extract(format: ExternalFormat, input: &Path, dest: &Path) -> Result<(), ExternalErr> {
+    let program = doctor::find_executable(format.program())
+        .ok_or_else(|| ExternalErr::ProgramNotFound(format.program()))?;
+
+    let mut cmd = Command::new(program);
+
+    match format {
+        // 'x' keeps the paths, '-o+' overwrites existing files, '-idq' disables messages
+        ExternalFormat::Rar => cmd
+            .arg("x")
+            .arg("-o+")
+            .arg("-idq")
+            .arg(input)
+            .arg(dest.join("")),
+
+        // 'x' keeps the paths, '-y' answers yes to all prompts
+        ExternalFormat::SevenZip => cmd
+            .arg("x")
+            .arg("-y")
+            .arg(format!("-o{}", dest.to_string_lossy()))
+            .arg(input),
+    };
+
+    trace!("Running external program: {:?}", cmd);
+
+    let output = cmd
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| ExternalErr::FailedToRun(format.program(), err))?;
+
+    if !output.status.success() {
+        return Err(ExternalErr::ProgramFailed(
+            format.program(),
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Create a 7-Zip archive from the files of a directory using the external '7z' program
+/// Files are stored without compression if `store` is set
+pub fn compress_7z(source: &Path, output: &Path, store: bool) -> Result<(), ExternalErr> {
+    let format = ExternalFormat::SevenZip;
+
+    let program = doctor::find_executable(format.program())
+        .ok_or_else(|| ExternalErr::ProgramNotFound(format.program()))?;
+
+    let mut cmd = Command::new(program);
+
+    // 'a' adds the files (relative to the source directory), '-mx' sets the compression level
+    cmd.current_dir(source)
+        .arg("a")
+        .arg("-t7z")
+        .arg(if store { "-mx=0" } else { "-mx=5" })
+        .arg("-y")
+        .arg(output)
+        .arg(".");
+
+    trace!("Running external program: {:?}", cmd);
+
+    let output = cmd
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| ExternalErr::FailedToRun(format.program(), err))?;
+
+    if !output.status.success() {
+        return Err(ExternalErr::ProgramFailed(
+            format.program(),
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// External program error
+#[derive(Debug)]
+pub enum ExternalErr {
+    ProgramNotFound(&'static str),
+    FailedToRun(&'static str, io::Error),
+    ProgramFailed(&'static str, String),
+}
+
+impl fmt::Display for ExternalErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ProgramNotFound(program) => write!(
+                f,
+                "Program '{}' was not found in PATH (it is required for this format)",
+                program
+            ),
+            Self::FailedToRun(program, err) => write!(f, "Failed to run '{}': {}", program, err),
+            Self::ProgramFailed(program, stderr) if stderr.is_empty() => {
+                write!(f, "Program '{}' failed", program)
+            }
+            Self::ProgramFailed(program, stderr) => {
+                write!(f, "Program '{}' failed: {}", program, stderr)
+            }
+        }
+    }
+}
+
+impl Error for ExternalErr {}
@@ -0,0 +1,89 @@
+use crate::lib::template::{self, TemplateErr};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::process::{Command, Stdio};
+
+/// Prefix of the environment variables the hook commands receive their values through (e.g. 'COMIC_ENC_HOOK_PATH')
+const HOOK_ENV_PREFIX: &str = "COMIC_ENC_HOOK_";
+
+/// Run a hook command through the system's shell
+/// Its `{name}` placeholders are replaced with the provided values, which are also available as environment variables
+/// named after them (e.g. `path` is provided as 'COMIC_ENC_HOOK_PATH')
+pub fn run_hook(template: &str, values: &[(&str, String)]) -> Result<(), HookErr> {
+    let command = template::render_template(template, |name| {
+        values
+            .iter()
+            .find(|(value_name, _)| *value_name == name)
+            .map(|(_, value)| value.clone())
+    })
+    .map_err(HookErr::InvalidTemplate)?;
+
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(&command);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(&command);
+        cmd
+    };
+
+    for (name, value) in values {
+        cmd.env(format!("{}{}", HOOK_ENV_PREFIX, name.to_uppercase()), value);
+    }
+
+    debug!("Running hook command: {}", command);
+
+    let output = cmd
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| HookErr::FailedToRun(command.clone(), err))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    if !stdout.trim().is_empty() {
+        debug!("Output of hook command: {}", stdout.trim());
+    }
+
+    if !output.status.success() {
+        return Err(HookErr::CommandFailed(
+            command,
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Error while running a hook command
+#[derive(Debug)]
+pub enum HookErr {
+    InvalidTemplate(TemplateErr),
+    FailedToRun(String, io::Error),
+    CommandFailed(String, Option<i32>, String),
+}
+
+impl fmt::Display for HookErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidTemplate(err) => write!(f, "Invalid command template: {}", err),
+            Self::FailedToRun(command, err) => write!(f, "Failed to run '{}': {}", command, err),
+            Self::CommandFailed(command, code, stderr) => {
+                match code {
+                    Some(code) => write!(f, "Command '{}' failed with code {}", command, code)?,
+                    None => write!(f, "Command '{}' was interrupted", command)?,
+                }
+
+                if !stderr.is_empty() {
+                    write!(f, ": {}", stderr)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for HookErr {}
@@ -0,0 +1,135 @@
+use crate::lib::archive::{ArchiveErr, ComicArchive};
+use crate::lib::metadata::{self, ArchiveMetadata, MetadataReadErr};
+use image::io::Reader as ImageReader;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// Informations about a comic archive
+#[derive(Debug, Serialize)]
+pub struct ArchiveInfo {
+    /// Path to the archive
+    pub path: PathBuf,
+
+    /// Size of the archive file, in bytes
+    pub file_size: u64,
+
+    /// Number of pages
+    pub pages: usize,
+
+    /// Number of pages for each image format
+    pub formats: BTreeMap<String, usize>,
+
+    /// Number of pages for each resolution (e.g. '1200x1800')
+    pub resolutions: BTreeMap<String, usize>,
+
+    /// Total size of the archive's files, in bytes
+    pub uncompressed_size: u64,
+
+    /// Total compressed size of the archive's files, in bytes
+    pub compressed_size: u64,
+
+    /// Number of files for each compression method
+    pub compression_methods: BTreeMap<String, usize>,
+
+    /// Embedded metadata
+    pub metadata: ArchiveMetadata,
+}
+
+/// Gather informations about a comic archive
+pub fn inspect_archive(path: &Path, extended_formats: bool) -> Result<ArchiveInfo, InspectErr> {
+    let mut archive = ComicArchive::open(path).map_err(InspectErr::ArchiveError)?;
+
+    let file_size = fs::metadata(path)
+        .map_err(|err| InspectErr::ArchiveError(ArchiveErr::FailedToOpenFile(err)))?
+        .len();
+
+    let mut uncompressed_size = 0;
+    let mut compressed_size = 0;
+    let mut compression_methods = BTreeMap::new();
+
+    for entry in archive.entries().iter().filter(|entry| !entry.is_dir) {
+        uncompressed_size += entry.size;
+        compressed_size += entry.compressed_size;
+        *compression_methods
+            .entry(format!("{:?}", entry.compression))
+            .or_insert(0) += 1;
+    }
+
+    let pages = archive
+        .pages(extended_formats, false)
+        .into_iter()
+        .map(|page| page.index)
+        .collect::<Vec<_>>();
+
+    let mut formats = BTreeMap::new();
+    let mut resolutions = BTreeMap::new();
+
+    for index in &pages {
+        let content = archive
+            .read_entry(*index)
+            .map_err(InspectErr::ArchiveError)?;
+
+        let (format, resolution) = identify_page(&content);
+
+        *formats
+            .entry(format.unwrap_or_else(|| "UNKNOWN".to_owned()))
+            .or_insert(0) += 1;
+
+        if let Some((width, height)) = resolution {
+            *resolutions
+                .entry(format!("{}x{}", width, height))
+                .or_insert(0) += 1;
+        }
+    }
+
+    let metadata =
+        metadata::read_archive_metadata(&mut archive).map_err(InspectErr::MetadataError)?;
+
+    Ok(ArchiveInfo {
+        path: path.to_path_buf(),
+        file_size,
+        pages: pages.len(),
+        formats,
+        resolutions,
+        uncompressed_size,
+        compressed_size,
+        compression_methods,
+        metadata,
+    })
+}
+
+/// Get the image format (e.g. 'JPEG') and the resolution of a page, if they can be determined
+pub fn identify_page(content: &[u8]) -> (Option<String>, Option<(u32, u32)>) {
+    let reader = ImageReader::new(Cursor::new(content))
+        .with_guessed_format()
+        .ok()
+        .filter(|reader| reader.format().is_some());
+
+    match reader {
+        Some(reader) => {
+            let format = format!("{:?}", reader.format().unwrap()).to_uppercase();
+            (Some(format), reader.into_dimensions().ok())
+        }
+
+        None => (None, None),
+    }
+}
+
+/// Archive inspection error
+pub enum InspectErr {
+    ArchiveError(ArchiveErr),
+    MetadataError(MetadataReadErr),
+}
+
+impl fmt::Display for InspectErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ArchiveError(err) => write!(f, "{}", err),
+            Self::MetadataError(err) => write!(f, "Failed to read metadata: {}", err),
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use crate::cli::opts::LibraryIndex;
+use crate::lib::deter::{self, RecursiveFilesSearchErr};
+use quick_xml::escape::escape;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+/// Name of the OPDS feed written at the root of the library
+pub const OPDS_FILENAME: &str = "opds.xml";
+
+/// Name of the JSON index written at the root of the library
+pub const JSON_INDEX_FILENAME: &str = "index.json";
+
+/// Extensions of the comic books listed in the index, with their media type
+const COMIC_TYPES: &[(&str, &str)] = &[
+    ("cbz", "application/vnd.comicbook+zip"),
+    ("cbt", "application/x-cbt"),
+    ("cb7", "application/x-cb7"),
+    ("cbr", "application/vnd.comicbook-rar"),
+    ("pdf", "application/pdf"),
+];
+
+/// Index of the comic books of a library
+#[derive(Debug, Serialize)]
+pub struct Library {
+    /// Name of the library (its directory's name)
+    pub name: String,
+
+    /// Date the index was generated, in UTC
+    pub updated: String,
+
+    /// Series of the library, by name
+    pub series: Vec<IndexedSeries>,
+}
+
+/// Series of a library
+#[derive(Debug, Serialize)]
+pub struct IndexedSeries {
+    /// Name of the series (its directory's name)
+    pub name: String,
+
+    /// Volumes of the series, in reading order
+    pub volumes: Vec<IndexedVolume>,
+}
+
+/// Comic book of a library
+#[derive(Debug, Serialize)]
+pub struct IndexedVolume {
+    /// Title of the volume (its file name without extension)
+    pub title: String,
+
+    /// Path to the volume, relative to the library's root and with '/' separators
+    pub path: String,
+
+    /// Media type of the volume
+    pub media_type: &'static str,
+
+    /// Size of the volume's file, in bytes
+    pub size: u64,
+
+    /// Date the volume's file was last modified, in UTC
+    pub updated: String,
+}
+
+impl Library {
+    /// Index the comic books of a library directory (recursively)
+    /// The volumes are grouped by series, which are the directories directly under the root
+    /// (volumes put at the root get a series named after the library)
+    pub fn scan(root: &Path) -> Result<Self, LibraryIndexErr> {
+        let name = root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut files =
+            deter::readdir_files_recursive(root, Some(&|path: &Path| media_type(path).is_some()))
+                .map_err(LibraryIndexErr::FailedToListFiles)?;
+
+        deter::natural_sort_by_path(&mut files, |path| path);
+
+        let mut series = BTreeMap::<String, Vec<IndexedVolume>>::new();
+
+        for file in files {
+            let relative = file.strip_prefix(root).unwrap_or(&file);
+
+            let components = relative
+                .components()
+                .filter_map(|component| match component {
+                    Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            let series_name = match components.as_slice() {
+                [series, _, ..] => series.clone(),
+                _ => name.clone(),
+            };
+
+            let metadata = fs::metadata(&file)
+                .map_err(|err| LibraryIndexErr::FailedToReadFile(file.clone(), err))?;
+
+            series.entry(series_name).or_default().push(IndexedVolume {
+                title: file
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                path: components.join("/"),
+                media_type: media_type(&file).unwrap(),
+                size: metadata.len(),
+                updated: deter::format_utc(
+                    metadata.modified().unwrap_or_else(|_| SystemTime::now()),
+                    'T',
+                    false,
+                ) + "Z",
+            });
+        }
+
+        Ok(Self {
+            name,
+            updated: deter::format_utc(SystemTime::now(), 'T', false) + "Z",
+            series: series
+                .into_iter()
+                .map(|(name, volumes)| IndexedSeries { name, volumes })
+                .collect(),
+        })
+    }
+
+    /// Write the index of the library at its root, replacing the previous one
+    pub fn write(&self, root: &Path, format: LibraryIndex) -> Result<PathBuf, LibraryIndexErr> {
+        let (path, content) = match format {
+            LibraryIndex::Opds => (root.join(OPDS_FILENAME), self.to_opds()),
+            LibraryIndex::Json => (
+                root.join(JSON_INDEX_FILENAME),
+                serde_json::to_string_pretty(self).map_err(LibraryIndexErr::FailedToSerialize)?,
+            ),
+        };
+
+        fs::write(&path, content)
+            .map_err(|err| LibraryIndexErr::FailedToWriteIndex(path.clone(), err))?;
+
+        Ok(path)
+    }
+
+    /// Generate an OPDS 1.2 acquisition feed listing the volumes, with links relative to the feed
+    pub fn to_opds(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+        xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\" xmlns:opds=\"http://opds-spec.org/2010/catalog\">\n");
+        xml.push_str(&format!(
+            "  <id>urn:comic-enc:{}</id>\n",
+            escape(&url_path(&self.name))
+        ));
+        xml.push_str(&format!("  <title>{}</title>\n", escape(&self.name)));
+        xml.push_str(&format!("  <updated>{}</updated>\n", self.updated));
+        xml.push_str(&format!(
+            "  <link rel=\"self\" href=\"{}\" type=\"application/atom+xml;profile=opds-catalog;kind=acquisition\"/>\n",
+            OPDS_FILENAME
+        ));
+
+        for series in &self.series {
+            for volume in &series.volumes {
+                let href = url_path(&volume.path);
+
+                xml.push_str("  <entry>\n");
+                xml.push_str(&format!("    <title>{}</title>\n", escape(&volume.title)));
+                xml.push_str(&format!("    <id>urn:comic-enc:{}</id>\n", escape(&href)));
+                xml.push_str(&format!("    <updated>{}</updated>\n", volume.updated));
+                xml.push_str(&format!(
+                    "    <category term=\"{}\" label=\"{}\"/>\n",
+                    escape(&series.name),
+                    escape(&series.name)
+                ));
+                xml.push_str(&format!(
+                    "    <link rel=\"http://opds-spec.org/acquisition\" href=\"{}\" type=\"{}\" length=\"{}\"/>\n",
+                    escape(&href),
+                    volume.media_type,
+                    volume.size
+                ));
+                xml.push_str("  </entry>\n");
+            }
+        }
+
+        xml.push_str("</feed>\n");
+        xml
+    }
+}
+
+/// Get the media type of a comic book from its extension
+fn media_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+
+    COMIC_TYPES
+        .iter()
+        .find(|(comic_ext, _)| *comic_ext == ext)
+        .map(|(_, media_type)| *media_type)
+}
+
+/// Percent-encode a relative path so it can be used in a link, keeping its '/' separators
+fn url_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+/// Error while generating the index of a library
+#[derive(Debug)]
+pub enum LibraryIndexErr {
+    FailedToListFiles(RecursiveFilesSearchErr),
+    FailedToReadFile(PathBuf, io::Error),
+    FailedToSerialize(serde_json::Error),
+    FailedToWriteIndex(PathBuf, io::Error),
+}
+
+impl fmt::Display for LibraryIndexErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FailedToListFiles(err) => {
+                write!(f, "Failed to list the library's files: {}", err)
+            }
+            Self::FailedToReadFile(path, err) => {
+                write!(
+                    f,
+                    "Failed to read file '{}': {}",
+                    path.to_string_lossy(),
+                    err
+                )
+            }
+            Self::FailedToSerialize(err) => write!(f, "Failed to serialize index: {}", err),
+            Self::FailedToWriteIndex(path, err) => {
+                write!(
+                    f,
+                    "Failed to write index '{}': {}",
+                    path.to_string_lossy(),
+                    err
+                )
+            }
+        }
+    }
+}
+
+impl Error for LibraryIndexErr {}
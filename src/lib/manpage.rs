@@ -0,0 +1,135 @@
+use clap::{Arg, Command};
+
+/// Escape a text for roff
+fn escape(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let line = line.replace('\\', "\\e").replace('-', "\\-");
+
+            // Lines starting with a dot or an apostrophe would be interpreted as requests
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{}", line)
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Get the placeholder of an argument's value (e.g. '<OUTPUT>')
+fn value_placeholder(arg: &Arg) -> String {
+    match arg.get_value_names() {
+        Some(names) => names
+            .iter()
+            .map(|name| format!("<{}>", name))
+            .collect::<Vec<_>>()
+            .join(" "),
+        None => format!("<{}>", arg.get_id().to_uppercase()),
+    }
+}
+
+/// Describe an argument as a tagged paragraph
+fn describe_arg(arg: &Arg) -> String {
+    let mut flags = vec![];
+
+    if arg.is_positional() {
+        flags.push(format!("\\fI{}\\fR", escape(&value_placeholder(arg))));
+    } else {
+        if let Some(short) = arg.get_short() {
+            flags.push(format!("\\fB\\-{}\\fR", short));
+        }
+
+        if let Some(long) = arg.get_long() {
+            flags.push(format!("\\fB\\-\\-{}\\fR", escape(long)));
+        }
+    }
+
+    let mut tag = flags.join(", ");
+
+    if !arg.is_positional() && arg.is_takes_value_set() {
+        tag.push_str(&format!(" \\fI{}\\fR", escape(&value_placeholder(arg))));
+    }
+
+    let mut help = arg
+        .get_long_help()
+        .or_else(|| arg.get_help())
+        .map(escape)
+        .unwrap_or_default();
+
+    if let Some(values) = arg.get_possible_values() {
+        let values = values
+            .iter()
+            .filter(|value| !value.is_hide_set())
+            .map(|value| escape(value.get_name()))
+            .collect::<Vec<_>>();
+
+        if !values.is_empty() {
+            help.push_str(&format!("\n[possible values: {}]", values.join(", ")));
+        }
+    }
+
+    format!(".TP\n{}\n{}\n", tag, help)
+}
+
+/// Describe a command and its subcommands, recursively
+fn describe_command(cmd: &Command, path: &str, page: &mut String) {
+    let args = cmd
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .collect::<Vec<_>>();
+
+    page.push_str(&format!(".SS \"{}\"\n", escape(path)));
+
+    if let Some(about) = cmd.get_long_about().or_else(|| cmd.get_about()) {
+        page.push_str(&format!("{}\n", escape(about)));
+    }
+
+    // Global arguments are only described once, on the command declaring them
+    for arg in args {
+        page.push_str(&describe_arg(arg));
+    }
+
+    for sub in cmd.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        describe_command(sub, &format!("{} {}", path, sub.get_name()), page);
+    }
+}
+
+/// Generate a manual page in the roff format
+pub fn manpage(cmd: &Command, bin_name: &str) -> String {
+    let mut page = format!(
+        ".TH {} 1 \"\" \"{} {}\" \"User Commands\"\n",
+        escape(&bin_name.to_uppercase()),
+        escape(bin_name),
+        escape(cmd.get_version().unwrap_or_default())
+    );
+
+    page.push_str(&format!(
+        ".SH NAME\n{} \\- {}\n",
+        escape(bin_name),
+        escape(cmd.get_about().unwrap_or_default())
+    ));
+
+    page.push_str(&format!(
+        ".SH SYNOPSIS\n\\fB{}\\fR [\\fIOPTIONS\\fR] \\fICOMMAND\\fR\n",
+        escape(bin_name)
+    ));
+
+    page.push_str(".SH OPTIONS\n");
+
+    for arg in cmd.get_arguments().filter(|arg| !arg.is_hide_set()) {
+        page.push_str(&describe_arg(arg));
+    }
+
+    page.push_str(".SH COMMANDS\n");
+
+    for sub in cmd.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        describe_command(sub, &format!("{} {}", bin_name, sub.get_name()), &mut page);
+    }
+
+    if let Some(author) = cmd.get_author() {
+        page.push_str(&format!(".SH AUTHORS\n{}\n", escape(author)));
+    }
+
+    page
+}
@@ -0,0 +1,757 @@
+use crate::lib::archive::{ArchiveErr, ComicArchive};
+use crate::lib::chapter::ChapterNumber;
+use csv::{ReaderBuilder, Trim};
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+/// Name of the ComicInfo metadata file, at the root of comic archives
+pub const COMIC_INFO_FILENAME: &str = "ComicInfo.xml";
+
+/// Name of the CoMet metadata file, at the root of comic archives
+pub const COMET_FILENAME: &str = "CoMet.xml";
+
+/// Key of the ComicBookInfo object in ZIP comments
+const COMIC_BOOK_INFO_KEY: &str = "ComicBookInfo/1.0";
+
+/// Generate the metadata structure along with a name-based accessor for each of its textual fields
+/// Each field is associated to its name in the ComicInfo format
+macro_rules! comic_metadata {
+    ($($(#[$doc: meta])* $field: ident => $name: literal),* $(,)?) => {
+        /// Metadata of a comic, modeled after the ComicInfo format
+        /// Other formats (CoMet, ComicBookInfo) are mapped to their ComicInfo equivalent
+        #[derive(Debug, Clone, Default, Serialize)]
+        pub struct ComicMetadata {
+            $(
+                $(#[$doc])*
+                #[serde(skip_serializing_if = "Option::is_none")]
+                pub $field: Option<String>,
+            )*
+
+            /// Informations about individual pages
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            pub pages: Vec<PageMetadata>,
+        }
+
+        impl ComicMetadata {
+            /// ComicInfo name of all textual fields, in the format's order
+            pub const FIELD_NAMES: &'static [&'static str] = &[$($name),*];
+
+            /// List all textual fields with their ComicInfo name, in the format's order
+            pub fn fields(&self) -> Vec<(&'static str, &Option<String>)> {
+                vec![$(($name, &self.$field)),*]
+            }
+
+            /// Get a textual field from its ComicInfo name
+            pub fn field_mut(&mut self, name: &str) -> Option<&mut Option<String>> {
+                match name {
+                    $($name => Some(&mut self.$field),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+comic_metadata! {
+    /// Title of the book
+    title => "Title",
+    /// Name of the series
+    series => "Series",
+    /// Number of the book in the series
+    number => "Number",
+    /// Number of books in the series
+    count => "Count",
+    /// Volume containing the book
+    volume => "Volume",
+    /// Description of the book
+    summary => "Summary",
+    /// Free text
+    notes => "Notes",
+    /// Release year
+    year => "Year",
+    /// Release month
+    month => "Month",
+    /// Release day
+    day => "Day",
+    writer => "Writer",
+    penciller => "Penciller",
+    inker => "Inker",
+    colorist => "Colorist",
+    letterer => "Letterer",
+    cover_artist => "CoverArtist",
+    editor => "Editor",
+    publisher => "Publisher",
+    genre => "Genre",
+    tags => "Tags",
+    /// Website related to the book
+    web => "Web",
+    /// Number of pages in the book
+    page_count => "PageCount",
+    /// ISO code of the book's language
+    language_iso => "LanguageISO",
+    format => "Format",
+    characters => "Characters",
+    /// Either 'Unknown', 'No', 'Yes' or 'YesAndRightToLeft'
+    manga => "Manga",
+    /// Global Trade Item Number (e.g. ISBN)
+    gtin => "GTIN",
+}
+
+/// Metadata of a single page
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageMetadata {
+    /// Index of the page in the book, starting at 0
+    pub image: usize,
+
+    /// Type of the page (e.g. 'FrontCover', 'Story', ...)
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+
+    /// Bookmark associated to the page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bookmark: Option<String>,
+
+    /// Is the page a double page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub double_page: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_width: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_height: Option<u32>,
+}
+
+/// All the metadata found in a comic archive, by format
+#[derive(Debug, Default, Serialize)]
+pub struct ArchiveMetadata {
+    /// Content of the 'ComicInfo.xml' file
+    pub comic_info: Option<ComicMetadata>,
+
+    /// Content of the 'CoMet.xml' file
+    pub comet: Option<ComicMetadata>,
+
+    /// ComicBookInfo object stored in the archive's comment
+    pub comic_book_info: Option<ComicMetadata>,
+}
+
+impl ComicMetadata {
+    /// Check if no field at all is set
+    pub fn is_empty(&self) -> bool {
+        self.fields().iter().all(|(_, value)| value.is_none()) && self.pages.is_empty()
+    }
+
+    /// Merge another metadata object into this one
+    /// Fields set in the other object take precedence over this object's
+    pub fn merge(&mut self, other: &ComicMetadata) {
+        for (name, value) in other.fields() {
+            if let Some(value) = value {
+                *self.field_mut(name).unwrap() = Some(value.clone());
+            }
+        }
+
+        if !other.pages.is_empty() {
+            self.pages = other.pages.clone();
+        }
+    }
+
+    /// Serialize the metadata to the content of a 'ComicInfo.xml' file
+    pub fn to_comic_info_xml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<ComicInfo xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\n",
+        );
+
+        for (name, value) in self.fields() {
+            if let Some(value) = value {
+                xml.push_str(&format!("  <{}>{}</{}>\n", name, escape(value), name));
+            }
+        }
+
+        if !self.pages.is_empty() {
+            xml.push_str("  <Pages>\n");
+
+            for page in &self.pages {
+                xml.push_str(&format!("    <Page Image=\"{}\"", page.image));
+
+                if let Some(kind) = &page.kind {
+                    xml.push_str(&format!(" Type=\"{}\"", escape(kind)));
+                }
+
+                if let Some(double_page) = page.double_page {
+                    xml.push_str(&format!(" DoublePage=\"{}\"", double_page));
+                }
+
+                if let Some(bookmark) = &page.bookmark {
+                    xml.push_str(&format!(" Bookmark=\"{}\"", escape(bookmark)));
+                }
+
+                if let Some(width) = page.image_width {
+                    xml.push_str(&format!(" ImageWidth=\"{}\"", width));
+                }
+
+                if let Some(height) = page.image_height {
+                    xml.push_str(&format!(" ImageHeight=\"{}\"", height));
+                }
+
+                xml.push_str(" />\n");
+            }
+
+            xml.push_str("  </Pages>\n");
+        }
+
+        xml.push_str("</ComicInfo>\n");
+        xml
+    }
+}
+
+/// Metadata to write in the generated volumes
+#[derive(Debug, Default)]
+pub struct VolumesMetadata {
+    /// Metadata common to all volumes
+    pub common: ComicMetadata,
+
+    /// Metadata specific to each volume, indexed by volume number
+    pub volumes: HashMap<usize, ComicMetadata>,
+
+    /// Titles of the chapters, indexed by chapter number or directory name
+    /// Chapters are bookmarked at their first page only if this is set
+    pub chapter_titles: Option<HashMap<String, String>>,
+
+    /// ComicInfo fields set to the volume's number unless provided explicitly (e.g. 'Volume')
+    pub volume_number_fields: &'static [&'static str],
+}
+
+impl VolumesMetadata {
+    /// Get the complete metadata of a volume, or `None` if there is nothing to write
+    pub fn for_volume(&self, volume: usize) -> Option<ComicMetadata> {
+        let mut metadata = self.common.clone();
+
+        if let Some(specific) = self.volumes.get(&volume) {
+            metadata.merge(specific);
+        }
+
+        for name in self.volume_number_fields {
+            if let Some(field) = metadata.field_mut(name) {
+                field.get_or_insert_with(|| volume.to_string());
+            }
+        }
+
+        if metadata.is_empty() {
+            None
+        } else {
+            Some(metadata)
+        }
+    }
+
+    /// Get the title to bookmark a chapter with, or `None` if chapters should not be bookmarked
+    /// Chapters without an explicit title are named after their directory
+    pub fn chapter_title(&self, chapter: ChapterNumber, dir_name: &str) -> Option<String> {
+        let titles = self.chapter_titles.as_ref()?;
+
+        Some(
+            titles
+                .get(&chapter.to_string())
+                .or_else(|| titles.get(dir_name))
+                .cloned()
+                .unwrap_or_else(|| dir_name.to_owned()),
+        )
+    }
+}
+
+impl ArchiveMetadata {
+    /// Check if no metadata at all was found
+    pub fn is_empty(&self) -> bool {
+        self.comic_info.is_none() && self.comet.is_none() && self.comic_book_info.is_none()
+    }
+
+    /// Merge the metadata of all formats into a single object
+    /// ComicInfo takes precedence over CoMet, which takes precedence over ComicBookInfo
+    pub fn merged(&self) -> ComicMetadata {
+        let mut merged = ComicMetadata::default();
+
+        for metadata in [&self.comic_book_info, &self.comet, &self.comic_info]
+            .iter()
+            .filter_map(|metadata| metadata.as_ref())
+        {
+            merged.merge(metadata);
+        }
+
+        merged
+    }
+}
+
+/// Read all metadata from a comic archive
+pub fn read_archive_metadata(
+    archive: &mut ComicArchive,
+) -> Result<ArchiveMetadata, MetadataReadErr> {
+    let comic_info = read_root_text_file(archive, COMIC_INFO_FILENAME)?;
+    let comet = read_root_text_file(archive, COMET_FILENAME)?;
+
+    parse_archive_metadata(
+        comic_info.as_deref(),
+        comet.as_deref(),
+        &String::from_utf8_lossy(archive.comment()),
+    )
+}
+
+/// Parse the metadata files and comment of an archive, whatever its format
+pub fn parse_archive_metadata(
+    comic_info: Option<&str>,
+    comet: Option<&str>,
+    comment: &str,
+) -> Result<ArchiveMetadata, MetadataReadErr> {
+    Ok(ArchiveMetadata {
+        comic_info: comic_info.map(parse_comic_info).transpose()?,
+        comet: comet.map(parse_comet).transpose()?,
+        comic_book_info: parse_comic_book_info(comment)?,
+    })
+}
+
+/// Read a text file at the root of a comic archive, if it exists
+fn read_root_text_file(
+    archive: &mut ComicArchive,
+    name: &str,
+) -> Result<Option<String>, MetadataReadErr> {
+    let index = match archive.find_root_entry(name) {
+        Some(entry) => entry.index,
+        None => return Ok(None),
+    };
+
+    let content = archive
+        .read_entry(index)
+        .map_err(MetadataReadErr::ArchiveError)?;
+
+    Ok(Some(String::from_utf8_lossy(&content).to_string()))
+}
+
+/// Get the (unescaped) value of an attribute from an XML tag
+fn xml_attr(tag: &BytesStart, name: &str) -> Option<String> {
+    tag.attributes()
+        .filter_map(Result::ok)
+        .find(|attr| attr.key.local_name().as_ref() == name.as_bytes())
+        .and_then(|attr| attr.unescape_value().ok().map(|value| value.to_string()))
+}
+
+/// Walk through a flat XML document, calling the provided function for each (tag name, text) pair
+/// found directly under the root element
+/// Tags nested deeper are provided to `on_nested` along with the names of their parent tags
+fn walk_flat_xml(
+    file_name: &str,
+    xml: &str,
+    mut on_text: impl FnMut(&str, String),
+    mut on_nested: impl FnMut(&[String], &BytesStart),
+) -> Result<(), MetadataReadErr> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+
+    // Names of the currently opened tags
+    let mut path: Vec<String> = vec![];
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|err| MetadataReadErr::InvalidXml(file_name.to_owned(), err))?;
+
+        match event {
+            Event::Start(tag) => {
+                if path.len() >= 2 {
+                    on_nested(&path, &tag);
+                }
+
+                path.push(String::from_utf8_lossy(tag.local_name().as_ref()).to_string());
+            }
+
+            Event::Empty(tag) if path.len() >= 2 => on_nested(&path, &tag),
+
+            Event::End(_) => {
+                path.pop();
+            }
+
+            Event::Text(text) if path.len() == 2 => {
+                let text = text
+                    .unescape()
+                    .map_err(|err| MetadataReadErr::InvalidXml(file_name.to_owned(), err))?;
+
+                on_text(&path[1], text.to_string());
+            }
+
+            Event::Eof => break Ok(()),
+
+            _ => {}
+        }
+    }
+}
+
+/// Parse the content of a 'ComicInfo.xml' file
+pub fn parse_comic_info(xml: &str) -> Result<ComicMetadata, MetadataReadErr> {
+    let mut metadata = ComicMetadata::default();
+    let mut pages = vec![];
+
+    walk_flat_xml(
+        COMIC_INFO_FILENAME,
+        xml,
+        |name, text| {
+            if let Some(field) = metadata.field_mut(name) {
+                *field = Some(text);
+            }
+        },
+        |path, tag| {
+            if path.len() == 2 && path[1] == "Pages" && tag.local_name().as_ref() == b"Page" {
+                pages.push(PageMetadata {
+                    image: xml_attr(tag, "Image")
+                        .and_then(|image| image.parse().ok())
+                        .unwrap_or(pages.len()),
+                    kind: xml_attr(tag, "Type"),
+                    bookmark: xml_attr(tag, "Bookmark"),
+                    double_page: xml_attr(tag, "DoublePage").map(|double| double == "true"),
+                    image_width: xml_attr(tag, "ImageWidth").and_then(|w| w.parse().ok()),
+                    image_height: xml_attr(tag, "ImageHeight").and_then(|h| h.parse().ok()),
+                });
+            }
+        },
+    )?;
+
+    metadata.pages = pages;
+
+    Ok(metadata)
+}
+
+/// Append a value to a comma-separated list field
+fn push_list_value(field: &mut Option<String>, value: String) {
+    *field = Some(match field.take() {
+        None => value,
+        Some(list) => format!("{}, {}", list, value),
+    });
+}
+
+/// Parse the content of a 'CoMet.xml' file
+pub fn parse_comet(xml: &str) -> Result<ComicMetadata, MetadataReadErr> {
+    let mut metadata = ComicMetadata::default();
+
+    walk_flat_xml(
+        COMET_FILENAME,
+        xml,
+        |name, text| {
+            let field = match name {
+                "title" => &mut metadata.title,
+                "description" => &mut metadata.summary,
+                "series" => &mut metadata.series,
+                "issue" => &mut metadata.number,
+                "volume" => &mut metadata.volume,
+                "publisher" => &mut metadata.publisher,
+                "format" => &mut metadata.format,
+                "language" => &mut metadata.language_iso,
+                "identifier" => &mut metadata.gtin,
+                "pages" => &mut metadata.page_count,
+                "editor" => &mut metadata.editor,
+                "coverDesigner" => &mut metadata.cover_artist,
+
+                // Fields that may appear multiple times
+                "genre" => return push_list_value(&mut metadata.genre, text),
+                "character" => return push_list_value(&mut metadata.characters, text),
+                "creator" | "writer" => return push_list_value(&mut metadata.writer, text),
+                "penciller" => return push_list_value(&mut metadata.penciller, text),
+                "inker" => return push_list_value(&mut metadata.inker, text),
+                "colorist" => return push_list_value(&mut metadata.colorist, text),
+                "letterer" => return push_list_value(&mut metadata.letterer, text),
+
+                // Dates are formatted as 'YYYY-MM-DD', 'YYYY-MM' or 'YYYY'
+                "date" => {
+                    let mut segments = text.split('-').map(str::to_owned);
+                    metadata.year = segments.next();
+                    metadata.month = segments.next();
+                    metadata.day = segments.next();
+                    return;
+                }
+
+                "readingDirection" => {
+                    if text.eq_ignore_ascii_case("rtl") {
+                        metadata.manga = Some("YesAndRightToLeft".to_owned());
+                    }
+                    return;
+                }
+
+                _ => return,
+            };
+
+            *field = Some(text);
+        },
+        |_, _| {},
+    )?;
+
+    Ok(metadata)
+}
+
+/// Parse a ComicBookInfo object from a ZIP archive's comment
+/// Returns `None` if the comment does not contain any ComicBookInfo object
+pub fn parse_comic_book_info(comment: &str) -> Result<Option<ComicMetadata>, MetadataReadErr> {
+    if !comment.contains(COMIC_BOOK_INFO_KEY) {
+        return Ok(None);
+    }
+
+    let json: JsonValue = serde_json::from_str(comment).map_err(MetadataReadErr::InvalidJson)?;
+
+    let info = match json.get(COMIC_BOOK_INFO_KEY) {
+        Some(JsonValue::Object(info)) => info,
+        _ => return Ok(None),
+    };
+
+    // Get a field as a string, whatever its JSON type is
+    let text = |name: &str| match info.get(name) {
+        None | Some(JsonValue::Null) => None,
+        Some(JsonValue::String(string)) => Some(string.clone()),
+        Some(JsonValue::Array(items)) => Some(
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        Some(value) => Some(value.to_string()),
+    };
+
+    let mut metadata = ComicMetadata {
+        title: text("title"),
+        series: text("series"),
+        number: text("issue"),
+        count: text("numberOfIssues"),
+        volume: text("volume"),
+        summary: text("comments"),
+        year: text("publicationYear"),
+        month: text("publicationMonth"),
+        publisher: text("publisher"),
+        genre: text("genre"),
+        tags: text("tags"),
+        language_iso: text("language"),
+        ..ComicMetadata::default()
+    };
+
+    if let Some(JsonValue::Array(credits)) = info.get("credits") {
+        for credit in credits {
+            let person = match credit.get("person").and_then(JsonValue::as_str) {
+                Some(person) => person.to_owned(),
+                None => continue,
+            };
+
+            let field = match credit.get("role").and_then(JsonValue::as_str) {
+                Some("Writer") => &mut metadata.writer,
+                Some("Artist") | Some("Penciller") => &mut metadata.penciller,
+                Some("Inker") => &mut metadata.inker,
+                Some("Colorer") | Some("Colorist") => &mut metadata.colorist,
+                Some("Letterer") => &mut metadata.letterer,
+                Some("Cover") | Some("Cover Artist") => &mut metadata.cover_artist,
+                Some("Editor") => &mut metadata.editor,
+                _ => continue,
+            };
+
+            push_list_value(field, person);
+        }
+    }
+
+    Ok(Some(metadata))
+}
+
+/// Read a CSV file mapping volume numbers to their metadata
+/// The first line must be a header, with a 'volume' column containing the volume numbers
+/// 'release_date' (or 'date') columns are split into the year, month and day fields (format: YYYY-MM-DD)
+/// 'isbn' columns are mapped to the GTIN field
+/// Other columns are matched case-insensitively against ComicInfo field names (e.g. 'title', 'summary', 'writer')
+pub fn read_metadata_csv(path: &Path) -> Result<HashMap<usize, ComicMetadata>, MetadataCsvErr> {
+    let mut reader = ReaderBuilder::new()
+        .trim(Trim::All)
+        .flexible(true)
+        .from_path(path)
+        .map_err(MetadataCsvErr::CsvError)?;
+
+    let headers = reader
+        .headers()
+        .map_err(MetadataCsvErr::CsvError)?
+        .iter()
+        .map(|header| header.to_lowercase().replace([' ', '-'], "_"))
+        .collect::<Vec<_>>();
+
+    let volume_col = headers
+        .iter()
+        .position(|header| header == "volume")
+        .ok_or(MetadataCsvErr::MissingColumn("volume"))?;
+
+    // Match the other columns against the ComicInfo field names once, so unknown columns are only reported once
+    let fields = headers
+        .iter()
+        .map(|header| match header.as_str() {
+            "volume" | "release_date" | "date" | "isbn" => None,
+            _ => {
+                let name = ComicMetadata::FIELD_NAMES
+                    .iter()
+                    .find(|name| name.to_lowercase() == header.replace('_', ""))
+                    .copied();
+
+                if name.is_none() {
+                    warn!("Ignoring unknown metadata CSV column '{}'", header);
+                }
+
+                name
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut volumes = HashMap::new();
+
+    for (line, record) in reader.records().enumerate() {
+        let record = record.map_err(MetadataCsvErr::CsvError)?;
+
+        // Lines are counted from 1, the header being the first one
+        let line = line + 2;
+
+        let volume = record.get(volume_col).unwrap_or_default();
+
+        // Ignore empty lines
+        if volume.is_empty() {
+            continue;
+        }
+
+        let volume = volume
+            .parse::<usize>()
+            .map_err(|_| MetadataCsvErr::InvalidVolumeNumber(line, volume.to_owned()))?;
+
+        let mut metadata = ComicMetadata::default();
+
+        for ((header, field), value) in headers.iter().zip(&fields).zip(record.iter()) {
+            if value.is_empty() || header == "volume" {
+                continue;
+            }
+
+            match header.as_str() {
+                "release_date" | "date" => {
+                    let segments = value
+                        .split('-')
+                        .map(|segment| segment.parse::<u16>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .ok()
+                        .filter(|segments| !segments.is_empty() && segments.len() <= 3)
+                        .ok_or_else(|| {
+                            MetadataCsvErr::InvalidReleaseDate(line, value.to_owned())
+                        })?;
+
+                    metadata.year = segments.first().map(u16::to_string);
+                    metadata.month = segments.get(1).map(u16::to_string);
+                    metadata.day = segments.get(2).map(u16::to_string);
+                }
+
+                "isbn" => metadata.gtin = Some(value.to_owned()),
+
+                _ => {
+                    if let Some(name) = field {
+                        *metadata.field_mut(name).unwrap() = Some(value.to_owned());
+                    }
+                }
+            }
+        }
+
+        volumes.insert(volume, metadata);
+    }
+
+    Ok(volumes)
+}
+
+/// Read a CSV file mapping chapters to their title
+/// The first line must be a header, with a 'chapter' column containing either chapter numbers or directory names
+/// and a 'title' column
+pub fn read_chapter_titles_csv(path: &Path) -> Result<HashMap<String, String>, MetadataCsvErr> {
+    let mut reader = ReaderBuilder::new()
+        .trim(Trim::All)
+        .flexible(true)
+        .from_path(path)
+        .map_err(MetadataCsvErr::CsvError)?;
+
+    let headers = reader.headers().map_err(MetadataCsvErr::CsvError)?.clone();
+
+    let find_col = |name: &'static str| {
+        headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name))
+            .ok_or(MetadataCsvErr::MissingColumn(name))
+    };
+
+    let chapter_col = find_col("chapter")?;
+    let title_col = find_col("title")?;
+
+    let mut titles = HashMap::new();
+
+    for record in reader.records() {
+        let record = record.map_err(MetadataCsvErr::CsvError)?;
+
+        match (record.get(chapter_col), record.get(title_col)) {
+            (Some(chapter), Some(title)) if !chapter.is_empty() && !title.is_empty() => {
+                // Normalize chapter numbers (e.g. '007' => '7')
+                let chapter = match chapter.parse::<usize>() {
+                    Ok(num) => num.to_string(),
+                    Err(_) => chapter.to_owned(),
+                };
+
+                titles.insert(chapter, title.to_owned());
+            }
+
+            _ => continue,
+        }
+    }
+
+    Ok(titles)
+}
+
+/// Metadata reading error
+#[derive(Debug)]
+pub enum MetadataReadErr {
+    ArchiveError(ArchiveErr),
+    InvalidXml(String, quick_xml::Error),
+    InvalidJson(serde_json::Error),
+}
+
+impl fmt::Display for MetadataReadErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ArchiveError(err) => write!(f, "{}", err),
+            Self::InvalidXml(name, err) => write!(f, "Invalid XML in file '{}': {}", name, err),
+            Self::InvalidJson(err) => {
+                write!(f, "Invalid ComicBookInfo JSON in archive comment: {}", err)
+            }
+        }
+    }
+}
+
+impl Error for MetadataReadErr {}
+
+/// Metadata CSV file reading error
+#[derive(Debug)]
+pub enum MetadataCsvErr {
+    CsvError(csv::Error),
+    MissingColumn(&'static str),
+    InvalidVolumeNumber(usize, String),
+    InvalidReleaseDate(usize, String),
+}
+
+impl fmt::Display for MetadataCsvErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::CsvError(err) => write!(f, "{}", err),
+            Self::MissingColumn(name) => write!(f, "Missing '{}' column in header", name),
+            Self::InvalidVolumeNumber(line, value) => {
+                write!(f, "Invalid volume number '{}' at line {}", value, line)
+            }
+            Self::InvalidReleaseDate(line, value) => write!(
+                f,
+                "Invalid release date '{}' at line {} (expected format: YYYY-MM-DD)",
+                value, line
+            ),
+        }
+    }
+}
+
+impl Error for MetadataCsvErr {}
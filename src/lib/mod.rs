@@ -1,2 +1,42 @@
+pub mod archive;
+pub mod batch;
+pub mod bench;
 pub mod build_vol;
+pub mod cache;
+pub mod calibre;
+pub mod cancel;
+pub mod chapter;
+pub mod checksum;
+pub mod codec;
+pub mod completions;
+pub mod config;
+pub mod convert;
+pub mod cover;
 pub mod deter;
+pub mod diff;
+pub mod doctor;
+pub mod events;
+pub mod external;
+pub mod hooks;
+pub mod inspect;
+pub mod library_index;
+pub mod manpage;
+pub mod metadata;
+pub mod notify;
+pub mod pipeline;
+pub mod plan;
+pub mod progress;
+pub mod progress_bars;
+pub mod queue;
+pub mod selection;
+pub mod serde_str;
+pub mod serve;
+pub mod source;
+pub mod stats;
+pub mod summary;
+pub mod tar;
+pub mod template;
+pub mod validate;
+pub mod volume_map;
+pub mod watch;
+pub mod writer;
@@ -0,0 +1,217 @@
+use crate::lib::doctor;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Title of the desktop notifications
+const NOTIFICATION_TITLE: &str = "Comic Encoder";
+
+/// Where to send a notification when a run finishes or fails
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotifyTarget {
+    /// Desktop notification, through 'notify-send' (Linux) or 'osascript' (macOS)
+    Desktop,
+    /// HTTP(S) URL the run's outcome is POSTed to as JSON, through 'curl'
+    Webhook(String),
+}
+
+impl FromStr for NotifyTarget {
+    type Err = NotifyTargetErr;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if input == "desktop" {
+            return Ok(Self::Desktop);
+        }
+
+        match input.strip_prefix("webhook:") {
+            Some(url) if url.starts_with("http://") || url.starts_with("https://") => {
+                Ok(Self::Webhook(url.to_owned()))
+            }
+            _ => Err(NotifyTargetErr(input.to_owned())),
+        }
+    }
+}
+
+/// Error while parsing a notification target
+#[derive(Debug)]
+pub struct NotifyTargetErr(String);
+
+impl fmt::Display for NotifyTargetErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid notification target '{}' (expected 'desktop' or 'webhook:<url>')",
+            self.0
+        )
+    }
+}
+
+impl Error for NotifyTargetErr {}
+
+/// Outcome of a run, as sent to the webhooks
+#[derive(Debug, Serialize)]
+pub struct RunNotification {
+    /// Did the run succeed?
+    pub success: bool,
+    /// Command-line arguments of the run
+    pub command: Vec<String>,
+    /// Error message, if the run failed
+    pub error: Option<String>,
+    /// Code the program exits with
+    pub exit_code: i32,
+    /// Duration of the run, in seconds
+    pub elapsed_secs: f64,
+    /// Files produced by the run
+    pub output_files: Vec<PathBuf>,
+}
+
+impl RunNotification {
+    pub fn new(
+        result: Result<&[PathBuf], &str>,
+        exit_code: i32,
+        command: Vec<String>,
+        elapsed: Duration,
+    ) -> Self {
+        Self {
+            success: result.is_ok(),
+            command,
+            error: result.err().map(str::to_owned),
+            exit_code,
+            elapsed_secs: elapsed.as_secs_f64(),
+            output_files: result.map(<[PathBuf]>::to_vec).unwrap_or_default(),
+        }
+    }
+
+    /// Get the message displayed in desktop notifications
+    fn message(&self) -> String {
+        match &self.error {
+            None => format!(
+                "Done in {}m{:02}s ({} file{} produced)",
+                self.elapsed_secs as u64 / 60,
+                self.elapsed_secs as u64 % 60,
+                self.output_files.len(),
+                if self.output_files.len() > 1 { "s" } else { "" }
+            ),
+            Some(err) => format!("Failed: {}", err),
+        }
+    }
+}
+
+/// Send a notification of a run's outcome
+pub fn send(target: &NotifyTarget, notification: &RunNotification) -> Result<(), NotifyErr> {
+    match target {
+        NotifyTarget::Desktop => {
+            let message = notification.message();
+
+            if cfg!(target_os = "macos") {
+                run(
+                    "osascript",
+                    &[
+                        "-e".to_owned(),
+                        format!(
+                            "display notification {:?} with title {:?}",
+                            message, NOTIFICATION_TITLE
+                        ),
+                    ],
+                    None,
+                )
+            } else if cfg!(unix) {
+                run("notify-send", &[NOTIFICATION_TITLE.to_owned(), message], None)
+            } else {
+                Err(NotifyErr::DesktopNotSupported)
+            }
+        }
+
+        NotifyTarget::Webhook(url) => {
+            let body = serde_json::to_vec(notification).map_err(NotifyErr::FailedToSerialize)?;
+
+            // '-f' fails on HTTP errors, '-sS' only displays errors, '@-' reads the body from the standard input
+            run(
+                "curl",
+                &[
+                    "-fsS".to_owned(),
+                    "-X".to_owned(),
+                    "POST".to_owned(),
+                    "-H".to_owned(),
+                    "Content-Type: application/json".to_owned(),
+                    "--data-binary".to_owned(),
+                    "@-".to_owned(),
+                    url.clone(),
+                ],
+                Some(&body),
+            )
+        }
+    }
+}
+
+/// Run an external program, optionally writing data to its standard input
+fn run(program: &'static str, args: &[String], stdin: Option<&[u8]>) -> Result<(), NotifyErr> {
+    let path = doctor::find_executable(program).ok_or(NotifyErr::ProgramNotFound(program))?;
+
+    let mut cmd = Command::new(path);
+
+    cmd.args(args)
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    trace!("Running external program: {:?}", cmd);
+
+    let mut child = cmd.spawn().map_err(|err| NotifyErr::FailedToRun(program, err))?;
+
+    if let (Some(data), Some(mut child_stdin)) = (stdin, child.stdin.take()) {
+        child_stdin
+            .write_all(data)
+            .map_err(|err| NotifyErr::FailedToRun(program, err))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|err| NotifyErr::FailedToRun(program, err))?;
+
+    if !output.status.success() {
+        return Err(NotifyErr::ProgramFailed(
+            program,
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Error while sending a notification
+pub enum NotifyErr {
+    DesktopNotSupported,
+    FailedToSerialize(serde_json::Error),
+    ProgramNotFound(&'static str),
+    FailedToRun(&'static str, io::Error),
+    ProgramFailed(&'static str, String),
+}
+
+impl fmt::Display for NotifyErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DesktopNotSupported => {
+                write!(f, "Desktop notifications are not supported on this platform")
+            }
+            Self::FailedToSerialize(err) => write!(f, "Failed to serialize notification: {}", err),
+            Self::ProgramNotFound(program) => write!(
+                f,
+                "Program '{}' was not found in PATH (it is required to send this notification)",
+                program
+            ),
+            Self::FailedToRun(program, err) => write!(f, "Failed to run '{}': {}", program, err),
+            Self::ProgramFailed(program, stderr) if stderr.is_empty() => {
+                write!(f, "Program '{}' failed", program)
+            }
+            Self::ProgramFailed(program, stderr) => {
+                write!(f, "Program '{}' failed: {}", program, stderr)
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use rayon::ThreadPool;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Condvar, Mutex};
+use std::thread;
+
+/// Limits on the items loaded ahead of the one being consumed
+pub struct PipelineLimits<'a> {
+    /// Maximum number of items loaded ahead
+    pub window: usize,
+    /// Estimated cost (e.g. memory) of each item
+    pub costs: &'a [u64],
+    /// Maximum total cost of the items loaded ahead (an item exceeding it on its own is still loaded)
+    pub max_cost: u64,
+}
+
+/// Buffers reused across items, to avoid allocating a new one for each of them
+#[derive(Default)]
+pub struct BufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+}
+
+impl BufferPool {
+    /// Take an empty buffer from the pool, which may already have some capacity
+    pub fn take(&self) -> Vec<u8> {
+        self.buffers.lock().unwrap().pop().unwrap_or_default()
+    }
+
+    /// Give a buffer back to the pool once its content isn't needed anymore
+    pub fn give_back(&self, mut buffer: Vec<u8>) {
+        buffer.clear();
+        self.buffers.lock().unwrap().push(buffer);
+    }
+}
+
+/// State shared between the loading threads and the consumer
+struct PipelineState {
+    /// Number of items consumed so far
+    consumed: usize,
+    /// Set when the consumer stops, e.g. after an error
+    stopped: bool,
+}
+
+/// Load items in parallel in the provided thread pool, and consume them in order as soon as they are loaded
+/// Each thread loads the next item as soon as it's done with the previous one, so a slow item only delays the consumer
+/// The consumer's first error stops the pipeline and is returned
+pub fn ordered_pipeline<T, R, E>(
+    pool: &ThreadPool,
+    items: &[T],
+    limits: &PipelineLimits,
+    load: impl Fn(&T) -> R + Sync,
+    mut consume: impl FnMut(&T, R) -> Result<(), E>,
+) -> Result<(), E>
+where
+    T: Sync,
+    R: Send,
+{
+    // Total cost of the items before each index
+    let mut total_costs = vec![0u64];
+
+    for i in 0..items.len() {
+        let cost = limits.costs.get(i).copied().unwrap_or(0);
+        total_costs.push(total_costs[i].saturating_add(cost));
+    }
+
+    let next = AtomicUsize::new(0);
+    let state = Mutex::new(PipelineState {
+        consumed: 0,
+        stopped: false,
+    });
+    let state_changed = Condvar::new();
+
+    // Check if an item can be loaded without exceeding the limits
+    let can_load = |i: usize, state: &PipelineState| {
+        i == state.consumed
+            || (i < state.consumed + limits.window.max(1)
+                && total_costs[i + 1] - total_costs[state.consumed] <= limits.max_cost)
+    };
+
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+
+        let (next, state, state_changed, load, can_load) =
+            (&next, &state, &state_changed, &load, &can_load);
+
+        scope.spawn(move || {
+            pool.scope(|pool_scope| {
+                for _ in 0..pool.current_num_threads() {
+                    let sender = sender.clone();
+
+                    pool_scope.spawn(move |_| loop {
+                        // Items are picked in order, so the one to consume next is always being loaded
+                        let i = next.fetch_add(1, Ordering::SeqCst);
+
+                        if i >= items.len() {
+                            return;
+                        }
+
+                        let mut guard = state.lock().unwrap();
+
+                        while !guard.stopped && !can_load(i, &guard) {
+                            guard = state_changed.wait(guard).unwrap();
+                        }
+
+                        if guard.stopped {
+                            return;
+                        }
+
+                        drop(guard);
+
+                        if sender.send((i, load(&items[i]))).is_err() {
+                            return;
+                        }
+                    });
+                }
+            });
+        });
+
+        // Items loaded before the ones preceding them
+        let mut pending = BTreeMap::new();
+
+        let mut consume_all = || {
+            for (i, item) in items.iter().enumerate() {
+                let loaded = loop {
+                    if let Some(loaded) = pending.remove(&i) {
+                        break loaded;
+                    }
+
+                    let (j, loaded) = receiver
+                        .recv()
+                        .expect("Internal error: pipeline stopped before loading all items");
+
+                    pending.insert(j, loaded);
+                };
+
+                consume(item, loaded)?;
+
+                state.lock().unwrap().consumed = i + 1;
+                state_changed.notify_all();
+            }
+
+            Ok(())
+        };
+
+        let result = consume_all();
+
+        // Stop the loading threads
+        state.lock().unwrap().stopped = true;
+        state_changed.notify_all();
+        drop(receiver);
+
+        result
+    })
+}
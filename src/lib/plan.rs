@@ -0,0 +1,180 @@
+use crate::lib::chapter::ChapterNumber;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the file tracking the progress of a compilation, in the output directory
+pub const PROGRESS_FILENAME: &str = ".comic-enc-progress.json";
+
+/// A chapter to put in a volume
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlannedChapter {
+    /// Number of the chapter, starting at 1 (e.g. 10, "10.5" or "Extra 3")
+    pub number: ChapterNumber,
+    /// Path to the chapter's directory
+    pub path: PathBuf,
+    /// Chapter's directory's file name
+    pub name: String,
+}
+
+impl PlannedChapter {
+    /// Get the chapter as expected by the volumes builder
+    pub fn to_tuple(&self) -> (ChapterNumber, PathBuf, String) {
+        (self.number, self.path.clone(), self.name.clone())
+    }
+}
+
+/// A volume to build, with the chapters it contains
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlannedVolume {
+    /// Number of the volume, starting at 1
+    pub volume: usize,
+    /// Chapters of this volume
+    pub chapters: Vec<PlannedChapter>,
+}
+
+impl PlannedVolume {
+    /// Get the number of the first chapter in this volume
+    pub fn start_chapter(&self) -> ChapterNumber {
+        self.chapters
+            .first()
+            .map(|chapter| chapter.number)
+            .unwrap_or_else(|| ChapterNumber::new(1))
+    }
+}
+
+/// The volumes to build from a directory of chapters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompilationPlan {
+    /// Directory where the volumes will be put
+    pub output: PathBuf,
+    /// Maximum string length of the volume number
+    pub vol_num_len: usize,
+    /// Maximum string length of the chapter number
+    pub chapter_num_len: usize,
+    /// Volumes to build
+    pub volumes: Vec<PlannedVolume>,
+}
+
+impl CompilationPlan {
+    /// Load a plan from a JSON file
+    pub fn load(path: &Path) -> Result<Self, PlanErr> {
+        let content = fs::read_to_string(path).map_err(PlanErr::IOError)?;
+        let plan: Self = serde_json::from_str(&content).map_err(PlanErr::InvalidPlan)?;
+
+        for (i, volume) in plan.volumes.iter().enumerate() {
+            if volume.chapters.is_empty() {
+                return Err(PlanErr::EmptyVolume(volume.volume));
+            }
+
+            if plan.volumes[..i]
+                .iter()
+                .any(|other| other.volume == volume.volume)
+            {
+                return Err(PlanErr::DuplicateVolume(volume.volume));
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Save the plan to a JSON file
+    pub fn save(&self, path: &Path) -> Result<(), PlanErr> {
+        let content = serde_json::to_string_pretty(self).map_err(PlanErr::InvalidPlan)?;
+        fs::write(path, content).map_err(PlanErr::IOError)
+    }
+}
+
+/// A volume built by an interrupted compilation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompletedVolume {
+    pub volume: usize,
+    pub path: PathBuf,
+}
+
+/// Progress of a compilation, used to resume it if it gets interrupted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompilationProgress {
+    /// Plan of the compilation
+    pub plan: CompilationPlan,
+    /// Volumes already built
+    pub completed: Vec<CompletedVolume>,
+}
+
+impl CompilationProgress {
+    /// Start tracking the progress of a plan
+    pub fn new(plan: CompilationPlan) -> Self {
+        Self {
+            plan,
+            completed: vec![],
+        }
+    }
+
+    /// Load the progress of an interrupted compilation from its output directory, if any
+    pub fn load(output: &Path) -> Result<Option<Self>, PlanErr> {
+        let path = output.join(PROGRESS_FILENAME);
+
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path).map_err(PlanErr::IOError)?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(PlanErr::InvalidPlan)
+    }
+
+    /// Save the progress to the output directory
+    pub fn save(&self) -> Result<(), PlanErr> {
+        let content = serde_json::to_string_pretty(self).map_err(PlanErr::InvalidPlan)?;
+        fs::write(self.plan.output.join(PROGRESS_FILENAME), content).map_err(PlanErr::IOError)
+    }
+
+    /// Remove the progress file once the compilation is complete
+    pub fn remove(&self) -> Result<(), PlanErr> {
+        fs::remove_file(self.plan.output.join(PROGRESS_FILENAME)).map_err(PlanErr::IOError)
+    }
+
+    /// Get the path of a volume built before the compilation was interrupted, if it still exists
+    pub fn completed_volume(&self, volume: usize) -> Option<&PathBuf> {
+        self.completed
+            .iter()
+            .find(|completed| completed.volume == volume)
+            .map(|completed| &completed.path)
+            .filter(|path| path.is_file())
+    }
+}
+
+/// Error while loading or saving a plan
+#[derive(Debug)]
+pub enum PlanErr {
+    IOError(io::Error),
+    InvalidPlan(serde_json::Error),
+    EmptyVolume(usize),
+    DuplicateVolume(usize),
+}
+
+impl fmt::Display for PlanErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidPlan(err) => write!(f, "Invalid plan file: {}", err),
+            Self::EmptyVolume(volume) => {
+                write!(f, "Volume {} does not contain any chapter", volume)
+            }
+            Self::DuplicateVolume(volume) => {
+                write!(f, "Volume {} is planned multiple times", volume)
+            }
+        }
+    }
+}
+
+impl Error for PlanErr {}
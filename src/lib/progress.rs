@@ -0,0 +1,144 @@
+use crate::lib::chapter::ChapterNumber;
+use crate::lib::events::{self, Event};
+use crate::lib::progress_bars;
+use crate::lib::summary::VolumeSummary;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Receiver of the progress of the volumes being built
+/// Volumes may be built in parallel, so the methods can be called from multiple threads at once
+pub trait ProgressSink: Send + Sync {
+    /// A volume containing the provided number of chapters started being built
+    fn volume_started(&self, _volume: usize, _chapters: usize) {}
+
+    /// The pages of a new chapter of a volume started being written (with no page if the chapter is left out)
+    fn chapter_started(&self, _volume: usize, _chapter: ChapterNumber, _pages: usize) {}
+
+    /// A page was written to a volume, after being converted if required
+    fn page_processed(
+        &self,
+        _volume: usize,
+        _chapter: ChapterNumber,
+        _page: usize,
+        _source: &Path,
+        _size: u64,
+    ) {
+    }
+
+    /// A volume was built
+    fn volume_finished(&self, _summary: &VolumeSummary) {}
+
+    /// A volume was not built as it already exists
+    fn volume_skipped(&self, _volume: usize, _path: &Path) {}
+
+    /// Something went wrong without preventing the volumes from being built
+    fn warning(&self, _message: &str) {}
+}
+
+/// Progress reporting of the command line: progress bars, JSON events with '--log-format json' and warning messages
+pub struct CliProgress;
+
+impl ProgressSink for CliProgress {
+    fn volume_started(&self, volume: usize, chapters: usize) {
+        progress_bars::volume_started(volume, chapters);
+        events::emit(&Event::VolumeStarted { volume, chapters });
+    }
+
+    fn chapter_started(&self, volume: usize, _chapter: ChapterNumber, pages: usize) {
+        progress_bars::chapter_started(volume, pages);
+    }
+
+    fn page_processed(
+        &self,
+        volume: usize,
+        chapter: ChapterNumber,
+        page: usize,
+        source: &Path,
+        size: u64,
+    ) {
+        progress_bars::page_written(volume, size);
+        events::emit(&Event::PageConverted {
+            volume,
+            chapter,
+            page,
+            source,
+            size,
+        });
+    }
+
+    fn volume_finished(&self, summary: &VolumeSummary) {
+        events::emit(&Event::VolumeFinished {
+            volume: summary.volume,
+            path: &summary.path,
+            size: summary.size,
+            pages: summary.pages,
+            seconds: summary.seconds,
+        });
+        progress_bars::volume_finished(summary.volume);
+    }
+
+    fn volume_skipped(&self, volume: usize, path: &Path) {
+        progress_bars::volume_finished(volume);
+        events::emit(&Event::VolumeSkipped { volume, path });
+    }
+
+    fn warning(&self, message: &str) {
+        warn!("Warning: {}", message);
+    }
+}
+
+/// Progress sink forwarding everything to another one, while keeping the warnings for the run's report
+pub struct ReportingProgress<'a> {
+    inner: &'a dyn ProgressSink,
+    warnings: Mutex<Vec<String>>,
+}
+
+impl<'a> ReportingProgress<'a> {
+    /// Forward the progress to the provided sink
+    pub fn new(inner: &'a dyn ProgressSink) -> Self {
+        Self {
+            inner,
+            warnings: Mutex::new(vec![]),
+        }
+    }
+
+    /// Take the warnings reported so far
+    pub fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut *self.warnings.lock().unwrap())
+    }
+}
+
+impl ProgressSink for ReportingProgress<'_> {
+    fn volume_started(&self, volume: usize, chapters: usize) {
+        self.inner.volume_started(volume, chapters);
+    }
+
+    fn chapter_started(&self, volume: usize, chapter: ChapterNumber, pages: usize) {
+        self.inner.chapter_started(volume, chapter, pages);
+    }
+
+    fn page_processed(
+        &self,
+        volume: usize,
+        chapter: ChapterNumber,
+        page: usize,
+        source: &Path,
+        size: u64,
+    ) {
+        self.inner
+            .page_processed(volume, chapter, page, source, size);
+    }
+
+    fn volume_finished(&self, summary: &VolumeSummary) {
+        self.inner.volume_finished(summary);
+    }
+
+    fn volume_skipped(&self, volume: usize, path: &Path) {
+        self.inner.volume_skipped(volume, path);
+    }
+
+    fn warning(&self, message: &str) {
+        self.warnings.lock().unwrap().push(message.to_owned());
+        self.inner.warning(message);
+    }
+}
@@ -0,0 +1,256 @@
+use crate::lib::deter;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Width of the bars, in characters
+const BAR_WIDTH: usize = 24;
+
+/// Minimum delay between two redraws of the bars triggered by written pages
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Are progress bars allowed to be displayed?
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Progress bars currently displayed, shared by all the threads building volumes
+static DISPLAY: Mutex<Option<Display>> = Mutex::new(None);
+
+/// Progress of a volume being built
+struct VolumeBar {
+    volume: usize,
+    chapters: usize,
+    chapter: usize,
+    chapter_pages: usize,
+    chapter_pages_done: usize,
+    pages: usize,
+}
+
+impl VolumeBar {
+    /// Get the part of the volume that has been built, between 0 and 1
+    fn ratio(&self) -> f64 {
+        if self.chapters == 0 {
+            return 0.0;
+        }
+
+        let chapter_ratio = if self.chapter_pages > 0 {
+            self.chapter_pages_done as f64 / self.chapter_pages as f64
+        } else {
+            0.0
+        };
+
+        ((self.chapter.saturating_sub(1) as f64 + chapter_ratio) / self.chapters as f64).min(1.0)
+    }
+}
+
+/// State of the progress bars
+struct Display {
+    started: Instant,
+    volumes: usize,
+    volumes_done: usize,
+    pages: usize,
+    written: u64,
+    building: Vec<VolumeBar>,
+    drawn_lines: usize,
+    last_draw: Instant,
+}
+
+impl Display {
+    /// Get the part of the whole work that has been done, between 0 and 1
+    fn ratio(&self) -> f64 {
+        if self.volumes == 0 {
+            return 1.0;
+        }
+
+        let building: f64 = self.building.iter().map(VolumeBar::ratio).sum();
+
+        ((self.volumes_done as f64 + building) / self.volumes as f64).min(1.0)
+    }
+
+    /// Erase the bars from the terminal
+    fn clear(&mut self, out: &mut impl Write) {
+        if self.drawn_lines > 0 {
+            let _ = write!(out, "\x1B[{}A\x1B[J", self.drawn_lines);
+            self.drawn_lines = 0;
+        }
+    }
+
+    /// Draw the bars on the terminal, replacing the previous ones
+    fn draw(&mut self, out: &mut impl Write) {
+        self.clear(out);
+
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let ratio = self.ratio();
+
+        let rate = if elapsed > 0.0 {
+            self.pages as f64 / elapsed
+        } else {
+            0.0
+        };
+
+        let eta = if ratio > 0.0 && ratio < 1.0 {
+            format_duration(elapsed * (1.0 - ratio) / ratio)
+        } else {
+            "-".to_owned()
+        };
+
+        let mut lines = vec![format!(
+            "Overall   {} {}/{} volumes | {} pages | {} written | {:.1} pages/s | ETA {}",
+            bar(ratio),
+            self.volumes_done,
+            self.volumes,
+            self.pages,
+            deter::format_size(self.written),
+            rate,
+            eta
+        )];
+
+        for vol in &self.building {
+            lines.push(format!(
+                "Volume {:<3}{} chapter {}/{} | {} pages",
+                vol.volume,
+                bar(vol.ratio()),
+                vol.chapter.max(1),
+                vol.chapters,
+                vol.pages
+            ));
+        }
+
+        for line in &lines {
+            let _ = writeln!(out, "\x1B[2K{}", line);
+        }
+
+        let _ = out.flush();
+
+        self.drawn_lines = lines.len();
+        self.last_draw = Instant::now();
+    }
+}
+
+/// Draw a bar filled up to the provided ratio
+fn bar(ratio: f64) -> String {
+    let filled = ((ratio * BAR_WIDTH as f64) as usize).min(BAR_WIDTH);
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled))
+}
+
+/// Format a number of seconds as minutes and seconds
+fn format_duration(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    format!("{}m {:02}s", secs / 60, secs % 60)
+}
+
+/// Update the bars if they are displayed, redrawing them if required
+fn update(force_redraw: bool, func: impl FnOnce(&mut Display)) {
+    let mut display = DISPLAY.lock().unwrap();
+
+    if let Some(display) = display.as_mut() {
+        func(display);
+
+        if force_redraw || display.last_draw.elapsed() >= REDRAW_INTERVAL {
+            display.draw(&mut io::stderr());
+        }
+    }
+}
+
+/// Allow progress bars to be displayed, if STDERR is a terminal
+pub fn enable() {
+    ENABLED.store(io::stderr().is_terminal(), Ordering::SeqCst);
+}
+
+/// Guard removing the progress bars from the terminal when dropped
+pub struct ProgressGuard;
+
+impl Drop for ProgressGuard {
+    fn drop(&mut self) {
+        if let Some(mut display) = DISPLAY.lock().unwrap().take() {
+            display.clear(&mut io::stderr());
+        }
+    }
+}
+
+/// Start displaying progress bars for the provided number of volumes, if they are enabled
+/// The bars are removed when the returned guard is dropped
+pub fn start(volumes: usize) -> ProgressGuard {
+    if ENABLED.load(Ordering::SeqCst) {
+        let mut display = Display {
+            started: Instant::now(),
+            volumes,
+            volumes_done: 0,
+            pages: 0,
+            written: 0,
+            building: vec![],
+            drawn_lines: 0,
+            last_draw: Instant::now(),
+        };
+
+        display.draw(&mut io::stderr());
+
+        *DISPLAY.lock().unwrap() = Some(display);
+    }
+
+    ProgressGuard
+}
+
+/// Indicate a volume containing the provided number of chapters started being built
+pub fn volume_started(volume: usize, chapters: usize) {
+    update(true, |display| {
+        display.building.push(VolumeBar {
+            volume,
+            chapters,
+            chapter: 0,
+            chapter_pages: 0,
+            chapter_pages_done: 0,
+            pages: 0,
+        })
+    });
+}
+
+/// Indicate the pages of a new chapter of a volume started being written
+pub fn chapter_started(volume: usize, pages: usize) {
+    update(false, |display| {
+        if let Some(vol) = display.building.iter_mut().find(|vol| vol.volume == volume) {
+            vol.chapter += 1;
+            vol.chapter_pages = pages;
+            vol.chapter_pages_done = 0;
+        }
+    });
+}
+
+/// Indicate a page of the provided size was written to a volume
+pub fn page_written(volume: usize, bytes: u64) {
+    update(false, |display| {
+        display.pages += 1;
+        display.written += bytes;
+
+        if let Some(vol) = display.building.iter_mut().find(|vol| vol.volume == volume) {
+            vol.chapter_pages_done += 1;
+            vol.pages += 1;
+        }
+    });
+}
+
+/// Indicate a volume is complete (or was skipped)
+pub fn volume_finished(volume: usize) {
+    update(true, |display| {
+        display.building.retain(|vol| vol.volume != volume);
+        display.volumes_done += 1;
+    });
+}
+
+/// Run a function printing to the terminal, removing the progress bars beforehand and drawing them back afterwards
+pub fn suspend<T>(func: impl FnOnce() -> T) -> T {
+    let mut display = DISPLAY.lock().unwrap();
+
+    match display.as_mut() {
+        None => func(),
+        Some(display) => {
+            let mut stderr = io::stderr();
+
+            display.clear(&mut stderr);
+            let result = func();
+            display.draw(&mut stderr);
+
+            result
+        }
+    }
+}
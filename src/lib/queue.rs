@@ -0,0 +1,85 @@
+use crate::lib::serve::{JobOptions, JobState, JobStatus};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Job of the server's queue, as stored in the queue file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedJob {
+    /// Options the job was submitted with
+    pub options: JobOptions,
+
+    /// Progress and outcome of the job
+    pub status: JobStatus,
+}
+
+/// Jobs submitted to the server, kept on disk so they survive restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobQueue {
+    /// Identifier of the next submitted job
+    pub next_job: u64,
+
+    /// Jobs in submission order, including the finished ones
+    pub jobs: Vec<QueuedJob>,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self {
+            next_job: 1,
+            jobs: vec![],
+        }
+    }
+}
+
+impl JobQueue {
+    /// Load the queue from a file, or start with an empty one if it doesn't exist yet
+    /// Jobs that were running when the server stopped are put back in the queue, their volumes being resumed
+    pub fn load(path: &Path) -> Result<Self, QueueErr> {
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path).map_err(QueueErr::IOError)?;
+        let mut queue: Self = serde_json::from_str(&content).map_err(QueueErr::InvalidQueue)?;
+
+        for job in &mut queue.jobs {
+            if job.status.state == JobState::Running {
+                job.status = JobStatus::new(job.status.job, job.status.input.clone());
+            }
+        }
+
+        Ok(queue)
+    }
+
+    /// Save the queue to a file
+    /// It is written to a temporary file first, so an interrupted write doesn't lose the queue
+    pub fn save(&self, path: &Path) -> Result<(), QueueErr> {
+        let content = serde_json::to_string_pretty(self).map_err(QueueErr::InvalidQueue)?;
+        let tmp_path = path.with_extension("tmp");
+
+        fs::write(&tmp_path, content).map_err(QueueErr::IOError)?;
+        fs::rename(&tmp_path, path).map_err(QueueErr::IOError)
+    }
+}
+
+/// Error while loading or saving the job queue
+#[derive(Debug)]
+pub enum QueueErr {
+    IOError(io::Error),
+    InvalidQueue(serde_json::Error),
+}
+
+impl fmt::Display for QueueErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidQueue(err) => write!(f, "Invalid queue file: {}", err),
+        }
+    }
+}
+
+impl Error for QueueErr {}
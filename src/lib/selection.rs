@@ -0,0 +1,95 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Selection of pages, as a list of numbers and ranges (e.g. '1-20,35' or '100-')
+/// Pages are numbered from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageSelection {
+    /// Inclusive ranges of pages, the end being optional for open ranges
+    ranges: Vec<(usize, Option<usize>)>,
+}
+
+impl PageSelection {
+    /// Check if a page is part of the selection
+    pub fn contains(&self, page: usize) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(start, end)| page >= start && end.map_or(true, |end| page <= end))
+    }
+}
+
+/// Parse a page number
+fn parse_page(text: &str) -> Result<usize, SelectionErr> {
+    match text.trim().parse::<usize>() {
+        Ok(0) => Err(SelectionErr::ZeroPage),
+        Ok(page) => Ok(page),
+        Err(_) => Err(SelectionErr::InvalidNumber(text.trim().to_owned())),
+    }
+}
+
+impl FromStr for PageSelection {
+    type Err = SelectionErr;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut ranges = vec![];
+
+        for segment in text.split(',') {
+            let segment = segment.trim();
+
+            if segment.is_empty() {
+                return Err(SelectionErr::EmptySegment);
+            }
+
+            let range = match segment.split_once('-') {
+                None => {
+                    let page = parse_page(segment)?;
+                    (page, Some(page))
+                }
+
+                Some((start, end)) if end.trim().is_empty() => (parse_page(start)?, None),
+
+                Some((start, end)) => {
+                    let start = parse_page(start)?;
+                    let end = parse_page(end)?;
+
+                    if start > end {
+                        return Err(SelectionErr::ReversedRange(start, end));
+                    }
+
+                    (start, Some(end))
+                }
+            };
+
+            ranges.push(range);
+        }
+
+        Ok(Self { ranges })
+    }
+}
+
+/// Page selection parsing error
+#[derive(Debug)]
+pub enum SelectionErr {
+    EmptySegment,
+    InvalidNumber(String),
+    ZeroPage,
+    ReversedRange(usize, usize),
+}
+
+impl fmt::Display for SelectionErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::EmptySegment => write!(f, "Page selection contains an empty item"),
+            Self::InvalidNumber(text) => write!(f, "Invalid page number '{}'", text),
+            Self::ZeroPage => write!(f, "Pages are numbered from 1"),
+            Self::ReversedRange(start, end) => write!(
+                f,
+                "Invalid page range '{}-{}' (start is higher than end)",
+                start, end
+            ),
+        }
+    }
+}
+
+impl Error for SelectionErr {}
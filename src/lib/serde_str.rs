@@ -0,0 +1,72 @@
+use serde::de::{Deserialize, Deserializer, Error};
+use serde::Serializer;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Serialize a value as text, for types parsed from the command line (e.g. regular expressions, glob patterns)
+/// To use with `#[serde(with = "serde_str")]`
+pub fn serialize<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// Deserialize a value from text, the same way it is parsed from the command line
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(D::Error::custom)
+}
+
+/// Optional values serialized as text, to use with `#[serde(default, with = "serde_str::option")]`
+pub mod option {
+    use super::*;
+
+    pub fn serialize<T: Display, S: Serializer>(
+        value: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_some(&value.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| value.parse().map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+/// Lists of values serialized as text, to use with `#[serde(default, with = "serde_str::vec")]`
+pub mod vec {
+    use super::*;
+
+    pub fn serialize<T: Display, S: Serializer>(
+        values: &[T],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(ToString::to_string))
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|value| value.parse().map_err(D::Error::custom))
+            .collect()
+    }
+}
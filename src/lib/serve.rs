@@ -0,0 +1,329 @@
+use crate::cli::opts::{CompilationOptions, EncodingOptions};
+use crate::lib::summary::JobReport;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::path::PathBuf;
+
+/// Path of the JSON-RPC endpoint
+pub const RPC_PATH: &str = "/rpc";
+
+/// Maximum size of a request's body, in bytes
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Maximum number of headers in a request
+const MAX_HEADERS: usize = 100;
+
+/// Options of a compilation job, in the options' serialized form (see `EncodingOptions` and `CompilationOptions`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobOptions {
+    pub encoding: EncodingOptions,
+    pub compilation: CompilationOptions,
+}
+
+/// State of a submitted job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Pending,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+/// Progress and outcome of a submitted job, as returned by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatus {
+    /// Identifier of the job
+    pub job: u64,
+
+    /// State of the job
+    pub state: JobState,
+
+    /// Directory or archive containing the chapters
+    pub input: PathBuf,
+
+    /// Number of volumes that started being built
+    pub volumes_started: usize,
+
+    /// Number of volumes that were built (or skipped as they already existed)
+    pub volumes_finished: usize,
+
+    /// Number of pages written so far
+    pub pages: usize,
+
+    /// Number of bytes written so far
+    pub size: u64,
+
+    /// Problems that didn't prevent the volumes from being built
+    pub warnings: Vec<String>,
+
+    /// Report of the job, once it is done
+    pub report: Option<JobReport>,
+
+    /// Error's message, if the job failed
+    pub error: Option<String>,
+
+    /// Code the command line would have exited with, once the job is finished
+    pub exit_code: Option<i32>,
+}
+
+impl JobStatus {
+    /// Status of a job that was just submitted
+    pub fn new(job: u64, input: PathBuf) -> Self {
+        Self {
+            job,
+            state: JobState::Pending,
+            input,
+            volumes_started: 0,
+            volumes_finished: 0,
+            pages: 0,
+            size: 0,
+            warnings: vec![],
+            report: None,
+            error: None,
+            exit_code: None,
+        }
+    }
+
+    /// Check if the job is finished, successfully or not
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self.state,
+            JobState::Done | JobState::Failed | JobState::Cancelled
+        )
+    }
+}
+
+/// Parameters of the methods targeting a single job
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobParams {
+    pub job: u64,
+}
+
+/// JSON-RPC request
+#[derive(Debug, Deserialize)]
+pub struct RpcRequest {
+    /// Identifier of the request, provided back in the response (none for notifications)
+    #[serde(default)]
+    pub id: Value,
+
+    /// Name of the called method
+    pub method: String,
+
+    /// Parameters of the method
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// JSON-RPC response
+#[derive(Debug, Serialize)]
+pub struct RpcResponse {
+    pub jsonrpc: &'static str,
+
+    pub id: Value,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    /// Response to a successful call
+    pub fn success(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    /// Response to a failed call
+    pub fn failure(id: Value, error: RpcError) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Error of a JSON-RPC call
+#[derive(Debug, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    /// The request is not valid JSON
+    pub fn parse_error(err: impl fmt::Display) -> Self {
+        Self {
+            code: -32700,
+            message: format!("Parse error: {}", err),
+        }
+    }
+
+    /// The request is not a valid call
+    pub fn invalid_request(err: impl fmt::Display) -> Self {
+        Self {
+            code: -32600,
+            message: format!("Invalid request: {}", err),
+        }
+    }
+
+    /// The called method doesn't exist
+    pub fn method_not_found(method: &str) -> Self {
+        Self {
+            code: -32601,
+            message: format!("Method not found: '{}'", method),
+        }
+    }
+
+    /// The parameters of the method are invalid
+    pub fn invalid_params(err: impl fmt::Display) -> Self {
+        Self {
+            code: -32602,
+            message: format!("Invalid parameters: {}", err),
+        }
+    }
+
+    /// The targeted job doesn't exist
+    pub fn job_not_found(job: u64) -> Self {
+        Self {
+            code: 1,
+            message: format!("Job {} was not found", job),
+        }
+    }
+}
+
+/// HTTP request received by the server
+#[derive(Debug)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+/// Read an HTTP request from a connection
+pub fn read_request(reader: &mut impl BufRead) -> Result<HttpRequest, HttpErr> {
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(HttpErr::IOError)?;
+
+    let mut parts = line.split_whitespace();
+
+    let (method, path) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(path), Some(version)) if version.starts_with("HTTP/1.") => {
+            (method.to_owned(), path.to_owned())
+        }
+        _ => return Err(HttpErr::InvalidRequestLine(line.trim_end().to_owned())),
+    };
+
+    let mut content_length = 0;
+    let mut headers = 0;
+
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(HttpErr::IOError)?;
+
+        let header = line.trim_end();
+
+        if header.is_empty() {
+            break;
+        }
+
+        headers += 1;
+
+        if headers > MAX_HEADERS {
+            return Err(HttpErr::TooManyHeaders);
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| HttpErr::InvalidContentLength(value.trim().to_owned()))?;
+            }
+        }
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        return Err(HttpErr::BodyTooLarge(content_length));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(HttpErr::IOError)?;
+
+    Ok(HttpRequest { method, path, body })
+}
+
+/// Write an HTTP response with a JSON body to a connection, which is closed afterwards
+pub fn write_response(writer: &mut impl Write, status: u16, body: &[u8]) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    };
+
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        body.len()
+    )?;
+
+    writer.write_all(body)?;
+    writer.flush()
+}
+
+/// Error while reading an HTTP request
+#[derive(Debug)]
+pub enum HttpErr {
+    IOError(io::Error),
+    InvalidRequestLine(String),
+    TooManyHeaders,
+    InvalidContentLength(String),
+    BodyTooLarge(usize),
+}
+
+impl HttpErr {
+    /// Get the HTTP status to respond with
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::BodyTooLarge(_) => 413,
+            _ => 400,
+        }
+    }
+}
+
+impl fmt::Display for HttpErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidRequestLine(line) => write!(f, "Invalid request line '{}'", line),
+            Self::TooManyHeaders => write!(f, "Too many headers (maximum {})", MAX_HEADERS),
+            Self::InvalidContentLength(value) => write!(f, "Invalid content length '{}'", value),
+            Self::BodyTooLarge(size) => write!(
+                f,
+                "Request body is too large ({} bytes, maximum {})",
+                size, MAX_BODY_SIZE
+            ),
+        }
+    }
+}
+
+impl Error for HttpErr {}
@@ -0,0 +1,368 @@
+use crate::lib::archive::{ArchiveErr, ComicArchive};
+use crate::lib::deter::{self, RecursiveFilesSearchErr};
+use crate::lib::external::{self, ExternalErr, ExternalFormat};
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Number of archives extracted by this process, to give each one its own temporary directory
+static EXTRACTED: AtomicUsize = AtomicUsize::new(0);
+
+/// Where the chapters and pages of a compilation are read from
+/// Chapters are the directories directly under the source's root, and their pages are the files they contain
+/// (recursively). Paths are rooted at `root()`, even when the pages don't live on the filesystem, so they can be
+/// displayed and stored in plans the same way for every source
+pub trait PageSource: Send + Sync {
+    /// Get the root of the source (the input directory or archive)
+    fn root(&self) -> &Path;
+
+    /// List the chapters' directories, with their name
+    fn chapters(&self) -> Result<Vec<(PathBuf, String)>, RecursiveFilesSearchErr>;
+
+    /// List the files of a chapter, recursively
+    fn files(&self, chapter: &Path) -> Result<Vec<PathBuf>, RecursiveFilesSearchErr>;
+
+    /// Get the size of a file, in bytes
+    fn size(&self, file: &Path) -> io::Result<u64>;
+
+    /// Open a file for reading
+    fn open(&self, file: &Path) -> io::Result<Box<dyn Read + '_>>;
+
+    /// Get the date a file or a chapter's directory was last modified
+    fn modified(&self, path: &Path) -> io::Result<SystemTime>;
+}
+
+/// Open the chapters of a compilation: a directory, or an archive containing one directory per chapter
+/// With `default_ignores`, the files created by operating systems and hidden files are ignored (see `deter::is_os_junk`)
+pub fn open_source(path: &Path, default_ignores: bool) -> Result<Box<dyn PageSource>, SourceErr> {
+    if path.is_dir() {
+        return Ok(Box::new(DirSource::new(path, default_ignores)));
+    }
+
+    if !path.is_file() {
+        return Err(SourceErr::NotFound);
+    }
+
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    match ext.as_str() {
+        "zip" | "cbz" => Ok(Box::new(ZipSource::open(path, default_ignores)?)),
+        "rar" | "cbr" => Ok(Box::new(ExtractedSource::extract(
+            ExternalFormat::Rar,
+            path,
+            default_ignores,
+        )?)),
+        "7z" | "cb7" => Ok(Box::new(ExtractedSource::extract(
+            ExternalFormat::SevenZip,
+            path,
+            default_ignores,
+        )?)),
+        _ => Err(SourceErr::UnsupportedFormat(ext)),
+    }
+}
+
+/// Chapters read from a directory
+pub struct DirSource {
+    root: PathBuf,
+    default_ignores: bool,
+}
+
+impl DirSource {
+    /// Read the chapters of a directory
+    pub fn new(root: impl Into<PathBuf>, default_ignores: bool) -> Self {
+        Self {
+            root: root.into(),
+            default_ignores,
+        }
+    }
+
+    /// Check if a file or directory must be ignored
+    fn is_ignored(&self, path: &Path) -> bool {
+        self.default_ignores && deter::is_os_junk(path)
+    }
+}
+
+impl PageSource for DirSource {
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn chapters(&self) -> Result<Vec<(PathBuf, String)>, RecursiveFilesSearchErr> {
+        let mut chapters = vec![];
+
+        for entry in fs::read_dir(&self.root).map_err(RecursiveFilesSearchErr::IOError)? {
+            let entry = entry.map_err(RecursiveFilesSearchErr::IOError)?;
+            let path = entry.path();
+
+            // Ignore files
+            // Names that are not valid UTF-8 are handled by the caller, depending on the '--on-invalid-name' option
+            if path.is_dir() && !self.is_ignored(&path) {
+                let name = entry.file_name().to_string_lossy().into_owned();
+
+                chapters.push((path, name));
+            }
+        }
+
+        Ok(chapters)
+    }
+
+    fn files(&self, chapter: &Path) -> Result<Vec<PathBuf>, RecursiveFilesSearchErr> {
+        deter::readdir_files_recursive_skipping(chapter, &|path: &Path| self.is_ignored(path))
+    }
+
+    fn size(&self, file: &Path) -> io::Result<u64> {
+        Ok(fs::metadata(file)?.len())
+    }
+
+    fn open(&self, file: &Path) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(File::open(file)?))
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(path)?.modified()
+    }
+}
+
+/// Chapters read from a ZIP archive, without extracting it
+/// Pages are decompressed one at a time when they are opened
+pub struct ZipSource {
+    root: PathBuf,
+    archive: Mutex<ComicArchive>,
+    /// Index, size and modification date of each file, by path
+    files: BTreeMap<PathBuf, (usize, u64, SystemTime)>,
+}
+
+impl ZipSource {
+    /// Open a ZIP archive
+    /// With `default_ignores`, entries created by operating systems or hidden (or inside such a directory) are ignored
+    pub fn open(path: &Path, default_ignores: bool) -> Result<Self, SourceErr> {
+        let archive = ComicArchive::open(path).map_err(SourceErr::ArchiveError)?;
+
+        let files = archive
+            .entries()
+            .iter()
+            .filter(|entry| !entry.is_dir)
+            .filter(|entry| !default_ignores || !entry.path.ancestors().any(deter::is_os_junk))
+            .map(|entry| {
+                (
+                    path.join(&entry.path),
+                    (entry.index, entry.size, entry.modified),
+                )
+            })
+            .collect();
+
+        Ok(Self {
+            root: path.to_path_buf(),
+            archive: Mutex::new(archive),
+            files,
+        })
+    }
+}
+
+impl PageSource for ZipSource {
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn chapters(&self) -> Result<Vec<(PathBuf, String)>, RecursiveFilesSearchErr> {
+        let mut chapters = BTreeMap::new();
+
+        // Directories are not always stored in ZIP archives, so they are found from their files' path
+        for file in self.files.keys() {
+            let relative = file.strip_prefix(&self.root).unwrap_or(file);
+            let mut components = relative.components();
+
+            if let (Some(Component::Normal(dir)), Some(_)) = (components.next(), components.next()) {
+                let name = dir
+                    .to_str()
+                    .ok_or_else(|| RecursiveFilesSearchErr::InvalidFileName(file.clone()))?;
+
+                chapters
+                    .entry(self.root.join(dir))
+                    .or_insert_with(|| name.to_owned());
+            }
+        }
+
+        Ok(chapters.into_iter().collect())
+    }
+
+    fn files(&self, chapter: &Path) -> Result<Vec<PathBuf>, RecursiveFilesSearchErr> {
+        Ok(self
+            .files
+            .keys()
+            .filter(|file| file.starts_with(chapter))
+            .cloned()
+            .collect())
+    }
+
+    fn size(&self, file: &Path) -> io::Result<u64> {
+        self.files
+            .get(file)
+            .map(|&(_, size, _)| size)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "file not found in archive"))
+    }
+
+    fn open(&self, file: &Path) -> io::Result<Box<dyn Read + '_>> {
+        let &(index, _, _) = self
+            .files
+            .get(file)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "file not found in archive"))?;
+
+        let content = self
+            .archive
+            .lock()
+            .expect("Internal error: archive's lock is poisoned")
+            .read_entry(index)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+        Ok(Box::new(Cursor::new(content)))
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        // Directories are not always stored in ZIP archives, so they are as recent as their most recent file
+        self.files
+            .iter()
+            .filter(|(file, _)| file.starts_with(path))
+            .map(|(_, &(_, _, modified))| modified)
+            .max()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "file not found in archive"))
+    }
+}
+
+/// Chapters read from an archive extracted by an external program (RAR and 7-Zip archives) to a temporary directory,
+/// which is removed once the source is dropped
+pub struct ExtractedSource {
+    root: PathBuf,
+    dir: PathBuf,
+    default_ignores: bool,
+}
+
+impl ExtractedSource {
+    /// Extract an archive to a temporary directory
+    pub fn extract(
+        format: ExternalFormat,
+        path: &Path,
+        default_ignores: bool,
+    ) -> Result<Self, SourceErr> {
+        let dir = env::temp_dir().join(format!(
+            "comic-enc-{}-{}",
+            process::id(),
+            EXTRACTED.fetch_add(1, Ordering::SeqCst)
+        ));
+
+        fs::create_dir_all(&dir).map_err(SourceErr::IOError)?;
+
+        // The directory is removed if the extraction fails
+        let source = Self {
+            root: path.to_path_buf(),
+            dir,
+            default_ignores,
+        };
+
+        debug!(
+            "Extracting '{}' to '{}'...",
+            path.to_string_lossy(),
+            source.dir.to_string_lossy()
+        );
+
+        external::extract(format, path, &source.dir).map_err(SourceErr::ExtractionFailed)?;
+
+        Ok(source)
+    }
+
+    /// Get the path of a file in the temporary directory
+    fn real_path(&self, path: &Path) -> PathBuf {
+        self.dir.join(path.strip_prefix(&self.root).unwrap_or(path))
+    }
+
+    /// Get the path of a file of the temporary directory, rooted at the archive
+    fn virtual_path(&self, path: &Path) -> PathBuf {
+        self.root.join(path.strip_prefix(&self.dir).unwrap_or(path))
+    }
+}
+
+impl PageSource for ExtractedSource {
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn chapters(&self) -> Result<Vec<(PathBuf, String)>, RecursiveFilesSearchErr> {
+        Ok(DirSource::new(&self.dir, self.default_ignores)
+            .chapters()?
+            .into_iter()
+            .map(|(path, name)| (self.virtual_path(&path), name))
+            .collect())
+    }
+
+    fn files(&self, chapter: &Path) -> Result<Vec<PathBuf>, RecursiveFilesSearchErr> {
+        Ok(DirSource::new(&self.dir, self.default_ignores)
+            .files(&self.real_path(chapter))?
+            .iter()
+            .map(|path| self.virtual_path(path))
+            .collect())
+    }
+
+    fn size(&self, file: &Path) -> io::Result<u64> {
+        Ok(fs::metadata(self.real_path(file))?.len())
+    }
+
+    fn open(&self, file: &Path) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(File::open(self.real_path(file))?))
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(self.real_path(path))?.modified()
+    }
+}
+
+impl Drop for ExtractedSource {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.dir) {
+            warn!(
+                "Warning: failed to remove temporary directory '{}': {}",
+                self.dir.to_string_lossy(),
+                err
+            );
+        }
+    }
+}
+
+/// Error while opening the chapters of a compilation
+#[derive(Debug)]
+pub enum SourceErr {
+    NotFound,
+    UnsupportedFormat(String),
+    ArchiveError(ArchiveErr),
+    ExtractionFailed(ExternalErr),
+    IOError(io::Error),
+}
+
+impl fmt::Display for SourceErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Input was not found"),
+            Self::UnsupportedFormat(ext) => write!(
+                f,
+                "Unsupported input format '{}' (expected a directory or a ZIP, CBZ, RAR, CBR, 7Z or CB7 archive)",
+                ext
+            ),
+            Self::ArchiveError(err) => write!(f, "{}", err),
+            Self::ExtractionFailed(err) => write!(f, "Failed to extract archive: {}", err),
+            Self::IOError(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for SourceErr {}
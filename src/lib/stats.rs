@@ -0,0 +1,140 @@
+use crate::lib::archive::{ArchiveErr, ComicArchive};
+use crate::lib::deter;
+use crate::lib::inspect::{self, InspectErr};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Statistics about the pages of a given image format
+#[derive(Debug, Default, Serialize)]
+pub struct FormatStats {
+    /// Number of pages
+    pub pages: usize,
+
+    /// Total size of the pages, in bytes
+    pub uncompressed_size: u64,
+
+    /// Total compressed size of the pages, in bytes
+    pub compressed_size: u64,
+}
+
+/// Average resolution of the pages
+#[derive(Debug, Serialize)]
+pub struct Resolution {
+    pub width: u64,
+    pub height: u64,
+}
+
+/// Statistics about a set of comic archives
+#[derive(Debug, Default, Serialize)]
+pub struct LibraryStats {
+    /// Number of comic archives
+    pub comics: usize,
+
+    /// Total number of pages
+    pub pages: usize,
+
+    /// Total size of the archive files, in bytes
+    pub file_size: u64,
+
+    /// Total size of the archives' files, in bytes
+    pub uncompressed_size: u64,
+
+    /// Total compressed size of the archives' files, in bytes
+    pub compressed_size: u64,
+
+    /// Total size of the source pictures the archives were built from, in bytes, if provided
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_size: Option<u64>,
+
+    /// Statistics for each image format
+    pub formats: BTreeMap<String, FormatStats>,
+
+    /// Average resolution of the pages, if any could be determined
+    pub average_resolution: Option<Resolution>,
+
+    /// Sum of the pages' width and height, and number of pages they were determined for
+    #[serde(skip)]
+    resolutions_sum: (u64, u64, u64),
+}
+
+impl LibraryStats {
+    /// Add a comic archive to the statistics
+    pub fn add_archive(&mut self, path: &Path, extended_formats: bool) -> Result<(), InspectErr> {
+        let mut archive = ComicArchive::open(path).map_err(InspectErr::ArchiveError)?;
+
+        self.file_size += fs::metadata(path)
+            .map_err(|err| InspectErr::ArchiveError(ArchiveErr::FailedToOpenFile(err)))?
+            .len();
+
+        for entry in archive.entries().iter().filter(|entry| !entry.is_dir) {
+            self.uncompressed_size += entry.size;
+            self.compressed_size += entry.compressed_size;
+        }
+
+        let pages = archive
+            .pages(extended_formats, false)
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for page in &pages {
+            let content = archive
+                .read_entry(page.index)
+                .map_err(InspectErr::ArchiveError)?;
+
+            let (format, resolution) = inspect::identify_page(&content);
+
+            let format = self
+                .formats
+                .entry(format.unwrap_or_else(|| "UNKNOWN".to_owned()))
+                .or_default();
+
+            format.pages += 1;
+            format.uncompressed_size += page.size;
+            format.compressed_size += page.compressed_size;
+
+            if let Some((width, height)) = resolution {
+                self.resolutions_sum.0 += u64::from(width);
+                self.resolutions_sum.1 += u64::from(height);
+                self.resolutions_sum.2 += 1;
+            }
+        }
+
+        self.comics += 1;
+        self.pages += pages.len();
+
+        let (width, height, count) = self.resolutions_sum;
+
+        if let (Some(width), Some(height)) = (width.checked_div(count), height.checked_div(count)) {
+            self.average_resolution = Some(Resolution { width, height });
+        }
+
+        Ok(())
+    }
+
+    /// Add the size of the source pictures found in a directory, recursively
+    pub fn add_source(
+        &mut self,
+        dir: &Path,
+        extended_formats: bool,
+    ) -> Result<(), deter::RecursiveFilesSearchErr> {
+        let pictures = deter::readdir_files_recursive(
+            dir,
+            Some(&|path: &Path| deter::has_image_ext(path, extended_formats)),
+        )?;
+
+        let mut size = self.source_size.unwrap_or(0);
+
+        for path in pictures {
+            size += fs::metadata(&path)
+                .map_err(deter::RecursiveFilesSearchErr::IOError)?
+                .len();
+        }
+
+        self.source_size = Some(size);
+
+        Ok(())
+    }
+}
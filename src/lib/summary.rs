@@ -0,0 +1,236 @@
+use crate::lib::archive::{ArchiveErr, ArchiveFormat, ComicArchive};
+use crate::lib::chapter::ChapterNumber;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Name of the file the summary of a run is written to, in the output directory
+pub const SUMMARY_FILENAME: &str = "summary.json";
+
+/// Summary of a volume produced by a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeSummary {
+    /// Number of the volume
+    pub volume: usize,
+
+    /// Path to the volume's file
+    pub path: PathBuf,
+
+    /// Number of pages in the volume
+    pub pages: usize,
+
+    /// Size of the volume's file, in bytes
+    pub size: u64,
+
+    /// Number of the volume's first chapter
+    pub first_chapter: ChapterNumber,
+
+    /// Number of the volume's last chapter
+    pub last_chapter: ChapterNumber,
+
+    /// Time taken to build the volume, in seconds
+    pub seconds: f64,
+
+    /// Was the volume already built (e.g. by an interrupted run)?
+    pub skipped: bool,
+}
+
+impl VolumeSummary {
+    /// Summarize a volume that was already built, reading its file
+    pub fn existing(
+        volume: usize,
+        path: &Path,
+        first_chapter: ChapterNumber,
+        last_chapter: ChapterNumber,
+        extended_formats: bool,
+    ) -> Result<Self, ArchiveErr> {
+        let size = fs::metadata(path)
+            .map_err(ArchiveErr::FailedToOpenFile)?
+            .len();
+
+        // Only the pages of ZIP archives can be counted
+        let pages = match ArchiveFormat::from_path(path) {
+            Some(_) => ComicArchive::open(path)?
+                .pages(extended_formats, true)
+                .len(),
+            None => 0,
+        };
+
+        Ok(Self {
+            volume,
+            path: path.to_path_buf(),
+            pages,
+            size,
+            first_chapter,
+            last_chapter,
+            seconds: 0.0,
+            skipped: true,
+        })
+    }
+}
+
+/// Summary of a comic book extracted by a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecodedSummary {
+    /// Path to the comic book (none when it was read from the standard input)
+    pub input: Option<PathBuf>,
+
+    /// Directory the comic book was extracted to
+    pub output: PathBuf,
+
+    /// Number of extracted files
+    pub pages: usize,
+
+    /// Total size of the extracted files, in bytes
+    pub size: u64,
+
+    /// Time taken to extract the comic book, in seconds
+    pub seconds: f64,
+
+    /// Extracted files
+    #[serde(skip)]
+    pub files: Vec<PathBuf>,
+}
+
+impl DecodedSummary {
+    /// Summarize an extracted comic book from its files
+    pub fn new(
+        input: Option<PathBuf>,
+        output: PathBuf,
+        files: Vec<PathBuf>,
+        started: Instant,
+    ) -> Self {
+        Self {
+            input,
+            output,
+            pages: files.len(),
+            size: files
+                .iter()
+                .filter_map(|file| fs::metadata(file).ok())
+                .map(|metadata| metadata.len())
+                .sum(),
+            seconds: started.elapsed().as_secs_f64(),
+            files,
+        }
+    }
+}
+
+/// Report of a run, listing the volumes it produced or the comic books it extracted
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobReport {
+    /// Produced volumes, including the ones that were already built
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<VolumeSummary>,
+
+    /// Extracted comic books
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub decoded: Vec<DecodedSummary>,
+
+    /// Total number of pages in the volumes or extracted comic books
+    pub pages: usize,
+
+    /// Total size of the volumes or extracted files, in bytes
+    pub size: u64,
+
+    /// Number of volumes that were not built as they already existed
+    pub skipped: usize,
+
+    /// Time taken by the whole run, in seconds
+    pub seconds: f64,
+
+    /// Problems that didn't prevent the run from succeeding
+    pub warnings: Vec<String>,
+
+    /// Was the run interrupted (e.g. by Ctrl-C) before all volumes were built?
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+impl JobReport {
+    /// Report a run from its volumes
+    pub fn from_volumes(
+        volumes: Vec<VolumeSummary>,
+        warnings: Vec<String>,
+        started: Instant,
+    ) -> Self {
+        Self {
+            pages: volumes.iter().map(|vol| vol.pages).sum(),
+            size: volumes.iter().map(|vol| vol.size).sum(),
+            skipped: volumes.iter().filter(|vol| vol.skipped).count(),
+            volumes,
+            decoded: vec![],
+            seconds: started.elapsed().as_secs_f64(),
+            warnings,
+            cancelled: false,
+        }
+    }
+
+    /// Report a run from its extracted comic books
+    pub fn from_decoded(decoded: Vec<DecodedSummary>, started: Instant) -> Self {
+        Self {
+            pages: decoded.iter().map(|comic| comic.pages).sum(),
+            size: decoded.iter().map(|comic| comic.size).sum(),
+            decoded,
+            seconds: started.elapsed().as_secs_f64(),
+            ..Self::default()
+        }
+    }
+
+    /// Add the content of another report to this one (e.g. when compiling multiple series)
+    pub fn merge(&mut self, other: JobReport) {
+        self.volumes.extend(other.volumes);
+        self.decoded.extend(other.decoded);
+        self.pages += other.pages;
+        self.size += other.size;
+        self.skipped += other.skipped;
+        self.seconds += other.seconds;
+        self.warnings.extend(other.warnings);
+        self.cancelled |= other.cancelled;
+    }
+
+    /// Get the paths of the produced volumes or extracted files
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.volumes
+            .iter()
+            .map(|vol| vol.path.clone())
+            .chain(
+                self.decoded
+                    .iter()
+                    .flat_map(|comic| comic.files.iter().cloned()),
+            )
+            .collect()
+    }
+
+    /// Serialize the report to JSON
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Write the report to the provided directory
+    pub fn save(&self, dir: &Path) -> Result<(), SummaryErr> {
+        let content = self.to_json().map_err(SummaryErr::FailedToSerialize)?;
+        fs::write(dir.join(SUMMARY_FILENAME), content).map_err(SummaryErr::IOError)
+    }
+}
+
+/// Error while writing a run's summary
+#[derive(Debug)]
+pub enum SummaryErr {
+    IOError(io::Error),
+    FailedToSerialize(serde_json::Error),
+}
+
+impl fmt::Display for SummaryErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::FailedToSerialize(err) => write!(f, "Failed to serialize summary: {}", err),
+        }
+    }
+}
+
+impl Error for SummaryErr {}
@@ -0,0 +1,283 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// Size of a TAR block (headers and contents are aligned on it)
+const BLOCK_SIZE: usize = 512;
+
+/// A regular file found in a TAR archive
+pub struct TarEntry {
+    /// Path of the file in the archive, without any absolute or parent component
+    pub path: PathBuf,
+    /// Size of the file, in bytes
+    pub size: u64,
+}
+
+/// Streaming reader for TAR archives (POSIX ustar, with GNU and PAX long names)
+/// Only regular files are returned, and their content is read from the archive itself after calling `next_file()`
+pub struct TarArchive<R: Read> {
+    reader: R,
+    remaining: u64,
+    padding: u64,
+}
+
+impl<R: Read> TarArchive<R> {
+    /// Create a reader from a TAR stream
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            remaining: 0,
+            padding: 0,
+        }
+    }
+
+    /// Skip the given amount of bytes from the underlying reader
+    fn skip(&mut self, bytes: u64) -> Result<(), TarErr> {
+        let skipped = io::copy(&mut (&mut self.reader).take(bytes), &mut io::sink())
+            .map_err(TarErr::IOError)?;
+
+        if skipped < bytes {
+            Err(TarErr::UnexpectedEnd)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Read the content of the current entry as a whole (used for long names)
+    fn read_entry_data(&mut self) -> Result<Vec<u8>, TarErr> {
+        let mut data = vec![];
+        self.read_to_end(&mut data).map_err(TarErr::IOError)?;
+
+        if self.remaining > 0 {
+            return Err(TarErr::UnexpectedEnd);
+        }
+
+        Ok(data)
+    }
+
+    /// Go to the next regular file of the archive
+    /// Returns `None` when the end of the archive is reached
+    pub fn next_file(&mut self) -> Result<Option<TarEntry>, TarErr> {
+        let mut long_name = None;
+
+        loop {
+            // Skip what is left of the previous entry
+            let leftover = self.remaining + self.padding;
+            self.skip(leftover)?;
+            self.remaining = 0;
+            self.padding = 0;
+
+            let mut header = [0; BLOCK_SIZE];
+
+            match read_block(&mut self.reader, &mut header).map_err(TarErr::IOError)? {
+                0 => return Ok(None),
+                BLOCK_SIZE => {}
+                _ => return Err(TarErr::UnexpectedEnd),
+            }
+
+            // An empty block marks the end of the archive
+            if header.iter().all(|&byte| byte == 0) {
+                return Ok(None);
+            }
+
+            if !has_valid_checksum(&header) {
+                return Err(TarErr::InvalidHeader("checksum mismatch"));
+            }
+
+            let size = parse_number(&header[124..136])?;
+
+            self.remaining = size;
+            self.padding = (BLOCK_SIZE as u64 - size % BLOCK_SIZE as u64) % BLOCK_SIZE as u64;
+
+            match header[156] {
+                // GNU long name for the next entry
+                b'L' => long_name = Some(trim_nul(&self.read_entry_data()?).to_vec()),
+
+                // PAX extended header for the next entry
+                b'x' => {
+                    if let Some(path) = pax_path(&self.read_entry_data()?) {
+                        long_name = Some(path);
+                    }
+                }
+
+                // Regular files
+                b'0' | b'\0' | b'7' => {
+                    let name = match long_name.take() {
+                        Some(name) => name,
+                        None => ustar_name(&header),
+                    };
+
+                    return Ok(Some(TarEntry {
+                        path: mangle_path(&String::from_utf8_lossy(&name)),
+                        size,
+                    }));
+                }
+
+                // Directories, links, devices, global headers...
+                _ => long_name = None,
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for TarArchive<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+
+        let max = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let read = self.reader.read(&mut buf[..max])?;
+
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "TAR archive ended in the middle of a file",
+            ));
+        }
+
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+/// Read a full block, returning the number of bytes read (less than a block only at the end of the stream)
+fn read_block(reader: &mut impl Read, block: &mut [u8; BLOCK_SIZE]) -> io::Result<usize> {
+    let mut read = 0;
+
+    while read < BLOCK_SIZE {
+        match reader.read(&mut block[read..]) {
+            Ok(0) => break,
+            Ok(bytes) => read += bytes,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(read)
+}
+
+/// Get the bytes before the first NUL byte
+fn trim_nul(bytes: &[u8]) -> &[u8] {
+    match bytes.iter().position(|&byte| byte == 0) {
+        Some(end) => &bytes[..end],
+        None => bytes,
+    }
+}
+
+/// Check the checksum of a header, computed with the checksum field itself filled with spaces
+fn has_valid_checksum(header: &[u8; BLOCK_SIZE]) -> bool {
+    let expected = match parse_number(&header[148..156]) {
+        Ok(expected) => expected,
+        Err(_) => return false,
+    };
+
+    let sum: u64 = header
+        .iter()
+        .enumerate()
+        .map(|(i, &byte)| {
+            if (148..156).contains(&i) {
+                32
+            } else {
+                u64::from(byte)
+            }
+        })
+        .sum();
+
+    sum == expected
+}
+
+/// Parse a numeric header field, either octal text or GNU base-256 for large values
+fn parse_number(field: &[u8]) -> Result<u64, TarErr> {
+    if field.first().map(|&byte| byte & 0x80 != 0).unwrap_or(false) {
+        return field[1..]
+            .iter()
+            .try_fold(u64::from(field[0] & 0x7f), |num, &byte| {
+                num.checked_mul(256).map(|num| num + u64::from(byte))
+            })
+            .ok_or(TarErr::InvalidHeader("number is too large"));
+    }
+
+    let text = String::from_utf8_lossy(trim_nul(field));
+    let text = text.trim();
+
+    if text.is_empty() {
+        return Ok(0);
+    }
+
+    u64::from_str_radix(text, 8).map_err(|_| TarErr::InvalidHeader("invalid octal number"))
+}
+
+/// Get the name of an entry from a ustar header (prefix + name)
+fn ustar_name(header: &[u8; BLOCK_SIZE]) -> Vec<u8> {
+    let name = trim_nul(&header[0..100]);
+
+    if &header[257..262] != b"ustar" {
+        return name.to_vec();
+    }
+
+    let prefix = trim_nul(&header[345..500]);
+
+    if prefix.is_empty() {
+        name.to_vec()
+    } else {
+        [prefix, b"/", name].concat()
+    }
+}
+
+/// Get the 'path' record of a PAX extended header
+/// Records have the "<length> <key>=<value>\n" format
+fn pax_path(data: &[u8]) -> Option<Vec<u8>> {
+    let mut rest = data;
+
+    while !rest.is_empty() {
+        let space = rest.iter().position(|&byte| byte == b' ')?;
+        let len: usize = std::str::from_utf8(&rest[..space]).ok()?.parse().ok()?;
+
+        if len <= space || len > rest.len() {
+            return None;
+        }
+
+        let record = &rest[space + 1..len];
+        let record = record.strip_suffix(b"\n").unwrap_or(record);
+
+        if let Some(path) = record.strip_prefix(b"path=") {
+            return Some(path.to_vec());
+        }
+
+        rest = &rest[len..];
+    }
+
+    None
+}
+
+/// Keep only the normal components of a path, so it cannot escape the extraction directory
+fn mangle_path(name: &str) -> PathBuf {
+    Path::new(name)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part),
+            _ => None,
+        })
+        .collect()
+}
+
+/// TAR archive reading error
+pub enum TarErr {
+    IOError(io::Error),
+    UnexpectedEnd,
+    InvalidHeader(&'static str),
+}
+
+impl fmt::Display for TarErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "Failed to read TAR archive: {}", err),
+            Self::UnexpectedEnd => write!(f, "TAR archive ended unexpectedly"),
+            Self::InvalidHeader(reason) => write!(f, "Invalid TAR header: {}", reason),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::error::Error;
+use std::fmt;
+
+/// Render a naming template, replacing `{name}` placeholders with the provided values
+/// Values can be padded to a minimum width using a specifier, e.g. `{volume:02}` for zero-padding
+/// Literal braces are written by doubling them (`{{` and `}}`)
+///
+/// # Examples
+///
+/// ```
+/// let values = |name: &str| if name == "volume" { Some("3".to_owned()) } else { None };
+/// assert_eq!(render_template("Vol. {volume:02}", values), Ok("Vol. 03".to_owned()));
+/// ```
+pub fn render_template<F: Fn(&str) -> Option<String>>(
+    template: &str,
+    values: F,
+) -> Result<String, TemplateErr> {
+    let mut rendered = String::new();
+    let mut chars = template.char_indices().peekable();
+
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '{' if matches!(chars.peek(), Some((_, '{'))) => {
+                chars.next();
+                rendered.push('{');
+            }
+
+            '}' if matches!(chars.peek(), Some((_, '}'))) => {
+                chars.next();
+                rendered.push('}');
+            }
+
+            '}' => return Err(TemplateErr::UnexpectedClosingBrace(pos)),
+
+            '{' => {
+                let mut placeholder = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some((_, '}')) => break,
+                        Some((_, c)) => placeholder.push(c),
+                        None => return Err(TemplateErr::UnclosedPlaceholder(pos)),
+                    }
+                }
+
+                let (name, spec) = match placeholder.split_once(':') {
+                    Some((name, spec)) => (name.trim(), Some(spec)),
+                    None => (placeholder.trim(), None),
+                };
+
+                let value =
+                    values(name).ok_or_else(|| TemplateErr::MissingValue(name.to_owned()))?;
+
+                match spec {
+                    None => rendered.push_str(&value),
+                    Some(spec) => {
+                        let width = spec
+                            .parse::<usize>()
+                            .map_err(|_| TemplateErr::InvalidSpecifier(placeholder.clone()))?;
+
+                        let padding = width.saturating_sub(value.chars().count());
+                        let fill = if spec.starts_with('0') { '0' } else { ' ' };
+
+                        rendered.extend(std::iter::repeat_n(fill, padding));
+                        rendered.push_str(&value);
+                    }
+                }
+            }
+
+            c => rendered.push(c),
+        }
+    }
+
+    Ok(rendered)
+}
+
+/// Naming template rendering error
+#[derive(Debug, PartialEq, Eq)]
+pub enum TemplateErr {
+    UnclosedPlaceholder(usize),
+    UnexpectedClosingBrace(usize),
+    InvalidSpecifier(String),
+    MissingValue(String),
+}
+
+impl fmt::Display for TemplateErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnclosedPlaceholder(pos) => {
+                write!(f, "Placeholder at position {} is never closed", pos)
+            }
+            Self::UnexpectedClosingBrace(pos) => write!(
+                f,
+                "Unexpected closing brace at position {} (use '}}}}' to write a literal brace)",
+                pos
+            ),
+            Self::InvalidSpecifier(placeholder) => write!(
+                f,
+                "Invalid width specifier in placeholder '{{{}}}'",
+                placeholder
+            ),
+            Self::MissingValue(name) => write!(f, "No value available for placeholder '{}'", name),
+        }
+    }
+}
+
+impl Error for TemplateErr {}
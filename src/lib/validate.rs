@@ -0,0 +1,266 @@
+use crate::lib::archive::ComicArchive;
+use crate::lib::deter;
+use crate::lib::external::ExternalFormat;
+use crate::lib::source::{ExtractedSource, PageSource};
+use image::io::Reader as ImageReader;
+use image::ImageFormat;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+/// Severity of a problem found in an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The archive may be displayed incorrectly by some readers
+    Warning,
+    /// The archive is corrupted or unreadable
+    Error,
+}
+
+/// Problem found in an archive
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Result of an archive's validation
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    /// Number of pages in the archive
+    pub pages: usize,
+
+    /// Problems found in the archive
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    fn warn(&mut self, message: String) {
+        self.issues.push(ValidationIssue {
+            severity: Severity::Warning,
+            message,
+        });
+    }
+
+    fn error(&mut self, message: String) {
+        self.issues.push(ValidationIssue {
+            severity: Severity::Error,
+            message,
+        });
+    }
+
+    /// Check if the report contains errors (or warnings, if `strict` is set)
+    pub fn has_problems(&self, strict: bool) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| strict || issue.severity == Severity::Error)
+    }
+}
+
+/// Validate a comic archive:
+/// * Every file must be readable and match its checksum
+/// * Every page must be a decodable image
+/// * Pages must be in a consistent order
+pub fn validate_archive(path: &Path, extended_formats: bool) -> ValidationReport {
+    match ExternalFormat::from_path(path) {
+        Some(format) => validate_extracted_archive(path, format, extended_formats),
+        None => validate_zip_archive(path, extended_formats),
+    }
+}
+
+/// Validate a ZIP archive, reading its files directly
+fn validate_zip_archive(path: &Path, extended_formats: bool) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let mut archive = match ComicArchive::open(path) {
+        Ok(archive) => archive,
+        Err(err) => {
+            report.error(format!("{}", err));
+            return report;
+        }
+    };
+
+    let pages = archive
+        .pages(extended_formats, false)
+        .into_iter()
+        .map(|page| page.path.clone())
+        .collect::<Vec<_>>();
+
+    report.pages = pages.len();
+
+    if pages.is_empty() {
+        report.error("Archive does not contain any page".to_owned());
+    }
+
+    // Read every file to check its integrity
+    for entry in archive.entries().to_vec() {
+        if entry.is_dir {
+            continue;
+        }
+
+        trace!("Checking file '{}'...", entry.path.to_string_lossy());
+
+        let content = match archive.read_entry(entry.index) {
+            Ok(content) => content,
+            Err(err) => {
+                report.error(format!("{}", err));
+                continue;
+            }
+        };
+
+        if deter::has_image_ext(&entry.path, extended_formats) {
+            check_page(&mut report, &entry.path, &content);
+        }
+    }
+
+    check_pages_order(&mut report, pages);
+
+    report
+}
+
+/// Validate a RAR or 7-Zip archive, extracted by its external program
+/// The program checks every file against its checksum while extracting the archive, and fails if one doesn't match
+fn validate_extracted_archive(
+    path: &Path,
+    format: ExternalFormat,
+    extended_formats: bool,
+) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let source = match ExtractedSource::extract(format, path, false) {
+        Ok(source) => source,
+        Err(err) => {
+            report.error(format!("{}", err));
+            return report;
+        }
+    };
+
+    let files = match source.files(path) {
+        Ok(files) => files,
+        Err(err) => {
+            report.error(format!("Failed to list extracted files: {}", err));
+            return report;
+        }
+    };
+
+    let mut pages = vec![];
+
+    for file in files {
+        let path_in_archive = file.strip_prefix(path).unwrap_or(&file).to_path_buf();
+
+        if !deter::has_image_ext(&path_in_archive, extended_formats) {
+            continue;
+        }
+
+        trace!("Checking file '{}'...", path_in_archive.to_string_lossy());
+
+        let mut content = vec![];
+
+        if let Err(err) = source
+            .open(&file)
+            .and_then(|mut reader| reader.read_to_end(&mut content))
+        {
+            report.error(format!(
+                "Failed to read page '{}': {}",
+                path_in_archive.to_string_lossy(),
+                err
+            ));
+            continue;
+        }
+
+        check_page(&mut report, &path_in_archive, &content);
+
+        pages.push(path_in_archive);
+    }
+
+    report.pages = pages.len();
+
+    if pages.is_empty() {
+        report.error("Archive does not contain any page".to_owned());
+    }
+
+    check_pages_order(&mut report, pages);
+
+    report
+}
+
+/// Check that a page is an image whose header can be decoded
+fn check_page(report: &mut ValidationReport, path: &Path, content: &[u8]) {
+    let reader = match ImageReader::new(Cursor::new(content)).with_guessed_format() {
+        Ok(reader) => reader,
+        Err(err) => {
+            report.error(format!(
+                "Failed to read page '{}': {}",
+                path.to_string_lossy(),
+                err
+            ));
+            return;
+        }
+    };
+
+    let format = match reader.format() {
+        Some(format) => format,
+        None => {
+            report.error(format!(
+                "Page '{}' is not a recognized image",
+                path.to_string_lossy()
+            ));
+            return;
+        }
+    };
+
+    if let Ok(ext_format) = ImageFormat::from_path(path) {
+        if ext_format != format {
+            report.warn(format!(
+                "Page '{}' is a {:?} image but its extension suggests a {:?} image",
+                path.to_string_lossy(),
+                format,
+                ext_format
+            ));
+        }
+    }
+
+    if let Err(err) = reader.into_dimensions() {
+        report.error(format!(
+            "Failed to decode header of page '{}': {}",
+            path.to_string_lossy(),
+            err
+        ));
+    }
+}
+
+/// Check that pages are displayed in the same order by every reader
+fn check_pages_order(report: &mut ValidationReport, mut pages: Vec<PathBuf>) {
+    deter::natural_sort_by_path(&mut pages, |page| page);
+
+    // Check for pages whose order depends on case
+    let mut lowercase_names = HashMap::new();
+
+    for page in &pages {
+        let lowercase = page.to_string_lossy().to_lowercase();
+
+        if let Some(other) = lowercase_names.insert(lowercase, page) {
+            report.error(format!(
+                "Pages '{}' and '{}' only differ by case",
+                other.to_string_lossy(),
+                page.to_string_lossy()
+            ));
+        }
+    }
+
+    // Check if readers using a simple sorting would display pages in the same order
+    let mut simple_order = pages.clone();
+    simple_order.sort();
+
+    if let Some((natural, simple)) = pages
+        .iter()
+        .zip(simple_order.iter())
+        .find(|(natural, simple)| natural != simple)
+    {
+        report.warn(format!(
+            "Pages are not zero-padded, readers without natural sorting will display '{}' where '{}' is expected",
+            simple.to_string_lossy(),
+            natural.to_string_lossy()
+        ));
+    }
+}
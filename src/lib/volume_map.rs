@@ -0,0 +1,124 @@
+use crate::lib::chapter::{ChapterNumber, ChapterNumberErr};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Chapter starting each volume (e.g. "v1: 1, v2: 8, v3: 16")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeMap {
+    /// Number of each volume with its first chapter, sorted by volume
+    starts: Vec<(usize, ChapterNumber)>,
+}
+
+impl VolumeMap {
+    /// Load a map from a file
+    pub fn load(path: &Path) -> Result<Self, VolumeMapErr> {
+        fs::read_to_string(path)
+            .map_err(VolumeMapErr::IOError)?
+            .parse()
+    }
+
+    /// Get the volume a chapter belongs to, or `None` if it comes before the first volume
+    pub fn volume_of(&self, chapter: ChapterNumber) -> Option<usize> {
+        self.starts
+            .iter()
+            .rev()
+            .find(|(_, start)| *start <= chapter)
+            .map(|(volume, _)| *volume)
+    }
+
+    /// Get the number of the last volume
+    pub fn last_volume(&self) -> usize {
+        self.starts.last().map(|(volume, _)| *volume).unwrap_or(0)
+    }
+}
+
+impl FromStr for VolumeMap {
+    type Err = VolumeMapErr;
+
+    /// Parse entries separated by commas or new lines, ignoring lines starting with '#'
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        let mut starts: Vec<(usize, ChapterNumber)> = vec![];
+
+        let entries = content
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .flat_map(|line| line.split(','))
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty());
+
+        for entry in entries {
+            let (volume, chapter) = entry
+                .split_once(':')
+                .ok_or_else(|| VolumeMapErr::InvalidEntry(entry.to_owned()))?;
+
+            let volume = volume
+                .trim()
+                .trim_start_matches(|c: char| c.is_alphabetic() || c == '.')
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| VolumeMapErr::InvalidEntry(entry.to_owned()))?;
+
+            let chapter = chapter
+                .parse::<ChapterNumber>()
+                .map_err(VolumeMapErr::InvalidChapter)?;
+
+            if let Some((prev_volume, prev_chapter)) = starts.last() {
+                if volume <= *prev_volume {
+                    return Err(VolumeMapErr::VolumesNotIncreasing(volume));
+                }
+
+                if chapter <= *prev_chapter {
+                    return Err(VolumeMapErr::ChaptersNotIncreasing(volume));
+                }
+            }
+
+            starts.push((volume, chapter));
+        }
+
+        if starts.is_empty() {
+            return Err(VolumeMapErr::Empty);
+        }
+
+        Ok(Self { starts })
+    }
+}
+
+/// Error while loading a volume map
+#[derive(Debug)]
+pub enum VolumeMapErr {
+    IOError(io::Error),
+    InvalidEntry(String),
+    InvalidChapter(ChapterNumberErr),
+    VolumesNotIncreasing(usize),
+    ChaptersNotIncreasing(usize),
+    Empty,
+}
+
+impl fmt::Display for VolumeMapErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidEntry(entry) => write!(
+                f,
+                "Invalid entry '{}' (expected a volume and its first chapter, e.g. 'v2: 8')",
+                entry
+            ),
+            Self::InvalidChapter(err) => write!(f, "{}", err),
+            Self::VolumesNotIncreasing(volume) => {
+                write!(f, "Volume {} is not listed in increasing order", volume)
+            }
+            Self::ChaptersNotIncreasing(volume) => write!(
+                f,
+                "Volume {} does not start after the previous volume's first chapter",
+                volume
+            ),
+            Self::Empty => write!(f, "Volume map does not contain any volume"),
+        }
+    }
+}
+
+impl Error for VolumeMapErr {}
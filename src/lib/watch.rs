@@ -0,0 +1,185 @@
+use crate::lib::deter::{self, RecursiveFilesSearchErr};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Name of the file keeping track of the volumes built by the watch mode, in the output directory
+pub const WATCH_STATE_FILENAME: &str = ".comic-enc-watch.json";
+
+/// Extensions of the temporary files download tools write while a file is being downloaded
+pub const PARTIAL_DOWNLOAD_EXTS: &[&str] =
+    &["part", "partial", "crdownload", "download", "tmp", "!ut"];
+
+/// Configuration of the watch mode
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchConfig {
+    /// Directory where the volumes should be put (relative to the configuration file)
+    pub output: PathBuf,
+
+    /// Number of chapters per volume
+    pub chapters_per_volume: u16,
+
+    /// Prefix in the name of the chapter directories
+    #[serde(default)]
+    pub dirs_prefix: Option<String>,
+
+    /// Number of seconds a chapter must remain untouched before being considered complete
+    #[serde(default = "default_debounce_secs")]
+    pub debounce_secs: u64,
+
+    /// Number of seconds between two checks of the watched directory, which is polled instead of relying on filesystem notifications
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+
+    /// Number of digits to display volume numbers with
+    #[serde(default = "default_volume_digits")]
+    pub volume_digits: usize,
+
+    /// Number of digits to display chapter numbers with
+    #[serde(default = "default_chapter_digits")]
+    pub chapter_digits: usize,
+
+    /// Additional encoding options, as provided on the command line (e.g. '--compress-webp')
+    #[serde(default)]
+    pub encode_options: Vec<String>,
+}
+
+fn default_debounce_secs() -> u64 {
+    60
+}
+
+fn default_poll_interval_secs() -> u64 {
+    10
+}
+
+fn default_volume_digits() -> usize {
+    2
+}
+
+fn default_chapter_digits() -> usize {
+    3
+}
+
+/// Volume built by the watch mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedVolume {
+    /// Number of the volume
+    pub volume: usize,
+
+    /// Name of the chapter directories put in the volume
+    pub chapters: Vec<String>,
+}
+
+/// Volumes built by the watch mode so far
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatchState {
+    pub volumes: Vec<WatchedVolume>,
+}
+
+impl WatchState {
+    /// Load the state from an output directory, or start from scratch if it has none
+    pub fn load(output: &Path) -> Result<Self, WatchStateErr> {
+        let path = output.join(WATCH_STATE_FILENAME);
+
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).map_err(WatchStateErr::IOError)?;
+        serde_json::from_str(&content).map_err(WatchStateErr::InvalidState)
+    }
+
+    /// Save the state to an output directory
+    pub fn save(&self, output: &Path) -> Result<(), WatchStateErr> {
+        let content = serde_json::to_string_pretty(self).map_err(WatchStateErr::InvalidState)?;
+        fs::write(output.join(WATCH_STATE_FILENAME), content).map_err(WatchStateErr::IOError)
+    }
+
+    /// Check if a chapter was already put in a volume
+    pub fn contains_chapter(&self, name: &str) -> bool {
+        self.volumes
+            .iter()
+            .any(|volume| volume.chapters.iter().any(|chapter| chapter == name))
+    }
+
+    /// Get the number of chapters already put in volumes
+    pub fn chapters_count(&self) -> usize {
+        self.volumes
+            .iter()
+            .map(|volume| volume.chapters.len())
+            .sum()
+    }
+
+    /// Get the number of the next volume to build
+    pub fn next_volume(&self) -> usize {
+        self.volumes
+            .iter()
+            .map(|volume| volume.volume)
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+}
+
+/// Check if a chapter directory looks completely downloaded:
+/// it must contain at least one file, none of them being a partial download,
+/// and no file must have been modified during the debounce delay
+pub fn is_chapter_complete(
+    dir: &Path,
+    debounce: Duration,
+) -> Result<bool, RecursiveFilesSearchErr> {
+    let files = deter::readdir_files_recursive(dir, None::<&fn(&Path) -> bool>)?;
+
+    if files.is_empty() {
+        return Ok(false);
+    }
+
+    let mut latest = modified_at(dir).map_err(RecursiveFilesSearchErr::IOError)?;
+
+    for file in &files {
+        let is_partial = file
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| {
+                PARTIAL_DOWNLOAD_EXTS
+                    .iter()
+                    .any(|partial| ext.eq_ignore_ascii_case(partial))
+            })
+            .unwrap_or(false);
+
+        if is_partial {
+            trace!("Found partial download '{}'", file.to_string_lossy());
+            return Ok(false);
+        }
+
+        latest = latest.max(modified_at(file).map_err(RecursiveFilesSearchErr::IOError)?);
+    }
+
+    Ok(SystemTime::now()
+        .duration_since(latest)
+        .map(|elapsed| elapsed >= debounce)
+        .unwrap_or(false))
+}
+
+fn modified_at(path: &Path) -> io::Result<SystemTime> {
+    fs::metadata(path)?.modified()
+}
+
+/// Watch state error
+pub enum WatchStateErr {
+    IOError(io::Error),
+    InvalidState(serde_json::Error),
+}
+
+impl fmt::Display for WatchStateErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidState(err) => write!(f, "Invalid state file: {}", err),
+        }
+    }
+}
@@ -0,0 +1,625 @@
+use crate::cli::opts::VolumeFormat;
+use crate::lib::archive::{self, ArchiveErr, ComicArchive, CopyErr};
+use crate::lib::deter;
+use crate::lib::external;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use image::ImageFormat;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use zip::write::{FileOptions, ZipWriter};
+use zip::CompressionMethod;
+
+/// Size of a TAR block (headers and contents are aligned on it)
+const TAR_BLOCK_SIZE: usize = 512;
+
+/// Maximum length of a name in a TAR header, longer ones are stored in a GNU long name entry
+const TAR_NAME_LEN: usize = 100;
+
+/// Container the pages of a volume are written to
+/// Writers are created on the volume's staging file, and `finish` returns it once complete
+pub trait ArchiveWriter {
+    /// Add an empty directory, e.g. for a chapter (ignored by containers without directories)
+    fn add_dir(&mut self, path: &str) -> io::Result<()>;
+
+    /// Add a page from its content
+    fn add_page(&mut self, path: &str, content: &[u8]) -> io::Result<()>;
+
+    /// Add a page streamed from a reader through a reused buffer
+    /// Returns the size of the page
+    fn add_page_from(
+        &mut self,
+        path: &str,
+        reader: &mut dyn Read,
+        _buffer: &mut [u8],
+    ) -> Result<u64, CopyErr> {
+        let mut content = vec![];
+        reader.read_to_end(&mut content).map_err(CopyErr::ReadError)?;
+
+        self.add_page(path, &content).map_err(CopyErr::WriteError)?;
+
+        Ok(content.len() as u64)
+    }
+
+    /// Add a file that isn't a page, e.g. the volume's metadata (ignored by containers that only hold pages)
+    fn add_file(&mut self, path: &str, content: &[u8]) -> io::Result<()>;
+
+    /// Copy an entry of a comic archive under a new name, as a page or as another file
+    fn copy_entry(
+        &mut self,
+        archive: &mut ComicArchive,
+        index: usize,
+        path: &str,
+        is_page: bool,
+        buffer: &mut [u8],
+    ) -> Result<(), ArchiveErr> {
+        let entry_path = archive.entries()[index].path.clone();
+        let mut reader = archive.entry_reader(index)?;
+
+        if is_page {
+            self.add_page_from(path, &mut reader, buffer)
+                .map_err(|err| match err {
+                    CopyErr::ReadError(err) => ArchiveErr::FailedToReadEntry(entry_path, err),
+                    CopyErr::WriteError(err) => ArchiveErr::FailedToWriteEntry(entry_path, err),
+                })?;
+
+            return Ok(());
+        }
+
+        let mut content = vec![];
+
+        reader
+            .read_to_end(&mut content)
+            .map_err(|err| ArchiveErr::FailedToReadEntry(entry_path.clone(), err))?;
+
+        self.add_file(path, &content)
+            .map_err(|err| ArchiveErr::FailedToWriteEntry(entry_path, err))
+    }
+
+    /// Set the container's comment (ignored by containers without comments)
+    fn set_comment(&mut self, _comment: &str) {}
+
+    /// Finish writing the container, returning its file
+    fn finish(self: Box<Self>) -> io::Result<File>;
+}
+
+/// Create a writer for a container, writing to the provided (staging) path
+/// Pages are stored with the provided compression method when the container supports it
+pub fn create_writer(
+    format: VolumeFormat,
+    path: &Path,
+    compression: CompressionMethod,
+) -> io::Result<Box<dyn ArchiveWriter>> {
+    let path = &deter::long_path(path);
+
+    Ok(match format {
+        VolumeFormat::Cbz => Box::new(ZipArchiveWriter::create(path, compression)?),
+        VolumeFormat::Cbt => Box::new(TarArchiveWriter::create(path)?),
+        VolumeFormat::Cb7 => Box::new(SevenZipArchiveWriter::create(path, compression)?),
+        VolumeFormat::Pdf => Box::new(PdfArchiveWriter::create(path)?),
+    })
+}
+
+/// ZIP archive writer
+pub struct ZipArchiveWriter {
+    zip_writer: ZipWriter<BufWriter<File>>,
+    compression: CompressionMethod,
+    options: FileOptions,
+}
+
+impl ZipArchiveWriter {
+    /// Create a ZIP archive at the provided path
+    pub fn create(path: &Path, compression: CompressionMethod) -> io::Result<Self> {
+        Ok(Self {
+            zip_writer: archive::buffered_zip_writer(File::create(path)?),
+            compression,
+            options: FileOptions::default().compression_method(compression),
+        })
+    }
+}
+
+impl ArchiveWriter for ZipArchiveWriter {
+    fn add_dir(&mut self, path: &str) -> io::Result<()> {
+        Ok(self.zip_writer.add_directory(path, self.options)?)
+    }
+
+    fn add_page(&mut self, path: &str, content: &[u8]) -> io::Result<()> {
+        self.add_file(path, content)
+    }
+
+    fn add_page_from(
+        &mut self,
+        path: &str,
+        reader: &mut dyn Read,
+        buffer: &mut [u8],
+    ) -> Result<u64, CopyErr> {
+        self.zip_writer
+            .start_file(path, self.options)
+            .map_err(|err| CopyErr::WriteError(err.into()))?;
+
+        archive::copy_to_zip(reader, &mut self.zip_writer, buffer)
+    }
+
+    fn add_file(&mut self, path: &str, content: &[u8]) -> io::Result<()> {
+        self.zip_writer.start_file(path, self.options)?;
+        self.zip_writer.write_all(content)
+    }
+
+    fn copy_entry(
+        &mut self,
+        archive: &mut ComicArchive,
+        index: usize,
+        path: &str,
+        _is_page: bool,
+        buffer: &mut [u8],
+    ) -> Result<(), ArchiveErr> {
+        // Entries already compressed the right way are copied without being decompressed and compressed again
+        archive.copy_entry(index, &mut self.zip_writer, path, self.compression, buffer)
+    }
+
+    fn set_comment(&mut self, comment: &str) {
+        self.zip_writer.set_comment(comment);
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<File> {
+        Ok(archive::finish_buffered_zip(self.zip_writer)?)
+    }
+}
+
+/// TAR archive writer (POSIX ustar, with GNU long names)
+pub struct TarArchiveWriter {
+    writer: BufWriter<File>,
+}
+
+impl TarArchiveWriter {
+    /// Create a TAR archive at the provided path
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            writer: BufWriter::with_capacity(archive::COPY_BUFFER_SIZE, File::create(path)?),
+        })
+    }
+
+    /// Write the header of an entry, preceded by a GNU long name entry if its name doesn't fit in the header
+    fn write_header(&mut self, path: &str, kind: u8, size: u64) -> io::Result<()> {
+        if path.len() > TAR_NAME_LEN {
+            let mut long_name = path.as_bytes().to_vec();
+            long_name.push(0);
+
+            self.writer
+                .write_all(&tar_header("././@LongLink", b'L', long_name.len() as u64))?;
+            self.writer.write_all(&long_name)?;
+            self.pad(long_name.len() as u64)?;
+        }
+
+        self.writer.write_all(&tar_header(path, kind, size))
+    }
+
+    /// Pad an entry's content to the next block
+    fn pad(&mut self, size: u64) -> io::Result<()> {
+        let padding = (TAR_BLOCK_SIZE - (size % TAR_BLOCK_SIZE as u64) as usize) % TAR_BLOCK_SIZE;
+        self.writer.write_all(&[0; TAR_BLOCK_SIZE][..padding])
+    }
+}
+
+impl ArchiveWriter for TarArchiveWriter {
+    fn add_dir(&mut self, path: &str) -> io::Result<()> {
+        self.write_header(&format!("{}/", path.trim_end_matches('/')), b'5', 0)
+    }
+
+    fn add_page(&mut self, path: &str, content: &[u8]) -> io::Result<()> {
+        self.add_file(path, content)
+    }
+
+    fn add_page_from(
+        &mut self,
+        path: &str,
+        reader: &mut dyn Read,
+        buffer: &mut [u8],
+    ) -> Result<u64, CopyErr> {
+        // The size isn't known before the page is copied, so its header is written again afterwards
+        self.write_header(path, b'0', 0).map_err(CopyErr::WriteError)?;
+
+        let content_pos = self
+            .writer
+            .stream_position()
+            .map_err(CopyErr::WriteError)?;
+
+        let copied = archive::copy_buffered(reader, &mut self.writer, buffer)?;
+
+        self.pad(copied).map_err(CopyErr::WriteError)?;
+
+        let mut rewrite_header = || -> io::Result<()> {
+            let end_pos = self.writer.stream_position()?;
+
+            // The header is the last block before the content, after the long name entry if any
+            self.writer.seek(SeekFrom::Start(content_pos - TAR_BLOCK_SIZE as u64))?;
+            self.writer.write_all(&tar_header(path, b'0', copied))?;
+            self.writer.seek(SeekFrom::Start(end_pos))?;
+
+            Ok(())
+        };
+
+        rewrite_header().map_err(CopyErr::WriteError)?;
+
+        Ok(copied)
+    }
+
+    fn add_file(&mut self, path: &str, content: &[u8]) -> io::Result<()> {
+        self.write_header(path, b'0', content.len() as u64)?;
+        self.writer.write_all(content)?;
+        self.pad(content.len() as u64)
+    }
+
+    fn finish(mut self: Box<Self>) -> io::Result<File> {
+        // Two empty blocks mark the end of the archive
+        self.writer.write_all(&[0; TAR_BLOCK_SIZE * 2])?;
+        self.writer.into_inner().map_err(|err| err.into_error())
+    }
+}
+
+/// Build a TAR header for an entry (its name is truncated if it is too long, see `TarArchiveWriter::write_header`)
+fn tar_header(path: &str, kind: u8, size: u64) -> [u8; TAR_BLOCK_SIZE] {
+    let mut header = [0; TAR_BLOCK_SIZE];
+
+    let name = path.as_bytes();
+    let name_len = name.len().min(TAR_NAME_LEN);
+    header[..name_len].copy_from_slice(&name[..name_len]);
+
+    let mode: &[u8] = if kind == b'5' { b"0000755\0" } else { b"0000644\0" };
+    header[100..108].copy_from_slice(mode);
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    header[136..148].copy_from_slice(b"00000000000\0");
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // The checksum is computed with its own field filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+    header
+}
+
+/// 7-Zip archive writer, using the external '7z' program
+/// Files are written to a temporary directory next to the archive, which is compressed when the archive is finished
+pub struct SevenZipArchiveWriter {
+    path: PathBuf,
+    dir: PathBuf,
+    store: bool,
+}
+
+impl SevenZipArchiveWriter {
+    /// Create a 7-Zip archive at the provided path
+    pub fn create(path: &Path, compression: CompressionMethod) -> io::Result<Self> {
+        // The program is run from the temporary directory, so the archive's path must not be relative
+        let path = env::current_dir()?.join(path);
+        let dir = path.with_extension(format!("{}-files", archive::STAGING_EXT));
+
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            path,
+            dir,
+            store: compression == CompressionMethod::Stored,
+        })
+    }
+
+    /// Get the path of a file in the temporary directory
+    fn file_path(&self, path: &str) -> PathBuf {
+        self.dir.join(path)
+    }
+}
+
+impl ArchiveWriter for SevenZipArchiveWriter {
+    fn add_dir(&mut self, path: &str) -> io::Result<()> {
+        fs::create_dir_all(self.file_path(path))
+    }
+
+    fn add_page(&mut self, path: &str, content: &[u8]) -> io::Result<()> {
+        self.add_file(path, content)
+    }
+
+    fn add_page_from(
+        &mut self,
+        path: &str,
+        reader: &mut dyn Read,
+        buffer: &mut [u8],
+    ) -> Result<u64, CopyErr> {
+        let file_path = self.file_path(path);
+
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).map_err(CopyErr::WriteError)?;
+        }
+
+        let mut file = File::create(file_path).map_err(CopyErr::WriteError)?;
+
+        archive::copy_buffered(reader, &mut file, buffer)
+    }
+
+    fn add_file(&mut self, path: &str, content: &[u8]) -> io::Result<()> {
+        let file_path = self.file_path(path);
+
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(file_path, content)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<File> {
+        // '7z a' adds files to an existing archive instead of replacing it
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+
+        external::compress_7z(&self.dir, &self.path, self.store)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+
+        File::open(&self.path)
+    }
+}
+
+impl Drop for SevenZipArchiveWriter {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.dir) {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!(
+                    "Warning: failed to remove temporary directory '{}': {}",
+                    self.dir.to_string_lossy(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// PDF document writer, with one picture per page
+/// JPEG pages are embedded as they are, other pages are decoded and compressed losslessly.
+/// Directories and files that aren't pages (e.g. the volume's metadata) are left out
+pub struct PdfArchiveWriter {
+    writer: BufWriter<File>,
+    /// Position of each object in the file, by number (starting at 1)
+    offsets: Vec<u64>,
+    /// Numbers of the page objects
+    pages: Vec<usize>,
+}
+
+/// Number of the catalog object
+const PDF_CATALOG_OBJ: usize = 1;
+
+/// Number of the page tree object, written once all pages are known
+const PDF_PAGES_OBJ: usize = 2;
+
+impl PdfArchiveWriter {
+    /// Create a PDF document at the provided path
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let mut writer = BufWriter::with_capacity(archive::COPY_BUFFER_SIZE, File::create(path)?);
+
+        // The binary comment tells readers the document contains binary data
+        writer.write_all(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n")?;
+
+        Ok(Self {
+            writer,
+            offsets: vec![0, 0],
+            pages: vec![],
+        })
+    }
+
+    /// Reserve the number of an object written later
+    fn reserve_obj(&mut self) -> usize {
+        self.offsets.push(0);
+        self.offsets.len()
+    }
+
+    /// Write an object with the provided number, optionally followed by a stream
+    fn write_obj(&mut self, num: usize, dict: &str, stream: Option<&[u8]>) -> io::Result<()> {
+        self.offsets[num - 1] = self.writer.stream_position()?;
+
+        writeln!(self.writer, "{} 0 obj\n{}", num, dict)?;
+
+        if let Some(stream) = stream {
+            self.writer.write_all(b"stream\n")?;
+            self.writer.write_all(stream)?;
+            self.writer.write_all(b"\nendstream\n")?;
+        }
+
+        self.writer.write_all(b"endobj\n")
+    }
+}
+
+impl ArchiveWriter for PdfArchiveWriter {
+    fn add_dir(&mut self, _path: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn add_page(&mut self, _path: &str, content: &[u8]) -> io::Result<()> {
+        let PdfImage {
+            width,
+            height,
+            color_space,
+            filter,
+            data,
+        } = pdf_image(content)?;
+
+        let image_obj = self.reserve_obj();
+        let content_obj = self.reserve_obj();
+        let page_obj = self.reserve_obj();
+
+        self.write_obj(
+            image_obj,
+            &format!(
+                "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} /BitsPerComponent 8 /Filter /{} /Length {} >>",
+                width, height, color_space, filter, data.len()
+            ),
+            Some(&data),
+        )?;
+
+        // Pictures are displayed at 72 DPI, filling the whole page
+        let drawing = format!("q {} 0 0 {} 0 0 cm /Im0 Do Q", width, height);
+
+        self.write_obj(
+            content_obj,
+            &format!("<< /Length {} >>", drawing.len()),
+            Some(drawing.as_bytes()),
+        )?;
+
+        self.write_obj(
+            page_obj,
+            &format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+                PDF_PAGES_OBJ, width, height, image_obj, content_obj
+            ),
+            None,
+        )?;
+
+        self.pages.push(page_obj);
+
+        Ok(())
+    }
+
+    fn add_file(&mut self, _path: &str, _content: &[u8]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> io::Result<File> {
+        let kids = self
+            .pages
+            .iter()
+            .map(|page| format!("{} 0 R", page))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        self.write_obj(
+            PDF_PAGES_OBJ,
+            &format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids,
+                self.pages.len()
+            ),
+            None,
+        )?;
+
+        self.write_obj(
+            PDF_CATALOG_OBJ,
+            &format!("<< /Type /Catalog /Pages {} 0 R >>", PDF_PAGES_OBJ),
+            None,
+        )?;
+
+        let xref_pos = self.writer.stream_position()?;
+
+        writeln!(
+            self.writer,
+            "xref\n0 {}\n0000000000 65535 f ",
+            self.offsets.len() + 1
+        )?;
+
+        for offset in &self.offsets {
+            writeln!(self.writer, "{:010} 00000 n ", offset)?;
+        }
+
+        writeln!(
+            self.writer,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF",
+            self.offsets.len() + 1,
+            PDF_CATALOG_OBJ,
+            xref_pos
+        )?;
+
+        self.writer.into_inner().map_err(|err| err.into_error())
+    }
+}
+
+/// Picture embedded in a PDF document
+struct PdfImage {
+    width: u32,
+    height: u32,
+    color_space: &'static str,
+    filter: &'static str,
+    data: Vec<u8>,
+}
+
+/// Get the picture to embed in a PDF document for a page
+fn pdf_image(content: &[u8]) -> io::Result<PdfImage> {
+    let invalid = |err: image::ImageError| io::Error::new(io::ErrorKind::InvalidData, err);
+
+    if image::guess_format(content).map_err(invalid)? == ImageFormat::Jpeg {
+        let frame = jpeg_frame(content).and_then(|(width, height, components)| {
+            match components {
+                1 => Some((width, height, "DeviceGray")),
+                3 => Some((width, height, "DeviceRGB")),
+
+                // CMYK pictures are decoded to RGB like other formats
+                _ => None,
+            }
+        });
+
+        if let Some((width, height, color_space)) = frame {
+            return Ok(PdfImage {
+                width,
+                height,
+                color_space,
+                filter: "DCTDecode",
+                data: content.to_vec(),
+            });
+        }
+    }
+
+    let im = image::load_from_memory(content).map_err(invalid)?;
+    let (width, height) = (im.width(), im.height());
+
+    let (color_space, pixels) = if im.color().has_color() {
+        ("DeviceRGB", im.into_rgb8().into_raw())
+    } else {
+        ("DeviceGray", im.into_luma8().into_raw())
+    };
+
+    let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+    encoder.write_all(&pixels)?;
+
+    Ok(PdfImage {
+        width,
+        height,
+        color_space,
+        filter: "FlateDecode",
+        data: encoder.finish()?,
+    })
+}
+
+/// Get the dimensions and number of color components of a JPEG picture from its frame header
+fn jpeg_frame(content: &[u8]) -> Option<(u32, u32, u8)> {
+    // Skip the start of image marker
+    let mut pos = 2;
+
+    while pos + 4 <= content.len() {
+        if content[pos] != 0xFF {
+            return None;
+        }
+
+        let marker = content[pos + 1];
+        let len = usize::from(u16::from_be_bytes([content[pos + 2], content[pos + 3]]));
+
+        // Start of frame markers (0xC4, 0xC8 and 0xCC are other kinds of segments)
+        if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+            let frame = content.get(pos + 4..pos + 10)?;
+
+            return Some((
+                u32::from(u16::from_be_bytes([frame[3], frame[4]])),
+                u32::from(u16::from_be_bytes([frame[1], frame[2]])),
+                frame[5],
+            ));
+        }
+
+        pos += 2 + len;
+    }
+
+    None
+}
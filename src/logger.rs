@@ -1,9 +1,114 @@
+use crate::lib::deter;
+use crate::lib::events::{self, Event};
+use crate::lib::progress_bars;
 use fern::colors::{Color, ColoredLevelConfig};
 use log::{Level, LevelFilter};
-use std::time::Instant;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+/// Number of rotated log files to keep (e.g. 'comic-enc.log.1' to 'comic-enc.log.5')
+const ROTATED_LOG_FILES: usize = 5;
 
 /// Start the logger, hiding every message whose level is under the provided one
-pub fn start(level: LevelFilter) {
+/// Messages are emitted as JSON events if events are enabled
+/// If a log file is provided, every message is also written to it with a timestamp, regardless of the level
+pub fn start(level: LevelFilter, log_file: Option<(&Path, u64)>) -> Result<(), io::Error> {
+    let console = console_dispatch(level);
+
+    let dispatch = match log_file {
+        None => console,
+        Some((path, max_size)) => fern::Dispatch::new()
+            .chain(console)
+            .chain(file_dispatch(path, max_size)?),
+    };
+
+    dispatch.apply().unwrap();
+
+    Ok(())
+}
+
+/// Build the logger writing messages with a timestamp to a file, which is rotated first if it exceeds the
+/// provided size (in bytes)
+fn file_dispatch(path: &Path, max_size: u64) -> Result<fern::Dispatch, io::Error> {
+    if fs::metadata(path).map(|metadata| metadata.len() > max_size).unwrap_or(false) {
+        rotate_log_files(path)?;
+    }
+
+    Ok(fern::Dispatch::new()
+        .format(|out, message, record| {
+            out.finish(format_args!(
+                "{} {: <7} {}",
+                utc_timestamp(),
+                level_name(record.level()),
+                message
+            ))
+        })
+        .level(LevelFilter::Trace)
+        .chain(fern::log_file(path)?))
+}
+
+/// Get the name of a level, as displayed in the messages
+fn level_name(level: Level) -> &'static str {
+    match level {
+        Level::Info => "INFO",
+        Level::Warn => "WARNING",
+        Level::Error => "ERROR",
+        Level::Debug => "VERBOSE",
+        Level::Trace => "DEBUG",
+    }
+}
+
+/// Rename 'file.log' to 'file.log.1', 'file.log.1' to 'file.log.2' and so on, removing the oldest one
+fn rotate_log_files(path: &Path) -> Result<(), io::Error> {
+    let rotated = |index: usize| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    };
+
+    let oldest = rotated(ROTATED_LOG_FILES);
+
+    if oldest.is_file() {
+        fs::remove_file(oldest)?;
+    }
+
+    for index in (1..ROTATED_LOG_FILES).rev() {
+        let from = rotated(index);
+
+        if from.is_file() {
+            fs::rename(from, rotated(index + 1))?;
+        }
+    }
+
+    fs::rename(path, rotated(1))
+}
+
+/// Get the current date and time in UTC, e.g. '2021-03-14 15:09:26.535'
+fn utc_timestamp() -> String {
+    deter::format_utc(SystemTime::now(), ' ', true)
+}
+
+/// Build the logger displaying messages in the console
+fn console_dispatch(level: LevelFilter) -> fern::Dispatch {
+    if events::enabled() {
+        return fern::Dispatch::new()
+            .level(level)
+            .chain(fern::Output::call(|record| {
+                events::emit(&Event::Log {
+                    level: match record.level() {
+                        Level::Info => "info",
+                        Level::Warn => "warning",
+                        Level::Error => "error",
+                        Level::Debug => "verbose",
+                        Level::Trace => "debug",
+                    },
+                    message: record.args().to_string(),
+                })
+            }));
+    }
+
     // Create color scheme
     let colors_line = ColoredLevelConfig::new()
         .error(Color::Red)
@@ -30,18 +135,13 @@ pub fn start(level: LevelFilter) {
                 secs / 60,
                 secs % 60,
                 elapsed.subsec_millis(),
-                match record.level() {
-                    Level::Info => "INFO",
-                    Level::Warn => "WARNING",
-                    Level::Error => "ERROR",
-                    Level::Debug => "VERBOSE",
-                    Level::Trace => "DEBUG",
-                },
+                level_name(record.level()),
                 message
             ))
         })
         .level(level)
-        .chain(std::io::stdout())
-        .apply()
-        .unwrap()
+        // Messages are printed above the progress bars, if any
+        .chain(fern::Output::call(|record| {
+            progress_bars::suspend(|| println!("{}", record.args()))
+        }))
 }
@@ -4,23 +4,49 @@
 #[macro_use]
 extern crate log;
 
-pub mod cli;
-pub mod lib;
-
-mod actions;
 mod logger;
 
 use clap::Parser;
-use cli::opts::{Action, EncodingMethod, Opts};
+use comic_encoder::{actions, cli, lib};
+use cli::exit_code::{ExitCode, ExitCodeError, Failure};
+use cli::opts::{
+    Action, CoverAction, EncodingMethod, LogFormat, MetadataAction, Opts, PresetAction,
+};
+use lib::cancel;
+use lib::config::{self, Config, ConfigErr};
+use lib::notify::{self, RunNotification};
 use log::LevelFilter;
+use std::env;
+use std::ffi::OsString;
 use std::time::Instant;
 
+/// Parse the command-line arguments, adding the options of the preset selected with '--preset'
+fn parse_opts() -> Result<Opts, ConfigErr> {
+    let mut args: Vec<OsString> = env::args_os().collect();
+    let opts = Opts::parse_from(&args);
+
+    match &opts.preset {
+        None => Ok(opts),
+        Some(name) => {
+            let config = Config::load()?;
+            config::apply_preset(&mut args, config.preset(name)?);
+            Ok(Opts::parse_from(&args))
+        }
+    }
+}
+
 fn main() {
     let started = Instant::now();
 
-    let opts: Opts = Opts::parse();
+    let opts = match parse_opts() {
+        Ok(opts) => opts,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(err.exit_code().code(false));
+        }
+    };
 
-    logger::start(if opts.silent {
+    let level = if opts.silent || opts.action.prints_json() {
         LevelFilter::Error
     } else if opts.verbose {
         LevelFilter::Debug
@@ -28,27 +54,149 @@ fn main() {
         LevelFilter::Trace
     } else {
         LevelFilter::Info
-    });
+    };
+
+    if opts.log_format == LogFormat::Json {
+        lib::events::enable();
+    }
+
+    let log_file = opts
+        .log_file
+        .as_deref()
+        .map(|path| (path, opts.log_file_max_size * 1024 * 1024));
+
+    if let Err(err) = logger::start(level, log_file) {
+        eprintln!("Failed to open log file: {}", err);
+        std::process::exit(ExitCode::IOError.code(false));
+    }
+
+    // Progress bars would be drowned in detailed messages, and the server's jobs report their progress through the API
+    if level == LevelFilter::Info
+        && !opts.no_progress
+        && opts.log_format == LogFormat::Text
+        && !matches!(opts.action, Action::Serve(_))
+    {
+        lib::progress_bars::enable();
+    }
 
     trace!("Command-line arguments were parsed successfully.");
 
+    // Volumes being built are cancelled cleanly on Ctrl-C, the other actions are simply interrupted
+    if let Action::Encode(_) | Action::Batch(_) = &opts.action {
+        if let Err(err) = cancel::handle_interrupts() {
+            warn!(
+                "Warning: failed to handle Ctrl-C, interrupting the program will leave partial files: {}",
+                err
+            );
+        }
+    }
+
     let result = match &opts.action {
         Action::Encode(opts) => match &opts.method {
-            EncodingMethod::Compile(compile_opts) => {
-                actions::compile(compile_opts, &opts.options).map_err(|err| format!("{}", err))
-            }
+            EncodingMethod::Compile(compile_opts) => actions::compile(compile_opts, &opts.options)
+                .map(|report| report.paths())
+                .map_err(Failure::from),
 
             EncodingMethod::Single(one_opts) => actions::encode_one(one_opts, &opts.options)
-                .map(|path| vec![path])
-                .map_err(|err| format!("{}", err)),
+                .map(|report| report.paths())
+                .map_err(Failure::from),
+        },
+
+        Action::Decode(decode) => actions::decode(decode)
+            .map(|report| report.paths())
+            .map_err(Failure::from),
+
+        Action::Preset(preset) => match &preset.action {
+            PresetAction::Save(save) => actions::preset_save(save)
+                .map(|_| vec![])
+                .map_err(Failure::from),
+            PresetAction::List(list) => actions::preset_list(list)
+                .map(|_| vec![])
+                .map_err(Failure::from),
+            PresetAction::Remove(remove) => actions::preset_remove(remove)
+                .map(|_| vec![])
+                .map_err(Failure::from),
+        },
+
+        Action::Metadata(metadata) => match &metadata.action {
+            MetadataAction::Show(show) => actions::metadata_show(show)
+                .map(|_| vec![])
+                .map_err(Failure::from),
+        },
+
+        Action::Validate(validate) => actions::validate(validate).map_err(Failure::from),
+
+        Action::Info(info) => actions::info(info)
+            .map(|_| vec![])
+            .map_err(Failure::from),
+
+        Action::Optimize(optimize) => actions::optimize(optimize).map_err(Failure::from),
+
+        Action::Merge(merge) => actions::merge(merge)
+            .map(|path| vec![path])
+            .map_err(Failure::from),
+
+        Action::Split(split) => actions::split(split).map_err(Failure::from),
+
+        Action::Convert(convert) => actions::convert(convert).map_err(Failure::from),
+
+        Action::Rename(rename) => actions::rename(rename).map_err(Failure::from),
+
+        Action::Cover(cover) => match &cover.action {
+            CoverAction::Extract(extract) => {
+                actions::cover_extract(extract).map_err(Failure::from)
+            }
         },
 
-        Action::Decode(decode) => actions::decode(decode).map_err(|err| format!("{}", err)),
+        Action::Thumbnails(thumbnails) => {
+            actions::thumbnails(thumbnails).map_err(Failure::from)
+        }
+
+        Action::Diff(diff) => actions::diff(diff)
+            .map(|_| vec![])
+            .map_err(Failure::from),
+
+        Action::Verify(verify) => actions::verify(verify).map_err(Failure::from),
+
+        Action::Watch(watch) => actions::watch(watch).map_err(Failure::from),
+
+        Action::Batch(batch) => actions::batch(batch).map_err(Failure::from),
+
+        Action::Serve(serve) => actions::serve(serve).map_err(Failure::from),
+
+        Action::Clean(clean) => actions::clean(clean).map_err(Failure::from),
+
+        Action::Dedupe(dedupe) => actions::dedupe(dedupe).map_err(Failure::from),
+
+        Action::Stats(stats) => actions::stats(stats)
+            .map(|_| vec![])
+            .map_err(Failure::from),
+
+        Action::Doctor(doctor) => actions::doctor(doctor)
+            .map(|_| vec![])
+            .map_err(Failure::from),
+
+        Action::Bench(bench) => actions::bench(bench)
+            .map(|_| vec![])
+            .map_err(Failure::from),
+
+        Action::Completions(completions) => {
+            actions::completions(completions);
+            Ok(vec![])
+        }
+
+        Action::Manpage(manpage) => {
+            actions::manpage(manpage);
+            Ok(vec![])
+        }
+
+        Action::Repack(repack) => actions::repack(repack).map_err(Failure::from),
     };
 
-    match result {
+    let elapsed = started.elapsed();
+
+    let exit_code = match &result {
         Ok(_) => {
-            let elapsed = started.elapsed();
             let secs = elapsed.as_secs();
             info!(
                 "Done in {}m{: >2}.{:03}s.",
@@ -56,11 +204,34 @@ fn main() {
                 secs % 60,
                 elapsed.subsec_millis()
             );
+            0
         }
 
-        Err(err) => {
-            error!("{}", err);
-            std::process::exit(1);
+        Err(failure) => {
+            error!("{}", failure.message);
+            failure.exit_code.code(opts.partial_success_exit_code)
         }
+    };
+
+    if !opts.notify.is_empty() {
+        let notification = RunNotification::new(
+            result
+                .as_ref()
+                .map(Vec::as_slice)
+                .map_err(|failure| failure.message.as_str()),
+            exit_code,
+            env::args().collect(),
+            elapsed,
+        );
+
+        for target in &opts.notify {
+            if let Err(err) = notify::send(target, &notification) {
+                warn!("Warning: failed to send notification: {}", err);
+            }
+        }
+    }
+
+    if exit_code != 0 {
+        std::process::exit(exit_code);
     }
 }
@@ -0,0 +1,200 @@
+//! Python module, to use the encoder from Python scripts instead of running 'comic-enc'
+//!
+//! Only available with the 'pyo3' feature ('python-extension' to build the module itself). Keyword arguments are the
+//! options of the command line, with underscores (e.g. `compress_webp=True`, `quality=80` or `include=["Chapter *"]`),
+//! so they are validated the same way.
+
+use crate::actions;
+use crate::cli::exit_code::ExitCodeError;
+use crate::cli::opts::{Action, EncodingMethod, Opts};
+use clap::{Arg, Command, CommandFactory, FromArgMatches};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyList, PyTuple};
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+
+create_exception!(
+    comic_encoder,
+    ComicEncoderError,
+    PyException,
+    "Failure of an action, with the error's message and the exit code of the command line as arguments"
+);
+
+/// Compile chapter directories into volumes, returning the path of each produced volume
+/// Volumes contain `chapters_per_volume` chapters, or a single one each with `each=True`
+#[pyfunction(
+    output = "None",
+    chapters_per_volume = "None",
+    each = "false",
+    options = "**"
+)]
+fn compile(
+    py: Python,
+    input: PathBuf,
+    output: Option<PathBuf>,
+    chapters_per_volume: Option<u16>,
+    each: bool,
+    options: Option<&PyDict>,
+) -> PyResult<Vec<PathBuf>> {
+    let mut args: Vec<OsString> = vec!["comic-enc".into(), "encode".into(), input.into()];
+
+    // The output is not an option of the compilation methods
+    if let Some(output) = output {
+        args.extend(vec!["--output".into(), output.into()]);
+    }
+
+    args.push("compile".into());
+
+    if each {
+        args.push("each".into());
+    } else {
+        args.push("ranges".into());
+        args.extend(chapters_per_volume.map(|chapters| chapters.to_string().into()));
+    }
+
+    args.extend(options_to_args(options)?);
+
+    let encode = match parse_args(args)?.action {
+        Action::Encode(encode) => encode,
+        _ => unreachable!(),
+    };
+
+    let compilation = match &encode.method {
+        EncodingMethod::Compile(compilation) => compilation,
+        EncodingMethod::Single(_) => unreachable!(),
+    };
+
+    py.allow_threads(|| actions::compile(compilation, &encode.options))
+        .map(|report| report.paths())
+        .map_err(to_py_err)
+}
+
+/// Extract the pages of comic books, returning the path of each produced directory
+#[pyfunction(inputs = "*", options = "**")]
+fn decode(py: Python, inputs: &PyTuple, options: Option<&PyDict>) -> PyResult<Vec<PathBuf>> {
+    let mut args: Vec<OsString> = vec!["comic-enc".into(), "decode".into()];
+    args.extend(inputs_to_args(inputs)?);
+    args.extend(options_to_args(options)?);
+
+    let decode = match parse_args(args)?.action {
+        Action::Decode(decode) => decode,
+        _ => unreachable!(),
+    };
+
+    py.allow_threads(|| actions::decode(&decode))
+        .map(|report| report.paths())
+        .map_err(to_py_err)
+}
+
+/// Rebuild comic books with the provided conversion options, returning the path of each produced comic book
+#[pyfunction(inputs = "*", options = "**")]
+fn optimize(py: Python, inputs: &PyTuple, options: Option<&PyDict>) -> PyResult<Vec<PathBuf>> {
+    let mut args: Vec<OsString> = vec!["comic-enc".into(), "optimize".into()];
+    args.extend(inputs_to_args(inputs)?);
+    args.extend(options_to_args(options)?);
+
+    let optimize = match parse_args(args)?.action {
+        Action::Optimize(optimize) => optimize,
+        _ => unreachable!(),
+    };
+
+    py.allow_threads(|| actions::optimize(&optimize))
+        .map_err(to_py_err)
+}
+
+/// Python module
+#[pymodule]
+fn comic_encoder(py: Python, module: &PyModule) -> PyResult<()> {
+    module.add("ComicEncoderError", py.get_type::<ComicEncoderError>())?;
+    module.add_function(wrap_pyfunction!(compile, module)?)?;
+    module.add_function(wrap_pyfunction!(decode, module)?)?;
+    module.add_function(wrap_pyfunction!(optimize, module)?)?;
+    Ok(())
+}
+
+/// Parse the command-line arguments built from a function's arguments
+/// The 'COMIC_ENC_*' environment variables are ignored, as they are meant for 'comic-enc' and would silently change the
+/// options of the functions
+fn parse_args(args: Vec<OsString>) -> PyResult<Opts> {
+    without_env(Opts::command())
+        .try_get_matches_from(args)
+        .and_then(|matches| Opts::from_arg_matches(&matches))
+        .map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+/// Stop reading the arguments of a command and of its subcommands from environment variables
+/// Values are read from the environment when the arguments are built, so their variable is replaced by an empty name,
+/// which can't be set
+fn without_env(cmd: Command<'static>) -> Command<'static> {
+    let env_args = cmd
+        .get_arguments()
+        .filter(|arg| arg.get_env().is_some())
+        .map(Arg::get_id)
+        .collect::<Vec<_>>();
+
+    let mut cmd = env_args
+        .into_iter()
+        .fold(cmd, |cmd, id| cmd.mut_arg(id, |arg| arg.env_os(OsStr::new(""))));
+
+    for subcommand in cmd.get_subcommands_mut() {
+        *subcommand = without_env(subcommand.clone());
+    }
+
+    cmd
+}
+
+/// Convert the paths provided as positional arguments to command-line arguments
+fn inputs_to_args(inputs: &PyTuple) -> PyResult<Vec<OsString>> {
+    inputs
+        .iter()
+        .map(|input| input.extract::<PathBuf>().map(OsString::from))
+        .collect()
+}
+
+/// Convert keyword arguments to command-line options (e.g. `max_dimension=2000` to '--max-dimension 2000')
+/// `True` gives a flag while `False` and `None` are ignored, and lists give the option once for each of their items
+fn options_to_args(options: Option<&PyDict>) -> PyResult<Vec<OsString>> {
+    let mut args = vec![];
+
+    for (name, value) in options.into_iter().flatten() {
+        if value.is_none() {
+            continue;
+        }
+
+        let flag = format!("--{}", name.extract::<String>()?.replace('_', "-"));
+
+        // Booleans must be checked first, as they are integers in Python
+        if let Ok(value) = value.downcast::<PyBool>() {
+            if value.is_true() {
+                args.push(flag.into());
+            }
+        } else if let Ok(values) = value.downcast::<PyList>() {
+            for value in values {
+                args.extend(vec![flag.clone().into(), option_value(value)?]);
+            }
+        } else if let Ok(values) = value.downcast::<PyTuple>() {
+            for value in values {
+                args.extend(vec![flag.clone().into(), option_value(value)?]);
+            }
+        } else {
+            args.extend(vec![flag.into(), option_value(value)?]);
+        }
+    }
+
+    Ok(args)
+}
+
+/// Convert the value of a keyword argument to a command-line argument (paths are kept as they are)
+fn option_value(value: &PyAny) -> PyResult<OsString> {
+    match value.extract::<PathBuf>() {
+        Ok(path) => Ok(path.into()),
+        Err(_) => Ok(value.str()?.to_string().into()),
+    }
+}
+
+/// Convert the failure of an action to a Python exception
+fn to_py_err(err: impl ExitCodeError) -> PyErr {
+    ComicEncoderError::new_err((err.message(), err.exit_code().code(false)))
+}